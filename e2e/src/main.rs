@@ -2,18 +2,20 @@ pub mod tests;
 
 use extel::prelude::*;
 use tests::{
-    command_tests::CommandTestSuite, math_tests::MathTestSuite,
-    unsupported_errors::UnsupportedErrorTestSuite, utf8_tests::Utf8TestSuite,
+    command_tests::CommandTestSuite, generated_tests::GeneratedTestSuite,
+    math_tests::MathTestSuite, unsupported_errors::UnsupportedErrorTestSuite,
+    utf8_tests::Utf8TestSuite,
 };
 
-fn main() {
-    run_tests::<MathTestSuite>();
-    run_tests::<CommandTestSuite>();
-    run_tests::<Utf8TestSuite>();
-    run_tests::<UnsupportedErrorTestSuite>();
-}
+compose_suites!(
+    AllTests,
+    MathTestSuite,
+    CommandTestSuite,
+    Utf8TestSuite,
+    UnsupportedErrorTestSuite,
+    GeneratedTestSuite
+);
 
-/// Run test set with default configuration.
-fn run_tests<S: RunnableTestSet>() {
-    S::run(TestConfig::default());
+fn main() {
+    AllTests::run(TestConfig::default());
 }
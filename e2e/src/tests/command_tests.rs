@@ -36,4 +36,4 @@ pub(crate) fn c_exe(x: Vec<usize>) -> ExtelResult {
     extel_assert!(code == 0, "returned exit code: {}", code)
 }
 
-init_test_suite!(CommandTestSuite, echo, c_exe);
+init_test_suite!(CommandTestSuite, echo_cases, c_exe_cases);
@@ -0,0 +1,33 @@
+use extel::{
+    prelude::*,
+    runner::{self, Test},
+    TestResult,
+};
+
+/// Words and whether they are palindromes, standing in for a table of cases read at runtime.
+const PALINDROMES: &str = "\
+level true
+extel false
+racecar true";
+
+/// A suite with one test per line of [`PALINDROMES`], implemented by hand rather than with
+/// `init_test_suite!` since its tests are not known until it runs.
+pub struct GeneratedTestSuite;
+
+impl RunnableTestSet for GeneratedTestSuite {
+    fn run(cfg: TestConfig) -> Vec<TestResult> {
+        let tests = PALINDROMES
+            .lines()
+            .filter_map(|line| line.split_once(' '))
+            .map(|(word, expected)| {
+                let (word, expected) = (word.to_string(), expected == "true");
+                Test::new(format!("palindrome_{}", word), move || {
+                    let reversed: String = word.chars().rev().collect();
+                    extel_assert_eq!(reversed == word, expected)
+                })
+            })
+            .collect();
+
+        runner::run_tests(Self::suite_name(), tests, cfg)
+    }
+}
@@ -19,4 +19,4 @@ pub fn calculate() -> ExtelResult {
     )
 }
 
-init_test_suite!(MathTestSuite, calculate, perfect_sqrt);
+init_test_suite!(MathTestSuite, calculate, perfect_sqrt_cases);
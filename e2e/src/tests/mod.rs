@@ -1,4 +1,5 @@
 pub mod command_tests;
+pub mod generated_tests;
 pub mod math_tests;
 pub mod unsupported_errors;
 pub mod utf8_tests;
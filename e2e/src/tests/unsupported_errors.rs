@@ -16,7 +16,10 @@ fn unsupported_error() -> ExtelResult {
 
     // This will compile!
     let res = foo().map_err(|e| err!("{}", e))?;
-    extel_assert!(res == 0)
+
+    // And so will this!
+    let other = foo().into_extel()?;
+    extel_assert!(res == 0 && other == 0)
 }
 
 init_test_suite!(UnsupportedErrorTestSuite, unsupported_error);
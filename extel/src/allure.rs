@@ -0,0 +1,393 @@
+//! Results in the [Allure](https://allurereport.org) format, so that runs can be shown in existing
+//! Allure dashboards. See [`TestConfig::allure_dir`](crate::TestConfig::allure_dir).
+//!
+//! Every test is written to its own `<uuid>-result.json` file, and every suite run to a
+//! `<uuid>-container.json` file listing its tests. Each case of a parameterized test is a step
+//! of its test, and the [artifacts](crate::artifacts) of a test are copied next to its result as
+//! attachments. Where and when the suite ran is written to `environment.properties`.
+//!
+//! A test passes, fails on an [`Error::TestFailed`], is skipped when it was
+//! [not run](Error::NotRun) or [skipped](Error::Skipped), and is broken on any other error, such as an I/O error or a panic.
+//! The description of a test that did not pass holds the
+//! [commands rerunning it](crate::report::rerun_command).
+
+use std::{
+    fs, io,
+    path::Path,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    errors::Error,
+    fs::fnv1a,
+    manifest::json_string,
+    report,
+    run_info::{self, RunInfo},
+    CaseResult, TestResult,
+};
+
+/// Distinguishes the ids generated within a single process.
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Write the results of a run of `suite_name` that ended at `finished` into the Allure results
+/// directory `dir`, creating it if needed. Tests are assumed to have run one after the other, so
+/// each starts when the previous one finished.
+///
+/// # Example
+/// ```rust
+/// use extel::{fixture::TempDir, prelude::*, OutputDest};
+///
+/// fn always_fail() -> ExtelResult {
+///     fail!("no luck")
+/// }
+///
+/// let tmp = TempDir::new().unwrap();
+///
+/// init_test_suite!(AllureSuite as "allure", always_fail);
+/// AllureSuite::run(TestConfig::default().output(OutputDest::None).allure_dir(tmp.path()));
+///
+/// let result = std::fs::read_dir(tmp.path())
+///     .unwrap()
+///     .map(|entry| entry.unwrap().path())
+///     .find(|path| path.to_string_lossy().ends_with("-result.json"))
+///     .unwrap();
+/// let contents = std::fs::read_to_string(result).unwrap();
+/// assert!(contents.contains(r#""name":"always_fail","fullName":"allure::always_fail","status":"failed""#));
+/// assert!(contents.contains(r#""statusDetails":{"message":"no luck"}"#));
+/// ```
+pub fn write_results(
+    dir: impl AsRef<Path>,
+    suite_name: &str,
+    results: &[TestResult],
+    finished: SystemTime,
+) -> io::Result<()> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+
+    let total: Duration = results.iter().map(|result| result.duration).sum();
+    let suite_start = finished.checked_sub(total).unwrap_or(finished);
+    let mut start = suite_start;
+    let mut children = Vec::new();
+
+    for result in results {
+        let uuid = new_uuid(&format!("{}::{}", suite_name, result.test_name));
+        let stop = start + result.duration;
+
+        let mut attachments = Vec::new();
+        for artifact in result.artifacts.iter().filter(|path| path.is_file()) {
+            let name = artifact
+                .file_name()
+                .map_or_else(String::new, |name| name.to_string_lossy().into_owned());
+            let source = format!("{}-attachment-{}", uuid, name);
+            fs::copy(artifact, dir.join(&source))?;
+            attachments.push(format!(
+                r#"{{"name":{},"source":{}}}"#,
+                json_string(&name),
+                json_string(&source)
+            ));
+        }
+
+        fs::write(
+            dir.join(format!("{}-result.json", uuid)),
+            test_json(
+                &uuid,
+                suite_name,
+                result,
+                (millis(start), millis(stop)),
+                &attachments,
+            ),
+        )?;
+
+        children.push(json_string(&uuid));
+        start = stop;
+    }
+
+    let uuid = new_uuid(suite_name);
+    fs::write(
+        dir.join(format!("{}-container.json", uuid)),
+        format!(
+            r#"{{"uuid":{},"name":{},"children":[{}],"start":{},"stop":{}}}"#,
+            json_string(&uuid),
+            json_string(suite_name),
+            children.join(","),
+            millis(suite_start),
+            millis(finished)
+        ),
+    )
+}
+
+/// Write where and when a suite ran to the `environment.properties` file of the Allure results
+/// directory `dir`, which Allure shows as the environment of the run.
+pub fn write_environment(dir: impl AsRef<Path>, run: &RunInfo) -> io::Result<()> {
+    let dir = dir.as_ref();
+    fs::create_dir_all(dir)?;
+
+    let mut properties = vec![
+        (String::from("run_id"), run.run_id.clone()),
+        (String::from("started"), run_info::timestamp(run.started)),
+        (String::from("host"), run.hostname.clone()),
+        (String::from("os"), run.os.clone()),
+    ];
+    if let Some(finished) = run.finished {
+        properties.insert(2, (String::from("finished"), run_info::timestamp(finished)));
+    }
+    properties.extend(
+        run.env
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone())),
+    );
+
+    let contents: String = properties
+        .iter()
+        .map(|(key, value)| {
+            format!(
+                "{}={}\n",
+                key,
+                value.replace('\\', "\\\\").replace('\n', "\\n")
+            )
+        })
+        .collect();
+    fs::write(dir.join("environment.properties"), contents)
+}
+
+fn test_json(
+    uuid: &str,
+    suite_name: &str,
+    result: &TestResult,
+    (start, stop): (u128, u128),
+    attachments: &[String],
+) -> String {
+    let full_name = format!("{}::{}", suite_name, result.test_name);
+    let cases = &result.test_result.cases;
+
+    let mut labels = vec![
+        label("suite", suite_name),
+        label("framework", "extel"),
+        label("language", "rust"),
+    ];
+    labels.extend(result.tags.iter().map(|tag| label("tag", tag)));
+
+    let mut parameters: Vec<(&String, &String)> = result.metadata.iter().collect();
+    parameters.sort();
+    let parameters: Vec<String> = parameters
+        .into_iter()
+        .map(|(name, value)| {
+            format!(
+                r#"{{"name":{},"value":{}}}"#,
+                json_string(name),
+                json_string(value)
+            )
+        })
+        .collect();
+
+    // A parameterized test has one step per case, and its status is the worst of them. Other tests
+    // have the steps they ran with `step!`.
+    let steps: Vec<String> = match result.test_result.parameterized {
+        true => cases
+            .iter()
+            .enumerate()
+            .map(|(idx, case)| {
+                let name = case
+                    .name
+                    .clone()
+                    .unwrap_or_else(|| format!("case #{}", idx + 1));
+                format!(
+                    r#"{{"name":{},"status":"{}",{}"stage":"finished"}}"#,
+                    json_string(&name),
+                    status(case),
+                    status_details(case.result.as_ref().err().map(ToString::to_string))
+                )
+            })
+            .collect(),
+        false => result
+            .steps
+            .iter()
+            .map(|step| {
+                format!(
+                    r#"{{"name":{},"status":"{}","stage":"finished"}}"#,
+                    json_string(&step.name),
+                    match step.passed {
+                        true => "passed",
+                        false => "failed",
+                    }
+                )
+            })
+            .collect(),
+    };
+
+    let worst = cases
+        .iter()
+        .map(status)
+        .max_by_key(|status| STATUSES.iter().position(|s| s == status))
+        .unwrap_or("passed");
+    let message = cases
+        .iter()
+        .enumerate()
+        .filter_map(|(idx, case)| {
+            let e = case.result.as_ref().err()?;
+            Some(match result.test_result.parameterized {
+                true => format!("case #{}: {}", idx + 1, e),
+                false => e.to_string(),
+            })
+        })
+        .collect::<Vec<_>>();
+    let description = match result.test_result.is_ok() {
+        true => String::new(),
+        false => format!(
+            r#""description":{},"#,
+            json_string(&format!(
+                "Rerun with:\n\n```sh\n{}\n```",
+                report::rerun_commands(suite_name, result).join("\n")
+            ))
+        ),
+    };
+
+    format!(
+        concat!(
+            r#"{{"uuid":{},"historyId":"{:016x}","name":{},"fullName":{},"status":"{}","#,
+            r#"{}{}"stage":"finished","start":{},"stop":{},"labels":[{}],"parameters":[{}],"#,
+            r#""steps":[{}],"attachments":[{}]}}"#
+        ),
+        json_string(uuid),
+        fnv1a(full_name.as_bytes()),
+        json_string(&result.test_name),
+        json_string(&full_name),
+        worst,
+        status_details((!message.is_empty()).then(|| message.join("\n"))),
+        description,
+        start,
+        stop,
+        labels.join(","),
+        parameters.join(","),
+        steps.join(","),
+        attachments.join(",")
+    )
+}
+
+/// Allure statuses, from best to worst.
+const STATUSES: [&str; 4] = ["passed", "skipped", "broken", "failed"];
+
+fn status(case: &CaseResult) -> &'static str {
+    match &case.result {
+        Ok(()) => "passed",
+        Err(Error::TestFailed(_)) => "failed",
+        Err(Error::NotRun(_) | Error::Skipped(_)) => "skipped",
+        Err(_) => "broken",
+    }
+}
+
+/// The `statusDetails` field, followed by a comma, if there is a message.
+fn status_details(message: Option<String>) -> String {
+    match message {
+        Some(message) => format!(
+            r#""statusDetails":{{"message":{}}},"#,
+            json_string(&message)
+        ),
+        None => String::new(),
+    }
+}
+
+fn label(name: &str, value: &str) -> String {
+    format!(
+        r#"{{"name":{},"value":{}}}"#,
+        json_string(name),
+        json_string(value)
+    )
+}
+
+fn millis(time: SystemTime) -> u128 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis()
+}
+
+/// A random-looking version 4 UUID, unique within the results directory.
+fn new_uuid(seed: &str) -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_nanos();
+    let seed = format!(
+        "{}\0{}\0{}\0{}",
+        seed,
+        nanos,
+        std::process::id(),
+        NEXT_ID.fetch_add(1, Ordering::Relaxed)
+    );
+    let high = fnv1a(seed.as_bytes());
+    let low = fnv1a(format!("{}\0low", seed).as_bytes());
+
+    format!(
+        "{:08x}-{:04x}-4{:03x}-{:04x}-{:012x}",
+        high >> 32,
+        (high >> 16) & 0xffff,
+        high & 0xfff,
+        0x8000 | ((low >> 48) & 0x3fff),
+        low & 0xffff_ffff_ffff
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fixture::TempDir, prelude::*, OutputDest, SuiteBuilder};
+
+    #[test]
+    fn writes_one_result_per_test_and_a_container() {
+        let tmp = TempDir::new().unwrap();
+        SuiteBuilder::new("Allure")
+            .add_tagged_test("plain", &["smoke"], || -> ExtelResult { pass!() })
+            .add_param_test("cases", [1, -1, 0], |n: i32| match n {
+                0 => Err(Error::NotRun(String::from("skipped"))),
+                n => extel_assert!(n > 0, "{} is negative", n),
+            })
+            .run(
+                TestConfig::default()
+                    .output(OutputDest::None)
+                    .allure_dir(tmp.path()),
+            );
+
+        let mut files: Vec<String> = fs::read_dir(tmp.path())
+            .unwrap()
+            .map(|entry| fs::read_to_string(entry.unwrap().path()).unwrap())
+            .collect();
+        files.sort();
+        assert_eq!(files.len(), 4);
+
+        let environment = files.iter().find(|f| f.starts_with("run_id=")).unwrap();
+        assert!(environment.contains("\nfinished="), "{}", environment);
+
+        let plain = files
+            .iter()
+            .find(|f| f.contains(r#""name":"plain""#))
+            .unwrap();
+        assert!(plain.contains(r#""status":"passed","stage":"finished""#));
+        assert!(plain.contains(r#"{"name":"tag","value":"smoke"}"#));
+
+        let cases = files
+            .iter()
+            .find(|f| f.contains(r#""name":"cases""#))
+            .unwrap();
+        assert!(cases.contains(
+            r#""status":"failed","statusDetails":{"message":"case #2: -1 is negative\ncase #3: not run: skipped"}"#
+        ));
+        assert!(cases.contains(r#"{"name":"case #3","status":"skipped","#));
+
+        let container = files
+            .iter()
+            .find(|f| f.contains(r#""children":["#))
+            .unwrap();
+        assert!(container.contains(r#""name":"Allure""#));
+        assert_eq!(container.matches('-').count(), 4 * 3);
+    }
+
+    #[test]
+    fn uuids_are_well_formed_and_unique() {
+        let (a, b) = (new_uuid("suite"), new_uuid("suite"));
+        assert_ne!(a, b);
+        assert_eq!(a.len(), 36);
+        assert_eq!(&a[14..15], "4");
+        assert!(matches!(&a[19..20], "8" | "9" | "a" | "b"));
+    }
+}
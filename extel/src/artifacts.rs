@@ -0,0 +1,134 @@
+//! Per-test artifact files, such as logs or core dumps, that are kept after the run.
+//!
+//! Every test run by a suite gets its own directory under the run's artifacts directory (see
+//! [`TestConfig::artifacts_dir`](crate::TestConfig::artifacts_dir)). Paths handed out by
+//! [`artifact_path`] are recorded on the test's [`TestResult`](crate::TestResult), and the default
+//! reporter lists them under failing tests.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::context;
+
+/// The artifacts directory used when none is configured: `extel-artifacts-<pid>` under the system
+/// temporary directory.
+pub fn default_dir() -> PathBuf {
+    std::env::temp_dir().join(format!("extel-artifacts-{}", std::process::id()))
+}
+
+/// The directory holding the artifacts of `test_name` in `suite_name`, under `base`.
+pub fn test_dir(base: &Path, suite_name: &str, test_name: &str) -> PathBuf {
+    let sanitize = |name: &str| -> String {
+        name.chars()
+            .map(|c| match c.is_alphanumeric() || c == '-' || c == '_' {
+                true => c,
+                false => '_',
+            })
+            .collect()
+    };
+
+    base.join(sanitize(suite_name)).join(sanitize(test_name))
+}
+
+/// Get a path for an artifact named `name` that belongs to the currently running test, creating
+/// its parent directories. When called outside of a test run, the path is placed directly under
+/// the [default artifacts directory](default_dir).
+///
+/// # Example
+/// ```rust
+/// use extel::{artifacts::artifact_path, prelude::*, OutputDest};
+///
+/// fn writes_log() -> ExtelResult {
+///     let log = artifact_path("server.log")?;
+///     std::fs::write(&log, "listening on 8080")?;
+///     fail!("server never became ready")
+/// }
+///
+/// init_test_suite!(ArtifactSuite, writes_log);
+/// let results = ArtifactSuite::run(TestConfig::default().output(OutputDest::None));
+///
+/// assert!(results[0].artifacts[0].ends_with("writes_log/server.log"));
+/// ```
+pub fn artifact_path(name: impl AsRef<Path>) -> io::Result<PathBuf> {
+    let path = context::with_scope(|scope| match scope {
+        Some(scope) => {
+            let path = scope.artifact_dir.join(name.as_ref());
+            if !scope.recorded.artifacts.contains(&path) {
+                scope.recorded.artifacts.push(path.clone());
+            }
+            path
+        }
+        None => default_dir().join(name.as_ref()),
+    });
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    Ok(path)
+}
+
+/// The name of the artifact holding the full failure message of case `case_idx` of a test, for
+/// failure messages [truncated](crate::TestConfig::max_failure_len) in the console output.
+pub(crate) fn failure_file_name(case_idx: usize, parameterized: bool) -> String {
+    match parameterized {
+        true => format!("failure-{}.txt", case_idx),
+        false => String::from("failure.txt"),
+    }
+}
+
+/// Write the full failure messages longer than `max_len` of `result` to artifacts under `dir`,
+/// adding them to the result's artifacts.
+pub(crate) fn write_long_failures(result: &mut crate::TestResult, dir: &Path, max_len: usize) {
+    let outcome = &result.test_result;
+    for (idx, case) in outcome.cases.iter().enumerate() {
+        let Err(err) = &case.result else {
+            continue;
+        };
+        let message = err.to_string();
+        if message.len() <= max_len {
+            continue;
+        }
+
+        let path = dir.join(failure_file_name(idx, outcome.parameterized));
+        let written = fs::create_dir_all(dir).and_then(|()| fs::write(&path, message));
+        if written.is_ok() && !result.artifacts.contains(&path) {
+            result.artifacts.push(path);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fixture::TempDir, prelude::*, OutputDest, SharedBuffer};
+
+    fn leaves_artifact() -> ExtelResult {
+        fs::write(artifact_path("out.log")?, "output")?;
+        fail!("failed with artifact")
+    }
+
+    #[test]
+    fn failing_tests_list_artifacts() {
+        init_test_suite!(ArtifactTestSet as "artifact suite", leaves_artifact);
+
+        let tmp = TempDir::new().unwrap();
+        let output_buffer = SharedBuffer::new();
+        let results = ArtifactTestSet::run(
+            TestConfig::default()
+                .output(OutputDest::Buffer(output_buffer.clone()))
+                .colored(false)
+                .rerun_hint(false)
+                .artifacts_dir(tmp.path()),
+        );
+
+        let expected = tmp.path().join("artifact_suite/leaves_artifact/out.log");
+        assert_eq!(results[0].artifacts, vec![expected.clone()]);
+        assert_eq!(fs::read_to_string(&expected).unwrap(), "output");
+        assert!(output_buffer
+            .to_string_lossy()
+            .ends_with(&format!("\t  [artifact] {}\n", expected.display())));
+    }
+}
@@ -0,0 +1,160 @@
+//! Assertions provided by other crates, such as protobuf equality or image comparison, that fail
+//! with the same message layout as the built-in assertions.
+//!
+//! An [`Assertion`] only decides whether a value passes and describes how it falls short with a
+//! [`Mismatch`]. The failure message, including abbreviated values and line diffs, is rendered by
+//! [`assert_that`](crate::assert_that).
+
+use std::fmt;
+
+use crate::{
+    diff::{abbreviate, line_diff, MESSAGE_LIMIT},
+    err, ExtelResult,
+};
+
+/// A check on values of type `T`, used with [`assert_that`](crate::assert_that).
+///
+/// # Example
+/// ```rust
+/// use extel::{assert_that, assertion::{Assertion, Mismatch}, prelude::*};
+///
+/// /// Expects a multiple of a number.
+/// struct MultipleOf(u32);
+///
+/// impl Assertion<u32> for MultipleOf {
+///     fn description(&self) -> String {
+///         format!("multiple of {}", self.0)
+///     }
+///
+///     fn check(&self, actual: &u32) -> Result<(), Mismatch> {
+///         match actual % self.0 {
+///             0 => Ok(()),
+///             rest => Err(Mismatch::new(format!("{} is left over", rest))),
+///         }
+///     }
+/// }
+///
+/// assert!(assert_that!(12, MultipleOf(4)).is_ok());
+/// assert_eq!(
+///     assert_that!(13, MultipleOf(4)).unwrap_err().to_string(),
+///     "[13 is multiple of 4] assertion failed\n  1 is left over"
+/// );
+/// ```
+pub trait Assertion<T: ?Sized> {
+    /// What the assertion expects, such as `multiple of 4`.
+    fn description(&self) -> String;
+
+    /// Check `actual`, describing how it falls short of the assertion if it does.
+    fn check(&self, actual: &T) -> Result<(), Mismatch>;
+}
+
+/// How a value failed an [`Assertion`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Mismatch {
+    reason: String,
+    expected: Option<String>,
+    actual: Option<String>,
+    diff: Option<String>,
+}
+
+impl Mismatch {
+    /// A mismatch explained by `reason`, such as `3 pixels differ`.
+    pub fn new(reason: impl Into<String>) -> Self {
+        Self {
+            reason: reason.into(),
+            ..Self::default()
+        }
+    }
+
+    /// Show the expected and actual values, abbreviated like those of the built-in assertions.
+    pub fn values(mut self, expected: impl fmt::Debug, actual: impl fmt::Debug) -> Self {
+        let show = |value: String| match value.chars().count() > MESSAGE_LIMIT {
+            true => abbreviate(&value, MESSAGE_LIMIT),
+            false => value,
+        };
+        self.expected = Some(show(format!("{:?}", expected)));
+        self.actual = Some(show(format!("{:?}", actual)));
+        self
+    }
+
+    /// Show a line diff between the expected and actual text, such as two pretty-printed messages.
+    pub fn diff(mut self, expected: &str, actual: &str) -> Self {
+        self.diff = Some(line_diff(expected, actual));
+        self
+    }
+}
+
+/// Check `actual` with `assertion`, failing with an
+/// [`Error::TestFailed`](crate::errors::Error::TestFailed) that names `expr` and the description
+/// of the assertion, followed by `msg`. Used by [`assert_that`](crate::assert_that).
+pub fn assert_that<T: ?Sized, A: Assertion<T> + ?Sized>(
+    actual: &T,
+    assertion: &A,
+    expr: &str,
+    msg: &str,
+) -> ExtelResult {
+    let mismatch = match assertion.check(actual) {
+        Ok(()) => return Ok(()),
+        Err(mismatch) => mismatch,
+    };
+
+    let mut message = format!(
+        "[{} is {}] assertion failed{}",
+        expr,
+        assertion.description(),
+        msg
+    );
+    if !mismatch.reason.is_empty() {
+        message.push_str(&format!("\n  {}", mismatch.reason));
+    }
+    if let (Some(expected), Some(actual)) = (&mismatch.expected, &mismatch.actual) {
+        message.push_str(&format!(
+            "\n  expected: {}\n    actual: {}",
+            expected, actual
+        ));
+    }
+    if let Some(diff) = &mismatch.diff {
+        message.push_str(&format!(
+            "\n--- expected\n+++ actual\n{}",
+            diff.trim_end_matches('\n')
+        ));
+    }
+    Err(err!("{}", message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Lines(&'static str);
+
+    impl Assertion<str> for Lines {
+        fn description(&self) -> String {
+            String::from("the expected lines")
+        }
+
+        fn check(&self, actual: &str) -> Result<(), Mismatch> {
+            match actual == self.0 {
+                true => Ok(()),
+                false => Err(Mismatch::new("lines differ")
+                    .values(self.0.lines().count(), actual.lines().count())
+                    .diff(self.0, actual)),
+            }
+        }
+    }
+
+    #[test]
+    fn mismatches_are_rendered_like_builtin_assertions() {
+        let err = crate::assert_that!(*"a\nc", Lines("a\nb\nc"), "in {}", "config")
+            .unwrap_err()
+            .to_string();
+        assert_eq!(
+            err,
+            "[*\"a\\nc\" is the expected lines] assertion failed: in config\n  \
+             lines differ\n  \
+             expected: 3\n    \
+             actual: 2\n\
+             --- expected\n+++ actual\n a\n-b\n c"
+        );
+    }
+}
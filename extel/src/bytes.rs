@@ -0,0 +1,215 @@
+//! Asserting on raw byte output, such as that of programs writing binary data, and rendering bytes
+//! that are not valid UTF-8 in failure messages without converting them first.
+
+use std::borrow::Cow;
+
+use crate::{err, ExtelResult};
+
+/// The number of bytes around the first difference shown by [`bytes_eq`].
+const CONTEXT: usize = 32;
+
+/// The number of bytes in each row of a [`hex_diff`].
+const ROW_LEN: usize = 16;
+
+/// `bytes` as text, with every byte that is not part of valid UTF-8 written as a `\xNN` escape
+/// rather than replaced. Valid UTF-8 is borrowed unchanged.
+///
+/// # Example
+/// ```rust
+/// use extel::bytes::lossy;
+///
+/// assert_eq!(lossy(b"caf\xc3\xa9\n"), "café\n");
+/// assert_eq!(lossy(b"\x89PNG\r\n"), "\\x89PNG\r\n");
+/// ```
+pub fn lossy(bytes: &[u8]) -> Cow<'_, str> {
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut text = String::with_capacity(bytes.len());
+    for chunk in bytes.utf8_chunks() {
+        text.push_str(chunk.valid());
+        for byte in chunk.invalid() {
+            text.push_str(&format!("\\x{:02x}", byte));
+        }
+    }
+    Cow::Owned(text)
+}
+
+/// `bytes` as a quoted string for a failure message, with control characters and every byte that
+/// is not part of valid UTF-8 escaped.
+///
+/// # Example
+/// ```rust
+/// use extel::bytes::quoted;
+///
+/// assert_eq!(quoted(b"tab\there\xff"), r#""tab\there\xff""#);
+/// ```
+pub fn quoted(bytes: &[u8]) -> String {
+    let mut text = String::from("\"");
+    for chunk in bytes.utf8_chunks() {
+        text.extend(chunk.valid().chars().flat_map(char::escape_debug));
+        for byte in chunk.invalid() {
+            text.push_str(&format!("\\x{:02x}", byte));
+        }
+    }
+    text.push('"');
+    text
+}
+
+/// Check that `actual` is exactly `expected`, byte for byte. On mismatch, the resulting
+/// [`Error::TestFailed`](crate::errors::Error::TestFailed) shows the offset of the first
+/// difference, both lengths, and the bytes around the difference.
+///
+/// # Example
+/// ```rust
+/// use extel::bytes::bytes_eq;
+///
+/// assert!(bytes_eq(b"\x00\x01\x02", b"\x00\x01\x02").is_ok());
+/// assert_eq!(
+///     bytes_eq(b"\x00\x01\xff", b"\x00\x01\x02").unwrap_err().to_string(),
+///     "bytes differ at offset 2 (expected 3 bytes, got 3)\n  \
+///      expected: \"\\0\\u{1}\\u{2}\"\n  \
+///      actual:   \"\\0\\u{1}\\xff\""
+/// );
+/// ```
+pub fn bytes_eq(actual: impl AsRef<[u8]>, expected: impl AsRef<[u8]>) -> ExtelResult {
+    let (actual, expected) = (actual.as_ref(), expected.as_ref());
+    if actual == expected {
+        return Ok(());
+    }
+
+    let offset = actual
+        .iter()
+        .zip(expected)
+        .position(|(a, e)| a != e)
+        .unwrap_or_else(|| actual.len().min(expected.len()));
+    let start = offset.saturating_sub(CONTEXT / 2);
+    let window = |bytes: &[u8]| {
+        let end = bytes.len().min(start + CONTEXT);
+        let (before, after) = (start > 0, end < bytes.len());
+        format!(
+            "{}{}{}",
+            if before { "..." } else { "" },
+            quoted(bytes.get(start..end).unwrap_or_default()),
+            if after { "..." } else { "" }
+        )
+    };
+
+    Err(err!(
+        "bytes differ at offset {} (expected {} bytes, got {})\n  expected: {}\n  actual:   {}",
+        offset,
+        expected.len(),
+        actual.len(),
+        window(expected),
+        window(actual)
+    ))
+}
+
+/// A hexdump-style diff of `expected` and `actual`, showing only the rows of 16 bytes that differ,
+/// up to `max_rows` of them. Each differing row is shown as it is expected, prefixed with `-`, and
+/// as it actually is, prefixed with `+`, with its offset, its bytes in hex, and its printable
+/// ASCII characters.
+///
+/// # Example
+/// ```rust
+/// use extel::bytes::hex_diff;
+///
+/// assert_eq!(
+///     hex_diff(b"\x89PNG\r\n", b"\x89PNG\n", 8),
+///     "-00000000  89 50 4e 47 0d 0a                                |.PNG..|\n\
+///      +00000000  89 50 4e 47 0a                                   |.PNG.|\n"
+/// );
+/// ```
+pub fn hex_diff(expected: &[u8], actual: &[u8], max_rows: usize) -> String {
+    let mut differing = (0..expected.len().max(actual.len()))
+        .step_by(ROW_LEN)
+        .filter(|&start| row(expected, start) != row(actual, start));
+
+    let mut diff = String::new();
+    for start in differing.by_ref().take(max_rows) {
+        for (sign, bytes) in [('-', expected), ('+', actual)] {
+            if start < bytes.len() {
+                diff.push_str(&format!(
+                    "{}{}\n",
+                    sign,
+                    hexdump_row(start, row(bytes, start))
+                ));
+            }
+        }
+    }
+    let more = differing.count();
+    if more > 0 {
+        diff.push_str(&format!("... ({} more differing rows)\n", more));
+    }
+    diff
+}
+
+/// The row of `bytes` starting at `start`, which is shorter or empty at the end of `bytes`.
+fn row(bytes: &[u8], start: usize) -> &[u8] {
+    &bytes[start.min(bytes.len())..bytes.len().min(start + ROW_LEN)]
+}
+
+/// One row of a hexdump: the offset of `row`, its bytes in hex, and its printable ASCII.
+fn hexdump_row(offset: usize, row: &[u8]) -> String {
+    let hex: Vec<String> = row.iter().map(|byte| format!("{:02x}", byte)).collect();
+    let ascii: String = row
+        .iter()
+        .map(|&byte| match byte.is_ascii_graphic() || byte == b' ' {
+            true => byte as char,
+            false => '.',
+        })
+        .collect();
+    format!(
+        "{:08x}  {:<width$}  |{}|",
+        offset,
+        hex.join(" "),
+        ascii,
+        width = ROW_LEN * 3 - 1
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn differences_are_shown_in_context() {
+        let expected: Vec<u8> = (0..=255).collect();
+        let mut actual = expected.clone();
+        actual[100] = 0;
+
+        let err = bytes_eq(&actual, &expected).unwrap_err().to_string();
+        assert!(err.starts_with("bytes differ at offset 100 (expected 256 bytes, got 256)\n"));
+        assert!(err.contains("expected: ...\"TUVWXYZ[\\\\]^_`abcdefghijklmnopqrs\"..."));
+        assert!(err.contains("actual:   ...\"TUVWXYZ[\\\\]^_`abc\\0efghijklmnopqrs\"..."));
+    }
+
+    #[test]
+    fn hex_diffs_show_only_differing_rows() {
+        let expected: Vec<u8> = (0..64).collect();
+        let mut actual = expected.clone();
+        actual[20] = b'A';
+        actual[40] = b'B';
+        actual[60] = b'C';
+
+        let diff = hex_diff(&expected, &actual, 2);
+        let lines: Vec<&str> = diff.lines().collect();
+        assert_eq!(lines.len(), 5);
+        assert!(lines[0].starts_with("-00000010  10 11 12 13 14 15"));
+        assert!(lines[1].starts_with("+00000010  10 11 12 13 41 15"));
+        assert!(lines[3].starts_with("+00000020"));
+        assert_eq!(lines[4], "... (1 more differing rows)");
+    }
+
+    #[test]
+    fn truncated_output_differs_at_its_end() {
+        let err = bytes_eq(b"abc", b"abcdef").unwrap_err().to_string();
+        assert_eq!(
+            err,
+            "bytes differ at offset 3 (expected 6 bytes, got 3)\n  \
+             expected: \"abcdef\"\n  \
+             actual:   \"abc\""
+        );
+    }
+}
@@ -0,0 +1,167 @@
+//! Capturing of everything a test function writes to stdout and stderr.
+//!
+//! Output is captured at the file descriptor level, so it includes writes made through
+//! [`print!`], [`eprint!`], [`std::io::stdout`], and by child processes that inherit the test's
+//! stdio. Because file descriptors are shared by the whole process, only one capture can be
+//! active at a time.
+//!
+//! > *This is only available with the `capture` feature enabled, on Unix platforms.*
+
+use std::{
+    fs::{File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    os::fd::AsRawFd,
+    sync::Mutex,
+};
+
+use crate::fixture::ScratchFile;
+
+/// Serializes captures, since redirecting a standard stream affects every thread.
+static CAPTURE_LOCK: Mutex<()> = Mutex::new(());
+
+/// The output a test wrote while it was running.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CapturedOutput {
+    pub stdout: String,
+    pub stderr: String,
+}
+
+impl CapturedOutput {
+    /// Whether or not nothing was written to either stream.
+    pub fn is_empty(&self) -> bool {
+        self.stdout.is_empty() && self.stderr.is_empty()
+    }
+}
+
+/// A standard stream temporarily redirected into a scratch file.
+struct Redirect {
+    fd: i32,
+    saved: i32,
+    _scratch: ScratchFile,
+    file: File,
+}
+
+impl Redirect {
+    fn new(fd: i32) -> io::Result<Self> {
+        let scratch = ScratchFile::new()?;
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(scratch.path())?;
+
+        // SAFETY: `fd` is a standard stream and `file` is an open descriptor owned by this
+        // redirect.
+        let saved = unsafe { libc::dup(fd) };
+        if saved < 0 || unsafe { libc::dup2(file.as_raw_fd(), fd) } < 0 {
+            return Err(io::Error::last_os_error());
+        }
+
+        Ok(Self {
+            fd,
+            saved,
+            _scratch: scratch,
+            file,
+        })
+    }
+
+    /// Restore the original stream and return everything written while it was redirected.
+    fn finish(mut self) -> io::Result<String> {
+        self.restore();
+        let mut contents = Vec::new();
+        self.file.seek(SeekFrom::Start(0))?;
+        self.file.read_to_end(&mut contents)?;
+        Ok(String::from_utf8_lossy(&contents).into_owned())
+    }
+
+    fn restore(&mut self) {
+        if self.saved >= 0 {
+            // SAFETY: `saved` is the duplicate of the original stream created in `new`.
+            unsafe {
+                libc::dup2(self.saved, self.fd);
+                libc::close(self.saved);
+            }
+            self.saved = -1;
+        }
+    }
+}
+
+impl Drop for Redirect {
+    fn drop(&mut self) {
+        self.restore();
+    }
+}
+
+/// Run `f` while capturing everything written to stdout and stderr.
+///
+/// # Example
+/// ```rust
+/// use extel::capture::capture_output;
+/// use std::io::Write;
+///
+/// let (value, output) = capture_output(|| {
+///     writeln!(std::io::stdout(), "captured").unwrap();
+///     5
+/// })
+/// .unwrap();
+///
+/// assert_eq!(value, 5);
+/// assert_eq!(output.stdout, "captured\n");
+/// ```
+pub fn capture_output<T>(f: impl FnOnce() -> T) -> io::Result<(T, CapturedOutput)> {
+    let _guard = CAPTURE_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    io::stdout().flush()?;
+    io::stderr().flush()?;
+    let stdout = Redirect::new(libc::STDOUT_FILENO)?;
+    let stderr = Redirect::new(libc::STDERR_FILENO)?;
+
+    let value = f();
+
+    io::stdout().flush()?;
+    io::stderr().flush()?;
+    let output = CapturedOutput {
+        stdout: stdout.finish()?,
+        stderr: stderr.finish()?,
+    };
+
+    Ok((value, output))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prelude::*, OutputDest, SharedBuffer};
+
+    fn noisy_failure() -> ExtelResult {
+        writeln!(io::stdout(), "to stdout")?;
+        writeln!(io::stderr(), "to stderr")?;
+        fail!("noisy failure")
+    }
+
+    #[test]
+    fn capture_attaches_output_to_result() {
+        init_test_suite!(CaptureTestSet, noisy_failure);
+
+        let output_buffer = SharedBuffer::new();
+        let results = CaptureTestSet::run(
+            TestConfig::default()
+                .output(OutputDest::Buffer(output_buffer.clone()))
+                .colored(false)
+                .rerun_hint(false)
+                .capture(true),
+        );
+
+        assert_eq!(
+            results[0].captured,
+            Some(CapturedOutput {
+                stdout: String::from("to stdout\n"),
+                stderr: String::from("to stderr\n"),
+            })
+        );
+        assert!(output_buffer.to_string_lossy().ends_with(
+            "[x] noisy failure\n\t  [stdout]\n\t    to stdout\n\t  [stderr]\n\t    to stderr\n"
+        ));
+    }
+}
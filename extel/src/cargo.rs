@@ -0,0 +1,186 @@
+//! Finding the binaries of the Cargo package under test, so that tests do not need to hard-code
+//! paths like `target/debug/app`, which break under other profiles, target triples, and workspace
+//! layouts.
+//!
+//! Binaries are looked up next to the runner itself, which Cargo builds into the same target and
+//! profile directory as the rest of the package, the same way `assert_cmd` does.
+//!
+//! ```rust,no_run
+//! use extel::{cargo, prelude::*};
+//!
+//! fn prints_help() -> ExtelResult {
+//!     let output = cmd!(cargo::bin_path("my-cli")? => ["--help"]).output_checked()?;
+//!     extel_assert!(String::from_utf8(output.stdout)?.contains("USAGE"))
+//! }
+//! ```
+
+use std::{
+    env,
+    ffi::OsString,
+    path::{Path, PathBuf},
+    process::Command,
+    sync::Mutex,
+};
+
+use crate::{err, errors::Error};
+
+/// The directory Cargo builds the binaries of the current target and profile into, such as
+/// `target/debug` or `target/x86_64-unknown-linux-musl/release`, found from the path of the
+/// running executable.
+pub fn target_dir() -> Result<PathBuf, Error> {
+    let exe = env::current_exe()?;
+    let mut dir = exe
+        .parent()
+        .ok_or_else(|| err!("{} has no parent directory", exe.display()))?
+        .to_path_buf();
+
+    // Tests, benches, and examples are built into `deps` or `examples` under the profile.
+    if dir.ends_with("deps") || dir.ends_with("examples") {
+        dir.pop();
+    }
+    Ok(dir)
+}
+
+/// The path of the binary `name` of the package under test, as built for the current target and
+/// profile. A `CARGO_BIN_EXE_<name>` environment variable takes precedence, as set by Cargo when
+/// building integration tests.
+///
+/// # Errors
+/// Fails if the binary has not been built. Use [`build_bin`] to build it first.
+///
+/// # Example
+/// ```rust
+/// use extel::cargo;
+///
+/// let err = cargo::bin_path("no-such-binary").unwrap_err();
+/// assert!(err.to_string().starts_with("could not find the binary 'no-such-binary' in "));
+/// ```
+pub fn bin_path(name: &str) -> Result<PathBuf, Error> {
+    if let Some(path) = env::var_os(format!("CARGO_BIN_EXE_{}", name)) {
+        return Ok(PathBuf::from(path));
+    }
+
+    let dir = target_dir()?;
+    let path = dir.join(format!("{}{}", name, env::consts::EXE_SUFFIX));
+    match path.is_file() {
+        true => Ok(path),
+        false => Err(err!(
+            "could not find the binary '{}' in {}; build it with `cargo build --bin {}` or \
+             cargo::build_bin",
+            name,
+            dir.display(),
+            name
+        )),
+    }
+}
+
+/// Build the binary `name` with Cargo for the current target and profile, and return its path
+/// like [`bin_path`]. Cargo is only run the first time a binary is built by this process, so this
+/// is cheap to call from every test.
+///
+/// # Errors
+/// Fails if Cargo cannot be run or the build fails, with Cargo's output in the message.
+pub fn build_bin(name: &str) -> Result<PathBuf, Error> {
+    static BUILT: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+    // Hold the lock while building, so that tests running at the same time build only once.
+    let mut built = BUILT
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    if !built.iter().any(|built| built == name) {
+        let output = build_command(name, &target_dir()?).output()?;
+        if !output.status.success() {
+            return Err(err!(
+                "could not build the binary '{}':\n{}",
+                name,
+                String::from_utf8_lossy(&output.stderr).trim_end()
+            ));
+        }
+        built.push(name.to_string());
+    }
+
+    bin_path(name)
+}
+
+/// The Cargo command building the binary `name` into the profile directory `profile_dir`.
+fn build_command(name: &str, profile_dir: &Path) -> Command {
+    let cargo = env::var_os("CARGO").unwrap_or_else(|| OsString::from("cargo"));
+    let mut command = Command::new(cargo);
+    command.args(["build", "--quiet", "--bin", name]);
+
+    let profile = profile_dir.file_name().unwrap_or_default();
+    if profile != "debug" {
+        command.arg("--profile").arg(profile);
+    }
+
+    // A profile directory is either directly under the target directory, which Cargo marks with
+    // a CACHEDIR.TAG file, or under a directory named after the target triple.
+    if let Some(parent) = profile_dir.parent() {
+        match parent.join("CACHEDIR.TAG").is_file() {
+            true => {
+                command.env("CARGO_TARGET_DIR", parent);
+            }
+            false => {
+                if let (Some(triple), Some(target)) = (parent.file_name(), parent.parent()) {
+                    command.arg("--target").arg(triple);
+                    command.env("CARGO_TARGET_DIR", target);
+                }
+            }
+        }
+    }
+    command
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::TempDir;
+
+    #[test]
+    fn target_dir_is_the_profile_dir() {
+        let dir = target_dir().unwrap();
+        assert!(dir.ends_with("debug"), "{}", dir.display());
+        assert!(!dir.ends_with("deps"));
+    }
+
+    #[test]
+    fn builds_use_the_profile_and_target_of_the_runner() {
+        let tmp = TempDir::new().unwrap();
+        std::fs::write(tmp.path().join("CACHEDIR.TAG"), "").unwrap();
+
+        let args = |command: &Command| -> Vec<String> {
+            command
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect()
+        };
+
+        let command = build_command("app", &tmp.path().join("release"));
+        assert_eq!(
+            args(&command),
+            ["build", "--quiet", "--bin", "app", "--profile", "release"]
+        );
+
+        let command = build_command(
+            "app",
+            &tmp.path().join("aarch64-apple-darwin").join("debug"),
+        );
+        assert_eq!(
+            args(&command),
+            [
+                "build",
+                "--quiet",
+                "--bin",
+                "app",
+                "--target",
+                "aarch64-apple-darwin"
+            ]
+        );
+        assert!(
+            command
+                .get_envs()
+                .any(|(key, value)| key == "CARGO_TARGET_DIR"
+                    && value == Some(tmp.path().as_os_str()))
+        );
+    }
+}
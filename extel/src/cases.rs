@@ -0,0 +1,485 @@
+//! Running the cases of a parameterized test, optionally several at a time.
+//!
+//! Cases are independent by definition, so a parameterized test whose cases each shell out to a
+//! slow binary can run them concurrently with
+//! [`TestConfig::case_jobs`](crate::TestConfig::case_jobs). Every case still sees the
+//! [context](crate::context) of its test, and anything it records, such as commands and
+//! artifacts, ends up on the result of the test.
+//!
+//! ```rust
+//! use extel::{prelude::*, OutputDest};
+//! use extel_parameterized::parameters;
+//!
+//! #[parameters(1, 2, 3, 4)]
+//! fn slow_binary(n: u32) -> ExtelResult {
+//!     let output = cmd!("echo {}", n).output_checked()?;
+//!     extel_assert!(String::from_utf8(output.stdout)?.trim() == n.to_string())
+//! }
+//!
+//! init_test_suite!(CaseSuite, slow_binary_cases);
+//! let results = CaseSuite::run(TestConfig::default().output(OutputDest::None).case_jobs(4));
+//!
+//! assert!(results[0].test_result.is_ok());
+//! assert_eq!(results[0].commands.len(), 4);
+//! ```
+//!
+//! A case can also state the outcome it is expected to have by being written as
+//! `case(..., pass)`, `case(..., fail)`, or `case(..., fail("message"))`, so one function can
+//! cover both valid and invalid input. A case expected to fail passes when the function returns
+//! an error, and when a message is given, only if the error contains it. Cases without an
+//! expectation are expected to pass.
+//!
+//! ```rust
+//! use extel::prelude::*;
+//! use extel_parameterized::parameters;
+//!
+//! #[parameters(case(4, pass), case(-1, fail("negative")), 9)]
+//! fn square_root(x: i32) -> ExtelResult {
+//!     extel_assert!(x >= 0, "{} is negative", x)
+//! }
+//!
+//! assert!(square_root_cases().iter().all(|result| result.is_ok()));
+//! ```
+
+use std::{
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use crate::{
+    catch_panic, context, err,
+    errors::Error,
+    outcome::{CaseResult, OmittedCases, TestOutcome},
+    report, ExtelResult, Reporter,
+};
+
+/// A table of cases for a parameterized test, so that one table can be shared by several tests
+/// with `#[parameters(from = TABLE)]`. Implemented for slices and arrays, `Vec`s, and functions returning
+/// any of those.
+///
+/// # Example
+/// ```rust
+/// use extel::cases::IntoParams;
+///
+/// const SIZES: &[usize] = &[1, 8, 64];
+/// fn names() -> Vec<&'static str> {
+///     vec!["a", "b"]
+/// }
+///
+/// assert_eq!(SIZES.into_params(), [1, 8, 64]);
+/// assert_eq!(names.into_params(), ["a", "b"]);
+/// ```
+pub trait IntoParams {
+    /// The type of each case.
+    type Param;
+
+    /// Collect every case of the table.
+    fn into_params(self) -> Vec<Self::Param>;
+}
+
+impl<T: Clone> IntoParams for &[T] {
+    type Param = T;
+
+    fn into_params(self) -> Vec<T> {
+        self.to_vec()
+    }
+}
+
+impl<T: Clone, const N: usize> IntoParams for &[T; N] {
+    type Param = T;
+
+    fn into_params(self) -> Vec<T> {
+        self.to_vec()
+    }
+}
+
+impl<T, const N: usize> IntoParams for [T; N] {
+    type Param = T;
+
+    fn into_params(self) -> Vec<T> {
+        self.into()
+    }
+}
+
+impl<T> IntoParams for Vec<T> {
+    type Param = T;
+
+    fn into_params(self) -> Vec<T> {
+        self
+    }
+}
+
+impl<P: IntoParams, F: FnOnce() -> P> IntoParams for F {
+    type Param = P::Param;
+
+    fn into_params(self) -> Vec<P::Param> {
+        self().into_params()
+    }
+}
+
+/// The outcome a case of a parameterized test is expected to have.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expected {
+    /// The case is expected to return `Ok`.
+    Pass,
+    /// The case is expected to return an error, which must contain the message if one is given.
+    /// A panic never counts as the expected failure, and a skipped case stays skipped.
+    Fail(Option<String>),
+}
+
+impl Expected {
+    /// Expect the case to fail with an error containing `message`.
+    pub fn fail_with(message: impl Into<String>) -> Self {
+        Self::Fail(Some(message.into()))
+    }
+
+    /// Check the `result` of a case against the expected outcome, passing if they agree.
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::{cases::Expected, prelude::*};
+    ///
+    /// assert!(Expected::fail_with("negative").check(fail!("-1 is negative")).is_ok());
+    /// assert_eq!(
+    ///     Expected::Fail(None).check(pass!()).unwrap_err().to_string(),
+    ///     "expected the case to fail, but it passed"
+    /// );
+    /// ```
+    pub fn check(&self, result: ExtelResult) -> ExtelResult {
+        match (self, result) {
+            (Self::Pass, result) => result,
+            (Self::Fail(_), Err(e @ (Error::Panicked(_) | Error::Skipped(_)))) => Err(e),
+            (Self::Fail(None), Err(_)) => Ok(()),
+            (Self::Fail(Some(message)), Err(e)) => match e.to_string().contains(message.as_str()) {
+                true => Ok(()),
+                false => Err(err!(
+                    "expected the case to fail with {:?}, but it failed with: {}",
+                    message,
+                    e
+                )),
+            },
+            (Self::Fail(None), Ok(())) => Err(err!("expected the case to fail, but it passed")),
+            (Self::Fail(Some(message)), Ok(())) => Err(err!(
+                "expected the case to fail with {:?}, but it passed",
+                message
+            )),
+        }
+    }
+}
+
+/// Run `test` once with each of `cases`, returning the results in the order of `cases`. A panic
+/// in a case fails only that case. Up to [`case_jobs`](crate::TestConfig::case_jobs) cases run at
+/// the same time, each on its own thread.
+///
+/// This is what the `<name>_cases` functions generated by `#[parameters(...)]` call.
+pub fn run<C, F>(cases: impl IntoIterator<Item = C>, test: F) -> Vec<ExtelResult>
+where
+    C: Send,
+    F: Fn(C) -> ExtelResult + Sync,
+{
+    run_streamed(cases, |case| {
+        CaseResult::new(catch_panic(|| test(case)).and_then(|result| result))
+    })
+    .into_iter()
+    .map(|case| case.result)
+    .collect()
+}
+
+/// Like [`run`], but each case comes with its [expected](Expected) outcome, and a case passes
+/// when its result agrees with it.
+///
+/// This is what the `<name>_cases` functions generated by `#[parameters(...)]` call when any of
+/// the cases is written as `case(...)`.
+pub fn run_expected<C, F>(
+    cases: impl IntoIterator<Item = (C, Expected)>,
+    test: F,
+) -> Vec<ExtelResult>
+where
+    C: Send,
+    F: Fn(C) -> ExtelResult + Sync,
+{
+    run(cases, |(case, expected)| expected.check(test(case)))
+}
+
+/// Where the cases of the running test go when only a summary of them is kept, as set up by the
+/// runner for [`TestConfig::summarize_cases`](crate::TestConfig::summarize_cases). Every case is
+/// sent to the reporter as it finishes, and only the first failures are kept, so that a test with
+/// a huge number of cases does not hold all of their results in memory.
+pub(crate) struct CaseStream {
+    test_name: String,
+    reporter: Option<Arc<Mutex<dyn Reporter + Send>>>,
+    max_failures: usize,
+    state: Mutex<StreamState>,
+}
+
+#[derive(Default)]
+struct StreamState {
+    omitted: OmittedCases,
+    /// The 1-based numbers of the cases that were kept.
+    kept: Vec<usize>,
+}
+
+impl CaseStream {
+    pub(crate) fn new(
+        test_name: impl Into<String>,
+        reporter: Option<Arc<Mutex<dyn Reporter + Send>>>,
+        max_failures: usize,
+    ) -> Self {
+        Self {
+            test_name: test_name.into(),
+            reporter,
+            max_failures,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Report a finished case, returning it if it should be kept on the outcome of the test.
+    fn record(&self, case_num: usize, case: CaseResult) -> Option<CaseResult> {
+        if let Some(reporter) = &self.reporter {
+            report::lock(reporter).on_case_finish(&self.test_name, case_num, &case);
+        }
+
+        let mut state = self
+            .state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if !case.is_ok() && state.kept.len() < self.max_failures {
+            state.kept.push(case_num);
+            return Some(case);
+        }
+
+        match (case.is_skipped(), case.is_ok()) {
+            (true, _) => state.omitted.skipped += 1,
+            (false, true) => state.omitted.passed += 1,
+            (false, false) => state.omitted.failed += 1,
+        }
+        None
+    }
+
+    /// Add the cases that were not kept to `outcome`, and name the kept cases after their
+    /// position among all of the cases, unless they already have a name.
+    pub(crate) fn finish(&self, outcome: &mut TestOutcome) {
+        let state = std::mem::take(
+            &mut *self
+                .state
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()),
+        );
+
+        let mut kept = state.kept;
+        kept.sort_unstable();
+        if kept.len() == outcome.cases.len() {
+            for (case, case_num) in outcome.cases.iter_mut().zip(kept) {
+                case.name
+                    .get_or_insert_with(|| format!("case {}", case_num));
+            }
+        }
+        outcome.omitted = state.omitted;
+    }
+}
+
+/// Call `f` with each of `cases` like [`run_each`], or only with the case selected by
+/// [`TestConfig::exact`](crate::TestConfig::exact). When the running test only keeps a summary of
+/// its cases, each case is sent to its [`CaseStream`] as it finishes, and only the cases it keeps
+/// are returned.
+pub(crate) fn run_streamed<C, F>(cases: impl IntoIterator<Item = C>, f: F) -> Vec<CaseResult>
+where
+    C: Send,
+    F: Fn(C) -> CaseResult + Sync,
+{
+    let (stream, only_case) = context::with_scope(|scope| {
+        scope.map_or((None, None), |s| (s.case_stream.clone(), s.only_case))
+    });
+    let cases = cases
+        .into_iter()
+        .enumerate()
+        .filter(|(idx, _)| only_case.is_none_or(|case_num| case_num == idx + 1));
+    let Some(stream) = stream else {
+        return run_each(cases, |(_, case)| f(case));
+    };
+
+    run_each(cases, |(idx, case)| stream.record(idx + 1, f(case)))
+        .into_iter()
+        .flatten()
+        .collect()
+}
+
+/// Call `f` with each of `cases`, running up to the configured number of calls at the same time.
+pub(crate) fn run_each<C, T, F>(cases: impl IntoIterator<Item = C>, f: F) -> Vec<T>
+where
+    C: Send,
+    T: Send,
+    F: Fn(C) -> T + Sync,
+{
+    let cases: Vec<C> = cases.into_iter().collect();
+    let jobs = context::with_scope(|scope| scope.map_or(1, |s| s.case_jobs));
+    if jobs <= 1 || cases.len() <= 1 {
+        return cases.into_iter().map(f).collect();
+    }
+
+    let parent = context::with_scope(|scope| scope.map(|s| s.fork()));
+    let slots: Mutex<Vec<Option<T>>> = Mutex::new(cases.iter().map(|_| None).collect());
+    let workers = jobs.min(cases.len());
+    let queue = Mutex::new(cases.into_iter().enumerate());
+
+    let work = || loop {
+        let next = queue
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .next();
+        let Some((idx, case)) = next else {
+            break;
+        };
+
+        let value = f(case);
+        slots
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())[idx] = Some(value);
+    };
+
+    thread::scope(|threads| {
+        let handles: Vec<_> = (0..workers)
+            .map(|_| {
+                threads.spawn(|| match &parent {
+                    Some(scope) => context::scoped(scope.fork(), work).1,
+                    None => {
+                        work();
+                        Default::default()
+                    }
+                })
+            })
+            .collect();
+
+        for handle in handles {
+            match handle.join() {
+                Ok(recorded) => context::with_scope(|scope| {
+                    if let Some(scope) = scope {
+                        scope.recorded.merge(recorded);
+                    }
+                }),
+                Err(payload) => std::panic::resume_unwind(payload),
+            }
+        }
+    });
+
+    slots
+        .into_inner()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .into_iter()
+        .map(|slot| slot.expect("every case is run by a worker"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{context::TestContext, prelude::*, OutputDest, SuiteBuilder};
+    use std::sync::{Arc, Mutex};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn cases_run_concurrently_in_order() {
+        let start = Instant::now();
+        let results = SuiteBuilder::new("Parallel")
+            .add_param_test("sleepy", 0..8u64, |n| {
+                std::thread::sleep(Duration::from_millis(200));
+                TestContext::current().set_metadata(format!("case{}", n), n.to_string());
+                extel_assert!(n % 2 == 0, "{} is odd", n)
+            })
+            .run(TestConfig::default().output(OutputDest::None).case_jobs(8));
+
+        assert!(start.elapsed() < Duration::from_millis(1200));
+        let cases = &results[0].test_result.cases;
+        assert_eq!(cases.len(), 8);
+        for (n, case) in cases.iter().enumerate() {
+            assert_eq!(case.is_ok(), n % 2 == 0);
+        }
+        assert_eq!(results[0].metadata.len(), 8);
+    }
+
+    #[test]
+    fn panicking_case_fails_alone() {
+        let results = run([1, 2, 3], |n| match n {
+            2 => panic!("two"),
+            _ => pass!(),
+        });
+
+        assert!(results[0].is_ok() && results[2].is_ok());
+        assert!(
+            matches!(&results[1], Err(Error::Panicked(msg)) if msg.starts_with("two at src/cases.rs:"))
+        );
+    }
+
+    #[test]
+    fn cases_are_checked_against_expectations() {
+        let results = run_expected(
+            [
+                (1, Expected::Pass),
+                (-1, Expected::fail_with("negative")),
+                (-2, Expected::fail_with("too small")),
+                (3, Expected::Fail(None)),
+                (-4, Expected::Fail(None)),
+            ],
+            |n| match n {
+                0.. => pass!(),
+                _ => fail!("{} is negative", n),
+            },
+        );
+
+        assert!(results[0].is_ok() && results[1].is_ok() && results[4].is_ok());
+        assert_eq!(
+            results[2].as_ref().unwrap_err().to_string(),
+            "expected the case to fail with \"too small\", but it failed with: -2 is negative"
+        );
+        assert_eq!(
+            results[3].as_ref().unwrap_err().to_string(),
+            "expected the case to fail, but it passed"
+        );
+        assert!(matches!(
+            &run_expected([(4, Expected::Fail(None))], |_: i32| panic!("too big"))[0],
+            Err(Error::Panicked(_))
+        ));
+    }
+
+    #[derive(Default)]
+    struct CaseLog(Vec<(String, usize, bool)>);
+
+    impl crate::Reporter for CaseLog {
+        fn on_case_finish(&mut self, test_name: &str, case_num: usize, case: &CaseResult) {
+            self.0.push((test_name.to_string(), case_num, case.is_ok()));
+        }
+    }
+
+    #[test]
+    fn summarized_cases_are_streamed_and_sampled() {
+        let log = Arc::new(Mutex::new(CaseLog::default()));
+        let results = SuiteBuilder::new("Sampled")
+            .add_param_test("under_seven", 0..10, |n| {
+                extel_assert!(n < 7, "{} is too big", n)
+            })
+            .run(
+                TestConfig::default()
+                    .reporter(log.clone())
+                    .case_jobs(4)
+                    .summarize_cases(1),
+            );
+
+        let mut streamed = log.lock().unwrap().0.clone();
+        streamed.sort_by_key(|(_, case_num, _)| *case_num);
+        assert_eq!(streamed.len(), 10);
+        assert!(streamed.iter().all(|(name, _, _)| name == "under_seven"));
+        assert!(!streamed[7].2 && streamed[6].2);
+
+        let outcome = &results[0].test_result;
+        assert_eq!(outcome.cases.len(), 1);
+        assert_eq!(outcome.omitted.total(), 9);
+        assert_eq!((outcome.passed(), outcome.failed()), (7, 3));
+
+        let report = crate::SuiteResults::from(results).into_report();
+        assert!(
+            report.contains("\t  ... and 9 more case(s) of (under_seven): 7 passed; 2 failed\n"),
+            "{}",
+            report
+        );
+    }
+}
@@ -0,0 +1,339 @@
+//! Recording the commands a suite runs into a cassette file and replaying them later without
+//! running anything, for fast and hermetic reruns of suites that drive slow external services.
+//! See [`TestConfig::cassette`](crate::TestConfig::cassette).
+//!
+//! Only commands run through [`CommandExt`](crate::command::CommandExt) are recorded and replayed,
+//! except for [`output_with_usage`](crate::command::CommandExt::output_with_usage) and commands
+//! that time out. While replaying, each run of a command by a test is answered with the next
+//! recorded run of the same command by the same test, and a command that was never recorded fails
+//! the test.
+//!
+//! The cassette is a plain text file with one
+//! `suite name<TAB>test name<TAB>command<TAB>status<TAB>stdout<TAB>stderr` line per recorded run,
+//! where the status is an exit code or `signal N`, and every field is escaped like a Rust byte
+//! string. Recording a suite replaces the recordings of the tests that ran and keeps the others.
+
+use std::{
+    collections::{BTreeMap, HashMap, VecDeque},
+    fs, io,
+    path::{Path, PathBuf},
+    process::{ExitStatus, Output},
+    sync::Mutex,
+};
+
+use crate::{command::exit_status, err, errors::Error, TestResult};
+
+/// Whether a cassette is written to or read from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// Run commands as usual and record their results.
+    Record,
+    /// Answer commands with their recorded results instead of running them.
+    Replay,
+}
+
+/// A cassette file and what to do with it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cassette {
+    pub path: PathBuf,
+    pub mode: CassetteMode,
+}
+
+/// One recorded run of a command.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Entry {
+    suite: String,
+    test: String,
+    command: String,
+    status: RecordedStatus,
+    stdout: Vec<u8>,
+    stderr: Vec<u8>,
+}
+
+/// How a recorded command exited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RecordedStatus {
+    Code(i32),
+    Signal(i32),
+}
+
+/// A cassette being recorded or replayed by a suite run.
+#[derive(Debug)]
+pub(crate) struct Tape {
+    path: PathBuf,
+    mode: CassetteMode,
+    suite_name: String,
+    recorded: Mutex<BTreeMap<String, Vec<Entry>>>,
+    replayed: Mutex<HashMap<(String, String), VecDeque<Entry>>>,
+}
+
+impl Tape {
+    /// Start using `cassette` for a run of `suite_name`. A cassette that cannot be read has no
+    /// recordings.
+    pub(crate) fn open(cassette: &Cassette, suite_name: &str) -> Self {
+        let mut replayed: HashMap<_, VecDeque<_>> = HashMap::new();
+        if cassette.mode == CassetteMode::Replay {
+            for entry in read_entries(&cassette.path).unwrap_or_default() {
+                if entry.suite == suite_name {
+                    replayed
+                        .entry((entry.test.clone(), entry.command.clone()))
+                        .or_default()
+                        .push_back(entry);
+                }
+            }
+        }
+
+        Self {
+            path: cassette.path.clone(),
+            mode: cassette.mode,
+            suite_name: suite_name.to_string(),
+            recorded: Mutex::new(BTreeMap::new()),
+            replayed: Mutex::new(replayed),
+        }
+    }
+
+    /// The recorded output of the next run of `command` by `test_name`, or `None` if the tape is
+    /// being recorded.
+    pub(crate) fn replay(&self, test_name: &str, command: &str) -> Option<Result<Output, Error>> {
+        if self.mode != CassetteMode::Replay {
+            return None;
+        }
+
+        let mut replayed = self
+            .replayed
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let entry = replayed
+            .get_mut(&(test_name.to_string(), command.to_string()))
+            .and_then(VecDeque::pop_front);
+        Some(match entry {
+            Some(entry) => Ok(Output {
+                status: entry.status.into_exit_status(),
+                stdout: entry.stdout,
+                stderr: entry.stderr,
+            }),
+            None => Err(err!(
+                "no recording of `{}` left in cassette {}",
+                command,
+                self.path.display()
+            )),
+        })
+    }
+
+    /// Record that `test_name` ran `command` with `output`, if the tape is being recorded.
+    pub(crate) fn record(&self, test_name: &str, command: &str, output: &Output) {
+        if self.mode != CassetteMode::Record {
+            return;
+        }
+        let Some(status) = RecordedStatus::from_exit_status(output.status) else {
+            return;
+        };
+
+        self.recorded
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .entry(test_name.to_string())
+            .or_default()
+            .push(Entry {
+                suite: self.suite_name.clone(),
+                test: test_name.to_string(),
+                command: command.to_string(),
+                status,
+                stdout: output.stdout.clone(),
+                stderr: output.stderr.clone(),
+            });
+    }
+
+    /// Write the recordings of the tests in `results` to the cassette, replacing their previous
+    /// recordings, if the tape is being recorded.
+    pub(crate) fn save(&self, results: &[TestResult]) -> io::Result<()> {
+        if self.mode != CassetteMode::Record {
+            return Ok(());
+        }
+
+        let mut recorded = self
+            .recorded
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let mut entries = read_entries(&self.path)?;
+        entries.retain(|entry| {
+            entry.suite != self.suite_name
+                || !results
+                    .iter()
+                    .any(|result| result.test_name == entry.test.as_str())
+        });
+        for result in results {
+            entries.extend(
+                recorded
+                    .remove(result.test_name.as_ref())
+                    .unwrap_or_default(),
+            );
+        }
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(
+            &self.path,
+            entries.iter().map(write_entry).collect::<String>(),
+        )
+    }
+}
+
+impl RecordedStatus {
+    fn from_exit_status(status: ExitStatus) -> Option<Self> {
+        if let Some(code) = status.code() {
+            return Some(Self::Code(code));
+        }
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::process::ExitStatusExt;
+            status.signal().map(Self::Signal)
+        }
+        #[cfg(not(unix))]
+        None
+    }
+
+    fn into_exit_status(self) -> ExitStatus {
+        match self {
+            Self::Code(code) => exit_status(code),
+            #[cfg(unix)]
+            Self::Signal(signal) => {
+                use std::os::unix::process::ExitStatusExt;
+                ExitStatus::from_raw(signal & 0x7f)
+            }
+            #[cfg(not(unix))]
+            Self::Signal(signal) => exit_status(signal),
+        }
+    }
+}
+
+/// Read every entry of the cassette at `path`. A missing file has no entries, and malformed lines
+/// are skipped.
+fn read_entries(path: &Path) -> io::Result<Vec<Entry>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    Ok(contents.lines().filter_map(read_entry).collect())
+}
+
+fn read_entry(line: &str) -> Option<Entry> {
+    let mut fields = line.split('\t');
+    let mut text = || String::from_utf8(unescape(fields.next()?)?).ok();
+    let (suite, test, command) = (text()?, text()?, text()?);
+    let status = match fields.next()? {
+        status if status.starts_with("signal ") => {
+            RecordedStatus::Signal(status[7..].parse().ok()?)
+        }
+        status => RecordedStatus::Code(status.parse().ok()?),
+    };
+
+    Some(Entry {
+        suite,
+        test,
+        command,
+        status,
+        stdout: unescape(fields.next()?)?,
+        stderr: unescape(fields.next()?)?,
+    })
+}
+
+fn write_entry(entry: &Entry) -> String {
+    format!(
+        "{}\t{}\t{}\t{}\t{}\t{}\n",
+        entry.suite.as_bytes().escape_ascii(),
+        entry.test.as_bytes().escape_ascii(),
+        entry.command.as_bytes().escape_ascii(),
+        match entry.status {
+            RecordedStatus::Code(code) => code.to_string(),
+            RecordedStatus::Signal(signal) => format!("signal {}", signal),
+        },
+        entry.stdout.escape_ascii(),
+        entry.stderr.escape_ascii(),
+    )
+}
+
+/// Reverse [`escape_ascii`](slice::escape_ascii).
+fn unescape(field: &str) -> Option<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(field.len());
+    let mut chars = field.bytes();
+    while let Some(byte) = chars.next() {
+        if byte != b'\\' {
+            bytes.push(byte);
+            continue;
+        }
+
+        bytes.push(match chars.next()? {
+            b'n' => b'\n',
+            b'r' => b'\r',
+            b't' => b'\t',
+            b'x' => {
+                let hex = [chars.next()?, chars.next()?];
+                u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?
+            }
+            escaped => escaped,
+        });
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{context::TestContext, fixture::TempDir, prelude::*, OutputDest};
+
+    #[test]
+    fn fields_round_trip() {
+        let bytes = b"tab\tquote\"'\\ newline\n\r\xff\x00".to_vec();
+        let escaped = bytes.escape_ascii().to_string();
+        assert!(!escaped.contains(['\t', '\n']));
+        assert_eq!(unescape(&escaped), Some(bytes));
+        assert_eq!(unescape("trailing\\"), None);
+    }
+
+    fn counts_runs(ctx: &TestContext) -> ExtelResult {
+        let runs = ctx.var("runs").unwrap_or_default();
+        let script = format!("echo run >> {0}; wc -l < {0}; echo oops >&2; exit 3", runs);
+        let first = cmd!("sh" => ["-c", &script]).output_checked()?;
+        let second = cmd!("printf" => ["a\tb"]).output_with_stdin("ignored")?;
+
+        extel_assert_eq!(String::from_utf8_lossy(&first.stdout).trim(), "1")?;
+        extel_assert_eq!(first.stderr, b"oops\n")?;
+        extel_assert_eq!(first.status.code(), Some(3))?;
+        extel_assert_eq!(second.stdout, b"a\tb")
+    }
+
+    fn runs_unrecorded() -> ExtelResult {
+        cmd!("true").output_checked()?;
+        pass!()
+    }
+
+    #[test]
+    fn commands_are_replayed_without_running() {
+        let tmp = TempDir::new().unwrap();
+        let (path, runs) = (tmp.path().join("cassette"), tmp.path().join("runs"));
+        let cfg = |mode| {
+            TestConfig::default()
+                .output(OutputDest::None)
+                .var("runs", runs.display().to_string())
+                .cassette(&path, mode)
+        };
+
+        init_test_suite!(RecordedSuite, counts_runs);
+        let results = RecordedSuite::run(cfg(CassetteMode::Record));
+        assert!(results[0].test_result.is_ok(), "{:?}", results[0]);
+
+        // The command would count a second run if it ran again.
+        let results = RecordedSuite::run(cfg(CassetteMode::Replay));
+        assert!(results[0].test_result.is_ok(), "{:?}", results[0]);
+        assert_eq!(fs::read_to_string(&runs).unwrap(), "run\n");
+
+        init_test_suite!(UnrecordedSuite, runs_unrecorded);
+        let results = UnrecordedSuite::run(cfg(CassetteMode::Replay));
+        assert!(!results[0].test_result.is_ok());
+    }
+}
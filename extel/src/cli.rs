@@ -0,0 +1,319 @@
+//! Command-line argument parsing for test runner binaries, giving them a libtest-like interface
+//! without every `main` having to reinvent it.
+//!
+//! ```text
+//! USAGE: <runner> [OPTIONS] [FILTER]
+//! ```
+//!
+//! See [`USAGE`] for the supported options.
+
+use std::time::Duration;
+
+use crate::{
+    cassette::CassetteMode, errors::Error, style::Style, OutputDest, TestConfig, TestOrder,
+};
+
+/// The help text printed for `--help`.
+pub const USAGE: &str = "\
+USAGE: <runner> [OPTIONS] [FILTER]
+
+Run only the tests whose name contains FILTER, if one is given.
+
+OPTIONS:
+    --filter <PATTERN>      Run only tests whose name contains PATTERN
+    --exact <SELECTOR>      Run only the test `SUITE::TEST`, or only its case `SUITE::TEST#N`
+    --tag <TAG>             Run only tests with TAG (may be repeated)
+    --list                  List the selected tests instead of running them
+    --output <DEST>         Write results to `stdout`, `none`, or a file path, or as `json` events
+    --color                 Always use colored output
+    --no-color              Never use colored output
+    --max-failure-len <N>   Truncate failure messages longer than N bytes in the console output
+    --style <STYLE>         Show statuses in the `default`, `unicode`, or `plain` style
+    --align                 Line up test names and statuses in columns
+    --cluster-failures      Show cases failing with the same message pattern as one line
+    --case-jobs <N>         Run up to N cases of each parameterized test at the same time
+    --summarize-cases <N>   Keep only counts and the first N failures of each parameterized test
+    --progress              Show a single progress line when writing to a terminal
+    --shuffle               Run tests in a random order
+    --shuffle-seed <SEED>   Run tests in the random order produced by SEED
+    --sort                  Run tests in alphabetical order
+    --failed-first          Run tests that failed in the previous run first (needs --history)
+    --slowest-first         Run the slowest tests of the previous run first (needs --history)
+    --state-file <PATH>     Record the tests that fail in PATH
+    --rerun-failed          Run only the tests that failed in the previous run (needs --state-file)
+    --artifacts-dir <DIR>   Store test artifacts under DIR
+    --var <KEY=VALUE>       Set a variable readable through the test context (may be repeated)
+    --verbose               Enable verbose logging in tests
+    --capture-logs          Capture log records emitted by each test (`log` or `tracing` feature)
+    --isolate-env           Restore environment variables and the working directory after each test
+    --isolate-process       Run each test in its own process
+    --wrap-command <CMD>    Run every command under CMD, such as `valgrind --error-exitcode=99`
+    --command-args <SPEC>   Add arguments to commands, as PROGRAM=ARGS (`*` for every program)
+    --executor <EXECUTOR>   Run every command with EXECUTOR, such as `docker:db` or `ssh:host`
+    --record <PATH>         Record the results of commands into the cassette at PATH
+    --replay <PATH>         Replay the results of commands from the cassette at PATH
+    --dry-run               Print the commands tests would run instead of running them
+    --qualified-names       Prefix test names with the module they were registered in
+    --tee                   Stream the output of commands to the console as they run
+    --suite-timeout <SECS>  Stop running tests once the suite has run for SECS seconds
+    --manifest <PATH>       Write a JSON Lines manifest of the tests and commands that ran to PATH
+    --allure-dir <DIR>      Write Allure result files to DIR
+    --run-info              Print the run ID, start time, host, and OS before the results
+    --report-env <NAME>     Record environment variable NAME with the run (may be repeated)
+    --history <PATH>        Report changes since the previous run recorded in PATH
+    --slow-threshold <X>    Flag tests taking over X times as long as in the previous run
+    --shard <INDEX/TOTAL>   Run only the tests in shard INDEX (from 0) of TOTAL
+    -h, --help              Print this help text";
+
+/// Build a [`TestConfig`] from the arguments the current process was started with. `--help`
+/// prints [`USAGE`] and exits, and invalid arguments print an error and exit with status 2.
+///
+/// # Example
+/// ```rust,no_run
+/// use extel::prelude::*;
+///
+/// fn always_succeed() -> ExtelResult {
+///     pass!()
+/// }
+///
+/// init_test_suite!(CliSuite, always_succeed);
+///
+/// fn main() {
+///     CliSuite::run(extel::cli::parse_args());
+/// }
+/// ```
+pub fn parse_args() -> TestConfig {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        std::process::exit(0);
+    }
+
+    match parse_from(args) {
+        Ok(cfg) => cfg,
+        Err(e) => {
+            eprintln!("error: {}\n\n{}", e, USAGE);
+            std::process::exit(crate::harness::EXIT_USAGE.into());
+        }
+    }
+}
+
+/// Build a [`TestConfig`] from `args`, which should not include the program name. Options that
+/// are not given keep their [default](TestConfig::default) values.
+///
+/// # Example
+/// ```rust
+/// use extel::{cli, TestOrder};
+///
+/// let cfg = cli::parse_from(["--no-color", "--shuffle-seed", "7", "http_"]).unwrap();
+/// assert_eq!(cfg.colored, Some(false));
+/// assert_eq!(cfg.order, TestOrder::Shuffled(7));
+/// assert_eq!(cfg.filter.as_deref(), Some("http_"));
+///
+/// assert!(cli::parse_from(["--frobnicate"]).is_err());
+/// ```
+pub fn parse_from<I, S>(args: I) -> Result<TestConfig, Error>
+where
+    I: IntoIterator<Item = S>,
+    S: Into<String>,
+{
+    let mut cfg = TestConfig::default();
+    let mut args = args.into_iter().map(Into::into);
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| Error::Config(format!("'{}' requires a value", arg)))
+        };
+
+        match arg.as_str() {
+            "--filter" => cfg.filter = Some(value()?),
+            "--exact" => cfg.exact = Some(value()?),
+            "--tag" => cfg.tags.push(value()?),
+            "--list" => cfg.list = true,
+            "--output" => cfg.output = OutputDest::parse(&value()?),
+            "--color" => cfg.colored = Some(true),
+            "--no-color" => cfg.colored = Some(false),
+            "--align" => cfg.align = true,
+            "--cluster-failures" => cfg.cluster_failures = true,
+            "--case-jobs" => {
+                cfg.case_jobs = value()?
+                    .parse()
+                    .map_err(|_| Error::Config(String::from("'--case-jobs' must be a number")))?;
+            }
+            "--summarize-cases" => {
+                cfg.summarize_cases = Some(value()?.parse().map_err(|_| {
+                    Error::Config(String::from("'--summarize-cases' must be a number"))
+                })?);
+            }
+            "--max-failure-len" => {
+                cfg.max_failure_len = Some(value()?.parse().map_err(|_| {
+                    Error::Config(String::from("'--max-failure-len' must be a number"))
+                })?);
+            }
+            "--style" => {
+                let name = value()?;
+                cfg.style = Style::named(&name)
+                    .ok_or_else(|| Error::Config(format!("unknown style '{}'", name)))?;
+            }
+            "--progress" => cfg.progress = true,
+            "--shuffle" => cfg = cfg.shuffle(None),
+            "--shuffle-seed" => {
+                let seed = value()?.parse().map_err(|_| {
+                    Error::Config(String::from("'--shuffle-seed' must be a number"))
+                })?;
+                cfg = cfg.shuffle(Some(seed));
+            }
+            "--sort" => cfg = cfg.sort_by_name(),
+            "--failed-first" => cfg = cfg.order(TestOrder::FailedFirst),
+            "--slowest-first" => cfg = cfg.order(TestOrder::SlowestFirst),
+            "--state-file" => cfg.state_file = Some(value()?.into()),
+            "--rerun-failed" => cfg.rerun_failed = true,
+            "--artifacts-dir" => cfg.artifacts_dir = Some(value()?.into()),
+            "--var" => {
+                let pair = value()?;
+                let (key, val) = pair
+                    .split_once('=')
+                    .ok_or_else(|| Error::Config(String::from("'--var' must be KEY=VALUE")))?;
+                cfg.vars.insert(key.to_string(), val.to_string());
+            }
+            "--verbose" => cfg.verbose = true,
+            #[cfg(any(feature = "log", feature = "tracing"))]
+            "--capture-logs" => cfg.capture_logs = true,
+            "--isolate-env" => cfg.isolate_env = true,
+            "--isolate-process" => cfg.isolate_process = true,
+            "--wrap-command" => cfg = cfg.wrap_command(&value()?),
+            "--command-args" => {
+                let pair = value()?;
+                let (program, args) = pair.split_once('=').ok_or_else(|| {
+                    Error::Config(String::from("'--command-args' must be PROGRAM=ARGS"))
+                })?;
+                cfg = cfg.command_args(program, args.split_whitespace());
+            }
+            "--executor" => cfg.executor = Some(crate::executor::parse(&value()?)?),
+            "--record" => cfg = cfg.cassette(value()?, CassetteMode::Record),
+            "--replay" => cfg = cfg.cassette(value()?, CassetteMode::Replay),
+            "--dry-run" => cfg.dry_run = true,
+            "--qualified-names" => cfg.qualified_names = true,
+            "--tee" => cfg.tee_commands = true,
+            "--manifest" => cfg.manifest = Some(value()?.into()),
+            "--allure-dir" => cfg.allure_dir = Some(value()?.into()),
+            "--run-info" => cfg.run_info = true,
+            "--report-env" => cfg.report_env.push(value()?),
+            "--history" => cfg.history = Some(value()?.into()),
+            "--shard" => cfg.shard = Some(value()?.parse()?),
+            "--slow-threshold" => {
+                cfg.slow_threshold = value()?.parse().map_err(|_| {
+                    Error::Config(String::from("'--slow-threshold' must be a number"))
+                })?;
+            }
+            "--suite-timeout" => {
+                let secs: f64 = value()?.parse().map_err(|_| {
+                    Error::Config(String::from(
+                        "'--suite-timeout' must be a number of seconds",
+                    ))
+                })?;
+                cfg.suite_timeout = Some(Duration::try_from_secs_f64(secs).map_err(|_| {
+                    Error::Config(String::from(
+                        "'--suite-timeout' must be a number of seconds",
+                    ))
+                })?);
+            }
+            flag if flag.starts_with('-') => {
+                return Err(Error::Config(format!("unknown flag '{}'", flag)));
+            }
+            _ => cfg.filter = Some(arg),
+        }
+    }
+
+    Ok(cfg)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::{prelude::*, SharedBuffer};
+
+    fn always_succeed() -> ExtelResult {
+        pass!()
+    }
+
+    fn always_fail() -> ExtelResult {
+        fail!("this test failed?")
+    }
+
+    #[test]
+    fn parse_from_reads_flags() {
+        let cfg = parse_from([
+            "--tag",
+            "smoke",
+            "--tag",
+            "fast",
+            "--var",
+            "server=localhost:8080",
+            "--list",
+            "--sort",
+            "--case-jobs",
+            "8",
+            "--suite-timeout",
+            "90",
+            "--command-args",
+            "app=--config test.toml",
+            "--report-env",
+            "CI",
+            "--report-env",
+            "GIT_COMMIT",
+            "--output",
+            "json",
+            "--state-file",
+            "target/last-run",
+        ])
+        .unwrap();
+
+        assert_eq!(cfg.tags, vec!["smoke", "fast"]);
+        assert_eq!(cfg.vars["server"], "localhost:8080");
+        assert!(cfg.list);
+        assert_eq!(cfg.order, TestOrder::Alphabetical);
+        assert_eq!(cfg.case_jobs, 8);
+        assert_eq!(cfg.suite_timeout, Some(Duration::from_secs(90)));
+        assert_eq!(cfg.state_file, Some(PathBuf::from("target/last-run")));
+        assert_eq!(cfg.command_args["app"], ["--config", "test.toml"]);
+        assert_eq!(cfg.report_env, vec!["CI", "GIT_COMMIT"]);
+        assert!(matches!(cfg.output, OutputDest::Json));
+    }
+
+    #[test]
+    fn parse_from_rejects_invalid_args() {
+        let err = parse_from(["--filter"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid configuration: '--filter' requires a value"
+        );
+
+        let err = parse_from(["--var", "server"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid configuration: '--var' must be KEY=VALUE"
+        );
+    }
+
+    #[test]
+    fn list_prints_selected_tests() {
+        init_test_suite!(ListSuite as "list", always_succeed, always_fail);
+
+        let output_buffer = SharedBuffer::new();
+        let results = ListSuite::run(
+            parse_from(["--list", "--sort", "--no-color"])
+                .unwrap()
+                .output(OutputDest::Buffer(output_buffer.clone())),
+        );
+
+        assert!(results.is_empty());
+        assert_eq!(
+            output_buffer.to_string_lossy(),
+            "[list]\n\talways_fail\n\talways_succeed\n"
+        );
+    }
+}
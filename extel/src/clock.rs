@@ -0,0 +1,426 @@
+//! Running commands against a fake clock, for testing programs that print or compare timestamps
+//! without matching their output with regular expressions.
+//!
+//! A [`FakeClock`] tells a command what time it is in one of two ways:
+//!
+//! - By setting environment variables, such as `SOURCE_DATE_EPOCH`, to the number of seconds since
+//!   the Unix epoch. This only works for programs that read them, but needs nothing installed.
+//! - By running the command under [`faketime`](https://github.com/wolfcw/libfaketime), which
+//!   changes the time every program sees. See [`FakeClock::faketime`].
+//!
+//! Either way, the command runs with `TZ=UTC`, so the times it prints do not depend on the time
+//! zone of the machine. Programs still take time to run and may round or truncate what they
+//! print, so timestamps in their output are checked with a [tolerance](FakeClock::tolerance)
+//! rather than for equality.
+//!
+//! ```rust,no_run
+//! use extel::{clock::FakeClock, prelude::*};
+//!
+//! fn prints_the_date() -> ExtelResult {
+//!     let clock = FakeClock::parse("2024-05-01T12:30:00Z")?.faketime(true);
+//!     let output = clock.command("date", ["+%FT%T"]).output_checked()?;
+//!     clock.assert_output(&String::from_utf8(output.stdout)?)
+//! }
+//! ```
+
+use std::{
+    ffi::OsStr,
+    ops::Range,
+    process::Command,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{command, err, errors::Error, run_info, ExtelResult};
+
+/// The environment variable set to the time of a [`FakeClock`] unless others are chosen with
+/// [`FakeClock::env`], as read by reproducible build tools.
+pub const EPOCH_ENV: &str = "SOURCE_DATE_EPOCH";
+
+/// How far a timestamp may be from the time of a [`FakeClock`] unless another
+/// [tolerance](FakeClock::tolerance) is set.
+pub const DEFAULT_TOLERANCE: Duration = Duration::from_secs(2);
+
+/// A fixed point in time that commands are run at.
+///
+/// # Example
+/// ```rust
+/// use extel::clock::FakeClock;
+/// use std::time::Duration;
+///
+/// let mut clock = FakeClock::at_secs(1_714_566_600);
+/// assert_eq!(clock.timestamp(), "2024-05-01T12:30:00Z");
+///
+/// clock.advance(Duration::from_secs(90));
+/// assert_eq!(clock.timestamp(), "2024-05-01T12:31:30Z");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FakeClock {
+    now: SystemTime,
+    env_vars: Vec<String>,
+    faketime: bool,
+    frozen: bool,
+    tolerance: Duration,
+}
+
+impl FakeClock {
+    /// A clock set to `time`.
+    pub fn at(time: SystemTime) -> Self {
+        Self {
+            now: time,
+            env_vars: vec![String::from(EPOCH_ENV)],
+            faketime: false,
+            frozen: false,
+            tolerance: DEFAULT_TOLERANCE,
+        }
+    }
+
+    /// A clock set to `secs` seconds after the Unix epoch.
+    pub fn at_secs(secs: u64) -> Self {
+        Self::at(UNIX_EPOCH + Duration::from_secs(secs))
+    }
+
+    /// A clock set to the RFC 3339 `timestamp`, such as `2024-05-01T12:30:00Z`.
+    ///
+    /// # Errors
+    /// Fails if `timestamp` is not a valid timestamp. See [`parse_timestamp`].
+    pub fn parse(timestamp: &str) -> Result<Self, Error> {
+        parse_timestamp(timestamp)
+            .map(Self::at)
+            .ok_or_else(|| Error::Config(format!("invalid timestamp '{}'", timestamp)))
+    }
+
+    /// Also set the environment variable `name` to the time of the clock, in seconds since the
+    /// Unix epoch. Only [`EPOCH_ENV`] is set by default.
+    pub fn env(mut self, name: impl Into<String>) -> Self {
+        self.env_vars.push(name.into());
+        self
+    }
+
+    /// Whether or not to run commands under `faketime`, which must be installed. Defaults to
+    /// `false`.
+    pub fn faketime(mut self, faketime: bool) -> Self {
+        self.faketime = faketime;
+        self
+    }
+
+    /// Whether or not the time seen by commands run under [`faketime`](Self::faketime) stands
+    /// still instead of starting at the time of the clock and moving on from there. Defaults to
+    /// `false`, since some programs wait forever for time to pass.
+    pub fn frozen(mut self, frozen: bool) -> Self {
+        self.frozen = frozen;
+        self
+    }
+
+    /// How far timestamps may be from the time of the clock in
+    /// [`assert_near`](Self::assert_near) and [`assert_output`](Self::assert_output). Defaults to
+    /// [`DEFAULT_TOLERANCE`].
+    pub fn tolerance(mut self, tolerance: Duration) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+
+    /// Move the clock forward by `duration`, for commands created afterwards.
+    pub fn advance(&mut self, duration: Duration) {
+        self.now += duration;
+    }
+
+    /// The time of the clock.
+    pub fn now(&self) -> SystemTime {
+        self.now
+    }
+
+    /// The time of the clock in seconds since the Unix epoch.
+    pub fn epoch_secs(&self) -> u64 {
+        self.now
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs()
+    }
+
+    /// The time of the clock in UTC, formatted like `2024-05-01T12:30:00Z`.
+    pub fn timestamp(&self) -> String {
+        run_info::timestamp(self.now)
+    }
+
+    /// Set the environment of `command` to the time of the clock. Commands that are not run under
+    /// [`faketime`](Self::faketime) can be created some other way and set up with this.
+    pub fn apply<'a>(&self, command: &'a mut Command) -> &'a mut Command {
+        let secs = self.epoch_secs().to_string();
+        for name in &self.env_vars {
+            command.env(name, &secs);
+        }
+        command.env("TZ", "UTC")
+    }
+
+    /// Create a [`Command`] running `program` with `args` at the time of the clock. Commands are
+    /// built like [`cmd!`](crate::cmd) builds them, under the wrapper, extra arguments, and
+    /// executor of the current test.
+    pub fn command<I, S>(&self, program: impl AsRef<OsStr>, args: I) -> Command
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let mut command = match self.faketime {
+            true => {
+                let mut argv = vec![
+                    OsStr::new("-f").to_os_string(),
+                    self.faketime_spec().into(),
+                    program.as_ref().to_os_string(),
+                ];
+                argv.extend(args.into_iter().map(|arg| arg.as_ref().to_os_string()));
+                command::build("faketime", argv)
+            }
+            false => command::build(program, args),
+        };
+        self.apply(&mut command);
+        command
+    }
+
+    /// The time of the clock in the format of `faketime -f`, where a leading `@` starts the clock
+    /// at that time instead of freezing it.
+    fn faketime_spec(&self) -> String {
+        let timestamp = self.timestamp().replace('T', " ").replace('Z', "");
+        match self.frozen {
+            true => timestamp,
+            false => format!("@{}", timestamp),
+        }
+    }
+
+    /// Check that `actual` is within the [tolerance](Self::tolerance) of the time of the clock.
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::clock::FakeClock;
+    /// use std::time::Duration;
+    ///
+    /// let clock = FakeClock::at_secs(1_714_566_600);
+    /// assert!(clock.assert_near(clock.now() + Duration::from_secs(1)).is_ok());
+    /// assert_eq!(
+    ///     clock.assert_near(clock.now() - Duration::from_secs(60)).unwrap_err().to_string(),
+    ///     "2024-05-01T12:29:00Z is 60s from the fake time 2024-05-01T12:30:00Z, more than the \
+    ///      tolerance of 2s"
+    /// );
+    /// ```
+    pub fn assert_near(&self, actual: SystemTime) -> ExtelResult {
+        assert_near(actual, self.now, self.tolerance)
+    }
+
+    /// Check that `output` has at least one RFC 3339 timestamp, and that every timestamp in it is
+    /// within the [tolerance](Self::tolerance) of the time of the clock.
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::clock::FakeClock;
+    ///
+    /// let clock = FakeClock::parse("2024-05-01T12:30:00Z").unwrap();
+    /// assert!(clock.assert_output("created 2024-05-01 12:30:01.250 by ci").is_ok());
+    /// assert!(clock.assert_output("created 2024-05-01T14:30:00+02:00").is_ok());
+    /// assert!(clock.assert_output("created 2024-05-02T12:30:00Z").is_err());
+    /// assert!(clock.assert_output("created just now").is_err());
+    /// ```
+    pub fn assert_output(&self, output: &str) -> ExtelResult {
+        let found = timestamps(output);
+        if found.is_empty() {
+            return Err(err!(
+                "expected a timestamp near {} in the output, found none:\n{}",
+                self.timestamp(),
+                output.trim_end()
+            ));
+        }
+
+        found
+            .into_iter()
+            .try_for_each(|actual| self.assert_near(actual))
+    }
+}
+
+/// Check that `actual` is within `tolerance` of `expected`, in either direction.
+pub fn assert_near(actual: SystemTime, expected: SystemTime, tolerance: Duration) -> ExtelResult {
+    let skew = match actual.duration_since(expected) {
+        Ok(ahead) => ahead,
+        Err(behind) => behind.duration(),
+    };
+
+    match skew <= tolerance {
+        true => Ok(()),
+        false => Err(err!(
+            "{} is {:?} from the fake time {}, more than the tolerance of {:?}",
+            run_info::timestamp(actual),
+            skew,
+            run_info::timestamp(expected),
+            tolerance
+        )),
+    }
+}
+
+/// Parse an RFC 3339 timestamp, such as `2024-05-01T12:30:00Z` or
+/// `2024-05-01 14:30:00.5+02:00`. Timestamps without an offset are taken to be in UTC. Times
+/// before the Unix epoch are not supported.
+///
+/// # Example
+/// ```rust
+/// use extel::clock::parse_timestamp;
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// let expected = UNIX_EPOCH + Duration::from_secs(1_714_566_600);
+/// assert_eq!(parse_timestamp("2024-05-01T12:30:00Z"), Some(expected));
+/// assert_eq!(parse_timestamp("2024-05-01T08:30:00-04:00"), Some(expected));
+/// assert_eq!(parse_timestamp("2024-05-01"), None);
+/// ```
+pub fn parse_timestamp(timestamp: &str) -> Option<SystemTime> {
+    match parse_prefix(timestamp.as_bytes()) {
+        Some((time, len)) if len == timestamp.len() => Some(time),
+        _ => None,
+    }
+}
+
+/// Every timestamp in `text` that [`parse_timestamp`] understands, in order.
+fn timestamps(text: &str) -> Vec<SystemTime> {
+    find_timestamps(text)
+        .into_iter()
+        .map(|(_, time)| time)
+        .collect()
+}
+
+/// Every timestamp in `text` that [`parse_timestamp`] understands along with where it is, in
+/// order.
+pub(crate) fn find_timestamps(text: &str) -> Vec<(Range<usize>, SystemTime)> {
+    let bytes = text.as_bytes();
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let starts_number = bytes[i].is_ascii_digit() && (i == 0 || !bytes[i - 1].is_ascii_digit());
+        match parse_prefix(&bytes[i..]).filter(|_| starts_number) {
+            Some((time, len)) => {
+                found.push((i..i + len, time));
+                i += len;
+            }
+            None => i += 1,
+        }
+    }
+    found
+}
+
+/// Parse the timestamp at the start of `bytes`, returning it and its length in bytes.
+fn parse_prefix(bytes: &[u8]) -> Option<(SystemTime, usize)> {
+    let number = |at: usize, len: usize| -> Option<i64> {
+        let digits = bytes.get(at..at + len)?;
+        match digits.iter().all(u8::is_ascii_digit) {
+            true => std::str::from_utf8(digits).ok()?.parse().ok(),
+            false => None,
+        }
+    };
+    let byte_is = |at: usize, expected: &[u8]| bytes.get(at).is_some_and(|b| expected.contains(b));
+
+    let separators = [(4, b"-"), (7, b"-"), (13, b":"), (16, b":")];
+    if !separators.iter().all(|(at, sep)| byte_is(*at, *sep)) || !byte_is(10, b"Tt ") {
+        return None;
+    }
+    let (year, month, day) = (number(0, 4)?, number(5, 2)?, number(8, 2)?);
+    let (hour, minute, second) = (number(11, 2)?, number(14, 2)?, number(17, 2)?);
+    // A leap second of 60 is counted as the first second of the next minute.
+    if !(1..=12).contains(&month)
+        || !(1..=31).contains(&day)
+        || hour > 23
+        || minute > 59
+        || second > 60
+    {
+        return None;
+    }
+
+    let mut len = 19;
+    let mut nanos = 0;
+    let digits = bytes[len..]
+        .iter()
+        .skip(1)
+        .take_while(|b| b.is_ascii_digit())
+        .count();
+    if byte_is(len, b".,") && digits > 0 {
+        for (i, digit) in bytes[len + 1..len + 1 + digits.min(9)].iter().enumerate() {
+            nanos += u32::from(digit - b'0') * 10u32.pow(8 - i as u32);
+        }
+        len += 1 + digits;
+    }
+
+    let mut offset = 0;
+    if byte_is(len, b"Zz") {
+        len += 1;
+    } else if byte_is(len, b"+-") && byte_is(len + 3, b":") {
+        let (hours, minutes) = (number(len + 1, 2)?, number(len + 4, 2)?);
+        offset = (hours * 60 + minutes) * 60;
+        if bytes[len] == b'-' {
+            offset = -offset;
+        }
+        len += 6;
+    }
+
+    let secs =
+        days_from_civil(year, month, day) * 86_400 + hour * 3_600 + minute * 60 + second - offset;
+    let secs = u64::try_from(secs).ok()?;
+    Some((UNIX_EPOCH + Duration::new(secs, nanos), len))
+}
+
+/// Days since the Unix epoch of a civil date, after Howard Hinnant's `days_from_civil`.
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = year - i64::from(month <= 2);
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let doy = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn timestamps_round_trip_through_formatting() {
+        for secs in [0, 951_782_400, 1_714_566_600, 4_102_444_799] {
+            let time = UNIX_EPOCH + Duration::from_secs(secs);
+            assert_eq!(parse_timestamp(&run_info::timestamp(time)), Some(time));
+        }
+
+        let found = timestamps(
+            "[2024-05-01T12:30:00.5Z] retried at 2024-05-01 12:30:02, id 12024-05-01T00:00:00",
+        );
+        assert_eq!(
+            found,
+            [
+                UNIX_EPOCH + Duration::new(1_714_566_600, 500_000_000),
+                UNIX_EPOCH + Duration::from_secs(1_714_566_602)
+            ]
+        );
+    }
+
+    #[test]
+    fn commands_see_the_fake_time() {
+        let mut clock = FakeClock::at_secs(1_714_566_600).env("APP_NOW");
+        clock.advance(Duration::from_secs(60));
+
+        let command = clock.command("app", ["--today"]);
+        assert_eq!(command.get_program(), "app");
+        assert!(command
+            .get_envs()
+            .any(|(key, value)| key == "APP_NOW" && value == Some(OsStr::new("1714566660"))));
+        assert!(command
+            .get_envs()
+            .any(|(key, value)| key == "TZ" && value == Some(OsStr::new("UTC"))));
+
+        let command = clock.clone().faketime(true).command("app", ["--today"]);
+        assert_eq!(command.get_program(), "faketime");
+        assert_eq!(
+            command.get_args().collect::<Vec<_>>(),
+            ["-f", "@2024-05-01 12:31:00", "app", "--today"]
+        );
+
+        let command = clock
+            .faketime(true)
+            .frozen(true)
+            .command("app", ["--today"]);
+        assert_eq!(
+            command.get_args().nth(1),
+            Some(OsStr::new("2024-05-01 12:31:00"))
+        );
+    }
+}
@@ -0,0 +1,1299 @@
+//! Extensions for running [`Command`]s built with [`cmd!`](crate::cmd).
+
+use std::{
+    collections::HashMap,
+    ffi::{OsStr, OsString},
+    io::{Read, Write},
+    path::{Path, PathBuf},
+    process::{Child, Command, ExitStatus, Output, Stdio},
+    sync::{Arc, Mutex},
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+#[cfg(all(feature = "resource-usage", unix))]
+use crate::usage::ResourceUsage;
+use crate::{
+    bytes, context,
+    diff::{abbreviate_tail, line_diff, MESSAGE_LIMIT},
+    err,
+    errors::Error,
+    executor::{CommandExecutor, Local},
+    report::shell_quote,
+    ExtelResult,
+};
+
+/// How often a running child is polled while waiting for it to exit.
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/// The environment variable set on commands [built](build) under a [wrapper](CommandWrapper), so
+/// that only their exit codes are checked for errors the wrapper found. [`Command`] has nowhere
+/// else to keep the mark.
+const WRAPPED_ENV: &str = "EXTEL_WRAPPED";
+
+/// An extension trait adding Extel-specific ways of running a [`Command`].
+pub trait CommandExt {
+    /// Run the command to completion like [`Command::output`], capturing stdout and stderr, but
+    /// kill it if it runs longer than `timeout`. A killed command results in an
+    /// [`Error::Timeout`] containing whatever output was captured before it was killed.
+    ///
+    /// Unlike [`Command::output`], stdin is left as configured on the command.
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::{command::CommandExt, prelude::*};
+    /// use std::time::Duration;
+    ///
+    /// fn hangs() -> ExtelResult {
+    ///     let output = cmd!("sleep 10").output_with_timeout(Duration::from_millis(100))?;
+    ///     extel_assert!(output.status.success())
+    /// }
+    ///
+    /// assert!(matches!(hangs(), Err(Error::Timeout { .. })));
+    /// ```
+    fn output_with_timeout(&mut self, timeout: Duration) -> Result<Output, Error>;
+
+    /// Run the command to completion like [`Command::output`], but report a command killed by a
+    /// signal as an [`Error::CommandCrashed`] instead of an unsuccessful exit. Every other way of
+    /// running a command on this trait does the same.
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::{command::CommandExt, prelude::*};
+    ///
+    /// # #[cfg(unix)]
+    /// assert!(matches!(
+    ///     cmd!("sh" => ["-c", "kill -SEGV $$"]).output_checked(),
+    ///     Err(Error::CommandCrashed { signal: 11, .. })
+    /// ));
+    /// ```
+    fn output_checked(&mut self) -> Result<Output, Error>;
+
+    /// Run the command to completion like [`Command::output`], writing `input` to its stdin and
+    /// then closing it. A command that exits without reading all of its input is not an error.
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::{command::CommandExt, prelude::*};
+    ///
+    /// let output = cmd!("wc -l").output_with_stdin("1\n2\n").unwrap();
+    /// assert_eq!(String::from_utf8_lossy(&output.stdout).trim(), "2");
+    /// ```
+    fn output_with_stdin(&mut self, input: impl AsRef<[u8]>) -> Result<Output, Error>;
+
+    /// Run the command with `input` on its stdin and check that it succeeds with exactly
+    /// `expected_stdout` on stdout, failing with a line diff otherwise. Paired with a
+    /// parameterized test, this checks a stream-processing binary against many inputs.
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::{command::CommandExt, prelude::*};
+    /// # #[cfg(feature = "parameterized")]
+    /// use extel_parameterized::parameters;
+    ///
+    /// # #[cfg(feature = "parameterized")]
+    /// #[parameters(("b\na\n", "a\nb\n"), ("", ""))]
+    /// fn sorts(input: &str, expected: &str) -> ExtelResult {
+    ///     cmd!("sort").expect_stdout(input, expected)
+    /// }
+    ///
+    /// # #[cfg(feature = "parameterized")]
+    /// assert!(sorts_cases().iter().all(Result::is_ok));
+    /// ```
+    fn expect_stdout(
+        &mut self,
+        input: impl AsRef<[u8]>,
+        expected_stdout: impl AsRef<str>,
+    ) -> ExtelResult;
+
+    /// Run the command to completion and check that it exits with `code`, failing with the code
+    /// it exited with and the end of its stderr otherwise. A command killed by a signal fails with
+    /// an [`Error::CommandCrashed`], like [`output_checked`](CommandExt::output_checked). Paired
+    /// with a parameterized test, this checks how a binary rejects many kinds of bad input. See
+    /// [`expect_exit`](crate::expect_exit).
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::{command::CommandExt, prelude::*};
+    /// # #[cfg(feature = "parameterized")]
+    /// use extel_parameterized::parameters;
+    ///
+    /// # #[cfg(feature = "parameterized")]
+    /// #[parameters(("0", 0), ("2", 2), ("64", 64))]
+    /// fn exits_with(code: &str, expected: i32) -> ExtelResult {
+    ///     cmd!("sh" => ["-c", &format!("exit {}", code)]).expect_exit(expected)
+    /// }
+    ///
+    /// # #[cfg(feature = "parameterized")]
+    /// assert!(exits_with_cases().iter().all(Result::is_ok));
+    /// ```
+    fn expect_exit(&mut self, code: i32) -> ExtelResult;
+
+    /// Run the command to completion like [`Command::output`], also measuring the peak memory and
+    /// CPU time it used. The measurement is recorded on the result of the test that ran the
+    /// command.
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::{command::CommandExt, prelude::*};
+    ///
+    /// fn lean() -> ExtelResult {
+    ///     let (output, usage) = cmd!("true").output_with_usage()?;
+    ///     extel_assert!(output.status.success() && usage.max_rss < 512 * 1024 * 1024)
+    /// }
+    ///
+    /// assert!(lean().is_ok());
+    /// ```
+    ///
+    /// > *This is only available with the `resource-usage` feature enabled, on Unix platforms.*
+    #[cfg(all(feature = "resource-usage", unix))]
+    fn output_with_usage(&mut self) -> Result<(Output, ResourceUsage), Error>;
+
+    /// Run the command attached to a new pseudo-terminal instead of pipes, so that it behaves
+    /// the way it would for a user at a terminal. See [`PtyCommand`](crate::pty::PtyCommand).
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::{command::CommandExt, prelude::*};
+    ///
+    /// let output = cmd!("echo hello").with_pty().output().unwrap();
+    /// assert_eq!(output.stdout, b"hello\r\n");
+    /// ```
+    ///
+    /// > *This is only available with the `pty` feature enabled, on Unix platforms.*
+    #[cfg(all(feature = "pty", unix))]
+    fn with_pty(&mut self) -> crate::pty::PtyCommand<'_>;
+}
+
+impl CommandExt for Command {
+    fn output_with_timeout(&mut self, timeout: Duration) -> Result<Output, Error> {
+        let started = Instant::now();
+        if let Some(output) = dry_run(self) {
+            return Ok(output);
+        }
+        if let Some(output) = replayed(self) {
+            return finish(self, started, output?);
+        }
+
+        let mut child = self.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+        let stdout = capture(child.stdout.take(), Stream::Stdout);
+        let stderr = capture(child.stderr.take(), Stream::Stderr);
+
+        match wait_with_deadline(&mut child, started + timeout)? {
+            Some(status) => finish(
+                self,
+                started,
+                Output {
+                    status,
+                    stdout: stdout.finish(),
+                    stderr: stderr.finish(),
+                },
+            ),
+            None => {
+                let _ = child.kill();
+                let _ = child.wait();
+                record(describe(self), started, None);
+
+                // Grandchildren may still hold the pipes open, so only take what has been read
+                // so far rather than waiting for the readers to finish.
+                Err(Error::Timeout {
+                    timeout,
+                    stdout: stdout.snapshot(),
+                    stderr: stderr.snapshot(),
+                })
+            }
+        }
+    }
+
+    fn output_checked(&mut self) -> Result<Output, Error> {
+        let started = Instant::now();
+        if let Some(output) = dry_run(self) {
+            return Ok(output);
+        }
+        if let Some(output) = replayed(self) {
+            return finish(self, started, output?);
+        }
+
+        let output = match tee_prefix() {
+            Some(_) => {
+                // Like `Command::output`, do not let the command read the runner's stdin.
+                if !stdin_configured(self) {
+                    self.stdin(Stdio::null());
+                }
+                let child = self.stdout(Stdio::piped()).stderr(Stdio::piped()).spawn()?;
+                wait_with_output(child)?
+            }
+            None => self.output()?,
+        };
+        finish(self, started, output)
+    }
+
+    fn output_with_stdin(&mut self, input: impl AsRef<[u8]>) -> Result<Output, Error> {
+        let started = Instant::now();
+        if let Some(output) = dry_run(self) {
+            return Ok(output);
+        }
+        if let Some(output) = replayed(self) {
+            return finish(self, started, output?);
+        }
+
+        let mut child = self
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        // Write from another thread so that a child filling its output pipes before reading all
+        // of its input cannot deadlock us.
+        let input = input.as_ref().to_vec();
+        let writer = child.stdin.take().map(|mut stdin| {
+            thread::spawn(move || match stdin.write_all(&input) {
+                Err(e) if e.kind() != std::io::ErrorKind::BrokenPipe => Err(e),
+                _ => Ok(()),
+            })
+        });
+
+        let output = wait_with_output(child)?;
+        if let Some(writer) = writer {
+            writer.join().expect("stdin writer panicked")?;
+        }
+
+        finish(self, started, output)
+    }
+
+    fn expect_stdout(
+        &mut self,
+        input: impl AsRef<[u8]>,
+        expected_stdout: impl AsRef<str>,
+    ) -> ExtelResult {
+        let output = self.output_with_stdin(input)?;
+        if !output.status.success() {
+            return Err(err!(
+                "command exited with {}\n{}",
+                output.status,
+                bytes::lossy(&output.stderr)
+            ));
+        }
+
+        let expected = expected_stdout.as_ref();
+        match output.stdout == expected.as_bytes() {
+            true => Ok(()),
+            false => Err(err!(
+                "stdout does not match\n--- expected\n+++ actual\n{}",
+                line_diff(expected, &bytes::lossy(&output.stdout))
+            )),
+        }
+    }
+
+    fn expect_exit(&mut self, code: i32) -> ExtelResult {
+        let output = self.output_checked()?;
+        match output.status.code() == Some(code) {
+            true => Ok(()),
+            false => Err(err!(
+                "expected exit code {}, got {}\n  stderr: {}",
+                code,
+                output
+                    .status
+                    .code()
+                    .map_or_else(|| String::from("none"), |code| code.to_string()),
+                abbreviate_tail(&bytes::lossy(&output.stderr), MESSAGE_LIMIT)
+            )),
+        }
+    }
+
+    #[cfg(all(feature = "resource-usage", unix))]
+    fn output_with_usage(&mut self) -> Result<(Output, ResourceUsage), Error> {
+        let started = Instant::now();
+        if let Some(output) = dry_run(self) {
+            return Ok((output, ResourceUsage::default()));
+        }
+        let (output, usage) = crate::usage::output_with_usage(self)?;
+        Ok((finish(self, started, output)?, usage))
+    }
+
+    #[cfg(all(feature = "pty", unix))]
+    fn with_pty(&mut self) -> crate::pty::PtyCommand<'_> {
+        crate::pty::PtyCommand::new(self)
+    }
+}
+
+/// A command that every command built with [`cmd!`](crate::cmd) during a test is run under, such
+/// as `valgrind` or a sanitizer runner. See
+/// [`TestConfig::wrap_command`](crate::TestConfig::wrap_command).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandWrapper {
+    /// The wrapper program followed by its arguments. The wrapped command is appended to these.
+    pub args: Vec<String>,
+    /// The exit code the wrapper uses to report errors it found, such as valgrind's
+    /// `--error-exitcode`. A wrapped command exiting with this code results in an
+    /// [`Error::WrapperFailed`].
+    pub error_code: Option<i32>,
+}
+
+impl CommandWrapper {
+    /// Parse a whitespace-separated wrapper command line. The error code is taken from an
+    /// `--error-exitcode=N` argument, if there is one.
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::command::CommandWrapper;
+    ///
+    /// let wrapper = CommandWrapper::parse("valgrind --error-exitcode=99 -q");
+    /// assert_eq!(wrapper.args, ["valgrind", "--error-exitcode=99", "-q"]);
+    /// assert_eq!(wrapper.error_code, Some(99));
+    /// ```
+    pub fn parse(command_line: &str) -> Self {
+        Self::from_args(command_line.split_whitespace().map(String::from).collect())
+    }
+
+    /// A wrapper running the program and arguments `args`, which may contain whitespace. The
+    /// error code is taken from them like in [`parse`](Self::parse).
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::command::CommandWrapper;
+    ///
+    /// let wrapper = CommandWrapper::from_args(vec!["valgrind".into(), "--log-file=a b.log".into()]);
+    /// assert_eq!(wrapper.program(), "valgrind");
+    /// assert_eq!(wrapper.error_code, None);
+    /// ```
+    pub fn from_args(args: Vec<String>) -> Self {
+        let error_code = args
+            .iter()
+            .find_map(|arg| arg.strip_prefix("--error-exitcode="))
+            .and_then(|code| code.parse().ok());
+
+        Self { args, error_code }
+    }
+
+    /// The wrapper program itself.
+    pub fn program(&self) -> &str {
+        self.args.first().map(String::as_str).unwrap_or_default()
+    }
+}
+
+/// Create a [`Command`] for `program`, run under the [wrapper](CommandWrapper) of the current test
+/// if it has one, by the [executor](crate::executor) of the current test. Arguments added to the
+/// command afterwards are passed to the executor's command rather than to `program`, which only
+/// works for executors that run the program with the rest of their arguments, so prefer
+/// [`build`].
+///
+/// # Example
+/// ```rust
+/// use extel::command::wrapped;
+///
+/// // Outside of a test run there is no wrapper.
+/// assert_eq!(wrapped("echo").get_program(), "echo");
+/// ```
+pub fn wrapped(program: impl AsRef<OsStr>) -> Command {
+    build(program, std::iter::empty::<&OsStr>())
+}
+
+/// Create a [`Command`] for `program` with `args`, run under the [wrapper](CommandWrapper) of the
+/// current test if it has one, by the [executor](crate::executor) of the current test. This is
+/// what [`cmd!`](crate::cmd) uses to create its commands.
+///
+/// # Example
+/// ```rust
+/// use extel::command::build;
+///
+/// // Outside of a test run, commands run locally without a wrapper.
+/// let command = build("echo", ["hi"]);
+/// assert_eq!(command.get_program(), "echo");
+/// assert_eq!(command.get_args().collect::<Vec<_>>(), ["hi"]);
+/// ```
+pub fn build<I, S>(program: impl AsRef<OsStr>, args: I) -> Command
+where
+    I: IntoIterator<Item = S>,
+    S: AsRef<OsStr>,
+{
+    let (wrapper, executor, extra_args) = context::with_scope(|scope| match scope {
+        Some(scope) => (
+            scope.wrapper.clone(),
+            scope.executor.clone(),
+            extra_args(&scope.command_args, program.as_ref()),
+        ),
+        None => (None, None, Vec::new()),
+    });
+
+    let mut argv: Vec<OsString> = Vec::new();
+    let wrapped = matches!(&wrapper, Some(wrapper) if !wrapper.args.is_empty());
+    let program = match wrapper {
+        Some(wrapper) if wrapped => {
+            argv.extend(wrapper.args[1..].iter().map(OsString::from));
+            argv.push(program.as_ref().to_os_string());
+            OsString::from(wrapper.program())
+        }
+        _ => program.as_ref().to_os_string(),
+    };
+    argv.extend(extra_args.into_iter().map(OsString::from));
+    argv.extend(args.into_iter().map(|arg| arg.as_ref().to_os_string()));
+
+    let mut command = match executor {
+        Some(executor) => executor.command(&program, &argv),
+        None => Local.command(&program, &argv),
+    };
+    if wrapped {
+        command.env(WRAPPED_ENV, "1");
+    }
+    #[cfg(all(
+        feature = "sandbox",
+        target_os = "linux",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    ))]
+    if let Some(profile) = context::with_scope(|scope| scope.and_then(|s| s.sandbox.clone())) {
+        crate::sandbox::apply(&mut command, &profile);
+    }
+    command
+}
+
+/// Whether `command` was [built](build) under the [wrapper](CommandWrapper) of the current test.
+pub(crate) fn is_wrapped(command: &Command) -> bool {
+    command
+        .get_envs()
+        .any(|(key, value)| key == WRAPPED_ENV && value.is_some())
+}
+
+/// A command created once and built into a [`Command`] for each test, so that a suite run against
+/// one binary names the binary and its common arguments, environment, and working directory in a
+/// single place. Commands are built like [`cmd!`](crate::cmd) builds them, under the wrapper,
+/// extra arguments, and executor of the current test. Usually created with
+/// [`cmd_template!`](crate::cmd_template).
+///
+/// # Example
+/// ```rust
+/// use extel::{command::CommandTemplate, prelude::*};
+///
+/// fn app() -> CommandTemplate {
+///     CommandTemplate::new("echo").arg("-n").env("APP_LOG", "debug")
+/// }
+///
+/// fn greets() -> ExtelResult {
+///     let output = app().build(["hello", "world"]).output()?;
+///     extel_assert_eq!(String::from_utf8(output.stdout)?, "hello world")
+/// }
+///
+/// assert!(greets().is_ok());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandTemplate {
+    program: OsString,
+    args: Vec<OsString>,
+    envs: Vec<(OsString, OsString)>,
+    current_dir: Option<PathBuf>,
+}
+
+impl CommandTemplate {
+    /// A template running `program` with no arguments.
+    pub fn new(program: impl AsRef<OsStr>) -> Self {
+        Self {
+            program: program.as_ref().to_os_string(),
+            args: Vec::new(),
+            envs: Vec::new(),
+            current_dir: None,
+        }
+    }
+
+    /// Add an argument given to every command, before the arguments given to
+    /// [`build`](Self::build).
+    pub fn arg(mut self, arg: impl AsRef<OsStr>) -> Self {
+        self.args.push(arg.as_ref().to_os_string());
+        self
+    }
+
+    /// Add arguments given to every command, before the arguments given to
+    /// [`build`](Self::build).
+    pub fn args<I, S>(mut self, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.args
+            .extend(args.into_iter().map(|arg| arg.as_ref().to_os_string()));
+        self
+    }
+
+    /// Set an environment variable for every command.
+    pub fn env(mut self, key: impl AsRef<OsStr>, value: impl AsRef<OsStr>) -> Self {
+        self.envs
+            .push((key.as_ref().to_os_string(), value.as_ref().to_os_string()));
+        self
+    }
+
+    /// Run every command in `dir`.
+    pub fn current_dir(mut self, dir: impl AsRef<Path>) -> Self {
+        self.current_dir = Some(dir.as_ref().to_path_buf());
+        self
+    }
+
+    /// The program every command runs.
+    pub fn program(&self) -> &OsStr {
+        &self.program
+    }
+
+    /// Build a command running the program with the template's arguments followed by `args`.
+    pub fn build<I, S>(&self, args: I) -> Command
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        let args = self
+            .args
+            .iter()
+            .cloned()
+            .chain(args.into_iter().map(|arg| arg.as_ref().to_os_string()));
+        let mut command = build(&self.program, args);
+        command.envs(self.envs.iter().map(|(key, value)| (key, value)));
+        if let Some(dir) = &self.current_dir {
+            command.current_dir(dir);
+        }
+        command
+    }
+
+    /// Build a command like [`build`](Self::build), splitting `args` into arguments the way
+    /// [`split_command_line`] does. Fails if a quote in `args` is never closed.
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::cmd_template;
+    ///
+    /// let git = cmd_template!("git --no-pager");
+    /// let log = git.build_str("log --oneline -n 5").unwrap();
+    /// assert_eq!(log.get_args().collect::<Vec<_>>(), ["--no-pager", "log", "--oneline", "-n", "5"]);
+    /// ```
+    pub fn build_str(&self, args: &str) -> Result<Command, Error> {
+        Ok(self.build(tokenize(args)?))
+    }
+}
+
+/// Split a command line into its program and arguments the way [`cmd!`](crate::cmd) does.
+///
+/// Arguments are separated by whitespace. Text in single or double quotes stays in one argument,
+/// without the quotes, so `--name="a b"` is the argument `--name=a b` and `''` is an empty
+/// argument. There are no escape sequences: a quote is passed by wrapping it in the other kind.
+///
+/// # Errors
+/// Fails if `command_line` has no program, or if a quote is never closed.
+///
+/// # Example
+/// ```rust
+/// use extel::command::split_command_line;
+///
+/// let (program, args) = split_command_line("app --verbose run 'two words'").unwrap();
+/// assert_eq!(program, "app");
+/// assert_eq!(args, ["--verbose", "run", "two words"]);
+///
+/// assert!(split_command_line("app 'unclosed").is_err());
+/// ```
+pub fn split_command_line(command_line: &str) -> Result<(String, Vec<String>), Error> {
+    let mut tokens = tokenize(command_line)?.into_iter();
+    let program = tokens
+        .next()
+        .ok_or_else(|| err!("no program in command line: {:?}", command_line))?;
+    Ok((program, tokens.collect()))
+}
+
+/// Split `line` into arguments as described by [`split_command_line`].
+fn tokenize(line: &str) -> Result<Vec<String>, Error> {
+    let mut tokens = Vec::new();
+    // `None` between arguments, so that an empty quoted argument is still an argument.
+    let mut token: Option<String> = None;
+    let mut quote = None;
+
+    for c in line.chars() {
+        match quote {
+            Some(q) if c == q => quote = None,
+            Some(_) => token.get_or_insert_with(String::new).push(c),
+            None if c == '"' || c == '\'' => {
+                quote = Some(c);
+                token.get_or_insert_with(String::new);
+            }
+            None if c.is_whitespace() => tokens.extend(token.take()),
+            None => token.get_or_insert_with(String::new).push(c),
+        }
+    }
+
+    if let Some(q) = quote {
+        return Err(err!("unclosed {} quote in command line: {}", q, line));
+    }
+    tokens.extend(token);
+    Ok(tokens)
+}
+
+/// The [extra arguments](crate::TestConfig::command_args) configured for `program`, those for
+/// every program first.
+fn extra_args(command_args: &HashMap<String, Vec<String>>, program: &OsStr) -> Vec<String> {
+    let name = Path::new(program).file_name().unwrap_or(program);
+    ["*", &name.to_string_lossy()]
+        .iter()
+        .filter_map(|key| command_args.get(*key))
+        .flatten()
+        .cloned()
+        .collect()
+}
+
+/// A command run by a test through [`CommandExt`], as listed in the [run manifest](crate::manifest).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandRecord {
+    /// The program and its arguments, separated by spaces.
+    pub command: String,
+    /// The exit code of the command, or `None` if it was killed by a signal or timed out.
+    pub exit_code: Option<i32>,
+    /// How long the command ran for.
+    pub duration: Duration,
+}
+
+/// The program and arguments of `command`, separated by spaces.
+pub(crate) fn describe(command: &Command) -> String {
+    std::iter::once(command.get_program())
+        .chain(command.get_args())
+        .map(OsStr::to_string_lossy)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Record that `command`, started at `started`, finished with `exit_code` on the current test.
+pub(crate) fn record(command: String, started: Instant, exit_code: Option<i32>) {
+    let duration = started.elapsed();
+    context::with_scope(|scope| {
+        if let Some(scope) = scope {
+            scope.recorded.commands.push(CommandRecord {
+                command,
+                exit_code,
+                duration,
+            });
+        }
+    });
+}
+
+/// Record the run of `command` and check how it exited.
+pub(crate) fn finish(command: &Command, started: Instant, output: Output) -> Result<Output, Error> {
+    let wrapped = is_wrapped(command);
+    let command = describe(command);
+    context::with_scope(|scope| {
+        if let Some(scope) = scope {
+            if let Some(tape) = &scope.tape {
+                tape.record(&scope.test_name, &command, &output);
+            }
+        }
+    });
+    record(command.clone(), started, output.status.code());
+    check_status(&command, wrapped, output)
+}
+
+/// The reason every test of a [dry run](crate::TestConfig::dry_run) is skipped for.
+pub(crate) const DRY_RUN_REASON: &str = "dry run";
+
+/// `command` as a shell command line, including the working directory and environment variables
+/// it sets. Arguments are quoted where a shell would split or expand them.
+///
+/// # Example
+/// ```rust
+/// use extel::command::command_line;
+/// use std::process::Command;
+///
+/// let mut command = Command::new("grep");
+/// command.args(["-r", "two words", "src/"]).current_dir("/repo").env("LC_ALL", "C");
+/// assert_eq!(command_line(&command), "cd /repo && env LC_ALL=C grep -r 'two words' src/");
+/// ```
+pub fn command_line(command: &Command) -> String {
+    let quote = |arg: &OsStr| {
+        let arg = arg.to_string_lossy();
+        let plain = !arg.is_empty()
+            && arg
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "-_./=:,@%+".contains(c));
+        match plain {
+            true => arg.into_owned(),
+            false => shell_quote(&arg),
+        }
+    };
+
+    let mut words = Vec::new();
+    if let Some(dir) = command.get_current_dir() {
+        words.extend([
+            String::from("cd"),
+            quote(dir.as_os_str()),
+            String::from("&&"),
+        ]);
+    }
+    if command.get_envs().len() > 0 {
+        words.push(String::from("env"));
+        for (key, value) in command.get_envs() {
+            words.push(match value {
+                Some(value) => quote(&[key, value].join(OsStr::new("="))),
+                None => format!("-u {}", quote(key)),
+            });
+        }
+    }
+    words.extend(
+        std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(quote),
+    );
+    words.join(" ")
+}
+
+/// An exit status that exited with `code`.
+pub(crate) fn exit_status(code: i32) -> ExitStatus {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+        ExitStatus::from_raw((code & 0xff) << 8)
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::ExitStatusExt;
+        ExitStatus::from_raw(code as u32)
+    }
+}
+
+/// A successful run with no output in place of running `command`, if the current test is part of
+/// a [dry run](crate::TestConfig::dry_run). The command line is printed and recorded instead.
+fn dry_run(command: &Command) -> Option<Output> {
+    let (test_name, line) = context::with_scope(|scope| {
+        let scope = scope.filter(|scope| scope.dry_run)?;
+        Some((scope.test_name.clone(), command_line(command)))
+    })?;
+
+    let _ = writeln!(
+        std::io::stdout().lock(),
+        "[{}] would run: {}",
+        test_name,
+        line
+    );
+    record(line, Instant::now(), None);
+    Some(Output {
+        status: exit_status(0),
+        stdout: Vec::new(),
+        stderr: Vec::new(),
+    })
+}
+
+/// The recorded output of `command`, if the current test is [replaying](crate::cassette) the
+/// commands it runs.
+fn replayed(command: &Command) -> Option<Result<Output, Error>> {
+    context::with_scope(|scope| {
+        let scope = scope?;
+        scope
+            .tape
+            .as_ref()?
+            .replay(&scope.test_name, &describe(command))
+    })
+}
+
+/// Check the exit of `command`, as [described](describe), turning a crash into an
+/// [`Error::CommandCrashed`] and, if the command was [wrapped](is_wrapped), errors reported by the
+/// current test's [wrapper](CommandWrapper) into an [`Error::WrapperFailed`]. Under the
+/// [sandbox](crate::TestConfig::sandbox) of the current test, violations are turned into an
+/// [`Error::SandboxViolation`] first.
+pub(crate) fn check_status(command: &str, wrapped: bool, output: Output) -> Result<Output, Error> {
+    check_sandbox(command, &output)?;
+
+    let wrapper = context::with_scope(|scope| scope.and_then(|s| s.wrapper.clone()));
+    if let Some(wrapper) = wrapper.filter(|_| wrapped) {
+        if wrapper.error_code.is_some() && output.status.code() == wrapper.error_code {
+            return Err(Error::WrapperFailed {
+                wrapper: wrapper.program().to_string(),
+                stdout: output.stdout,
+                stderr: output.stderr,
+            });
+        }
+    }
+
+    check_crashed(output)
+}
+
+/// Turn a violation of the current test's [sandbox](crate::TestConfig::sandbox) by `command` into
+/// an [`Error::SandboxViolation`].
+#[cfg(all(
+    feature = "sandbox",
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+fn check_sandbox(command: &str, output: &Output) -> Result<(), Error> {
+    let profile = context::with_scope(|scope| scope.and_then(|s| s.sandbox.clone()));
+    match profile.and_then(|profile| crate::sandbox::violation(command, &profile, output)) {
+        Some(err) => Err(err),
+        None => Ok(()),
+    }
+}
+
+#[cfg(not(all(
+    feature = "sandbox",
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+)))]
+fn check_sandbox(_command: &str, _output: &Output) -> Result<(), Error> {
+    Ok(())
+}
+
+/// Turn the output of a command that was killed by a signal into an [`Error::CommandCrashed`].
+fn check_crashed(output: Output) -> Result<Output, Error> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::ExitStatusExt;
+
+        if let Some(signal) = output.status.signal() {
+            return Err(Error::CommandCrashed {
+                signal,
+                stdout: output.stdout,
+                stderr: output.stderr,
+            });
+        }
+    }
+
+    Ok(output)
+}
+
+/// The name of a common signal that can kill a command, such as `SIGSEGV` for 11. Only signals
+/// with the same number on every Unix platform are named.
+///
+/// # Example
+/// ```rust
+/// use extel::command::signal_name;
+///
+/// assert_eq!(signal_name(6), Some("SIGABRT"));
+/// assert_eq!(signal_name(64), None);
+/// ```
+pub fn signal_name(signal: i32) -> Option<&'static str> {
+    Some(match signal {
+        1 => "SIGHUP",
+        2 => "SIGINT",
+        3 => "SIGQUIT",
+        4 => "SIGILL",
+        5 => "SIGTRAP",
+        6 => "SIGABRT",
+        8 => "SIGFPE",
+        9 => "SIGKILL",
+        11 => "SIGSEGV",
+        13 => "SIGPIPE",
+        14 => "SIGALRM",
+        15 => "SIGTERM",
+        _ => return None,
+    })
+}
+
+/// Poll `child` until it exits or `deadline` passes. Returns `None` if the deadline passed first.
+pub(crate) fn wait_with_deadline(
+    child: &mut Child,
+    deadline: Instant,
+) -> std::io::Result<Option<std::process::ExitStatus>> {
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok(Some(status));
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            return Ok(None);
+        }
+        thread::sleep(POLL_INTERVAL.min(deadline - now));
+    }
+}
+
+/// Whether stdin was set on `command`. `Command` has no getter for its stdio, but its alternate
+/// debug output lists the stdio it was given, each field on its own line.
+fn stdin_configured(command: &Command) -> bool {
+    format!("{:#?}", command).contains("\n    stdin: Some(")
+}
+
+/// Wait for `child` to exit like [`Child::wait_with_output`], teeing its output if enabled.
+fn wait_with_output(mut child: Child) -> std::io::Result<Output> {
+    let stdout = capture(child.stdout.take(), Stream::Stdout);
+    let stderr = capture(child.stderr.take(), Stream::Stderr);
+    let status = child.wait()?;
+
+    Ok(Output {
+        status,
+        stdout: stdout.finish(),
+        stderr: stderr.finish(),
+    })
+}
+
+/// The console stream that a [`Capture`] tees what it reads to, matching the pipe it reads.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// The prefix for the teed output of commands run by the current test, if
+/// [teeing](crate::TestConfig::tee_commands) is enabled.
+fn tee_prefix() -> Option<String> {
+    context::with_scope(|scope| {
+        scope
+            .filter(|scope| scope.tee_commands)
+            .map(|scope| format!("[{}] ", scope.test_name))
+    })
+}
+
+/// Splits teed output into complete lines, each starting with the prefix of the test that ran the
+/// command, so that the output of commands run at the same time is not mixed within a line.
+struct Tee {
+    prefix: String,
+    pending: Vec<u8>,
+}
+
+impl Tee {
+    /// Add `chunk` to the output, returning the lines it completed.
+    fn push(&mut self, chunk: &[u8]) -> Option<String> {
+        self.pending.extend_from_slice(chunk);
+        let end = self.pending.iter().rposition(|&b| b == b'\n')?;
+        let lines: Vec<u8> = self.pending.drain(..=end).collect();
+        Some(self.prefixed(&lines))
+    }
+
+    /// Return the last line of the output if it did not end with a newline.
+    fn finish(&mut self) -> Option<String> {
+        match self.pending.is_empty() {
+            true => None,
+            false => {
+                let mut line = std::mem::take(&mut self.pending);
+                line.push(b'\n');
+                Some(self.prefixed(&line))
+            }
+        }
+    }
+
+    fn prefixed(&self, lines: &[u8]) -> String {
+        lines
+            .split_inclusive(|&b| b == b'\n')
+            .map(|line| format!("{}{}", self.prefix, String::from_utf8_lossy(line)))
+            .collect()
+    }
+}
+
+/// A background reader that continuously drains a pipe into a shared buffer.
+pub(crate) struct Capture {
+    buffer: Arc<Mutex<Vec<u8>>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl Capture {
+    /// Wait for the pipe to close and return everything read from it.
+    pub(crate) fn finish(mut self) -> Vec<u8> {
+        self.join();
+        self.snapshot()
+    }
+
+    /// Wait for the pipe to close.
+    pub(crate) fn join(&mut self) {
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+
+    /// Return everything read from the pipe so far.
+    pub(crate) fn snapshot(&self) -> Vec<u8> {
+        self.buffer
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+}
+
+/// Start draining `pipe` in the background. If the current test
+/// [tees](crate::TestConfig::tee_commands) the output of its commands, everything read is also
+/// written to `stream` as it arrives.
+pub(crate) fn capture(pipe: Option<impl Read + Send + 'static>, stream: Stream) -> Capture {
+    let buffer = Arc::new(Mutex::new(Vec::new()));
+    let mut tee = tee_prefix().map(|prefix| Tee {
+        prefix,
+        pending: Vec::new(),
+    });
+    let handle = pipe.map(|mut pipe| {
+        let buffer = Arc::clone(&buffer);
+        thread::spawn(move || {
+            let write = |lines: String| {
+                let _ = match stream {
+                    Stream::Stdout => std::io::stdout().lock().write_all(lines.as_bytes()),
+                    Stream::Stderr => std::io::stderr().lock().write_all(lines.as_bytes()),
+                };
+            };
+
+            let mut chunk = [0u8; 4096];
+            while let Ok(n) = pipe.read(&mut chunk) {
+                if n == 0 {
+                    break;
+                }
+                buffer
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .extend_from_slice(&chunk[..n]);
+                if let Some(lines) = tee.as_mut().and_then(|tee| tee.push(&chunk[..n])) {
+                    write(lines);
+                }
+            }
+            if let Some(line) = tee.as_mut().and_then(Tee::finish) {
+                write(line);
+            }
+        })
+    });
+
+    Capture { buffer, handle }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fs::temp_file_with, prelude::*, OutputDest};
+
+    fn split(command_line: &str) -> (String, Vec<String>) {
+        split_command_line(command_line).unwrap()
+    }
+
+    #[test]
+    fn split_command_line_keeps_arguments_after_quotes() {
+        assert_eq!(
+            split("app 'a' b"),
+            ("app".into(), vec!["a".into(), "b".into()])
+        );
+    }
+
+    #[test]
+    fn split_command_line_keeps_quoted_spaces() {
+        assert_eq!(
+            split("app 'a b' c"),
+            ("app".into(), vec!["a b".into(), "c".into()])
+        );
+        assert_eq!(
+            split(r#"app --name="x  y" 'say "hi"'"#).1,
+            ["--name=x  y", r#"say "hi""#]
+        );
+    }
+
+    #[test]
+    fn split_command_line_skips_repeated_whitespace() {
+        assert_eq!(
+            split("  app  b\tc  "),
+            ("app".into(), vec!["b".into(), "c".into()])
+        );
+        assert_eq!(split("app '' b").1, ["", "b"]);
+    }
+
+    #[test]
+    fn split_command_line_rejects_bad_input() {
+        assert!(split_command_line("").is_err());
+        assert!(split_command_line("   ").is_err());
+        assert_eq!(
+            split_command_line("app 'a b").unwrap_err().to_string(),
+            "unclosed ' quote in command line: app 'a b"
+        );
+        assert!(split_command_line("app \"a").is_err());
+    }
+
+    #[test]
+    fn output_with_timeout_completes() {
+        let output = cmd!("echo -n hello")
+            .output_with_timeout(Duration::from_secs(5))
+            .unwrap();
+
+        assert!(output.status.success());
+        assert_eq!(output.stdout, b"hello");
+    }
+
+    #[test]
+    fn crashed_commands_are_classified() {
+        let err = cmd!("sh" => ["-c", "echo dying; kill -ABRT $$"])
+            .output_with_stdin("")
+            .unwrap_err();
+
+        match err {
+            Error::CommandCrashed { signal, stdout, .. } => {
+                assert_eq!(signal, 6);
+                assert_eq!(stdout, b"dying\n");
+            }
+            other => panic!("expected a crash, got {:?}", other),
+        }
+
+        let err = cmd!("sh" => ["-c", "kill -SEGV $$"])
+            .output_with_timeout(Duration::from_secs(5))
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "command crashed: killed by signal 11 (SIGSEGV)"
+        );
+        assert!(cmd!("sh" => ["-c", "exit 3"]).output_checked().is_ok());
+    }
+
+    #[test]
+    fn wrapped_commands_report_wrapper_errors() {
+        fn runs_wrapped() -> ExtelResult {
+            let output = cmd!("printenv" => ["WRAPPED"]).output_checked()?;
+            extel_assert_eq!(output.stdout, b"yes\n")
+        }
+
+        fn wrapper_finds_errors() -> ExtelResult {
+            match cmd!("true").output_checked() {
+                Err(Error::WrapperFailed { wrapper, .. }) => extel_assert_eq!(wrapper, "sh"),
+                other => fail!("expected the wrapper to fail, got {:?}", other),
+            }
+        }
+
+        // Only commands built under the wrapper are checked, even if they run its program.
+        fn unwrapped_exits_normally() -> ExtelResult {
+            let output = Command::new("sh")
+                .args(["-c", "exit 99"])
+                .output_checked()?;
+            extel_assert_eq!(output.status.code(), Some(99))
+        }
+
+        init_test_suite!(EnvWrapped, runs_wrapped);
+        init_test_suite!(
+            ScriptWrapped,
+            wrapper_finds_errors,
+            unwrapped_exits_normally
+        );
+
+        let cfg = || TestConfig::default().output(OutputDest::None);
+        let results = EnvWrapped::run(cfg().wrap_command("env WRAPPED=yes"));
+        assert!(results[0].test_result.is_ok(), "{:?}", results[0]);
+
+        let script = temp_file_with("exit 99\n").unwrap();
+        let wrapper = format!("sh {} --error-exitcode=99", script.path().display());
+        let results = ScriptWrapped::run(cfg().wrap_command(&wrapper));
+        assert!(
+            results.iter().all(|r| r.test_result.is_ok()),
+            "{:?}",
+            results
+        );
+    }
+
+    #[test]
+    fn wrappers_run_under_the_executor() {
+        #[derive(Debug)]
+        struct Env;
+
+        impl CommandExecutor for Env {
+            fn command(&self, program: &OsStr, args: &[OsString]) -> Command {
+                let mut command = Command::new("env");
+                command.arg("EXECUTED=yes").arg(program).args(args);
+                command
+            }
+        }
+
+        fn runs_executed() -> ExtelResult {
+            let output = cmd!("sh" => ["-c", "echo $EXECUTED $WRAPPED; exit 7"]).output_checked();
+            match output {
+                Err(Error::WrapperFailed { stdout, .. }) => {
+                    extel_assert_eq!(stdout, b"yes yes\n")
+                }
+                other => fail!("expected the wrapper to fail, got {:?}", other),
+            }
+        }
+
+        init_test_suite!(ExecutedSuite, runs_executed);
+        let script = temp_file_with("shift; WRAPPED=yes exec \"$@\"\n").unwrap();
+        let wrapper = format!("sh {} --error-exitcode=7", script.path().display());
+        let results = ExecutedSuite::run(
+            TestConfig::default()
+                .output(OutputDest::None)
+                .wrap_command(&wrapper)
+                .executor(Env),
+        );
+        assert!(results[0].test_result.is_ok(), "{:?}", results[0]);
+    }
+
+    #[test]
+    fn dry_runs_record_commands_without_running_them() {
+        fn deletes_everything() -> ExtelResult {
+            let output = cmd!("rm" => ["-rf", "/nonexistent/extel dry run"])
+                .env("KEEP", "1")
+                .output_checked()?;
+            extel_assert!(output.status.success())?;
+            fail!("assertions after a command still run")
+        }
+
+        init_test_suite!(DrySuite, deletes_everything);
+        let results = DrySuite::run(TestConfig::default().output(OutputDest::None).dry_run(true));
+
+        assert!(results[0].test_result.is_skipped(), "{:?}", results[0]);
+        assert_eq!(results[0].commands.len(), 1);
+        assert_eq!(
+            results[0].commands[0].command,
+            "env KEEP=1 rm -rf '/nonexistent/extel dry run'"
+        );
+        assert_eq!(results[0].commands[0].exit_code, None);
+    }
+
+    #[test]
+    fn output_with_stdin_tolerates_unread_input() {
+        let output = cmd!("true").output_with_stdin(vec![b'x'; 1 << 20]).unwrap();
+        assert!(output.status.success());
+    }
+
+    #[test]
+    fn expect_stdout_diffs_mismatches() {
+        assert!(cmd!("cat").expect_stdout("a\nb\n", "a\nb\n").is_ok());
+
+        let err = cmd!("cat").expect_stdout("a\nb\n", "a\nc\n").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "stdout does not match\n--- expected\n+++ actual\n a\n-c\n+b\n"
+        );
+    }
+
+    #[test]
+    fn teed_output_is_still_captured() {
+        fn noisy() -> ExtelResult {
+            let output =
+                cmd!("sh" => ["-c", "echo out; echo err >&2; printf partial"]).output_checked()?;
+            extel_assert_eq!(output.stdout, b"out\npartial")?;
+            extel_assert_eq!(output.stderr, b"err\n")?;
+
+            // Teeing must not hand the runner's stdin to the command, unless asked to.
+            extel_assert_eq!(cmd!("cat").output_checked()?.stdout, b"")?;
+            let input = temp_file_with("input").unwrap();
+            let output = cmd!("cat")
+                .stdin(std::fs::File::open(input.path())?)
+                .output_checked()?;
+            extel_assert_eq!(output.stdout, b"input")
+        }
+
+        init_test_suite!(TeeSuite, noisy);
+        let results = TeeSuite::run(
+            TestConfig::default()
+                .output(OutputDest::None)
+                .tee_commands(true),
+        );
+        assert!(results[0].test_result.is_ok(), "{:?}", results[0]);
+    }
+
+    #[test]
+    fn tee_prefixes_complete_lines() {
+        let mut tee = Tee {
+            prefix: String::from("[noisy] "),
+            pending: Vec::new(),
+        };
+
+        assert_eq!(tee.push(b"start"), None);
+        assert_eq!(
+            tee.push(b"ed\nstep 1\nstep"),
+            Some(String::from("[noisy] started\n[noisy] step 1\n"))
+        );
+        assert_eq!(tee.finish(), Some(String::from("[noisy] step\n")));
+        assert_eq!(tee.finish(), None);
+    }
+
+    #[test]
+    fn output_with_timeout_kills_with_partial_output() {
+        let start = Instant::now();
+        let result = cmd!("sh" => ["-c", "echo partial; echo oops >&2; sleep 5"])
+            .output_with_timeout(Duration::from_millis(300));
+
+        assert!(start.elapsed() < Duration::from_secs(5));
+        match result {
+            Err(Error::Timeout {
+                timeout,
+                stdout,
+                stderr,
+            }) => {
+                assert_eq!(timeout, Duration::from_millis(300));
+                assert_eq!(stdout, b"partial\n");
+                assert_eq!(stderr, b"oops\n");
+            }
+            other => panic!("expected a timeout, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,89 @@
+//! Running several suites one after the other as a single suite, with a combined summary. See
+//! [`compose_suites!`](crate::compose_suites).
+
+use crate::{Reporter, SuiteReport, TestConfig, TestResult};
+
+/// Run a suite with a configuration, as done by
+/// [`RunnableTestSet::run_report`](crate::RunnableTestSet::run_report).
+pub type SuiteRun = fn(TestConfig) -> SuiteReport;
+
+/// Run every suite in `suites` in order with a clone of `cfg`, then report the totals of each
+/// suite and of the whole run under `name`. The results of every suite are returned in the order
+/// they ran. This is what the suites generated by [`compose_suites!`](crate::compose_suites)
+/// call.
+///
+/// # Example
+/// ```rust
+/// use extel::{compose, prelude::*, OutputDest, SharedBuffer};
+///
+/// fn always_succeed() -> ExtelResult {
+///     pass!()
+/// }
+///
+/// init_test_suite!(FirstSuite as "first", always_succeed);
+/// init_test_suite!(SecondSuite as "second", always_succeed);
+///
+/// let output = SharedBuffer::new();
+/// let results = compose::run(
+///     "all",
+///     &[FirstSuite::run_report, SecondSuite::run_report],
+///     TestConfig::default()
+///         .output(OutputDest::Buffer(output.clone()))
+///         .colored(false),
+/// );
+///
+/// assert_eq!(results.len(), 2);
+/// assert!(output.to_string_lossy().ends_with(
+///     "[all]\n\
+///      \tfirst: 1 passed; 0 failed\n\
+///      \tsecond: 1 passed; 0 failed\n\
+///      \ttotal: 2 passed; 0 failed\n"
+/// ));
+/// ```
+pub fn run(name: &str, suites: &[SuiteRun], mut cfg: TestConfig) -> Vec<TestResult> {
+    let reports: Vec<SuiteReport> = suites.iter().map(|run| run(cfg.clone())).collect();
+
+    if !cfg.list {
+        if let Some(mut reporter) = cfg.take_reporter() {
+            reporter.on_summary(name, &reports);
+        }
+    }
+
+    reports
+        .into_iter()
+        .flat_map(|report| report.results.into_inner())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{fixture::TempDir, prelude::*, OutputDest};
+
+    fn always_succeed() -> ExtelResult {
+        pass!()
+    }
+
+    #[test]
+    fn composed_suites_share_an_output_file() {
+        init_test_suite!(FirstSuite as "first", always_succeed);
+        init_test_suite!(SecondSuite as "second", always_succeed);
+        compose_suites!(BothSuites as "both", FirstSuite, SecondSuite);
+
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("output.txt");
+        BothSuites::run(
+            TestConfig::default()
+                .output(OutputDest::File(path.clone()))
+                .colored(false),
+        );
+
+        let output = std::fs::read_to_string(&path).unwrap();
+        assert!(output.starts_with("[first]\n"), "{}", output);
+        assert!(output.contains("[second]\n"), "{}", output);
+        assert!(
+            output.ends_with("\ttotal: 2 passed; 0 failed\n"),
+            "{}",
+            output
+        );
+    }
+}
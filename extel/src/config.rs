@@ -0,0 +1,788 @@
+//! Loading a [`TestConfig`] from a TOML file, so that runner binaries do not need to hard-code
+//! their configuration.
+//!
+//! Every key is optional. Any key can also be overridden with an `EXTEL_<KEY>` environment
+//! variable (e.g. `EXTEL_COLOR=false`), where list values are comma separated.
+//!
+//! ```toml
+//! output = "stdout"          # "stdout", "json", "none", or a file path
+//! color = "auto"             # true, false, or "auto"
+//! filter = "http_"
+//! exact = "my_suite::my_test#2"  # only this test, or one of its cases
+//! tags = ["smoke"]
+//! progress = false
+//! rerun_hint = true
+//! order = "shuffled"         # "declared", "alphabetical", "shuffled", "failed_first", or "slowest_first"
+//! seed = 42                  # only used when shuffled
+//! state_file = ".extel/last-run"   # a path, true for the default path, or false (the default)
+//! rerun_failed = false
+//! artifacts_dir = "target/extel-artifacts"
+//! verbose = false
+//! isolate_env = false
+//! isolate_process = false
+//! wrap_command = "valgrind --error-exitcode=99"  # or an array, for arguments with spaces
+//! executor = "docker:db"     # "local", "docker:CONTAINER", "chroot:DIR", or "ssh:HOST"
+//! record_commands = "tests/cassette"
+//! # replay_commands = "tests/cassette"
+//! dry_run = false
+//! qualified_names = false
+//! tee_commands = false
+//! manifest = "target/extel-manifest.jsonl"
+//! allure_dir = "target/allure-results"
+//! run_info = false
+//! report_env = ["CI", "GIT_COMMIT"]
+//! history = "target/extel-history"
+//! slow_threshold = 1.5       # flag tests taking 50% longer than in the previous run
+//! shard = "0/4"              # run only the first of four shards
+//! style = "unicode"          # "default", "unicode", or "plain"
+//! align = true
+//! cluster_failures = true    # show cases failing with the same message pattern as one line
+//! max_failure_len = 4096     # truncate longer failure messages in the console output
+//! summarize_cases = 10       # keep only counts and the first 10 failures of each parameterized test
+//!
+//! [vars]
+//! server = "localhost:8080"
+//!
+//! [metadata]
+//! environment = "staging"
+//!
+//! [command_args]
+//! app = ["--config", "test.toml"]   # or a string of whitespace-separated arguments
+//!
+//! [presets.ci]                       # options applied by `TestConfig::from_file_preset`
+//! color = false
+//! progress = false
+//! ```
+//!
+//! > *This is only available with the `config-file` feature enabled.*
+
+use std::{collections::HashMap, fs, path::Path, time::Duration};
+
+use toml::{Table, Value};
+
+use crate::{
+    cassette::CassetteMode, command::CommandWrapper, errors::Error, executor, order, state,
+    style::Style, OutputDest, TestConfig, TestOrder,
+};
+
+/// The keys that may appear at the top level of a config file, other than the `vars`,
+/// `metadata`, and `command_args` tables.
+const KEYS: &[&str] = &[
+    "output",
+    "color",
+    "filter",
+    "exact",
+    "tags",
+    "progress",
+    "rerun_hint",
+    "order",
+    "seed",
+    "state_file",
+    "rerun_failed",
+    "artifacts_dir",
+    "verbose",
+    "isolate_env",
+    "isolate_process",
+    "wrap_command",
+    "executor",
+    "record_commands",
+    "replay_commands",
+    "dry_run",
+    "qualified_names",
+    "tee_commands",
+    "manifest",
+    "allure_dir",
+    "run_info",
+    "report_env",
+    "history",
+    "slow_threshold",
+    "shard",
+    "style",
+    "align",
+    "cluster_failures",
+    "case_jobs",
+    "max_failure_len",
+    "summarize_cases",
+    "suite_timeout",
+    #[cfg(all(feature = "capture", unix))]
+    "capture",
+    #[cfg(any(feature = "log", feature = "tracing"))]
+    "capture_logs",
+];
+
+impl TestConfig {
+    /// Load a configuration from the TOML file at `path`, applying `EXTEL_<KEY>` environment
+    /// variable overrides on top of it. Options that are not set keep their
+    /// [default](TestConfig::default) values.
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::{fixture::TempDir, TestConfig};
+    ///
+    /// let tmp = TempDir::new().unwrap();
+    /// let path = tmp.path().join("extel.toml");
+    /// std::fs::write(&path, "color = false\ntags = [\"smoke\"]\n").unwrap();
+    ///
+    /// let cfg = TestConfig::from_file(&path).unwrap();
+    /// assert_eq!(cfg.tags, vec!["smoke"]);
+    /// ```
+    ///
+    /// > *This is only available with the `config-file` feature enabled.*
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let contents = fs::read_to_string(path)?;
+        from_toml(&contents, None, |key| std::env::var(key).ok())
+    }
+
+    /// Load a configuration like [`from_file`](TestConfig::from_file), with the options of the
+    /// `[presets.<name>]` table of the file applied over the top-level ones, so that one file
+    /// can hold the configurations of several environments such as `ci` and `local`. The
+    /// `vars`, `metadata`, and `command_args` of a preset are added to those of the file.
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::{fixture::TempDir, TestConfig};
+    ///
+    /// let tmp = TempDir::new().unwrap();
+    /// let path = tmp.path().join("extel.toml");
+    /// std::fs::write(
+    ///     &path,
+    ///     "tags = [\"smoke\"]\n\n[presets.ci]\ncolor = false\n\n[presets.verbose]\nverbose = true\n",
+    /// )
+    /// .unwrap();
+    ///
+    /// let cfg = TestConfig::from_file_preset(&path, "ci").unwrap();
+    /// assert_eq!(cfg.tags, vec!["smoke"]);
+    /// assert_eq!(cfg.colored, Some(false));
+    /// assert!(!cfg.verbose);
+    ///
+    /// let err = TestConfig::from_file_preset(&path, "local").unwrap_err();
+    /// assert_eq!(err.to_string(), "invalid configuration: unknown preset 'local'");
+    /// ```
+    ///
+    /// > *This is only available with the `config-file` feature enabled.*
+    pub fn from_file_preset(path: impl AsRef<Path>, name: &str) -> Result<Self, Error> {
+        let contents = fs::read_to_string(path)?;
+        from_toml(&contents, Some(name), |key| std::env::var(key).ok())
+    }
+
+    /// Write the options of this configuration as a config file that
+    /// [`from_file`](TestConfig::from_file) loads back into the same configuration.
+    ///
+    /// Fails with an [`Error::Config`] if an option cannot be written to a file: a reporter,
+    /// failure hooks, a normalizer, a sandbox profile, a [buffer](OutputDest::Buffer) output, a
+    /// style other than the named ones, an executor that was not [parsed](executor::parse) from a
+    /// spec, or a wrapper whose error code does not come from its arguments.
+    /// [`list`](TestConfig::list) is not written either, as it is not a config file option.
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::{fixture::TempDir, TestConfig, TestOrder};
+    ///
+    /// let cfg = TestConfig::default()
+    ///     .colored(false)
+    ///     .shuffle(Some(42))
+    ///     .var("server", "localhost:8080");
+    ///
+    /// let toml = cfg.to_toml().unwrap();
+    /// assert!(toml.contains("order = \"shuffled\"\n"));
+    /// assert!(toml.contains("seed = 42\n"));
+    ///
+    /// let tmp = TempDir::new().unwrap();
+    /// let path = tmp.path().join("extel.toml");
+    /// std::fs::write(&path, toml).unwrap();
+    /// let loaded = TestConfig::from_file(&path).unwrap();
+    /// assert_eq!(loaded.colored, Some(false));
+    /// assert_eq!(loaded.order, TestOrder::Shuffled(42));
+    /// assert_eq!(loaded.vars["server"], "localhost:8080");
+    ///
+    /// let hooked = cfg.on_failure("uptime", |_| String::from("up"));
+    /// assert!(hooked.to_toml().is_err());
+    /// ```
+    ///
+    /// > *This is only available with the `config-file` feature enabled.*
+    pub fn to_toml(&self) -> Result<String, Error> {
+        Ok(self.to_table()?.to_string())
+    }
+
+    /// The options of this configuration as the top-level table of a config file. See
+    /// [`to_toml`](TestConfig::to_toml).
+    fn to_table(&self) -> Result<Table, Error> {
+        let unwritable =
+            |what: &str| Error::Config(format!("{} cannot be written to a config file", what));
+        if self.reporter.is_some() {
+            return Err(unwritable("a reporter"));
+        }
+        if !self.failure_hooks.is_empty() {
+            return Err(unwritable("a failure hook"));
+        }
+        if self.normalizer.is_some() {
+            return Err(unwritable("a normalizer"));
+        }
+        #[cfg(all(
+            feature = "sandbox",
+            target_os = "linux",
+            any(target_arch = "x86_64", target_arch = "aarch64")
+        ))]
+        if self.sandbox.is_some() {
+            return Err(unwritable("a sandbox profile"));
+        }
+
+        let mut table = Table::new();
+        let mut set = |key: &str, value: Value| {
+            table.insert(key.to_string(), value);
+        };
+        let path = |path: &Path| Value::String(path.display().to_string());
+        let strings =
+            |values: &[String]| Value::Array(values.iter().cloned().map(Value::String).collect());
+        let strings_table = |map: &HashMap<String, String>| {
+            Value::Table(
+                map.iter()
+                    .map(|(key, value)| (key.clone(), Value::String(value.clone())))
+                    .collect(),
+            )
+        };
+
+        match &self.output {
+            OutputDest::Stdout => set("output", Value::String(String::from("stdout"))),
+            OutputDest::Json => set("output", Value::String(String::from("json"))),
+            OutputDest::None => set("output", Value::String(String::from("none"))),
+            OutputDest::File(file_name) => set(
+                "output",
+                Value::String(file_name.to_string_lossy().into_owned()),
+            ),
+            OutputDest::Buffer(_) => return Err(unwritable("a buffer output")),
+        }
+        set(
+            "color",
+            self.colored
+                .map_or_else(|| Value::String(String::from("auto")), Value::Boolean),
+        );
+        if let Some(filter) = &self.filter {
+            set("filter", Value::String(filter.clone()));
+        }
+        if let Some(exact) = &self.exact {
+            set("exact", Value::String(exact.clone()));
+        }
+        set("tags", strings(&self.tags));
+        set("progress", Value::Boolean(self.progress));
+        set("rerun_hint", Value::Boolean(self.rerun_hint));
+        let order = match self.order {
+            TestOrder::Declared => "declared",
+            TestOrder::Alphabetical => "alphabetical",
+            TestOrder::Shuffled(seed) => {
+                // TOML integers are signed, so larger seeds are written as strings.
+                match i64::try_from(seed) {
+                    Ok(seed) => set("seed", Value::Integer(seed)),
+                    Err(_) => set("seed", Value::String(seed.to_string())),
+                }
+                "shuffled"
+            }
+            TestOrder::FailedFirst => "failed_first",
+            TestOrder::SlowestFirst => "slowest_first",
+        };
+        set("order", Value::String(String::from(order)));
+        set(
+            "state_file",
+            self.state_file
+                .as_deref()
+                .map_or(Value::Boolean(false), path),
+        );
+        set("rerun_failed", Value::Boolean(self.rerun_failed));
+        if let Some(dir) = &self.artifacts_dir {
+            set("artifacts_dir", path(dir));
+        }
+        set("verbose", Value::Boolean(self.verbose));
+        set("isolate_env", Value::Boolean(self.isolate_env));
+        set("isolate_process", Value::Boolean(self.isolate_process));
+        if let Some(wrapper) = &self.wrapper {
+            if CommandWrapper::from_args(wrapper.args.clone()) != *wrapper {
+                return Err(unwritable(
+                    "a wrapper whose error code is not in its arguments",
+                ));
+            }
+            match wrapper
+                .args
+                .iter()
+                .any(|arg| arg.is_empty() || arg.contains(char::is_whitespace))
+            {
+                true => set("wrap_command", strings(&wrapper.args)),
+                false => set("wrap_command", Value::String(wrapper.args.join(" "))),
+            }
+        }
+        if let Some(executor) = &self.executor {
+            match executor.spec() {
+                Some(spec) => set("executor", Value::String(spec.to_string())),
+                None => return Err(unwritable("an executor that was not parsed from a spec")),
+            }
+        }
+        if let Some(cassette) = &self.cassette {
+            let key = match cassette.mode {
+                CassetteMode::Record => "record_commands",
+                CassetteMode::Replay => "replay_commands",
+            };
+            set(key, path(&cassette.path));
+        }
+        set("dry_run", Value::Boolean(self.dry_run));
+        set("qualified_names", Value::Boolean(self.qualified_names));
+        set("tee_commands", Value::Boolean(self.tee_commands));
+        if let Some(manifest) = &self.manifest {
+            set("manifest", path(manifest));
+        }
+        if let Some(dir) = &self.allure_dir {
+            set("allure_dir", path(dir));
+        }
+        set("run_info", Value::Boolean(self.run_info));
+        set("report_env", strings(&self.report_env));
+        if let Some(history) = &self.history {
+            set("history", path(history));
+        }
+        set("slow_threshold", Value::Float(self.slow_threshold));
+        if let Some(shard) = &self.shard {
+            set("shard", Value::String(shard.to_string()));
+        }
+        match ["default", "unicode", "plain"]
+            .into_iter()
+            .find(|name| Style::named(name).as_ref() == Some(&self.style))
+        {
+            Some(name) => set("style", Value::String(String::from(name))),
+            None => return Err(unwritable("a style other than the named ones")),
+        }
+        set("align", Value::Boolean(self.align));
+        set("cluster_failures", Value::Boolean(self.cluster_failures));
+        set("case_jobs", Value::Integer(self.case_jobs as i64));
+        if let Some(max_len) = self.max_failure_len {
+            set("max_failure_len", Value::Integer(max_len as i64));
+        }
+        if let Some(max_failures) = self.summarize_cases {
+            set("summarize_cases", Value::Integer(max_failures as i64));
+        }
+        if let Some(timeout) = self.suite_timeout {
+            set("suite_timeout", Value::Float(timeout.as_secs_f64()));
+        }
+        #[cfg(all(feature = "capture", unix))]
+        set("capture", Value::Boolean(self.capture));
+        #[cfg(any(feature = "log", feature = "tracing"))]
+        set("capture_logs", Value::Boolean(self.capture_logs));
+        set("vars", strings_table(&self.vars));
+        set("metadata", strings_table(&self.metadata));
+        set(
+            "command_args",
+            Value::Table(
+                self.command_args
+                    .iter()
+                    .map(|(program, args)| (program.clone(), strings(args)))
+                    .collect(),
+            ),
+        );
+
+        Ok(table)
+    }
+}
+
+/// Serializes the options of the configuration as they are written by
+/// [`to_toml`](TestConfig::to_toml), failing on the same options.
+///
+/// > *This is only available with the `config-file` and `serde` features enabled.*
+#[cfg(feature = "serde")]
+impl serde::Serialize for TestConfig {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.to_table()
+            .map_err(serde::ser::Error::custom)?
+            .serialize(serializer)
+    }
+}
+
+/// Build a configuration from TOML `contents` with the options of the named `preset` applied
+/// over the top-level ones, reading overrides with `env`.
+fn from_toml(
+    contents: &str,
+    preset: Option<&str>,
+    env: impl Fn(&str) -> Option<String>,
+) -> Result<TestConfig, Error> {
+    let mut table: Table = contents
+        .parse()
+        .map_err(|e: toml::de::Error| Error::Config(e.message().to_string()))?;
+
+    let presets = match table.remove("presets") {
+        Some(Value::Table(presets)) => presets,
+        Some(_) => return Err(invalid("presets", "a table")),
+        None => Table::new(),
+    };
+    if let Some(name) = preset {
+        let Some(Value::Table(options)) = presets.get(name) else {
+            return Err(Error::Config(format!("unknown preset '{}'", name)));
+        };
+
+        for (key, value) in options.clone() {
+            match (table.get_mut(&key), value) {
+                (Some(Value::Table(entries)), Value::Table(overrides)) => entries.extend(overrides),
+                (_, value) => {
+                    table.insert(key, value);
+                }
+            }
+        }
+    }
+
+    for key in KEYS {
+        if let Some(raw) = env(&format!("EXTEL_{}", key.to_uppercase())) {
+            table.insert(key.to_string(), env_value(key, &raw)?);
+        }
+    }
+
+    let mut cfg = TestConfig::default();
+    let mut seed: Option<u64> = None;
+    for (key, value) in table {
+        match key.as_str() {
+            "output" => cfg.output = OutputDest::parse(as_str(&key, &value)?),
+            "color" => {
+                cfg.colored = match value {
+                    Value::Boolean(yes) => Some(yes),
+                    Value::String(s) if s == "auto" => None,
+                    _ => return Err(invalid(&key, "true, false, or \"auto\"")),
+                }
+            }
+            "filter" => cfg.filter = Some(as_str(&key, &value)?.to_string()),
+            "exact" => cfg.exact = Some(as_str(&key, &value)?.to_string()),
+            "tags" => cfg.tags = as_strings(&key, &value)?,
+            "progress" => cfg.progress = as_bool(&key, &value)?,
+            "rerun_hint" => cfg.rerun_hint = as_bool(&key, &value)?,
+            "order" => {
+                cfg.order = match as_str(&key, &value)? {
+                    "declared" => TestOrder::Declared,
+                    "alphabetical" => TestOrder::Alphabetical,
+                    "shuffled" => TestOrder::Shuffled(0),
+                    "failed_first" => TestOrder::FailedFirst,
+                    "slowest_first" => TestOrder::SlowestFirst,
+                    _ => {
+                        return Err(invalid(
+                            &key,
+                            "\"declared\", \"alphabetical\", \"shuffled\", \"failed_first\", \
+                             or \"slowest_first\"",
+                        ))
+                    }
+                }
+            }
+            "seed" => match value {
+                Value::Integer(n) if n >= 0 => seed = Some(n as u64),
+                Value::String(s) if s.parse::<u64>().is_ok() => seed = s.parse().ok(),
+                _ => return Err(invalid(&key, "a non-negative integer")),
+            },
+            "state_file" => {
+                cfg.state_file = match value {
+                    Value::Boolean(false) => None,
+                    Value::Boolean(true) => Some(state::DEFAULT_STATE_FILE.into()),
+                    Value::String(path) => Some(path.into()),
+                    _ => return Err(invalid(&key, "a path, true, or false")),
+                }
+            }
+            "rerun_failed" => cfg.rerun_failed = as_bool(&key, &value)?,
+            "artifacts_dir" => cfg.artifacts_dir = Some(as_str(&key, &value)?.into()),
+            "verbose" => cfg.verbose = as_bool(&key, &value)?,
+            "isolate_env" => cfg.isolate_env = as_bool(&key, &value)?,
+            "isolate_process" => cfg.isolate_process = as_bool(&key, &value)?,
+            "wrap_command" => {
+                cfg.wrapper = Some(match &value {
+                    Value::String(s) => CommandWrapper::parse(s),
+                    other => CommandWrapper::from_args(as_strings(&key, other)?),
+                })
+            }
+            "executor" => cfg.executor = Some(executor::parse(as_str(&key, &value)?)?),
+            "record_commands" => cfg = cfg.cassette(as_str(&key, &value)?, CassetteMode::Record),
+            "replay_commands" => cfg = cfg.cassette(as_str(&key, &value)?, CassetteMode::Replay),
+            "dry_run" => cfg.dry_run = as_bool(&key, &value)?,
+            "qualified_names" => cfg.qualified_names = as_bool(&key, &value)?,
+            "tee_commands" => cfg.tee_commands = as_bool(&key, &value)?,
+            "manifest" => cfg.manifest = Some(as_str(&key, &value)?.into()),
+            "allure_dir" => cfg.allure_dir = Some(as_str(&key, &value)?.into()),
+            "run_info" => cfg.run_info = as_bool(&key, &value)?,
+            "report_env" => cfg.report_env = as_strings(&key, &value)?,
+            "history" => cfg.history = Some(as_str(&key, &value)?.into()),
+            "shard" => cfg.shard = Some(as_str(&key, &value)?.parse()?),
+            "align" => cfg.align = as_bool(&key, &value)?,
+            "cluster_failures" => cfg.cluster_failures = as_bool(&key, &value)?,
+            "case_jobs" => match value {
+                Value::Integer(n) if n >= 0 => cfg.case_jobs = n as usize,
+                _ => return Err(invalid(&key, "a non-negative integer")),
+            },
+            "max_failure_len" => match value {
+                Value::Integer(n) if n >= 0 => cfg.max_failure_len = Some(n as usize),
+                _ => return Err(invalid(&key, "a non-negative integer")),
+            },
+            "summarize_cases" => match value {
+                Value::Integer(n) if n >= 0 => cfg.summarize_cases = Some(n as usize),
+                _ => return Err(invalid(&key, "a non-negative integer")),
+            },
+            "suite_timeout" => {
+                let secs = match value {
+                    Value::Integer(n) => n as f64,
+                    Value::Float(n) => n,
+                    _ => return Err(invalid(&key, "a number of seconds")),
+                };
+                cfg.suite_timeout = Some(
+                    Duration::try_from_secs_f64(secs)
+                        .map_err(|_| invalid(&key, "a number of seconds"))?,
+                );
+            }
+            "slow_threshold" => {
+                cfg.slow_threshold = match value {
+                    Value::Integer(n) => n as f64,
+                    Value::Float(n) => n,
+                    _ => return Err(invalid(&key, "a number")),
+                };
+            }
+            "style" => {
+                cfg.style = Style::named(as_str(&key, &value)?)
+                    .ok_or_else(|| invalid(&key, "\"default\", \"unicode\", or \"plain\""))?
+            }
+            #[cfg(all(feature = "capture", unix))]
+            "capture" => cfg.capture = as_bool(&key, &value)?,
+            #[cfg(any(feature = "log", feature = "tracing"))]
+            "capture_logs" => cfg.capture_logs = as_bool(&key, &value)?,
+            "vars" | "metadata" => {
+                let Value::Table(entries) = value else {
+                    return Err(invalid(&key, "a table"));
+                };
+
+                for (name, entry) in entries {
+                    let entry = match entry {
+                        Value::String(s) => s,
+                        other => other.to_string(),
+                    };
+                    match key.as_str() {
+                        "vars" => cfg.vars.insert(name, entry),
+                        _ => cfg.metadata.insert(name, entry),
+                    };
+                }
+            }
+            "command_args" => {
+                let Value::Table(entries) = value else {
+                    return Err(invalid(&key, "a table"));
+                };
+
+                for (program, args) in entries {
+                    let args = match &args {
+                        Value::String(s) => s.split_whitespace().map(String::from).collect(),
+                        other => as_strings(&format!("command_args.{}", program), other)?,
+                    };
+                    cfg = cfg.command_args(program, args);
+                }
+            }
+            _ => return Err(Error::Config(format!("unknown key '{}'", key))),
+        }
+    }
+
+    if let TestOrder::Shuffled(_) = cfg.order {
+        cfg.order = TestOrder::Shuffled(seed.unwrap_or_else(order::random_seed));
+    }
+
+    Ok(cfg)
+}
+
+/// Convert the raw value of an environment variable override into the TOML type expected for
+/// `key`.
+fn env_value(key: &str, raw: &str) -> Result<Value, Error> {
+    Ok(match key {
+        "progress" | "rerun_hint" | "rerun_failed" | "verbose" | "isolate_env"
+        | "isolate_process" | "dry_run" | "qualified_names" | "tee_commands" | "align"
+        | "cluster_failures" | "capture" | "capture_logs" | "run_info" => {
+            Value::Boolean(parse_bool(key, raw)?)
+        }
+        "color" | "state_file" => match parse_bool(key, raw) {
+            Ok(yes) => Value::Boolean(yes),
+            Err(_) => Value::String(raw.to_string()),
+        },
+        "tags" | "report_env" => Value::Array(
+            raw.split(',')
+                .map(str::trim)
+                .filter(|tag| !tag.is_empty())
+                .map(|tag| Value::String(tag.to_string()))
+                .collect(),
+        ),
+        // Seeds too large for a TOML integer are read as strings, like they are written.
+        "seed" => match raw.parse::<i64>() {
+            Ok(n) => Value::Integer(n),
+            Err(_) => Value::String(String::from(raw)),
+        },
+        "case_jobs" | "max_failure_len" | "summarize_cases" => Value::Integer(
+            raw.parse()
+                .map_err(|_| invalid(key, "a non-negative integer"))?,
+        ),
+        "slow_threshold" => Value::Float(raw.parse().map_err(|_| invalid(key, "a number"))?),
+        "suite_timeout" => Value::Float(
+            raw.parse()
+                .map_err(|_| invalid(key, "a number of seconds"))?,
+        ),
+        _ => Value::String(raw.to_string()),
+    })
+}
+
+fn parse_bool(key: &str, raw: &str) -> Result<bool, Error> {
+    match raw {
+        "true" | "1" => Ok(true),
+        "false" | "0" => Ok(false),
+        _ => Err(invalid(key, "true or false")),
+    }
+}
+
+fn as_str<'v>(key: &str, value: &'v Value) -> Result<&'v str, Error> {
+    value.as_str().ok_or_else(|| invalid(key, "a string"))
+}
+
+fn as_bool(key: &str, value: &Value) -> Result<bool, Error> {
+    value.as_bool().ok_or_else(|| invalid(key, "true or false"))
+}
+
+fn as_strings(key: &str, value: &Value) -> Result<Vec<String>, Error> {
+    value
+        .as_array()
+        .and_then(|values| {
+            values
+                .iter()
+                .map(|v| v.as_str().map(String::from))
+                .collect()
+        })
+        .ok_or_else(|| invalid(key, "a list of strings"))
+}
+
+fn invalid(key: &str, expected: &str) -> Error {
+    Error::Config(format!("'{}' must be {}", key, expected))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CONFIG: &str = r#"
+        output = "none"
+        color = false
+        tags = ["smoke", "fast"]
+        order = "shuffled"
+        seed = 7
+        state_file = false
+        case_jobs = 4
+        suite_timeout = 1.5
+
+        [vars]
+        server = "localhost:8080"
+        retries = 3
+
+        [command_args]
+        app = ["--config", "test.toml"]
+        "*" = "-v"
+    "#;
+
+    #[test]
+    fn from_toml_reads_every_option() {
+        let cfg = from_toml(CONFIG, None, |_| None).unwrap();
+
+        assert!(matches!(cfg.output, OutputDest::None));
+        assert_eq!(cfg.colored, Some(false));
+        assert_eq!(cfg.tags, vec!["smoke", "fast"]);
+        assert_eq!(cfg.order, TestOrder::Shuffled(7));
+        assert_eq!(cfg.state_file, None);
+        assert_eq!(cfg.case_jobs, 4);
+        assert_eq!(cfg.suite_timeout, Some(Duration::from_millis(1500)));
+        assert_eq!(cfg.vars["server"], "localhost:8080");
+        assert_eq!(cfg.vars["retries"], "3");
+        assert_eq!(cfg.command_args["app"], ["--config", "test.toml"]);
+        assert_eq!(cfg.command_args["*"], ["-v"]);
+    }
+
+    #[test]
+    fn env_overrides_file() {
+        let cfg = from_toml(CONFIG, None, |key| match key {
+            "EXTEL_COLOR" => Some(String::from("true")),
+            "EXTEL_TAGS" => Some(String::from("slow, network")),
+            "EXTEL_ORDER" => Some(String::from("alphabetical")),
+            _ => None,
+        })
+        .unwrap();
+
+        assert_eq!(cfg.colored, Some(true));
+        assert_eq!(cfg.tags, vec!["slow", "network"]);
+        assert_eq!(cfg.order, TestOrder::Alphabetical);
+    }
+
+    #[test]
+    fn presets_apply_over_the_file_and_under_the_env() {
+        let contents = format!(
+            "{}\n[presets.ci]\ncolor = true\nprogress = true\n[presets.ci.vars]\nserver = \"ci:8080\"\n",
+            CONFIG
+        );
+        let cfg = from_toml(&contents, Some("ci"), |key| match key {
+            "EXTEL_PROGRESS" => Some(String::from("false")),
+            _ => None,
+        })
+        .unwrap();
+
+        assert_eq!(cfg.colored, Some(true));
+        assert!(!cfg.progress);
+        assert_eq!(cfg.vars["server"], "ci:8080");
+        assert_eq!(cfg.vars["retries"], "3");
+        assert_eq!(cfg.tags, vec!["smoke", "fast"]);
+    }
+
+    #[test]
+    fn to_toml_round_trips() {
+        let cfg = from_toml(CONFIG, None, |_| None).unwrap();
+        let loaded = from_toml(&cfg.to_toml().unwrap(), None, |_| None).unwrap();
+        assert_eq!(loaded.to_toml().unwrap(), cfg.to_toml().unwrap());
+        assert_eq!(loaded.order, TestOrder::Shuffled(7));
+
+        let mut cfg = cfg.order(TestOrder::Shuffled(u64::MAX));
+        cfg.executor = Some(executor::parse("docker:db").unwrap());
+        cfg.wrapper = Some(CommandWrapper::from_args(
+            ["valgrind", "--log-file=a b.log", "--error-exitcode=99"]
+                .map(String::from)
+                .to_vec(),
+        ));
+        let loaded = from_toml(&cfg.to_toml().unwrap(), None, |_| None).unwrap();
+        assert_eq!(loaded.to_toml().unwrap(), cfg.to_toml().unwrap());
+        assert_eq!(loaded.order, TestOrder::Shuffled(u64::MAX));
+        assert_eq!(loaded.executor.unwrap().spec(), Some("docker:db"));
+        assert_eq!(loaded.wrapper, cfg.wrapper);
+        assert_eq!(loaded.wrapper.unwrap().error_code, Some(99));
+    }
+
+    #[test]
+    fn unwritable_options_fail_to_toml() {
+        let buffer = TestConfig::default().output(OutputDest::Buffer(Default::default()));
+        assert_eq!(
+            buffer.to_toml().unwrap_err().to_string(),
+            "invalid configuration: a buffer output cannot be written to a config file"
+        );
+
+        let local = TestConfig::default().executor(executor::Local);
+        assert!(local.to_toml().is_err());
+
+        let normalized = TestConfig::default().normalize(crate::normalize::Normalizer::new());
+        assert!(normalized.to_toml().is_err());
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn configs_serialize_like_to_toml() {
+        let cfg = from_toml(CONFIG, None, |_| None).unwrap();
+        let value = serde_json::to_value(&cfg).unwrap();
+        assert_eq!(value["seed"], 7);
+        assert_eq!(value["tags"], serde_json::json!(["smoke", "fast"]));
+
+        let hooked = cfg.on_failure("uptime", |_| String::from("up"));
+        assert!(serde_json::to_value(&hooked).is_err());
+    }
+
+    #[test]
+    fn invalid_config_is_rejected() {
+        let err = from_toml("colour = true", None, |_| None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid configuration: unknown key 'colour'"
+        );
+
+        let err = from_toml("progress = \"yes\"", None, |_| None).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "invalid configuration: 'progress' must be true or false"
+        );
+    }
+}
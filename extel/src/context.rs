@@ -0,0 +1,293 @@
+//! A handle to the test that is currently running, giving it access to runtime information such
+//! as its name, where to put files, and configuration values.
+//!
+//! Tests can receive the handle as an argument by taking a `&TestContext`, or get it from anywhere
+//! while the test runs with [`TestContext::current`].
+//!
+//! ```rust
+//! use extel::{context::TestContext, prelude::*, OutputDest};
+//!
+//! fn uses_context(ctx: &TestContext) -> ExtelResult {
+//!     ctx.log(format_args!("running {}", ctx.name()))?;
+//!     extel_assert!(ctx.var("server").as_deref() == Some("localhost:8080"))
+//! }
+//!
+//! fn stateless() -> ExtelResult {
+//!     pass!()
+//! }
+//!
+//! init_test_suite!(ContextSuite, uses_context, stateless);
+//! let results = ContextSuite::run(
+//!     TestConfig::default()
+//!         .output(OutputDest::None)
+//!         .var("server", "localhost:8080"),
+//! );
+//!
+//! assert!(results.iter().all(|result| result.test_result.is_ok()));
+//! ```
+
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fmt::Display,
+    fs::OpenOptions,
+    io::{self, Write},
+    path::PathBuf,
+    sync::Arc,
+};
+
+use crate::{
+    artifacts,
+    cases::CaseStream,
+    cassette::Tape,
+    command::{CommandRecord, CommandWrapper},
+    executor::CommandExecutor,
+    normalize::Normalizer,
+    step::StepRecord,
+    usage::ResourceUsage,
+};
+
+thread_local! {
+    static CURRENT_SCOPE: RefCell<Option<Scope>> = const { RefCell::new(None) };
+}
+
+/// The state of the test currently running on a thread.
+pub(crate) struct Scope {
+    pub(crate) suite_name: String,
+    pub(crate) test_name: String,
+    pub(crate) artifact_dir: PathBuf,
+    pub(crate) verbose: bool,
+    pub(crate) vars: HashMap<String, String>,
+    pub(crate) wrapper: Option<CommandWrapper>,
+    pub(crate) executor: Option<Arc<dyn CommandExecutor>>,
+    pub(crate) tape: Option<Arc<Tape>>,
+    pub(crate) dry_run: bool,
+    pub(crate) command_args: HashMap<String, Vec<String>>,
+    pub(crate) tee_commands: bool,
+    pub(crate) case_jobs: usize,
+    pub(crate) case_stream: Option<Arc<CaseStream>>,
+    /// The number (from 1) of the only case to run, as selected by
+    /// [`TestConfig::exact`](crate::TestConfig::exact).
+    pub(crate) only_case: Option<usize>,
+    pub(crate) normalizer: Option<Normalizer>,
+    #[cfg(all(
+        feature = "sandbox",
+        target_os = "linux",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    ))]
+    pub(crate) sandbox: Option<crate::sandbox::SandboxProfile>,
+    pub(crate) recorded: Recorded,
+}
+
+impl Scope {
+    /// A copy of the scope with nothing recorded, for running part of the test on another thread.
+    pub(crate) fn fork(&self) -> Self {
+        Self {
+            suite_name: self.suite_name.clone(),
+            test_name: self.test_name.clone(),
+            artifact_dir: self.artifact_dir.clone(),
+            verbose: self.verbose,
+            vars: self.vars.clone(),
+            wrapper: self.wrapper.clone(),
+            executor: self.executor.clone(),
+            tape: self.tape.clone(),
+            dry_run: self.dry_run,
+            command_args: self.command_args.clone(),
+            tee_commands: self.tee_commands,
+            case_jobs: self.case_jobs,
+            case_stream: self.case_stream.clone(),
+            only_case: self.only_case,
+            normalizer: self.normalizer.clone(),
+            #[cfg(all(
+                feature = "sandbox",
+                target_os = "linux",
+                any(target_arch = "x86_64", target_arch = "aarch64")
+            ))]
+            sandbox: self.sandbox.clone(),
+            recorded: Recorded::default(),
+        }
+    }
+}
+
+/// Everything a test recorded about itself while it ran, to be moved onto its result.
+#[derive(Debug, Default)]
+pub(crate) struct Recorded {
+    pub(crate) artifacts: Vec<PathBuf>,
+    pub(crate) resource_usage: Vec<ResourceUsage>,
+    pub(crate) metadata: HashMap<String, String>,
+    pub(crate) commands: Vec<CommandRecord>,
+    pub(crate) logs: Vec<String>,
+    pub(crate) steps: Vec<StepRecord>,
+}
+
+impl Recorded {
+    /// Add everything recorded by a [forked](Scope::fork) scope.
+    pub(crate) fn merge(&mut self, other: Recorded) {
+        for path in other.artifacts {
+            if !self.artifacts.contains(&path) {
+                self.artifacts.push(path);
+            }
+        }
+        self.resource_usage.extend(other.resource_usage);
+        self.metadata.extend(other.metadata);
+        self.commands.extend(other.commands);
+        self.logs.extend(other.logs);
+        self.steps.extend(other.steps);
+    }
+}
+
+/// Run `f` with access to the scope of the test currently running on this thread, if any.
+pub(crate) fn with_scope<R>(f: impl FnOnce(Option<&mut Scope>) -> R) -> R {
+    CURRENT_SCOPE.with_borrow_mut(|scope| f(scope.as_mut()))
+}
+
+/// Run `f` as the test described by `scope`, returning its result along with everything it
+/// recorded while it ran.
+pub(crate) fn scoped<T>(scope: Scope, f: impl FnOnce() -> T) -> (T, Recorded) {
+    let previous = CURRENT_SCOPE.replace(Some(scope));
+    let value = f();
+    let scope = CURRENT_SCOPE.replace(previous);
+
+    (value, scope.map(|s| s.recorded).unwrap_or_default())
+}
+
+/// A handle to the test currently running on this thread. Outside of a test run, the handle
+/// reports empty values and discards anything set through it.
+///
+/// # Example
+/// ```rust
+/// use extel::{context::TestContext, prelude::*, OutputDest};
+///
+/// fn versioned() -> ExtelResult {
+///     let ctx = TestContext::current();
+///     ctx.set_metadata("server_version", "1.4.2");
+///     pass!()
+/// }
+///
+/// init_test_suite!(VersionSuite, versioned);
+/// let results = VersionSuite::run(TestConfig::default().output(OutputDest::None));
+///
+/// assert_eq!(results[0].metadata["server_version"], "1.4.2");
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct TestContext {
+    _private: (),
+}
+
+impl TestContext {
+    /// Get a handle to the test currently running on this thread.
+    pub fn current() -> Self {
+        Self { _private: () }
+    }
+
+    /// The name of the current test.
+    pub fn name(&self) -> String {
+        with_scope(|scope| scope.map(|s| s.test_name.clone()).unwrap_or_default())
+    }
+
+    /// The name of the suite the current test belongs to.
+    pub fn suite_name(&self) -> String {
+        with_scope(|scope| scope.map(|s| s.suite_name.clone()).unwrap_or_default())
+    }
+
+    /// The directory that the current test's [artifacts](crate::artifacts) are written to. The
+    /// directory is only created once an artifact is requested.
+    pub fn artifact_dir(&self) -> PathBuf {
+        with_scope(|scope| match scope {
+            Some(scope) => scope.artifact_dir.clone(),
+            None => artifacts::default_dir(),
+        })
+    }
+
+    /// Whether or not the run is in [verbose mode](crate::TestConfig::verbose).
+    pub fn verbose(&self) -> bool {
+        with_scope(|scope| scope.is_some_and(|s| s.verbose))
+    }
+
+    /// Get a [configuration value](crate::TestConfig::var) by key.
+    pub fn var(&self, key: &str) -> Option<String> {
+        with_scope(|scope| scope.and_then(|s| s.vars.get(key).cloned()))
+    }
+
+    /// Append a line to the current test's `test.log` artifact.
+    pub fn log(&self, line: impl Display) -> io::Result<()> {
+        let path = artifacts::artifact_path("test.log")?;
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        writeln!(file, "{}", line)
+    }
+
+    /// Attach a key/value pair to the result of the current test, replacing any previous value
+    /// for `key`. Metadata is printed by the default reporter.
+    pub fn set_metadata(&self, key: impl Into<String>, value: impl Into<String>) {
+        with_scope(|scope| {
+            if let Some(scope) = scope {
+                scope.recorded.metadata.insert(key.into(), value.into());
+            }
+        });
+    }
+}
+
+/// Sort metadata by key so that it is always reported in the same order.
+pub(crate) fn sorted(metadata: &HashMap<String, String>) -> Vec<(&String, &String)> {
+    let mut entries: Vec<_> = metadata.iter().collect();
+    entries.sort();
+    entries
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fixture::TempDir, prelude::*, OutputDest, SharedBuffer};
+
+    fn tagged_failure() -> ExtelResult {
+        TestContext::current().set_metadata("git_sha", "abc123");
+        fail!("tagged failure")
+    }
+
+    fn logs_through_context(ctx: &TestContext) -> ExtelResult {
+        ctx.log("first")?;
+        ctx.log(format_args!("{} in {}", ctx.name(), ctx.suite_name()))?;
+        extel_assert!(ctx.verbose())
+    }
+
+    #[test]
+    fn metadata_is_reported() {
+        init_test_suite!(MetadataTestSet as "metadata", tagged_failure);
+
+        let output_buffer = SharedBuffer::new();
+        let results = MetadataTestSet::run(
+            TestConfig::default()
+                .output(OutputDest::Buffer(output_buffer.clone()))
+                .colored(false)
+                .rerun_hint(false)
+                .metadata("runner", "ci"),
+        );
+
+        assert_eq!(results[0].metadata["git_sha"], "abc123");
+        assert_eq!(
+            output_buffer.to_string_lossy(),
+            "[metadata]\n\t[runner: ci]\n\
+             \tTest #1 (tagged_failure) ... FAILED\n\t  [x] tagged failure\n\t  [git_sha: abc123]\n"
+        );
+    }
+
+    #[test]
+    fn context_is_passed_to_tests() {
+        init_test_suite!(ContextTestSet as "context", logs_through_context);
+
+        let tmp = TempDir::new().unwrap();
+        let results = ContextTestSet::run(
+            TestConfig::default()
+                .output(OutputDest::None)
+                .artifacts_dir(tmp.path())
+                .verbose(true),
+        );
+
+        assert!(results[0].test_result.is_ok());
+        assert_eq!(results[0].artifacts.len(), 1);
+        assert_eq!(
+            std::fs::read_to_string(&results[0].artifacts[0]).unwrap(),
+            "first\nlogs_through_context in context\n"
+        );
+    }
+}
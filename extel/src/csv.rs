@@ -0,0 +1,222 @@
+//! Assertions on tabular output, such as the CSV or TSV written by data-pipeline programs, by row,
+//! column, and cell rather than with string operations.
+//!
+//! The first line of the output is read as the header, and rows are numbered from 0 after it.
+//! Columns are picked out by header name or by index. CSV fields may be quoted as described in
+//! RFC 4180, while TSV fields are taken as they are.
+//!
+//! ```rust
+//! use extel::{csv::Table, prelude::*};
+//!
+//! fn exports_users() -> ExtelResult {
+//!     let output = cmd!("printf" => ["id,name\n1,\"Doe, Jane\"\n2,Bob\n"]).output()?;
+//!
+//!     assert_csv_column!(output.stdout, "name")?;
+//!     assert_csv_rows!(output.stdout, 2)?;
+//!     assert_csv_cell!(output.stdout, 0, "name", "Doe, Jane")?;
+//!     assert_csv_cell!(output.stdout, 1, 0, "2")
+//! }
+//!
+//! fn exports_tsv() -> ExtelResult {
+//!     let output = cmd!("printf" => ["id\tname\n1\tJane\n"]).output()?;
+//!     Table::tsv(output.stdout)?.expect_cell(0, "name", "Jane")
+//! }
+//!
+//! assert!(exports_users().is_ok());
+//! assert!(exports_tsv().is_ok());
+//! ```
+
+use crate::{
+    diff::{abbreviate, MESSAGE_LIMIT},
+    err,
+    errors::Error,
+    ExtelResult,
+};
+
+/// A column of a [`Table`], given by its header name or by its index.
+pub trait Column {
+    /// The index of the column in `table`, if it has one.
+    fn index(&self, table: &Table) -> Option<usize>;
+
+    /// How the column is named in failure messages.
+    fn describe(&self) -> String;
+}
+
+impl Column for usize {
+    fn index(&self, table: &Table) -> Option<usize> {
+        (*self < table.header.len()).then_some(*self)
+    }
+
+    fn describe(&self) -> String {
+        format!("column {}", self)
+    }
+}
+
+impl Column for &str {
+    fn index(&self, table: &Table) -> Option<usize> {
+        table.header.iter().position(|name| name == self)
+    }
+
+    fn describe(&self) -> String {
+        format!("column {:?}", self)
+    }
+}
+
+/// Tabular output, made of a header and rows of fields.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Table {
+    header: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Parse comma-separated values, failing if the output is not UTF-8 or ends inside a quoted
+    /// field.
+    pub fn csv(output: impl AsRef<[u8]>) -> Result<Self, Error> {
+        Self::parse(output.as_ref(), ',', true)
+    }
+
+    /// Parse tab-separated values, failing if the output is not UTF-8.
+    pub fn tsv(output: impl AsRef<[u8]>) -> Result<Self, Error> {
+        Self::parse(output.as_ref(), '\t', false)
+    }
+
+    fn parse(output: &[u8], delimiter: char, quoted: bool) -> Result<Self, Error> {
+        let text = std::str::from_utf8(output).map_err(|e| err!("output is not UTF-8: {}", e))?;
+        let mut records = Vec::new();
+        let (mut record, mut field) = (Vec::new(), String::new());
+        let (mut in_quotes, mut chars) = (false, text.chars().peekable());
+
+        while let Some(c) = chars.next() {
+            match c {
+                '"' if quoted && in_quotes && chars.peek() == Some(&'"') => {
+                    chars.next();
+                    field.push('"');
+                }
+                '"' if quoted && (in_quotes || field.is_empty()) => in_quotes = !in_quotes,
+                c if in_quotes => field.push(c),
+                c if c == delimiter => record.push(std::mem::take(&mut field)),
+                '\r' if chars.peek() == Some(&'\n') => {}
+                '\n' => {
+                    record.push(std::mem::take(&mut field));
+                    records.push(std::mem::take(&mut record));
+                }
+                c => field.push(c),
+            }
+        }
+        if in_quotes {
+            return Err(err!("output ends inside a quoted field"));
+        }
+        if !field.is_empty() || !record.is_empty() {
+            record.push(field);
+            records.push(record);
+        }
+
+        let mut records = records.into_iter();
+        Ok(Self {
+            header: records.next().unwrap_or_default(),
+            rows: records.collect(),
+        })
+    }
+
+    /// The names of the columns.
+    pub fn header(&self) -> &[String] {
+        &self.header
+    }
+
+    /// The rows after the header.
+    pub fn rows(&self) -> &[Vec<String>] {
+        &self.rows
+    }
+
+    /// The field of `row` in `column`, if there is one.
+    pub fn cell(&self, row: usize, column: impl Column) -> Option<&str> {
+        let column = column.index(self)?;
+        self.rows.get(row)?.get(column).map(String::as_str)
+    }
+
+    /// Check that there are `expected` rows after the header.
+    pub fn expect_rows(&self, expected: usize) -> ExtelResult {
+        match self.rows.len() == expected {
+            true => Ok(()),
+            false => Err(err!("expected {} rows, got {}", expected, self.rows.len())),
+        }
+    }
+
+    /// Check that the header has a column named `name`.
+    pub fn expect_column(&self, name: &str) -> ExtelResult {
+        match self.header.iter().any(|column| column == name) {
+            true => Ok(()),
+            false => Err(err!("no column {:?} in header {:?}", name, self.header)),
+        }
+    }
+
+    /// Check that the field of `row` in `column` is `expected`.
+    pub fn expect_cell(&self, row: usize, column: impl Column, expected: &str) -> ExtelResult {
+        let index = column
+            .index(self)
+            .ok_or_else(|| err!("no {} in header {:?}", column.describe(), self.header))?;
+        let actual = self
+            .rows
+            .get(row)
+            .ok_or_else(|| err!("no row {} (there are {} rows)", row, self.rows.len()))?
+            .get(index)
+            .map(String::as_str)
+            .unwrap_or_default();
+
+        match actual == expected {
+            true => Ok(()),
+            false => Err(err!(
+                "row {}, {}: expected {}, got {}",
+                row,
+                column.describe(),
+                abbreviate(expected, MESSAGE_LIMIT),
+                abbreviate(actual, MESSAGE_LIMIT)
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quoted_fields_are_unquoted() {
+        let table = Table::csv("a,b\r\n\"x, \"\"y\"\"\",\"multi\nline\"\r\n,\n").unwrap();
+        assert_eq!(table.header(), ["a", "b"]);
+        assert_eq!(table.cell(0, "a"), Some("x, \"y\""));
+        assert_eq!(table.cell(0, 1), Some("multi\nline"));
+        assert_eq!(table.cell(1, "b"), Some(""));
+        assert_eq!(table.rows().len(), 2);
+
+        assert!(Table::csv("a\n\"open").is_err());
+    }
+
+    #[test]
+    fn tsv_fields_are_taken_as_they_are() {
+        let table = Table::tsv("a\tb\n\"x\"\ty z").unwrap();
+        assert_eq!(table.cell(0, "a"), Some("\"x\""));
+        assert_eq!(table.cell(0, "b"), Some("y z"));
+    }
+
+    #[test]
+    fn failures_name_the_cell() {
+        let table = Table::csv("id,name\n1,Jane\n").unwrap();
+
+        let err = |result: ExtelResult| result.unwrap_err().to_string();
+        assert_eq!(
+            err(table.expect_cell(0, "name", "Bob")),
+            "row 0, column \"name\": expected \"Bob\", got \"Jane\""
+        );
+        assert_eq!(
+            err(table.expect_cell(0, "email", "")),
+            "no column \"email\" in header [\"id\", \"name\"]"
+        );
+        assert_eq!(
+            err(table.expect_cell(3, 0, "1")),
+            "no row 3 (there are 1 rows)"
+        );
+        assert_eq!(err(table.expect_rows(2)), "expected 2 rows, got 1");
+    }
+}
@@ -0,0 +1,242 @@
+//! Management of long-running background processes, such as servers that a client binary under
+//! test needs to talk to.
+
+use std::{
+    net::SocketAddr,
+    process::{Child, Command, ExitStatus, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    command::{capture, Capture, Stream},
+    err,
+    errors::Error,
+    wait::{port_open, POLL_INTERVAL},
+};
+
+/// The condition that signals a [`Daemon`] is ready to be used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Readiness {
+    /// The daemon is ready as soon as it has been spawned.
+    Immediate,
+    /// The daemon is ready once a TCP connection to the address succeeds.
+    Port(SocketAddr),
+    /// The daemon is ready once the given text appears in its stdout or stderr.
+    LogLine(String),
+    /// The daemon is ready once the gRPC server at `addr` reports `service` as serving through
+    /// the [standard health service](crate::grpc). An empty `service` means the whole server.
+    ///
+    /// > *This is only available with the `grpc` feature enabled.*
+    #[cfg(feature = "grpc")]
+    GrpcHealth { addr: SocketAddr, service: String },
+}
+
+/// A background process that is killed when dropped.
+///
+/// The daemon's stdout and stderr are captured and can be inspected at any point with
+/// [`Daemon::stdout`] and [`Daemon::stderr`], which is useful for explaining why a test against
+/// the daemon failed.
+///
+/// Dropping the daemon kills only the spawned process. When starting a daemon through a shell,
+/// use `exec` so that the shell is replaced by the daemon rather than leaving it orphaned.
+///
+/// # Example
+/// ```rust
+/// use extel::{daemon::{Daemon, Readiness}, prelude::*};
+/// use std::time::Duration;
+///
+/// fn client_against_server() -> ExtelResult {
+///     let server = Daemon::spawn(
+///         cmd!("sh" => ["-c", "echo listening; exec sleep 30"]),
+///         Readiness::LogLine("listening".into()),
+///         Duration::from_secs(5),
+///     )?;
+///
+///     extel_assert!(server.stdout().contains("listening"))
+/// }
+///
+/// assert!(client_against_server().is_ok());
+/// ```
+pub struct Daemon {
+    child: Child,
+    stdout: Capture,
+    stderr: Capture,
+}
+
+impl Daemon {
+    /// Spawn `command` in the background and block until `ready` is satisfied. Fails with an
+    /// [`Error::Timeout`] if the daemon is not ready within `timeout`, or with an
+    /// [`Error::TestFailed`] if it exits before becoming ready. In both cases, the process is
+    /// killed before returning.
+    pub fn spawn(
+        command: &mut Command,
+        ready: Readiness,
+        timeout: Duration,
+    ) -> Result<Self, Error> {
+        let mut child = command
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        let mut daemon = Self {
+            stdout: capture(child.stdout.take(), Stream::Stdout),
+            stderr: capture(child.stderr.take(), Stream::Stderr),
+            child,
+        };
+
+        let deadline = Instant::now() + timeout;
+        loop {
+            if daemon.is_ready(&ready) {
+                return Ok(daemon);
+            }
+
+            if let Some(status) = daemon.child.try_wait()? {
+                daemon.stderr.join();
+                return Err(err!(
+                    "daemon exited with {} before becoming ready\n{}",
+                    status,
+                    daemon.stderr()
+                ));
+            }
+
+            if Instant::now() >= deadline {
+                return Err(Error::Timeout {
+                    timeout,
+                    stdout: daemon.stdout.snapshot(),
+                    stderr: daemon.stderr.snapshot(),
+                });
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    fn is_ready(&self, ready: &Readiness) -> bool {
+        match ready {
+            Readiness::Immediate => true,
+            Readiness::Port(addr) => port_open(addr),
+            #[cfg(feature = "grpc")]
+            Readiness::GrpcHealth { addr, service } => crate::grpc::is_serving(*addr, service),
+            Readiness::LogLine(line) => {
+                self.stdout().contains(line.as_str()) || self.stderr().contains(line.as_str())
+            }
+        }
+    }
+
+    /// The OS-assigned process identifier of the daemon.
+    pub fn id(&self) -> u32 {
+        self.child.id()
+    }
+
+    /// Everything the daemon has written to stdout so far, lossily converted to UTF-8.
+    pub fn stdout(&self) -> String {
+        String::from_utf8_lossy(&self.stdout.snapshot()).into_owned()
+    }
+
+    /// Everything the daemon has written to stderr so far, lossily converted to UTF-8.
+    pub fn stderr(&self) -> String {
+        String::from_utf8_lossy(&self.stderr.snapshot()).into_owned()
+    }
+
+    /// Check whether the daemon has exited, returning its exit status if so.
+    pub fn try_wait(&mut self) -> Result<Option<ExitStatus>, Error> {
+        Ok(self.child.try_wait()?)
+    }
+
+    /// Kill the daemon and wait for it to exit.
+    pub fn kill(mut self) -> Result<ExitStatus, Error> {
+        self.child.kill()?;
+        Ok(self.child.wait()?)
+    }
+}
+
+impl Drop for Daemon {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd;
+    use std::net::TcpListener;
+
+    #[test]
+    fn daemon_waits_for_log_line() {
+        let daemon = Daemon::spawn(
+            cmd!("sh" => ["-c", "echo starting; sleep 0.2; echo ready >&2; exec sleep 30"]),
+            Readiness::LogLine("ready".into()),
+            Duration::from_secs(5),
+        )
+        .unwrap();
+
+        assert_eq!(daemon.stdout(), "starting\n");
+        assert_eq!(daemon.stderr(), "ready\n");
+    }
+
+    #[test]
+    fn daemon_waits_for_port() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let daemon = Daemon::spawn(
+            &mut cmd!("sleep 30"),
+            Readiness::Port(listener.local_addr().unwrap()),
+            Duration::from_secs(5),
+        );
+
+        assert!(daemon.is_ok());
+    }
+
+    #[test]
+    fn daemon_exits_before_ready() {
+        let result = Daemon::spawn(
+            cmd!("sh" => ["-c", "echo failed to bind >&2; exit 1"]),
+            Readiness::LogLine("ready".into()),
+            Duration::from_secs(5),
+        );
+
+        match result {
+            Err(Error::TestFailed(msg)) => assert!(msg.ends_with("failed to bind\n")),
+            _ => panic!("expected the daemon to fail"),
+        }
+    }
+
+    #[test]
+    fn daemon_killed_on_drop() {
+        let daemon = Daemon::spawn(
+            &mut cmd!("sleep 30"),
+            Readiness::Immediate,
+            Duration::from_secs(5),
+        )
+        .unwrap();
+
+        let is_running = |pid: u32| {
+            cmd!("kill" => ["-0", &pid.to_string()])
+                .stderr(std::process::Stdio::null())
+                .status()
+                .unwrap()
+                .success()
+        };
+        let pid = daemon.id();
+        assert!(is_running(pid));
+
+        drop(daemon);
+        assert!(!is_running(pid));
+    }
+
+    #[test]
+    fn daemon_kill_reports_exit_status() {
+        let mut daemon = Daemon::spawn(
+            &mut cmd!("sleep 30"),
+            Readiness::Immediate,
+            Duration::from_secs(5),
+        )
+        .unwrap();
+
+        assert!(daemon.try_wait().unwrap().is_none());
+        assert!(!daemon.kill().unwrap().success());
+    }
+}
@@ -0,0 +1,231 @@
+//! Enforcing a wall-clock budget for a whole suite. See
+//! [`TestConfig::suite_timeout`](crate::TestConfig::suite_timeout).
+
+use std::{
+    io::Write,
+    sync::{Arc, Condvar, Mutex},
+    thread,
+    time::{Duration, Instant},
+};
+
+/// The exit status of a process aborted because a test was still running when the suite timeout
+/// ran out.
+pub(crate) const EXIT_CODE: i32 = crate::harness::EXIT_TIMED_OUT as i32;
+
+#[derive(Default)]
+struct State {
+    in_flight: Option<String>,
+    pending: Vec<String>,
+    finished: bool,
+}
+
+/// A thread that aborts the process if a test is still running once the suite's budget runs out,
+/// after letting the runner report the suite as it stands. Tests that had not started by then
+/// are reported by the runner as not run instead.
+pub(crate) struct Watchdog {
+    timeout: Duration,
+    deadline: Instant,
+    state: Arc<(Mutex<State>, Condvar)>,
+}
+
+impl Watchdog {
+    /// Start watching a run of `suite_name` that may take up to `timeout`. If a test is still
+    /// running once it runs out, `on_abort` is called with the reason the remaining tests did not
+    /// finish, and then the process exits. Until then, no test can start or finish.
+    pub(crate) fn start(
+        suite_name: &str,
+        timeout: Duration,
+        pending: Vec<String>,
+        on_abort: impl FnOnce(&str) + Send + 'static,
+    ) -> Self {
+        let deadline = Instant::now() + timeout;
+        let state = Arc::new((
+            Mutex::new(State {
+                pending,
+                ..Default::default()
+            }),
+            Condvar::new(),
+        ));
+
+        let watched = Arc::clone(&state);
+        let suite_name = suite_name.to_string();
+        thread::spawn(move || {
+            let (lock, finished) = &*watched;
+            let mut state = lock.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            loop {
+                let now = Instant::now();
+                if state.finished {
+                    return;
+                }
+                if now >= deadline {
+                    break;
+                }
+
+                state = finished
+                    .wait_timeout(state, deadline - now)
+                    .unwrap_or_else(|poisoned| poisoned.into_inner())
+                    .0;
+            }
+
+            // Between tests, the runner reports the remaining tests as not run by itself.
+            let Some(test_name) = &state.in_flight else {
+                return;
+            };
+
+            on_abort(&not_run_reason(timeout));
+            let _ = std::io::stdout().flush();
+            eprintln!(
+                "{}",
+                abort_message(&suite_name, timeout, test_name, &state.pending)
+            );
+            std::process::exit(EXIT_CODE);
+        });
+
+        Self {
+            timeout,
+            deadline,
+            state,
+        }
+    }
+
+    /// Whether or not the budget has run out.
+    pub(crate) fn expired(&self) -> bool {
+        Instant::now() >= self.deadline
+    }
+
+    /// The reason given for tests that were not run because the budget ran out.
+    pub(crate) fn not_run_reason(&self) -> String {
+        not_run_reason(self.timeout)
+    }
+
+    /// Mark `test_name` as running.
+    pub(crate) fn enter(&self, test_name: &str) {
+        let mut state = self.lock();
+        state.pending.retain(|name| name != test_name);
+        state.in_flight = Some(test_name.to_string());
+    }
+
+    /// Mark the running test as finished.
+    pub(crate) fn exit(&self) {
+        self.lock().in_flight = None;
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Drop for Watchdog {
+    fn drop(&mut self) {
+        self.lock().finished = true;
+        self.state.1.notify_all();
+    }
+}
+
+fn not_run_reason(timeout: Duration) -> String {
+    format!("suite timeout of {:?} exceeded", timeout)
+}
+
+fn abort_message(
+    suite_name: &str,
+    timeout: Duration,
+    in_flight: &str,
+    pending: &[String],
+) -> String {
+    let mut msg = format!(
+        "error: {} while running '{}' in suite '{}'; aborting",
+        not_run_reason(timeout),
+        in_flight,
+        suite_name
+    );
+    if !pending.is_empty() {
+        msg.push_str(&format!(
+            "\n{} test(s) were not run: {}",
+            pending.len(),
+            pending.join(", ")
+        ));
+    }
+    msg
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, process::Command};
+
+    use super::*;
+    use crate::{fixture::TempDir, prelude::*};
+
+    /// Set to the manifest path in the process running [`hung_suite`] for real.
+    const CHILD_ENV: &str = "EXTEL_TEST_HUNG_SUITE";
+
+    #[test]
+    fn hung_suite() {
+        let Some(manifest) = std::env::var_os(CHILD_ENV) else {
+            return;
+        };
+
+        fn finishes() -> ExtelResult {
+            pass!()
+        }
+
+        fn hangs() -> ExtelResult {
+            thread::sleep(Duration::from_secs(30));
+            pass!()
+        }
+
+        fn never_starts() -> ExtelResult {
+            pass!()
+        }
+
+        init_test_suite!(HungSuite as "hung", finishes, hangs, never_starts);
+        HungSuite::run(
+            TestConfig::default()
+                .colored(false)
+                .rerun_hint(false)
+                .manifest(manifest)
+                .suite_timeout(Duration::from_millis(300)),
+        );
+    }
+
+    #[test]
+    fn timed_out_suites_are_finished_before_exiting() {
+        let tmp = TempDir::new().unwrap();
+        let manifest = tmp.path().join("manifest.jsonl");
+        let output = Command::new(std::env::current_exe().unwrap())
+            .args(["deadline::tests::hung_suite", "--exact", "--nocapture"])
+            .env(CHILD_ENV, &manifest)
+            .output()
+            .unwrap();
+
+        assert_eq!(output.status.code(), Some(EXIT_CODE));
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        assert!(stdout.contains("(finishes) ... ok"), "{}", stdout);
+        assert!(stdout.contains("(hangs) ... FAILED"), "{}", stdout);
+        assert!(stdout.contains("(never_starts) ... FAILED"), "{}", stdout);
+
+        let manifest = fs::read_to_string(&manifest).unwrap();
+        assert!(
+            manifest.contains(r#"{"name":"hangs","passed":false"#),
+            "{}",
+            manifest
+        );
+        assert!(String::from_utf8_lossy(&output.stderr).contains("while running 'hangs'"));
+    }
+
+    #[test]
+    fn abort_message_names_in_flight_and_pending_tests() {
+        assert_eq!(
+            abort_message(
+                "Service",
+                Duration::from_secs(30),
+                "hangs",
+                &[String::from("after_a"), String::from("after_b")]
+            ),
+            "error: suite timeout of 30s exceeded while running 'hangs' in suite 'Service'; \
+             aborting\n2 test(s) were not run: after_a, after_b"
+        );
+    }
+}
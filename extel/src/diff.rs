@@ -0,0 +1,183 @@
+//! Line-based diffing and abbreviation used to explain mismatches between expected and actual
+//! output.
+
+/// The number of characters of a value shown in an assertion failure message before it is
+/// [abbreviated](abbreviate).
+pub const MESSAGE_LIMIT: usize = 200;
+
+/// Format `text` for a failure message, keeping only its first `max_chars` characters and noting
+/// how many were left out.
+///
+/// # Example
+/// ```rust
+/// use extel::diff::abbreviate;
+///
+/// assert_eq!(abbreviate("hello", 10), "\"hello\"");
+/// assert_eq!(abbreviate("hello world", 5), "\"hello\"... (6 more chars)");
+/// ```
+pub fn abbreviate(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!(
+            "{:?}... ({} more chars)",
+            &text[..end],
+            text[end..].chars().count()
+        ),
+        None => format!("{:?}", text),
+    }
+}
+
+/// Like [`abbreviate`], but keeps the last `max_chars` characters of `text` instead of the first.
+///
+/// # Example
+/// ```rust
+/// use extel::diff::abbreviate_tail;
+///
+/// assert_eq!(abbreviate_tail("hello world", 5), "(6 more chars) ...\"world\"");
+/// ```
+pub fn abbreviate_tail(text: &str, max_chars: usize) -> String {
+    let skipped = text.chars().count().saturating_sub(max_chars);
+    match text.char_indices().nth(skipped) {
+        Some((start, _)) if skipped > 0 => {
+            format!("({} more chars) ...{:?}", skipped, &text[start..])
+        }
+        _ => format!("{:?}", text),
+    }
+}
+
+/// The most cells of the longest common subsequence table [`line_diff`] builds before it only
+/// reports the first differing line instead.
+pub const DIFF_LIMIT: usize = 4_000_000;
+
+/// Produce a unified-style line diff between `expected` and `actual`. Unchanged lines are prefixed
+/// with a space, removed lines with `-`, and added lines with `+`.
+///
+/// Lines the two have in common at their start and end are not diffed. If the lines between them
+/// are too many to diff (see [`DIFF_LIMIT`]), only the first differing line is shown.
+///
+/// # Example
+/// ```rust
+/// use extel::diff::line_diff;
+///
+/// assert_eq!(line_diff("a\nb\nc", "a\nx\nc"), " a\n-b\n+x\n c\n");
+///
+/// let (expected, actual) = ("same\n".repeat(3000), "other\n".repeat(3000));
+/// assert_eq!(
+///     line_diff(&expected, &actual),
+///     "first difference at line 1:\n-same\n+other\n(2999 more expected and 2999 more actual lines not diffed)\n"
+/// );
+/// ```
+pub fn line_diff(expected: &str, actual: &str) -> String {
+    let expected: Vec<&str> = expected.lines().collect();
+    let actual: Vec<&str> = actual.lines().collect();
+
+    let prefix = expected
+        .iter()
+        .zip(&actual)
+        .take_while(|(e, a)| e == a)
+        .count();
+    let suffix = expected[prefix..]
+        .iter()
+        .rev()
+        .zip(actual[prefix..].iter().rev())
+        .take_while(|(e, a)| e == a)
+        .count();
+    let (middle_expected, middle_actual) = (
+        &expected[prefix..expected.len() - suffix],
+        &actual[prefix..actual.len() - suffix],
+    );
+
+    if (middle_expected.len() + 1).saturating_mul(middle_actual.len() + 1) > DIFF_LIMIT {
+        return first_difference(prefix, middle_expected, middle_actual);
+    }
+
+    let mut diff = String::new();
+    for line in &expected[..prefix] {
+        diff.push_str(&format!(" {}\n", line));
+    }
+    diff.push_str(&lcs_diff(middle_expected, middle_actual));
+    for line in &expected[expected.len() - suffix..] {
+        diff.push_str(&format!(" {}\n", line));
+    }
+
+    diff
+}
+
+/// Describe the first differing line of the differing lines `expected` and `actual`, which come
+/// after `prefix` equal lines.
+fn first_difference(prefix: usize, expected: &[&str], actual: &[&str]) -> String {
+    let mut diff = format!("first difference at line {}:\n", prefix + 1);
+    if let Some(line) = expected.first() {
+        diff.push_str(&format!("-{}\n", line));
+    }
+    if let Some(line) = actual.first() {
+        diff.push_str(&format!("+{}\n", line));
+    }
+    diff.push_str(&format!(
+        "({} more expected and {} more actual lines not diffed)\n",
+        expected.len().saturating_sub(1),
+        actual.len().saturating_sub(1)
+    ));
+    diff
+}
+
+/// Diff `expected` and `actual` through their longest common subsequence.
+fn lcs_diff(expected: &[&str], actual: &[&str]) -> String {
+    let (n, m) = (expected.len(), actual.len());
+
+    // lcs[i][j] holds the length of the longest common subsequence of expected[i..] and
+    // actual[j..].
+    let mut lcs = vec![vec![0usize; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lcs[i][j] = match expected[i] == actual[j] {
+                true => lcs[i + 1][j + 1] + 1,
+                false => lcs[i + 1][j].max(lcs[i][j + 1]),
+            };
+        }
+    }
+
+    let mut diff = String::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n || j < m {
+        if i < n && j < m && expected[i] == actual[j] {
+            diff.push_str(&format!(" {}\n", expected[i]));
+            i += 1;
+            j += 1;
+        } else if i < n && (j == m || lcs[i + 1][j] >= lcs[i][j + 1]) {
+            diff.push_str(&format!("-{}\n", expected[i]));
+            i += 1;
+        } else {
+            diff.push_str(&format!("+{}\n", actual[j]));
+            j += 1;
+        }
+    }
+
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn line_diff_identical() {
+        assert_eq!(line_diff("a\nb", "a\nb"), " a\n b\n");
+    }
+
+    #[test]
+    fn line_diff_insertions_and_removals() {
+        assert_eq!(line_diff("a\nb", "b\nc"), "-a\n b\n+c\n");
+        assert_eq!(line_diff("", "a"), "+a\n");
+        assert_eq!(line_diff("a", ""), "-a\n");
+    }
+
+    #[test]
+    fn line_diff_keeps_common_ends_of_large_outputs() {
+        let expected = format!("{}old\n{}", "head\n".repeat(5000), "tail\n".repeat(5000));
+        let actual = format!("{}new\n{}", "head\n".repeat(5000), "tail\n".repeat(5000));
+
+        let diff = line_diff(&expected, &actual);
+        assert_eq!(diff.lines().count(), 10_002);
+        assert!(diff.contains(" head\n-old\n+new\n tail\n"));
+    }
+}
@@ -0,0 +1,371 @@
+//! Running containers for tests that need a service only distributed as an image, such as a
+//! database, through the `docker` command-line client.
+//!
+//! A [`Container`] is removed when it is dropped, so its lifetime follows the scope that started
+//! it. Start one inside a test, or in a [fixture](crate::fixture), for a container per test, or
+//! start one in `main` before running the suite for a container shared by every test, passing its
+//! mapped ports to the tests as [variables](crate::TestConfig::var):
+//!
+//! ```rust,no_run
+//! use extel::{context::TestContext, docker::Container, prelude::*};
+//!
+//! fn connects_to_database(ctx: &TestContext) -> ExtelResult {
+//!     let port = ctx.var("db_port").unwrap_or_default();
+//!     let output = cmd!("target/debug/app migrate --db-port {}", port).output_checked()?;
+//!     extel_assert!(output.status.success())
+//! }
+//!
+//! init_test_suite!(DatabaseSuite, connects_to_database);
+//!
+//! fn main() {
+//!     let db = Container::builder("postgres:16")
+//!         .env("POSTGRES_PASSWORD", "extel")
+//!         .port(5432)
+//!         .wait_for_log("ready to accept connections")
+//!         .start()
+//!         .expect("could not start postgres");
+//!
+//!     DatabaseSuite::run(
+//!         extel::cli::parse_args().var("db_port", db.host_port(5432).unwrap().to_string()),
+//!     );
+//! }
+//! ```
+//!
+//! > *This is only available with the `docker` feature enabled.*
+
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    net::{Ipv4Addr, SocketAddr},
+    process::{Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    command::CommandExt,
+    err,
+    errors::Error,
+    executor::DockerExec,
+    wait::{port_open, POLL_INTERVAL},
+};
+
+/// How long a container may take to become ready by default.
+pub const DEFAULT_READY_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// What signals that a started container is ready to be used.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Readiness {
+    Log(String),
+    Port(u16),
+}
+
+/// Configures and starts a [`Container`]. Created with [`Container::builder`].
+#[derive(Debug, Clone)]
+pub struct ContainerBuilder {
+    program: OsString,
+    image: String,
+    env: Vec<(String, String)>,
+    ports: Vec<u16>,
+    args: Vec<String>,
+    readiness: Vec<Readiness>,
+    timeout: Duration,
+}
+
+impl ContainerBuilder {
+    /// Set the environment variable `key` to `value` in the container.
+    pub fn env(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.env.push((key.into(), value.into()));
+        self
+    }
+
+    /// Publish the TCP `port` of the container on a free port of the host's loopback interface.
+    /// See [`Container::host_port`].
+    pub fn port(mut self, port: u16) -> Self {
+        self.ports.push(port);
+        self
+    }
+
+    /// Pass `arg` to the container's command, after the image name.
+    pub fn arg(mut self, arg: impl Into<String>) -> Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Wait until `text` appears in the container's logs before [`start`](Self::start) returns.
+    pub fn wait_for_log(mut self, text: impl Into<String>) -> Self {
+        self.readiness.push(Readiness::Log(text.into()));
+        self
+    }
+
+    /// Wait until the published container `port` accepts TCP connections before
+    /// [`start`](Self::start) returns. The port is published if it was not already.
+    pub fn wait_for_port(mut self, port: u16) -> Self {
+        if !self.ports.contains(&port) {
+            self.ports.push(port);
+        }
+        self.readiness.push(Readiness::Port(port));
+        self
+    }
+
+    /// Change how long the container may take to become ready. Defaults to
+    /// [`DEFAULT_READY_TIMEOUT`].
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Use another Docker-compatible client, such as `podman`, instead of `docker`.
+    pub fn program(mut self, program: impl Into<OsString>) -> Self {
+        self.program = program.into();
+        self
+    }
+
+    /// Start the container and wait until it is ready. A container that exits or does not become
+    /// ready in time is removed, and the error includes its logs.
+    pub fn start(self) -> Result<Container, Error> {
+        let output = Command::new(&self.program)
+            .args(self.run_args())
+            .stdin(Stdio::null())
+            .output_checked()?;
+        if !output.status.success() {
+            return Err(err!(
+                "could not start {}: {}",
+                self.image,
+                String::from_utf8_lossy(&output.stderr).trim()
+            ));
+        }
+
+        let mut container = Container {
+            program: self.program.clone(),
+            id: String::from_utf8_lossy(&output.stdout).trim().to_string(),
+            ports: HashMap::new(),
+        };
+        for &port in &self.ports {
+            let host_port = container.lookup_port(port)?;
+            container.ports.insert(port, host_port);
+        }
+
+        let deadline = Instant::now() + self.timeout;
+        loop {
+            let logs = container.logs()?;
+            let ready = self.readiness.iter().all(|ready| match ready {
+                Readiness::Log(text) => logs.contains(text.as_str()),
+                Readiness::Port(port) => container.addr(*port).is_some_and(|a| port_open(&a)),
+            });
+            if ready {
+                return Ok(container);
+            }
+
+            if !container.is_running()? {
+                return Err(err!(
+                    "{} exited before becoming ready\n{}",
+                    self.image,
+                    logs.trim_end()
+                ));
+            }
+            if Instant::now() >= deadline {
+                return Err(Error::Timeout {
+                    timeout: self.timeout,
+                    stdout: logs.into_bytes(),
+                    stderr: Vec::new(),
+                });
+            }
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// The arguments of the `docker run` command that starts the container.
+    fn run_args(&self) -> Vec<String> {
+        let mut args = vec![String::from("run"), String::from("--detach")];
+        for (key, value) in &self.env {
+            args.extend([String::from("--env"), format!("{}={}", key, value)]);
+        }
+        for port in &self.ports {
+            args.extend([String::from("--publish"), format!("127.0.0.1::{}", port)]);
+        }
+        args.push(self.image.clone());
+        args.extend(self.args.iter().cloned());
+        args
+    }
+}
+
+/// A running container, which is forcibly removed when dropped.
+#[derive(Debug)]
+pub struct Container {
+    program: OsString,
+    id: String,
+    ports: HashMap<u16, u16>,
+}
+
+impl Container {
+    /// Start configuring a container running `image`.
+    pub fn builder(image: impl Into<String>) -> ContainerBuilder {
+        ContainerBuilder {
+            program: OsString::from("docker"),
+            image: image.into(),
+            env: Vec::new(),
+            ports: Vec::new(),
+            args: Vec::new(),
+            readiness: Vec::new(),
+            timeout: DEFAULT_READY_TIMEOUT,
+        }
+    }
+
+    /// The ID of the container.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The host port that the container `port` is published on, if it was
+    /// [published](ContainerBuilder::port).
+    pub fn host_port(&self, port: u16) -> Option<u16> {
+        self.ports.get(&port).copied()
+    }
+
+    /// The loopback address that the container `port` is published on, if it was
+    /// [published](ContainerBuilder::port).
+    pub fn addr(&self, port: u16) -> Option<SocketAddr> {
+        self.host_port(port)
+            .map(|host_port| SocketAddr::from((Ipv4Addr::LOCALHOST, host_port)))
+    }
+
+    /// An [executor](crate::executor) that runs commands in the container, to run the commands of
+    /// a suite in it with [`TestConfig::executor`](crate::TestConfig::executor).
+    pub fn executor(&self) -> DockerExec {
+        DockerExec::new(&self.id).program(&self.program)
+    }
+
+    /// Everything the container has written to stdout and stderr so far.
+    pub fn logs(&self) -> Result<String, Error> {
+        let output = self.docker(&["logs", &self.id])?;
+        let mut logs = String::from_utf8_lossy(&output.stdout).into_owned();
+        logs.push_str(&String::from_utf8_lossy(&output.stderr));
+        Ok(logs)
+    }
+
+    /// A command that runs `program` inside the container with `docker exec`.
+    pub fn exec(&self, program: &str) -> Command {
+        let mut command = Command::new(&self.program);
+        command.args(["exec", &self.id, program]);
+        command
+    }
+
+    fn docker(&self, args: &[&str]) -> Result<std::process::Output, Error> {
+        Command::new(&self.program)
+            .args(args)
+            .stdin(Stdio::null())
+            .output_checked()
+    }
+
+    fn is_running(&self) -> Result<bool, Error> {
+        let output = self.docker(&["inspect", "--format", "{{.State.Running}}", &self.id])?;
+        Ok(String::from_utf8_lossy(&output.stdout).trim() == "true")
+    }
+
+    fn lookup_port(&self, port: u16) -> Result<u16, Error> {
+        let output = self.docker(&["port", &self.id, &format!("{}/tcp", port)])?;
+        parse_host_port(&String::from_utf8_lossy(&output.stdout))
+            .ok_or_else(|| err!("container port {} was not published", port))
+    }
+}
+
+impl Drop for Container {
+    fn drop(&mut self) {
+        let _ = Command::new(&self.program)
+            .args(["rm", "--force", "--volumes", &self.id])
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status();
+    }
+}
+
+/// The host port in the output of `docker port`, such as `127.0.0.1:49153`.
+fn parse_host_port(output: &str) -> Option<u16> {
+    output
+        .lines()
+        .find_map(|line| line.trim().rsplit_once(':')?.1.parse().ok())
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::fixture::TempDir;
+    use std::{fs, os::unix::fs::PermissionsExt};
+
+    /// A stand-in for the docker client that records its calls in `calls` and answers like a
+    /// container that logs `ready` and publishes every port on 49153.
+    fn fake_docker(tmp: &TempDir) -> std::path::PathBuf {
+        let path = tmp.path().join("docker");
+        fs::write(
+            &path,
+            format!(
+                "#!/bin/sh\n\
+                 echo \"$@\" >> {calls}\n\
+                 case \"$1\" in\n\
+                   run) echo c0ffee ;;\n\
+                   port) echo 127.0.0.1:49153 ;;\n\
+                   logs) echo starting; echo ready >&2 ;;\n\
+                   inspect) echo true ;;\n\
+                 esac\n",
+                calls = tmp.path().join("calls").display()
+            ),
+        )
+        .unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[test]
+    fn containers_start_and_are_removed() {
+        let tmp = TempDir::new().unwrap();
+        let container = Container::builder("postgres:16")
+            .program(fake_docker(&tmp))
+            .env("POSTGRES_PASSWORD", "extel")
+            .port(5432)
+            .arg("-c")
+            .arg("fsync=off")
+            .wait_for_log("ready")
+            .start()
+            .unwrap();
+
+        assert_eq!(container.id(), "c0ffee");
+        assert_eq!(container.host_port(5432), Some(49153));
+        assert_eq!(container.addr(80), None);
+        drop(container);
+
+        let calls = fs::read_to_string(tmp.path().join("calls")).unwrap();
+        assert_eq!(
+            calls,
+            "run --detach --env POSTGRES_PASSWORD=extel --publish 127.0.0.1::5432 postgres:16 -c fsync=off\n\
+             port c0ffee 5432/tcp\n\
+             logs c0ffee\n\
+             rm --force --volumes c0ffee\n"
+        );
+    }
+
+    #[test]
+    fn unready_containers_time_out() {
+        let tmp = TempDir::new().unwrap();
+        let result = Container::builder("redis")
+            .program(fake_docker(&tmp))
+            .wait_for_log("never logged")
+            .timeout(Duration::from_millis(50))
+            .start();
+
+        match result {
+            Err(Error::Timeout { stdout, .. }) => assert_eq!(stdout, b"starting\nready\n"),
+            other => panic!("expected a timeout, got {:?}", other),
+        }
+        let calls = fs::read_to_string(tmp.path().join("calls")).unwrap();
+        assert!(calls.ends_with("rm --force --volumes c0ffee\n"));
+    }
+
+    #[test]
+    fn host_ports_are_parsed() {
+        assert_eq!(parse_host_port("127.0.0.1:49153\n"), Some(49153));
+        assert_eq!(parse_host_port("[::1]:8080"), Some(8080));
+        assert_eq!(parse_host_port(""), None);
+    }
+}
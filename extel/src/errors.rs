@@ -1,6 +1,14 @@
 //! Extel errors built using `thiserror`.
 
-use std::{io, string::FromUtf8Error};
+use std::{
+    env::VarError,
+    error::Error as StdError,
+    io,
+    num::{ParseFloatError, ParseIntError},
+    str::Utf8Error,
+    string::FromUtf8Error,
+    time::Duration,
+};
 use thiserror::Error;
 
 /// An Extel error type. Allows error propagation with [`ExtelResult`](crate::ExtelResult). Note
@@ -21,8 +29,14 @@ use thiserror::Error;
 ///     extel_assert!(2 < 0, "test failed")
 /// }
 ///
+/// fn bad_number() -> ExtelResult {
+///     let n: i32 = "forty-two".parse()?;
+///     extel_assert!(n == 42)
+/// }
+///
 /// assert!(matches!(bad_file(), Err(Error::Io(_))));
 /// assert!(matches!(bad_test(), Err(Error::TestFailed(_))));
+/// assert!(matches!(bad_number(), Err(Error::ParseInt(_))));
 /// ```
 #[derive(Error, Debug)]
 pub enum Error {
@@ -32,4 +46,206 @@ pub enum Error {
     Io(#[from] io::Error),
     #[error("invalid conversion from UTF-8 ocurred")]
     FromUtf8(#[from] FromUtf8Error),
+    #[error("invalid UTF-8 ocurred: {0}")]
+    Utf8(#[from] Utf8Error),
+    #[error("could not parse integer: {0}")]
+    ParseInt(#[from] ParseIntError),
+    #[error("could not parse float: {0}")]
+    ParseFloat(#[from] ParseFloatError),
+    #[error("could not read environment variable: {0}")]
+    EnvVar(#[from] VarError),
+    /// > *This is only available with the `serde` feature enabled.*
+    #[cfg(feature = "serde")]
+    #[error("could not (de)serialize JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("command timed out after {timeout:?}")]
+    Timeout {
+        timeout: Duration,
+        stdout: Vec<u8>,
+        stderr: Vec<u8>,
+    },
+    /// A command was killed by a signal, such as `SIGSEGV` or `SIGABRT`, rather than exiting.
+    /// Only produced on Unix platforms.
+    #[error(
+        "command crashed: killed by signal {signal} ({})",
+        crate::command::signal_name(*signal).unwrap_or("unknown signal")
+    )]
+    CommandCrashed {
+        signal: i32,
+        stdout: Vec<u8>,
+        stderr: Vec<u8>,
+    },
+    /// A [command wrapper](crate::command::CommandWrapper) such as valgrind reported errors in
+    /// the command it ran.
+    #[error("{wrapper} reported errors in the command it ran")]
+    WrapperFailed {
+        wrapper: String,
+        stdout: Vec<u8>,
+        stderr: Vec<u8>,
+    },
+    /// A test was not run, such as when the [suite timeout](crate::TestConfig::suite_timeout)
+    /// ran out before it started.
+    #[error("not run: {0}")]
+    NotRun(String),
+    /// A test was skipped because the machine running it cannot run it, such as when a
+    /// [requirement](crate::require) is not met. Unlike other errors, a skipped test does not
+    /// fail the run.
+    #[error("skipped: {0}")]
+    Skipped(String),
+    #[error("test panicked: {0}")]
+    Panicked(String),
+    #[error("{0}")]
+    Other(#[from] Box<dyn StdError + Send + Sync>),
+    #[error("sandbox violation: {0}")]
+    SandboxViolation(String),
+    #[error("invalid configuration: {0}")]
+    Config(String),
+}
+
+impl Error {
+    /// Wrap any third-party error in an [`Error::Other`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::errors::Error;
+    /// use std::fmt;
+    ///
+    /// #[derive(Debug)]
+    /// struct LibraryError;
+    ///
+    /// impl fmt::Display for LibraryError {
+    ///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    ///         write!(f, "the library failed")
+    ///     }
+    /// }
+    ///
+    /// impl std::error::Error for LibraryError {}
+    ///
+    /// let err = Error::other(LibraryError);
+    /// assert!(matches!(err, Error::Other(_)));
+    /// assert_eq!(err.to_string(), "the library failed");
+    /// ```
+    pub fn other(err: impl Into<Box<dyn StdError + Send + Sync>>) -> Self {
+        Self::Other(err.into())
+    }
+}
+
+/// > *This is only available with the `anyhow` feature enabled.*
+#[cfg(feature = "anyhow")]
+impl From<anyhow::Error> for Error {
+    fn from(err: anyhow::Error) -> Self {
+        Self::Other(err.into())
+    }
+}
+
+/// An extension trait for converting results carrying any third-party error into results that
+/// can be propagated out of an Extel test with `?`.
+///
+/// # Example
+/// ```rust
+/// use extel::prelude::*;
+/// use std::fmt;
+///
+/// #[derive(Debug)]
+/// struct LibraryError;
+///
+/// impl fmt::Display for LibraryError {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         write!(f, "the library failed")
+///     }
+/// }
+///
+/// impl std::error::Error for LibraryError {}
+///
+/// fn call_library() -> Result<usize, LibraryError> {
+///     Err(LibraryError)
+/// }
+///
+/// fn library_test() -> ExtelResult {
+///     let n = call_library().into_extel()?;
+///     extel_assert!(n == 0)
+/// }
+///
+/// assert!(matches!(library_test(), Err(Error::Other(_))));
+/// ```
+pub trait IntoExtelResult<T> {
+    /// Convert the error, if any, into an [`Error::Other`].
+    fn into_extel(self) -> Result<T, Error>;
+}
+
+impl<T, E> IntoExtelResult<T> for Result<T, E>
+where
+    E: StdError + Send + Sync + 'static,
+{
+    fn into_extel(self) -> Result<T, Error> {
+        self.map_err(Error::other)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{pass, ExtelResult};
+
+    fn parse_all() -> ExtelResult {
+        let _: u8 = "255".parse()?;
+        let _: f64 = "1.5".parse()?;
+        let _ = std::str::from_utf8(b"valid")?;
+        let _ = std::env::var("EXTEL_THIS_VARIABLE_IS_NEVER_SET")?;
+        pass!()
+    }
+
+    #[test]
+    fn std_errors_propagate() {
+        assert!(matches!(
+            parse_all(),
+            Err(Error::EnvVar(VarError::NotPresent))
+        ));
+        assert!(matches!(
+            "x".parse::<f32>().map_err(Error::from),
+            Err(Error::ParseFloat(_))
+        ));
+        let invalid = vec![0xFF];
+        assert!(matches!(
+            std::str::from_utf8(&invalid).map_err(Error::from),
+            Err(Error::Utf8(_))
+        ));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn json_errors_propagate() {
+        assert!(matches!(
+            serde_json::from_str::<serde_json::Value>("{").map_err(Error::from),
+            Err(Error::Json(_))
+        ));
+    }
+
+    #[test]
+    fn boxed_errors_propagate() {
+        fn boxed() -> Result<(), Box<dyn StdError + Send + Sync>> {
+            Err("boxed failure".into())
+        }
+
+        fn boxed_test() -> ExtelResult {
+            boxed()?;
+            pass!()
+        }
+
+        match boxed_test() {
+            Err(e @ Error::Other(_)) => assert_eq!(e.to_string(), "boxed failure"),
+            other => panic!("expected Error::Other, got {:?}", other),
+        }
+    }
+
+    #[cfg(feature = "anyhow")]
+    #[test]
+    fn anyhow_errors_propagate() {
+        fn anyhow_test() -> ExtelResult {
+            Err(anyhow::anyhow!("anyhow failure"))?;
+            pass!()
+        }
+
+        assert!(matches!(anyhow_test(), Err(Error::Other(_))));
+    }
 }
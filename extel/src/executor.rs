@@ -0,0 +1,251 @@
+//! Choosing where the commands built with [`cmd!`](crate::cmd) run, so that a whole suite can be
+//! retargeted to another machine or container without changing its tests. See
+//! [`TestConfig::executor`](crate::TestConfig::executor).
+//!
+//! An executor can also be chosen with the `--executor` flag or the `executor` config key, written
+//! as one of:
+//!
+//! - `local`, to run commands on this machine, which is the default.
+//! - `docker:CONTAINER`, to run commands in a running container with `docker exec`.
+//! - `chroot:DIR`, to run commands with `DIR` as the root directory.
+//! - `ssh:[USER@]HOST[:PORT]`, to run commands on a remote host (`ssh` feature).
+
+use std::{
+    ffi::{OsStr, OsString},
+    fmt,
+    path::PathBuf,
+    process::Command,
+    sync::Arc,
+};
+
+use crate::errors::Error;
+
+/// Something that can run a program, such as this machine, a container, or a remote host.
+///
+/// # Example
+/// ```rust
+/// use extel::{executor::CommandExecutor, prelude::*, OutputDest};
+/// use std::{ffi::{OsStr, OsString}, process::Command};
+///
+/// /// Runs every command with a lower scheduling priority.
+/// #[derive(Debug)]
+/// struct Nice;
+///
+/// impl CommandExecutor for Nice {
+///     fn command(&self, program: &OsStr, args: &[OsString]) -> Command {
+///         let mut command = Command::new("nice");
+///         command.arg(program).args(args);
+///         command
+///     }
+/// }
+///
+/// fn runs_niced() -> ExtelResult {
+///     extel_assert_eq!(cmd!("echo hi").get_program(), "nice")
+/// }
+///
+/// init_test_suite!(NiceSuite, runs_niced);
+/// let results = NiceSuite::run(TestConfig::default().output(OutputDest::None).executor(Nice));
+/// assert!(results[0].test_result.is_ok());
+/// ```
+pub trait CommandExecutor: fmt::Debug + Send + Sync {
+    /// A command that runs `program` with `args` in this executor's environment.
+    fn command(&self, program: &OsStr, args: &[OsString]) -> Command;
+
+    /// The spec this executor was [parsed](parse) from, if it was parsed from one. Only those
+    /// executors can be written back to a config file.
+    fn spec(&self) -> Option<&str> {
+        None
+    }
+}
+
+/// An executor [parsed](parse) from a spec, which it keeps so that it can be written back out.
+#[derive(Debug)]
+struct Parsed {
+    spec: String,
+    executor: Arc<dyn CommandExecutor>,
+}
+
+impl CommandExecutor for Parsed {
+    fn command(&self, program: &OsStr, args: &[OsString]) -> Command {
+        self.executor.command(program, args)
+    }
+
+    fn spec(&self) -> Option<&str> {
+        Some(&self.spec)
+    }
+}
+
+/// Runs commands on this machine.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Local;
+
+impl CommandExecutor for Local {
+    fn command(&self, program: &OsStr, args: &[OsString]) -> Command {
+        let mut command = Command::new(program);
+        command.args(args);
+        command
+    }
+}
+
+/// Runs commands in a running container with `docker exec`, keeping stdin open.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DockerExec {
+    program: OsString,
+    container: String,
+}
+
+impl DockerExec {
+    /// Run commands in `container`, given as a name or an ID.
+    pub fn new(container: impl Into<String>) -> Self {
+        Self {
+            program: OsString::from("docker"),
+            container: container.into(),
+        }
+    }
+
+    /// Use another Docker-compatible client, such as `podman`, instead of `docker`.
+    pub fn program(mut self, program: impl Into<OsString>) -> Self {
+        self.program = program.into();
+        self
+    }
+}
+
+impl CommandExecutor for DockerExec {
+    fn command(&self, program: &OsStr, args: &[OsString]) -> Command {
+        let mut command = Command::new(&self.program);
+        command
+            .args(["exec", "--interactive", &self.container])
+            .arg(program)
+            .args(args);
+        command
+    }
+}
+
+/// Runs commands with `chroot`, using another directory as the root directory. This usually
+/// requires running as root.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chroot {
+    root: PathBuf,
+}
+
+impl Chroot {
+    /// Run commands with `root` as the root directory.
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+}
+
+impl CommandExecutor for Chroot {
+    fn command(&self, program: &OsStr, args: &[OsString]) -> Command {
+        let mut command = Command::new("chroot");
+        command.arg(&self.root).arg(program).args(args);
+        command
+    }
+}
+
+/// Parse an executor written as described in the [module documentation](self), failing with an
+/// [`Error::Config`] if it is not one.
+///
+/// # Example
+/// ```rust
+/// use extel::executor;
+/// use std::ffi::OsStr;
+///
+/// let docker = executor::parse("docker:db").unwrap();
+/// let command = docker.command(OsStr::new("psql"), &[]);
+/// assert_eq!(command.get_args().collect::<Vec<_>>(), ["exec", "--interactive", "db", "psql"]);
+///
+/// assert_eq!(docker.spec(), Some("docker:db"));
+///
+/// assert!(executor::parse("vm:staging").is_err());
+/// ```
+pub fn parse(spec: &str) -> Result<Arc<dyn CommandExecutor>, Error> {
+    let executor = parse_executor(spec)?;
+    Ok(Arc::new(Parsed {
+        spec: spec.to_string(),
+        executor,
+    }))
+}
+
+fn parse_executor(spec: &str) -> Result<Arc<dyn CommandExecutor>, Error> {
+    let invalid = || Error::Config(format!("unknown executor '{}'", spec));
+    if spec == "local" {
+        return Ok(Arc::new(Local));
+    }
+
+    let (kind, target) = spec.split_once(':').ok_or_else(invalid)?;
+    if target.is_empty() {
+        return Err(Error::Config(format!("executor '{}' has no target", spec)));
+    }
+    match kind {
+        "docker" => Ok(Arc::new(DockerExec::new(target))),
+        "chroot" => Ok(Arc::new(Chroot::new(target))),
+        #[cfg(feature = "ssh")]
+        "ssh" => {
+            let remote = match target.rsplit_once(':') {
+                Some((host, port)) => match port.parse() {
+                    Ok(port) => crate::ssh::Remote::new(host).port(port),
+                    Err(_) => return Err(Error::Config(format!("invalid ssh port '{}'", port))),
+                },
+                None => crate::ssh::Remote::new(target),
+            };
+            Ok(Arc::new(remote))
+        }
+        _ => Err(invalid()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(command: &Command) -> Vec<String> {
+        std::iter::once(command.get_program())
+            .chain(command.get_args())
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn executors_prefix_the_program() {
+        let run = |executor: &dyn CommandExecutor| {
+            args(&executor.command(OsStr::new("ls"), &[OsString::from("-l")]))
+        };
+
+        assert_eq!(run(&Local), ["ls", "-l"]);
+        assert_eq!(
+            run(&DockerExec::new("db").program("podman")),
+            ["podman", "exec", "--interactive", "db", "ls", "-l"]
+        );
+        assert_eq!(
+            run(&Chroot::new("/srv/root")),
+            ["chroot", "/srv/root", "ls", "-l"]
+        );
+    }
+
+    #[test]
+    fn executors_are_parsed() {
+        let run = |spec: &str| args(&parse(spec).unwrap().command(OsStr::new("ls"), &[]));
+
+        assert_eq!(run("local"), ["ls"]);
+        assert_eq!(run("chroot:/srv/root"), ["chroot", "/srv/root", "ls"]);
+        #[cfg(feature = "ssh")]
+        assert_eq!(
+            run("ssh:ci@board:2222"),
+            [
+                "ssh",
+                "-o",
+                "BatchMode=yes",
+                "-p",
+                "2222",
+                "ci@board",
+                "--",
+                "'ls'"
+            ]
+        );
+
+        for spec in ["docker", "docker:", "vm:staging"] {
+            assert!(matches!(parse(spec), Err(Error::Config(_))), "{}", spec);
+        }
+    }
+}
@@ -0,0 +1,188 @@
+//! Expectations on every channel of a command's output at once: stdout, stderr, and the exit
+//! code.
+//!
+//! An [`Expectation`] is checked with [`assert_cmd_matches`](crate::assert_cmd_matches). Every
+//! channel that does not match is described in the same failure message, so a regression on
+//! stderr or the exit code is not hidden behind a matching stdout.
+
+use std::process::{Command, Output};
+
+use crate::{
+    bytes,
+    command::CommandExt,
+    diff::{abbreviate, line_diff, MESSAGE_LIMIT},
+    err, normalize, ExtelResult,
+};
+
+/// What is expected of one output stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum StreamCheck {
+    Equals(String),
+    Contains(String),
+}
+
+impl StreamCheck {
+    /// Describe how `actual` differs from the expectation, if it does. Bytes that are not valid
+    /// UTF-8 are [escaped](bytes::lossy), and never equal the expected text. During a test, valid
+    /// UTF-8 is first [normalized](crate::TestConfig::normalize).
+    fn mismatch(&self, actual: &[u8]) -> Option<String> {
+        let actual = normalize::bytes_in_scope(actual);
+        let actual = actual.as_ref();
+        let text = bytes::lossy(actual);
+        match self {
+            Self::Equals(expected) if actual != expected.as_bytes() => Some(format!(
+                "--- expected\n+++ actual\n{}",
+                line_diff(expected, &text).trim_end_matches('\n')
+            )),
+            Self::Contains(needle) if !text.contains(needle.as_str()) => Some(format!(
+                "expected to contain {}\n  actual: {}",
+                abbreviate(needle, MESSAGE_LIMIT),
+                abbreviate(&text, MESSAGE_LIMIT)
+            )),
+            _ => None,
+        }
+    }
+}
+
+/// The expected stdout, stderr, and exit code of a command. Channels without an expectation are
+/// not checked.
+///
+/// # Example
+/// ```rust
+/// use extel::prelude::*;
+///
+/// fn warns_and_succeeds() -> ExtelResult {
+///     assert_cmd_matches!(
+///         cmd!("sh" => ["-c", "echo x; echo 'warn: careful' >&2"]),
+///         Expectation::new().stdout("x\n").exit(0).stderr_contains("warn")
+///     )
+/// }
+///
+/// fn wrong_exit_code() -> ExtelResult {
+///     assert_cmd_matches!(cmd!("false"), Expectation::new().stdout("").exit(0))
+/// }
+///
+/// assert!(warns_and_succeeds().is_ok());
+/// assert_eq!(
+///     wrong_exit_code().unwrap_err().to_string(),
+///     "command output does not match\n[exit code] expected 0, got 1"
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Expectation {
+    stdout: Vec<StreamCheck>,
+    stderr: Vec<StreamCheck>,
+    exit_code: Option<i32>,
+}
+
+impl Expectation {
+    /// An expectation that any output matches.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expect stdout to be exactly `expected`.
+    pub fn stdout(mut self, expected: impl Into<String>) -> Self {
+        self.stdout.push(StreamCheck::Equals(expected.into()));
+        self
+    }
+
+    /// Expect stdout to contain `needle`.
+    pub fn stdout_contains(mut self, needle: impl Into<String>) -> Self {
+        self.stdout.push(StreamCheck::Contains(needle.into()));
+        self
+    }
+
+    /// Expect stderr to be exactly `expected`.
+    pub fn stderr(mut self, expected: impl Into<String>) -> Self {
+        self.stderr.push(StreamCheck::Equals(expected.into()));
+        self
+    }
+
+    /// Expect stderr to contain `needle`.
+    pub fn stderr_contains(mut self, needle: impl Into<String>) -> Self {
+        self.stderr.push(StreamCheck::Contains(needle.into()));
+        self
+    }
+
+    /// Expect the command to exit with `code`.
+    pub fn exit(mut self, code: i32) -> Self {
+        self.exit_code = Some(code);
+        self
+    }
+
+    /// Check the output of a command that already ran, failing with a description of every
+    /// channel that does not match.
+    pub fn check(&self, output: &Output) -> ExtelResult {
+        let mut mismatches = Vec::new();
+
+        if let Some(expected) = self.exit_code {
+            if output.status.code() != Some(expected) {
+                mismatches.push(format!(
+                    "[exit code] expected {}, got {}",
+                    expected,
+                    output
+                        .status
+                        .code()
+                        .map_or_else(|| String::from("none"), |code| code.to_string())
+                ));
+            }
+        }
+
+        for (stream, checks, bytes) in [
+            ("stdout", &self.stdout, &output.stdout),
+            ("stderr", &self.stderr, &output.stderr),
+        ] {
+            for check in checks {
+                if let Some(mismatch) = check.mismatch(bytes) {
+                    mismatches.push(format!("[{}] {}", stream, mismatch));
+                }
+            }
+        }
+
+        match mismatches.is_empty() {
+            true => Ok(()),
+            false => Err(err!(
+                "command output does not match\n{}",
+                mismatches.join("\n")
+            )),
+        }
+    }
+
+    /// Run `command` to completion and [check](Expectation::check) its output.
+    pub fn verify(&self, command: &mut Command) -> ExtelResult {
+        self.check(&command.output_checked()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd;
+
+    #[test]
+    fn every_mismatching_channel_is_reported() {
+        let result = Expectation::new()
+            .stdout("a\nb\n")
+            .stderr_contains("warn")
+            .exit(0)
+            .verify(cmd!("sh" => ["-c", "printf 'a\\nc\\n'; echo oops >&2; exit 3"]));
+
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "command output does not match\n\
+             [exit code] expected 0, got 3\n\
+             [stdout] --- expected\n+++ actual\n a\n-b\n+c\n\
+             [stderr] expected to contain \"warn\"\n  actual: \"oops\\n\""
+        );
+    }
+
+    #[test]
+    fn unchecked_channels_are_ignored() {
+        let result = Expectation::new()
+            .stdout_contains("hello")
+            .verify(cmd!("sh" => ["-c", "echo hello world; echo noise >&2; exit 1"]));
+
+        assert!(result.is_ok());
+    }
+}
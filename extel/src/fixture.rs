@@ -0,0 +1,219 @@
+//! Fixtures that are provisioned before a test runs and torn down after it finishes.
+//!
+//! A fixture is any type implementing [`Fixture`]. Setup happens in [`Fixture::setup`] and
+//! teardown happens when the value is dropped, so every fixture is scoped to the test that
+//! requested it. Tests declare fixtures as arguments and use the `fixtures` attribute macro to
+//! have them injected:
+//!
+//! ```rust
+//! use extel::{fixture::{FreePort, TempDir}, prelude::*};
+//! use extel_parameterized::fixtures;
+//!
+//! #[fixtures]
+//! fn writes_into_temp_dir(tmp: TempDir, port: FreePort) -> ExtelResult {
+//!     let output = cmd!("touch" => [tmp.path().join("out.txt")]).status()?;
+//!     extel_assert!(output.success() && tmp.path().join("out.txt").exists() && port.port() > 0)
+//! }
+//!
+//! assert!(writes_into_temp_dir().is_ok());
+//! ```
+//!
+//! > *The `fixtures` macro is only available with the `fixtures` feature enabled.*
+
+use std::{
+    ffi::OsStr,
+    fs, io,
+    ops::Deref,
+    path::{Path, PathBuf},
+    process,
+    sync::atomic::{AtomicUsize, Ordering},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{errors::Error, net};
+
+/// A value that can be provisioned for a single test. Implementors should release any resources
+/// they hold in their [`Drop`] implementation.
+pub trait Fixture: Sized {
+    /// Create the fixture. An error fails the test before its body runs.
+    fn setup() -> Result<Self, Error>;
+}
+
+/// A uniquely named directory under the system temporary directory. The directory and all of its
+/// contents are removed when the fixture is dropped.
+#[derive(Debug)]
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// Create a new, empty temporary directory.
+    pub fn new() -> io::Result<Self> {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.subsec_nanos())
+            .unwrap_or_default();
+        let path = std::env::temp_dir().join(format!(
+            "extel-{}-{}-{}",
+            process::id(),
+            nanos,
+            COUNTER.fetch_add(1, Ordering::SeqCst)
+        ));
+
+        fs::create_dir_all(&path)?;
+        Ok(Self { path })
+    }
+
+    /// The path to the directory.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<Path> for TempDir {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<OsStr> for TempDir {
+    fn as_ref(&self) -> &OsStr {
+        self.path.as_os_str()
+    }
+}
+
+impl Deref for TempDir {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+impl Fixture for TempDir {
+    fn setup() -> Result<Self, Error> {
+        Ok(Self::new()?)
+    }
+}
+
+/// An empty file inside its own [`TempDir`]. The file is removed when the fixture is dropped.
+#[derive(Debug)]
+pub struct ScratchFile {
+    dir: TempDir,
+    path: PathBuf,
+}
+
+impl ScratchFile {
+    /// Create a new, empty scratch file.
+    pub fn new() -> io::Result<Self> {
+        let dir = TempDir::new()?;
+        let path = dir.path().join("scratch");
+        fs::File::create(&path)?;
+        Ok(Self { dir, path })
+    }
+
+    /// The path to the file.
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// The directory containing the file.
+    pub fn dir(&self) -> &Path {
+        self.dir.path()
+    }
+}
+
+impl AsRef<Path> for ScratchFile {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl AsRef<OsStr> for ScratchFile {
+    fn as_ref(&self) -> &OsStr {
+        self.path.as_os_str()
+    }
+}
+
+impl Deref for ScratchFile {
+    type Target = Path;
+
+    fn deref(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Fixture for ScratchFile {
+    fn setup() -> Result<Self, Error> {
+        Ok(Self::new()?)
+    }
+}
+
+/// A TCP port on the loopback interface that was free when the fixture was created.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FreePort(u16);
+
+impl FreePort {
+    /// Find a free port with [`net::free_port`], so that it is never one already handed out in
+    /// this process.
+    pub fn new() -> Result<Self, Error> {
+        net::free_port().map(Self)
+    }
+
+    /// The port number.
+    pub fn port(&self) -> u16 {
+        self.0
+    }
+}
+
+impl Fixture for FreePort {
+    fn setup() -> Result<Self, Error> {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temp_dir_is_removed_on_drop() {
+        let tmp = TempDir::setup().unwrap();
+        let path = tmp.path().to_path_buf();
+        fs::write(path.join("file.txt"), "contents").unwrap();
+        assert!(path.is_dir());
+
+        drop(tmp);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn scratch_files_are_unique() {
+        let (a, b) = (ScratchFile::setup().unwrap(), ScratchFile::setup().unwrap());
+        assert_ne!(a.path(), b.path());
+        assert!(a.path().is_file() && b.path().is_file());
+    }
+
+    #[test]
+    fn free_ports_are_not_handed_out_twice() {
+        let ports: Vec<u16> = (0..20)
+            .map(|i| match i % 2 {
+                0 => FreePort::setup().unwrap().port(),
+                _ => net::free_port().unwrap(),
+            })
+            .collect();
+
+        let mut unique = ports.clone();
+        unique.sort_unstable();
+        unique.dedup();
+        assert_eq!(unique.len(), ports.len());
+    }
+}
@@ -0,0 +1,223 @@
+//! Helpers for creating sandboxed working directories and scratch files inside tests, and for
+//! checking the files that commands leave behind.
+//!
+//! Every helper that creates something returns a guard that removes it when dropped, so cleanup
+//! is tied to the scope of the test that created it. The guards can be passed straight to
+//! [`cmd!`](crate::cmd) as arguments.
+//!
+//! # Example
+//! ```rust
+//! use extel::{fs::{temp_dir, temp_file_with}, prelude::*};
+//!
+//! fn cat_scratch_file() -> ExtelResult {
+//!     let workdir = temp_dir()?;
+//!     let input = temp_file_with("hello world")?;
+//!
+//!     let output = cmd!("cat" => [&input]).current_dir(&workdir).output()?;
+//!     extel_assert!(output.stdout == b"hello world")
+//! }
+//!
+//! assert!(cat_scratch_file().is_ok());
+//! ```
+
+use std::{
+    fmt::Write,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+pub use crate::fixture::{ScratchFile, TempDir};
+use crate::{diff::line_diff, err, golden, ExtelResult};
+
+/// Create an empty, uniquely named directory that is removed when the returned guard is dropped.
+pub fn temp_dir() -> io::Result<TempDir> {
+    TempDir::new()
+}
+
+/// Create an empty scratch file that is removed when the returned guard is dropped.
+pub fn temp_file() -> io::Result<ScratchFile> {
+    ScratchFile::new()
+}
+
+/// Create a scratch file containing `contents` that is removed when the returned guard is
+/// dropped.
+pub fn temp_file_with(contents: impl AsRef<[u8]>) -> io::Result<ScratchFile> {
+    let file = ScratchFile::new()?;
+    std::fs::write(file.path(), contents)?;
+    Ok(file)
+}
+
+/// Check that `path` exists and is a file. Used by
+/// [`assert_file_exists`](crate::assert_file_exists).
+pub fn file_exists(path: impl AsRef<Path>) -> ExtelResult {
+    let path = path.as_ref();
+    match (path.is_file(), path.exists()) {
+        (true, _) => Ok(()),
+        (false, true) => Err(err!("{} exists but is not a file", path.display())),
+        (false, false) => Err(err!("file {} does not exist", path.display())),
+    }
+}
+
+/// Check that the file at `path` contains exactly `expected`, failing with a line diff
+/// otherwise. Used by [`assert_file_eq`](crate::assert_file_eq).
+pub fn file_eq(path: impl AsRef<Path>, expected: impl AsRef<[u8]>) -> ExtelResult {
+    let path = path.as_ref();
+    file_exists(path)?;
+
+    let (actual, expected) = (fs::read(path)?, expected.as_ref());
+    match actual == expected {
+        true => Ok(()),
+        false => Err(err!(
+            "file {} does not match\n--- expected\n+++ actual\n{}",
+            path.display(),
+            line_diff(
+                &String::from_utf8_lossy(expected),
+                &String::from_utf8_lossy(&actual)
+            )
+        )),
+    }
+}
+
+/// List every entry under `dir`, sorted by path, one per line. Directories end with `/`, symbolic
+/// links show their target, and files are preceded by a hash of their contents, so that a
+/// listing changes whenever any file in the tree does.
+///
+/// # Example
+/// ```rust
+/// use extel::fs::{dir_listing, temp_dir};
+///
+/// let dir = temp_dir().unwrap();
+/// std::fs::create_dir(dir.join("src")).unwrap();
+/// std::fs::write(dir.join("src/main.rs"), "fn main() {}\n").unwrap();
+/// std::fs::write(dir.join("Cargo.toml"), "").unwrap();
+///
+/// assert_eq!(
+///     dir_listing(&dir).unwrap(),
+///     "cbf29ce484222325  Cargo.toml\n\
+///      src/\n\
+///      355463d2db8c9b7f  src/main.rs\n"
+/// );
+/// ```
+pub fn dir_listing(dir: impl AsRef<Path>) -> io::Result<String> {
+    let dir = dir.as_ref();
+    let mut entries = Vec::new();
+    collect_entries(dir, dir, &mut entries)?;
+    entries.sort();
+
+    let mut listing = String::new();
+    for (relative, path) in entries {
+        let file_type = fs::symlink_metadata(&path)?.file_type();
+        let _ = match (file_type.is_dir(), file_type.is_symlink()) {
+            (true, _) => writeln!(listing, "{}/", relative),
+            (_, true) => writeln!(
+                listing,
+                "{} -> {}",
+                relative,
+                fs::read_link(&path)?.display()
+            ),
+            _ => writeln!(listing, "{:016x}  {}", fnv1a(&fs::read(&path)?), relative),
+        };
+    }
+    Ok(listing)
+}
+
+/// Compare the [listing](dir_listing) of `dir` against the golden file at `golden`, like
+/// [`assert_matches_file`](crate::assert_matches_file). Used by
+/// [`assert_dir_snapshot`](crate::assert_dir_snapshot).
+pub fn dir_snapshot(dir: impl AsRef<Path>, golden: impl AsRef<Path>) -> ExtelResult {
+    golden::matches_file(dir_listing(dir)?, golden)
+}
+
+/// Collect every entry under `dir` along with its path relative to `root`, using `/` as the
+/// separator on every platform.
+fn collect_entries(
+    root: &Path,
+    dir: &Path,
+    entries: &mut Vec<(String, PathBuf)>,
+) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let relative = path
+            .strip_prefix(root)
+            .unwrap_or(&path)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+
+        if fs::symlink_metadata(&path)?.is_dir() {
+            collect_entries(root, &path, entries)?;
+        }
+        entries.push((relative, path));
+    }
+    Ok(())
+}
+
+/// The 64-bit FNV-1a hash of `bytes`, which is stable across platforms and Rust versions.
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf29ce484222325, |hash, &b| {
+        (hash ^ b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn temp_file_with_contents() {
+        let file = temp_file_with("contents").unwrap();
+        let path = file.path().to_path_buf();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "contents");
+
+        drop(file);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn temp_dir_usable_in_cmd() {
+        let dir = temp_dir().unwrap();
+        let status = crate::cmd!("touch" => ["created.txt"])
+            .current_dir(&dir)
+            .status()
+            .unwrap();
+
+        assert!(status.success());
+        assert!(dir.join("created.txt").is_file());
+    }
+
+    #[test]
+    fn file_assertions_report_mismatches() {
+        let dir = temp_dir().unwrap();
+        let path = dir.join("out.txt");
+
+        assert!(file_exists(&path)
+            .unwrap_err()
+            .to_string()
+            .ends_with("out.txt does not exist"));
+        assert!(file_exists(&dir)
+            .unwrap_err()
+            .to_string()
+            .ends_with("exists but is not a file"));
+
+        std::fs::write(&path, "a\nb\n").unwrap();
+        assert!(file_eq(&path, "a\nb\n").is_ok());
+        assert!(file_eq(&path, "a\nc\n")
+            .unwrap_err()
+            .to_string()
+            .ends_with("--- expected\n+++ actual\n a\n-c\n+b\n"));
+    }
+
+    #[test]
+    fn dir_snapshot_matches_golden_listing() {
+        let dir = temp_dir().unwrap();
+        std::fs::create_dir_all(dir.join("b/c")).unwrap();
+        std::fs::write(dir.join("b/c/file"), "x").unwrap();
+        let golden = temp_file_with("b/\nb/c/\naf63f54c86021707  b/c/file\n").unwrap();
+
+        assert!(dir_snapshot(&dir, &golden).is_ok());
+
+        std::fs::write(dir.join("b/c/file"), "y").unwrap();
+        assert!(dir_snapshot(&dir, &golden).is_err());
+    }
+}
@@ -0,0 +1,152 @@
+//! Golden-file comparisons for verifying command output against checked-in files.
+//!
+//! Golden files are compared with [`assert_matches_file`](crate::assert_matches_file), or with
+//! [`assert_bytes_snapshot`](crate::assert_bytes_snapshot) for binary output. When the
+//! [`UPDATE_ENV`] environment variable is set, mismatching or missing golden files are rewritten
+//! with the actual output instead of failing the test.
+
+use std::{fs, io, path::Path};
+
+use crate::{bytes::hex_diff, diff::line_diff, err, normalize, ExtelResult};
+
+/// The environment variable that switches golden-file comparisons into update mode.
+pub const UPDATE_ENV: &str = "EXTEL_UPDATE_GOLDEN";
+
+/// The number of differing 16-byte rows shown when binary output does not match its golden file.
+pub const HEX_DIFF_ROWS: usize = 8;
+
+/// Whether or not golden files should be rewritten rather than compared.
+pub fn update_mode() -> bool {
+    std::env::var_os(UPDATE_ENV).is_some_and(|v| !v.is_empty() && v != "0")
+}
+
+/// Compare `actual` against the contents of the golden file at `path`, failing with a line diff
+/// if they differ. In [update mode](update_mode), the file is written with `actual` instead.
+/// During a test, `actual` is first [normalized](crate::TestConfig::normalize).
+pub fn matches_file(actual: impl AsRef<str>, path: impl AsRef<Path>) -> ExtelResult {
+    let (actual, path) = (normalize::in_scope(actual.as_ref()), path.as_ref());
+    let actual = actual.as_ref();
+
+    if update_mode() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, actual)?;
+        return Ok(());
+    }
+
+    let expected = match fs::read_to_string(path) {
+        Ok(expected) => expected,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Err(err!(
+                "golden file {} does not exist (set {}=1 to create it)",
+                path.display(),
+                UPDATE_ENV
+            ))
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    match expected == actual {
+        true => Ok(()),
+        false => Err(err!(
+            "output does not match golden file {} (set {}=1 to update it)\n--- expected\n+++ actual\n{}",
+            path.display(),
+            UPDATE_ENV,
+            line_diff(&expected, actual)
+        )),
+    }
+}
+
+/// Compare binary `actual` against the contents of the golden file at `path`, failing with the
+/// offset of the first difference and a [hexdump diff](hex_diff) of the first [`HEX_DIFF_ROWS`]
+/// differing rows. In [update mode](update_mode), the file is written with `actual` instead.
+pub fn matches_bytes_file(actual: impl AsRef<[u8]>, path: impl AsRef<Path>) -> ExtelResult {
+    let (actual, path) = (actual.as_ref(), path.as_ref());
+
+    if update_mode() {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, actual)?;
+        return Ok(());
+    }
+
+    let expected = match fs::read(path) {
+        Ok(expected) => expected,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            return Err(err!(
+                "golden file {} does not exist (set {}=1 to create it)",
+                path.display(),
+                UPDATE_ENV
+            ))
+        }
+        Err(e) => return Err(e.into()),
+    };
+    if expected == actual {
+        return Ok(());
+    }
+
+    let offset = expected
+        .iter()
+        .zip(actual)
+        .position(|(e, a)| e != a)
+        .unwrap_or_else(|| expected.len().min(actual.len()));
+    Err(err!(
+        "bytes do not match golden file {} (set {}=1 to update it)\n\
+         first difference at offset {:#x} (expected {} bytes, got {})\n{}",
+        path.display(),
+        UPDATE_ENV,
+        offset,
+        expected.len(),
+        actual.len(),
+        hex_diff(&expected, actual, HEX_DIFF_ROWS)
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fs::temp_file_with;
+
+    #[test]
+    fn matches_file_identical() {
+        let golden = temp_file_with("line 1\nline 2\n").unwrap();
+        assert!(matches_file("line 1\nline 2\n", &golden).is_ok());
+    }
+
+    #[test]
+    fn matches_file_reports_diff() {
+        let golden = temp_file_with("line 1\nline 2\n").unwrap();
+        let err = matches_file("line 1\nline 3\n", &golden)
+            .unwrap_err()
+            .to_string();
+
+        assert!(err.ends_with("--- expected\n+++ actual\n line 1\n-line 2\n+line 3\n"));
+    }
+
+    #[test]
+    fn matches_bytes_file_reports_hex_diff() {
+        let golden = temp_file_with(b"\x00\x01\x02\x03".as_slice()).unwrap();
+        assert!(matches_bytes_file(b"\x00\x01\x02\x03", &golden).is_ok());
+
+        let err = matches_bytes_file(b"\x00\x01\xff", &golden)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains(
+            "first difference at offset 0x2 (expected 4 bytes, got 3)\n-00000000  00 01 02 03"
+        ));
+        let padding = " ".repeat(39);
+        assert!(err.ends_with(&format!("\n+00000000  00 01 ff{}  |...|\n", padding)));
+    }
+
+    #[test]
+    fn matches_file_missing() {
+        let dir = crate::fs::temp_dir().unwrap();
+        let err = matches_file("", dir.join("missing.txt"))
+            .unwrap_err()
+            .to_string();
+
+        assert!(err.contains("does not exist"));
+    }
+}
@@ -0,0 +1,428 @@
+//! Checking the health of gRPC services started by tests through the standard
+//! [`grpc.health.v1.Health`](https://github.com/grpc/grpc/blob/master/doc/health-checking.md)
+//! service, so that tests can wait until a service is ready before talking to it.
+//!
+//! Health checks are sent over plaintext HTTP/2 (h2c) with just enough of the protocol to make a
+//! single `Check` call, so no gRPC library is needed. TLS is not supported.
+//!
+//! ```rust,no_run
+//! use extel::{daemon::{Daemon, Readiness}, grpc, prelude::*};
+//! use std::time::Duration;
+//!
+//! fn orders_service_is_healthy() -> ExtelResult {
+//!     let addr = "127.0.0.1:50051".parse().unwrap();
+//!     let _server = Daemon::spawn(
+//!         &mut cmd!("target/debug/orders-service"),
+//!         Readiness::GrpcHealth { addr, service: String::new() },
+//!         Duration::from_secs(10),
+//!     )?;
+//!
+//!     grpc::assert_serving(addr, "orders.v1.Orders")
+//! }
+//! ```
+//!
+//! > *This is only available with the `grpc` feature enabled.*
+
+use std::{
+    fmt,
+    io::{Read, Write},
+    net::{SocketAddr, TcpStream, ToSocketAddrs},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{err, errors::Error, wait::POLL_INTERVAL, ExtelResult};
+
+/// How long a single health check may take to connect and to receive each frame.
+pub const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// The serving status reported by a health check, as defined by `grpc.health.v1`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ServingStatus {
+    Unknown,
+    Serving,
+    NotServing,
+    /// The server does not know the service. Only reported by the `Watch` call, but some servers
+    /// return it from `Check` too.
+    ServiceUnknown,
+}
+
+impl fmt::Display for ServingStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Unknown => "UNKNOWN",
+            Self::Serving => "SERVING",
+            Self::NotServing => "NOT_SERVING",
+            Self::ServiceUnknown => "SERVICE_UNKNOWN",
+        })
+    }
+}
+
+/// Ask the server at `addr` for the health of `service`. An empty `service` asks for the health
+/// of the server as a whole.
+///
+/// A server that does not know `service` ends the call with an error status instead of a
+/// response, which fails with an [`Error::TestFailed`] like any other failed call.
+pub fn check(addr: impl ToSocketAddrs, service: &str) -> Result<ServingStatus, Error> {
+    check_with_timeout(addr, service, CHECK_TIMEOUT)
+}
+
+fn check_with_timeout(
+    addr: impl ToSocketAddrs,
+    service: &str,
+    timeout: Duration,
+) -> Result<ServingStatus, Error> {
+    let addr: SocketAddr = addr
+        .to_socket_addrs()?
+        .next()
+        .ok_or_else(|| err!("could not resolve the address of the gRPC server"))?;
+    let mut stream = TcpStream::connect_timeout(&addr, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+
+    stream.write_all(PREFACE)?;
+    write_frame(&mut stream, SETTINGS, 0, 0, &[])?;
+    write_frame(
+        &mut stream,
+        HEADERS,
+        END_HEADERS,
+        STREAM_ID,
+        &request_headers(&addr.to_string()),
+    )?;
+    write_frame(
+        &mut stream,
+        DATA,
+        END_STREAM,
+        STREAM_ID,
+        &grpc_message(&check_request(service)),
+    )?;
+
+    let mut data = Vec::new();
+    loop {
+        let frame = read_frame(&mut stream)?;
+        match frame.kind {
+            SETTINGS if frame.flags & ACK == 0 => write_frame(&mut stream, SETTINGS, ACK, 0, &[])?,
+            PING if frame.flags & ACK == 0 => {
+                write_frame(&mut stream, PING, ACK, 0, &frame.payload)?
+            }
+            GOAWAY => return Err(err!("the gRPC server closed the connection")),
+            RST_STREAM if frame.stream_id == STREAM_ID => {
+                return Err(err!("the gRPC server reset the health check"))
+            }
+            DATA if frame.stream_id == STREAM_ID => data.extend(frame.data()?),
+            _ => {}
+        }
+
+        if frame.stream_id == STREAM_ID
+            && matches!(frame.kind, DATA | HEADERS)
+            && frame.flags & END_STREAM != 0
+        {
+            break;
+        }
+    }
+
+    match data.get(5..) {
+        Some(message) => Ok(parse_check_response(message)),
+        None => Err(err!(
+            "the health check for {:?} returned no status; the server may not implement \
+             grpc.health.v1 or may not know the service",
+            service
+        )),
+    }
+}
+
+/// Check that the server at `addr` reports `service` as [serving](ServingStatus::Serving).
+pub fn assert_serving(addr: impl ToSocketAddrs, service: &str) -> ExtelResult {
+    match check(addr, service)? {
+        ServingStatus::Serving => Ok(()),
+        status => Err(err!(
+            "expected {:?} to be SERVING, but it is {}",
+            service,
+            status
+        )),
+    }
+}
+
+/// Wait until the server at `addr` reports `service` as [serving](ServingStatus::Serving),
+/// failing with the last status or error if it does not within `timeout`. A server that is not
+/// listening yet is waited for like any other status.
+///
+/// # Example
+/// ```rust
+/// use extel::{grpc, prelude::*};
+/// use std::{net::TcpListener, time::Duration};
+///
+/// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+/// let addr = listener.local_addr().unwrap();
+/// drop(listener);
+///
+/// assert!(grpc::wait_for_serving(addr, "", Duration::from_millis(100)).is_err());
+/// ```
+pub fn wait_for_serving(addr: impl ToSocketAddrs, service: &str, timeout: Duration) -> ExtelResult {
+    let addrs: Vec<SocketAddr> = addr.to_socket_addrs()?.collect();
+    let deadline = Instant::now() + timeout;
+    loop {
+        let remaining = deadline.saturating_duration_since(Instant::now());
+        let last = match check_with_timeout(&addrs[..], service, remaining.max(POLL_INTERVAL)) {
+            Ok(ServingStatus::Serving) => return Ok(()),
+            Ok(status) => status.to_string(),
+            Err(e) => e.to_string(),
+        };
+
+        let now = Instant::now();
+        if now >= deadline {
+            return Err(err!(
+                "{:?} was not SERVING within {:?}, last saw: {}",
+                service,
+                timeout,
+                last
+            ));
+        }
+        thread::sleep(POLL_INTERVAL.min(deadline - now));
+    }
+}
+
+/// Whether the server at `addr` currently reports `service` as serving, for polling readiness.
+pub(crate) fn is_serving(addr: SocketAddr, service: &str) -> bool {
+    matches!(
+        check_with_timeout(addr, service, Duration::from_secs(1)),
+        Ok(ServingStatus::Serving)
+    )
+}
+
+const PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+const STREAM_ID: u32 = 1;
+
+const DATA: u8 = 0x0;
+const HEADERS: u8 = 0x1;
+const RST_STREAM: u8 = 0x3;
+const SETTINGS: u8 = 0x4;
+const PING: u8 = 0x6;
+const GOAWAY: u8 = 0x7;
+
+const END_STREAM: u8 = 0x1;
+const ACK: u8 = 0x1;
+const END_HEADERS: u8 = 0x4;
+const PADDED: u8 = 0x8;
+
+struct Frame {
+    kind: u8,
+    flags: u8,
+    stream_id: u32,
+    payload: Vec<u8>,
+}
+
+impl Frame {
+    /// The payload of a DATA frame without its padding.
+    fn data(&self) -> Result<&[u8], Error> {
+        match self.flags & PADDED {
+            0 => Ok(&self.payload),
+            _ => {
+                let padding = *self.payload.first().unwrap_or(&0) as usize;
+                self.payload
+                    .get(1..self.payload.len().saturating_sub(padding))
+                    .ok_or_else(|| err!("malformed padded DATA frame"))
+            }
+        }
+    }
+}
+
+fn write_frame(
+    stream: &mut impl Write,
+    kind: u8,
+    flags: u8,
+    stream_id: u32,
+    payload: &[u8],
+) -> std::io::Result<()> {
+    let length = (payload.len() as u32).to_be_bytes();
+    stream.write_all(&length[1..])?;
+    stream.write_all(&[kind, flags])?;
+    stream.write_all(&stream_id.to_be_bytes())?;
+    stream.write_all(payload)
+}
+
+fn read_frame(stream: &mut impl Read) -> std::io::Result<Frame> {
+    let mut header = [0; 9];
+    stream.read_exact(&mut header)?;
+    let length = u32::from_be_bytes([0, header[0], header[1], header[2]]) as usize;
+    let mut payload = vec![0; length];
+    stream.read_exact(&mut payload)?;
+
+    Ok(Frame {
+        kind: header[3],
+        flags: header[4],
+        stream_id: u32::from_be_bytes([header[5], header[6], header[7], header[8]]) & 0x7fff_ffff,
+        payload,
+    })
+}
+
+/// The HPACK-encoded headers of a `Check` call, using only the static table and literals so that
+/// no compression state is needed.
+fn request_headers(authority: &str) -> Vec<u8> {
+    // `:method: POST` and `:scheme: http` are entries 3 and 6 of the static table.
+    let mut block = vec![0x80 | 3, 0x80 | 6];
+    // Literals without indexing, named by `:path`, `:authority`, and `content-type`.
+    for (name_index, value) in [
+        (4, "/grpc.health.v1.Health/Check"),
+        (1, authority),
+        (31, "application/grpc"),
+    ] {
+        hpack_integer(&mut block, 0x00, 4, name_index);
+        hpack_string(&mut block, value);
+    }
+    block.push(0x00);
+    hpack_string(&mut block, "te");
+    hpack_string(&mut block, "trailers");
+    block
+}
+
+fn hpack_integer(block: &mut Vec<u8>, first_bits: u8, prefix_bits: u32, value: usize) {
+    let max = (1 << prefix_bits) - 1;
+    if value < max {
+        block.push(first_bits | value as u8);
+        return;
+    }
+
+    block.push(first_bits | max as u8);
+    let mut rest = value - max;
+    while rest >= 128 {
+        block.push((rest % 128) as u8 | 0x80);
+        rest /= 128;
+    }
+    block.push(rest as u8);
+}
+
+fn hpack_string(block: &mut Vec<u8>, value: &str) {
+    hpack_integer(block, 0x00, 7, value.len());
+    block.extend_from_slice(value.as_bytes());
+}
+
+/// Frame a protobuf message as an uncompressed gRPC message.
+fn grpc_message(message: &[u8]) -> Vec<u8> {
+    let mut framed = vec![0];
+    framed.extend_from_slice(&(message.len() as u32).to_be_bytes());
+    framed.extend_from_slice(message);
+    framed
+}
+
+/// A `HealthCheckRequest`, whose only field is the service name.
+fn check_request(service: &str) -> Vec<u8> {
+    let mut message = Vec::new();
+    if !service.is_empty() {
+        message.push(0x0a);
+        varint(&mut message, service.len() as u64);
+        message.extend_from_slice(service.as_bytes());
+    }
+    message
+}
+
+fn varint(buffer: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        buffer.push((value as u8 & 0x7f) | 0x80);
+        value >>= 7;
+    }
+    buffer.push(value as u8);
+}
+
+/// Read the `status` field of a `HealthCheckResponse`, which defaults to unknown when missing.
+fn parse_check_response(message: &[u8]) -> ServingStatus {
+    let status = match message {
+        [0x08, status, ..] => *status,
+        _ => 0,
+    };
+
+    match status {
+        1 => ServingStatus::Serving,
+        2 => ServingStatus::NotServing,
+        3 => ServingStatus::ServiceUnknown,
+        _ => ServingStatus::Unknown,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{net::TcpListener, sync::mpsc};
+
+    /// Serve a single health check, answering with `status`, or ending the call with an error
+    /// status if `status` is `None`. Sends the service name that was asked about.
+    fn serve_once(status: Option<u8>) -> (SocketAddr, mpsc::Receiver<Vec<u8>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut preface = [0; PREFACE.len()];
+            stream.read_exact(&mut preface).unwrap();
+            assert_eq!(preface, PREFACE);
+            write_frame(&mut stream, SETTINGS, 0, 0, &[0, 3, 0, 0, 0, 100]).unwrap();
+            write_frame(&mut stream, PING, 0, 0, &[7; 8]).unwrap();
+
+            let request = loop {
+                let frame = read_frame(&mut stream).unwrap();
+                if frame.kind == DATA && frame.flags & END_STREAM != 0 {
+                    break frame.payload[5..].to_vec();
+                }
+            };
+
+            // `:status: 200` is entry 8 of the static table.
+            write_frame(&mut stream, HEADERS, END_HEADERS, STREAM_ID, &[0x88]).unwrap();
+            if let Some(status) = status {
+                let response = grpc_message(&[0x08, status]);
+                let mut padded = vec![2];
+                padded.extend(response);
+                padded.extend([0, 0]);
+                write_frame(&mut stream, DATA, PADDED, STREAM_ID, &padded).unwrap();
+            }
+            let mut trailers = vec![0x00];
+            hpack_string(&mut trailers, "grpc-status");
+            hpack_string(&mut trailers, if status.is_some() { "0" } else { "5" });
+            write_frame(
+                &mut stream,
+                HEADERS,
+                END_HEADERS | END_STREAM,
+                STREAM_ID,
+                &trailers,
+            )
+            .unwrap();
+
+            let _ = tx.send(request);
+            // Keep the connection open until the client has read everything and hung up.
+            let _ = std::io::copy(&mut stream, &mut std::io::sink());
+        });
+
+        (addr, rx)
+    }
+
+    #[test]
+    fn statuses_are_reported() {
+        let (addr, request) = serve_once(Some(1));
+        assert_eq!(check(addr, "orders").unwrap(), ServingStatus::Serving);
+        assert_eq!(request.recv().unwrap(), b"\x0a\x06orders");
+
+        let (addr, request) = serve_once(Some(2));
+        assert_eq!(
+            assert_serving(addr, "").unwrap_err().to_string(),
+            "expected \"\" to be SERVING, but it is NOT_SERVING"
+        );
+        assert_eq!(request.recv().unwrap(), b"");
+    }
+
+    #[test]
+    fn missing_statuses_fail() {
+        let (addr, _) = serve_once(None);
+        assert!(check(addr, "unknown")
+            .unwrap_err()
+            .to_string()
+            .starts_with("the health check for \"unknown\" returned no status"));
+    }
+
+    #[test]
+    fn large_integers_use_continuation_bytes() {
+        let mut block = Vec::new();
+        hpack_integer(&mut block, 0x00, 4, 31);
+        hpack_integer(&mut block, 0x00, 5, 1337);
+        assert_eq!(block, [0x0f, 0x10, 0x1f, 0x9a, 0x0a]);
+    }
+}
@@ -0,0 +1,151 @@
+//! Running a suite as the whole program, for test binaries invoked from scripts and build systems
+//! that only look at the exit status and a few lines of stderr. See [`main_with`].
+//!
+//! The exit status of the process is one of:
+//!
+//! | Status | Meaning |
+//! |--------|---------|
+//! | [`EXIT_SUCCESS`] (0) | Every test passed or was skipped |
+//! | [`EXIT_FAILED`] (1) | At least one test failed |
+//! | [`EXIT_USAGE`] (2) | The [command line](crate::cli) could not be parsed |
+//! | [`EXIT_NO_TESTS`] (3) | No test ran, such as when the filter matched nothing |
+//! | [`EXIT_TIMED_OUT`] (124) | The [suite timeout](crate::TestConfig::suite_timeout) ran out |
+
+use std::{io::Write, process::ExitCode};
+
+use crate::{cli, RunnableTestSet, SuiteReport};
+
+/// Every test passed or was skipped.
+pub const EXIT_SUCCESS: u8 = 0;
+/// At least one test failed.
+pub const EXIT_FAILED: u8 = 1;
+/// The command line arguments could not be parsed.
+pub const EXIT_USAGE: u8 = 2;
+/// The suite ran no tests at all.
+pub const EXIT_NO_TESTS: u8 = 3;
+/// A test was still running when the suite timeout ran out, matching the status used by
+/// `timeout(1)`.
+pub const EXIT_TIMED_OUT: u8 = 124;
+
+/// Run the suite `S` with the configuration given on the [command line](cli::parse_args), print
+/// a short summary to stderr, and return the [exit status](self) for the run. The report itself
+/// goes wherever the configuration sends it, so stderr only has the summary, which makes it easy
+/// to spot in the logs of a `make` run.
+///
+/// ```text
+/// AllTests: 11 passed; 1 failed; 2 skipped in 0.84s
+/// failed: divide_by_zero
+/// ```
+///
+/// # Example
+/// ```rust,no_run
+/// use extel::prelude::*;
+/// use std::process::ExitCode;
+///
+/// fn always_succeed() -> ExtelResult {
+///     pass!()
+/// }
+///
+/// init_test_suite!(FirstSuite, always_succeed);
+/// init_test_suite!(SecondSuite, always_succeed);
+/// compose_suites!(AllTests, FirstSuite, SecondSuite);
+///
+/// fn main() -> ExitCode {
+///     extel::harness::main_with::<AllTests>()
+/// }
+/// ```
+pub fn main_with<S: RunnableTestSet>() -> ExitCode {
+    let cfg = cli::parse_args();
+    let list = cfg.list;
+    let report = S::run_report(cfg);
+
+    // Listing tests runs nothing, so there is nothing to summarize.
+    if list {
+        return ExitCode::from(EXIT_SUCCESS);
+    }
+
+    let _ = std::io::stdout().flush();
+    eprint!("{}", summary(&report));
+    ExitCode::from(exit_status(&report))
+}
+
+/// The exit status for a suite run: [`EXIT_FAILED`] if any test failed, [`EXIT_NO_TESTS`] if no
+/// test ran, and [`EXIT_SUCCESS`] otherwise.
+pub fn exit_status(report: &SuiteReport) -> u8 {
+    match (report.counts.failed, report.counts.total) {
+        (0, 0) => EXIT_NO_TESTS,
+        (0, _) => EXIT_SUCCESS,
+        _ => EXIT_FAILED,
+    }
+}
+
+/// The summary printed to stderr by [`main_with`]: one line with the totals of the run, followed
+/// by the name of each failed test on its own line.
+///
+/// # Example
+/// ```rust
+/// use extel::{harness, prelude::*, OutputDest};
+///
+/// fn always_succeed() -> ExtelResult {
+///     pass!()
+/// }
+///
+/// fn always_fail() -> ExtelResult {
+///     fail!("no luck")
+/// }
+///
+/// init_test_suite!(MixedSuite as "mixed", always_succeed, always_fail);
+/// let report = MixedSuite::run_report(TestConfig::default().output(OutputDest::None));
+///
+/// assert_eq!(harness::exit_status(&report), harness::EXIT_FAILED);
+/// assert!(harness::summary(&report).starts_with("mixed: 1 passed; 1 failed in "));
+/// assert!(harness::summary(&report).ends_with("\nfailed: always_fail\n"));
+/// ```
+pub fn summary(report: &SuiteReport) -> String {
+    if report.counts.total == 0 {
+        return format!("{}: no tests ran\n", report.name);
+    }
+
+    let mut summary = format!(
+        "{}: {} passed; {} failed",
+        report.name, report.counts.passed, report.counts.failed
+    );
+    if report.counts.skipped > 0 {
+        summary.push_str(&format!("; {} skipped", report.counts.skipped));
+    }
+    summary.push_str(&format!(" in {:.2}s\n", report.duration.as_secs_f64()));
+
+    for failure in report.results.failures() {
+        summary.push_str(&format!("failed: {}\n", failure.test_name));
+    }
+    summary
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{errors::Error, prelude::*, OutputDest, TestConfig};
+
+    fn always_succeed() -> ExtelResult {
+        pass!()
+    }
+
+    fn always_skip() -> ExtelResult {
+        Err(Error::Skipped(String::from("not today")))
+    }
+
+    #[test]
+    fn runs_without_failures_succeed_unless_nothing_ran() {
+        init_test_suite!(QuietSuite as "quiet", always_succeed, always_skip);
+
+        let report = QuietSuite::run_report(TestConfig::default().output(OutputDest::None));
+        assert_eq!(exit_status(&report), EXIT_SUCCESS);
+        assert!(summary(&report).starts_with("quiet: 1 passed; 0 failed; 1 skipped in "));
+
+        let mut cfg = TestConfig::default().output(OutputDest::None);
+        cfg.filter = Some(String::from("no_such_test"));
+        let report = QuietSuite::run_report(cfg);
+        assert_eq!(exit_status(&report), EXIT_NO_TESTS);
+        assert_eq!(summary(&report), "quiet: no tests ran\n");
+    }
+}
@@ -0,0 +1,308 @@
+//! Comparing a run against the previous one, to answer "what changed since last time" for
+//! regularly scheduled runs. See [`TestConfig::history`](crate::TestConfig::history).
+//!
+//! The history file is a plain text file with one `suite name<TAB>test name<TAB>status<TAB>ms`
+//! line per test, where the status is `passed` or `failed`. After every suite run, the tests that
+//! ran replace their previous entries. Tests that did not run keep them.
+//!
+//! The history also drives the [`TestOrder::FailedFirst`](crate::TestOrder::FailedFirst) and
+//! [`TestOrder::SlowestFirst`](crate::TestOrder::SlowestFirst) orders.
+
+use std::{collections::BTreeMap, fs, io, path::Path, sync::Mutex, time::Duration};
+
+use crate::{errors::Error, TestResult};
+
+/// The default [`TestConfig::slow_threshold`](crate::TestConfig::slow_threshold).
+pub const DEFAULT_SLOW_THRESHOLD: f64 = 1.5;
+
+/// Tests that take less than this are never flagged as slow, since their timings are mostly noise.
+pub const MIN_SLOW_DURATION: Duration = Duration::from_millis(100);
+
+/// Serializes updates so that suites run on different threads do not clobber each other's history.
+static HISTORY_LOCK: Mutex<()> = Mutex::new(());
+
+/// The outcome and duration of a test in the previous run that recorded it.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PreviousRun {
+    /// Whether or not the test passed.
+    pub passed: bool,
+    /// How long the test took to run.
+    pub duration: Duration,
+}
+
+/// A test that took noticeably longer than it did in the previous run.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SlowTest {
+    pub test_name: String,
+    pub previous: Duration,
+    pub current: Duration,
+}
+
+/// How the results of a run differ from the previous run of the same suite. Tests that have no
+/// previous result are not compared.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Comparison {
+    /// Tests that passed last time and fail now.
+    pub newly_failing: Vec<String>,
+    /// Tests that failed last time and pass now.
+    pub newly_passing: Vec<String>,
+    /// Tests that took longer than the slow threshold allows.
+    pub newly_slow: Vec<SlowTest>,
+}
+
+impl Comparison {
+    /// Whether or not nothing changed since the previous run.
+    pub fn is_empty(&self) -> bool {
+        self.newly_failing.is_empty() && self.newly_passing.is_empty() && self.newly_slow.is_empty()
+    }
+}
+
+/// Read every entry of the history file, keyed by suite and test name. A missing file has no
+/// entries, and malformed lines are skipped.
+fn read_entries(path: &Path) -> io::Result<BTreeMap<(String, String), PreviousRun>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(BTreeMap::new()),
+        Err(e) => return Err(e),
+    };
+
+    Ok(contents
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split('\t');
+            let (suite, test) = (fields.next()?, fields.next()?);
+            let passed = match fields.next()? {
+                "passed" => true,
+                "failed" => false,
+                _ => return None,
+            };
+            let millis: f64 = fields.next()?.parse().ok()?;
+            let duration = Duration::try_from_secs_f64(millis / 1000.0).ok()?;
+            Some((
+                (suite.to_string(), test.to_string()),
+                PreviousRun { passed, duration },
+            ))
+        })
+        .collect())
+}
+
+/// Whether `result` should be compared and recorded at all. Tests that were not run or were
+/// skipped have no outcome of their own.
+fn ran(result: &TestResult) -> bool {
+    !result
+        .test_result
+        .cases
+        .iter()
+        .any(|case| matches!(case.result, Err(Error::NotRun(_) | Error::Skipped(_))))
+}
+
+/// The previous run of every test of `suite_name` recorded in the history file at `path`, by test
+/// name. A missing file has no previous runs.
+pub fn previous_runs(
+    path: impl AsRef<Path>,
+    suite_name: &str,
+) -> io::Result<BTreeMap<String, PreviousRun>> {
+    let _guard = HISTORY_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    Ok(read_entries(path.as_ref())?
+        .into_iter()
+        .filter(|((suite, _), _)| suite == suite_name)
+        .map(|((_, test), previous)| (test, previous))
+        .collect())
+}
+
+/// Compare the `results` of a run of `suite_name` against the previous results in the history
+/// file at `path`. A test is newly slow when it took more than `slow_threshold` times as long as
+/// it did before, and at least [`MIN_SLOW_DURATION`].
+///
+/// # Example
+/// ```rust
+/// use extel::{fixture::TempDir, history, prelude::*, OutputDest};
+///
+/// fn always_fail() -> ExtelResult {
+///     fail!("no luck")
+/// }
+///
+/// let tmp = TempDir::new().unwrap();
+/// let history_file = tmp.path().join("history");
+/// std::fs::write(&history_file, "nightly\talways_fail\tpassed\t1.5\n").unwrap();
+///
+/// init_test_suite!(NightlySuite as "nightly", always_fail);
+/// let results = NightlySuite::run(TestConfig::default().output(OutputDest::None));
+///
+/// let comparison = history::compare(&history_file, "nightly", &results, 1.5).unwrap();
+/// assert_eq!(comparison.newly_failing, ["always_fail"]);
+/// ```
+pub fn compare(
+    path: impl AsRef<Path>,
+    suite_name: &str,
+    results: &[TestResult],
+    slow_threshold: f64,
+) -> io::Result<Comparison> {
+    let previous = {
+        let _guard = HISTORY_LOCK
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        read_entries(path.as_ref())?
+    };
+
+    let mut comparison = Comparison::default();
+    for result in results.iter().filter(|result| ran(result)) {
+        let key = (suite_name.to_string(), result.test_name.to_string());
+        let Some(before) = previous.get(&key) else {
+            continue;
+        };
+
+        let test_name = result.test_name.to_string();
+        match (before.passed, result.test_result.is_ok()) {
+            (true, false) => comparison.newly_failing.push(test_name.clone()),
+            (false, true) => comparison.newly_passing.push(test_name.clone()),
+            _ => {}
+        }
+
+        if result.duration >= MIN_SLOW_DURATION
+            && result.duration.as_secs_f64() > before.duration.as_secs_f64() * slow_threshold
+        {
+            comparison.newly_slow.push(SlowTest {
+                test_name,
+                previous: before.duration,
+                current: result.duration,
+            });
+        }
+    }
+
+    Ok(comparison)
+}
+
+/// Update the history file with the results of a run of `suite_name`.
+pub fn record_results(
+    path: impl AsRef<Path>,
+    suite_name: &str,
+    results: &[TestResult],
+) -> io::Result<()> {
+    let path = path.as_ref();
+    let _guard = HISTORY_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    let mut entries = read_entries(path)?;
+    for result in results.iter().filter(|result| ran(result)) {
+        entries.insert(
+            (suite_name.to_string(), result.test_name.to_string()),
+            PreviousRun {
+                passed: result.test_result.is_ok(),
+                duration: result.duration,
+            },
+        );
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let contents: String = entries
+        .iter()
+        .map(|((suite, test), entry)| {
+            format!(
+                "{}\t{}\t{}\t{:.3}\n",
+                suite,
+                test,
+                match entry.passed {
+                    true => "passed",
+                    false => "failed",
+                },
+                entry.duration.as_secs_f64() * 1000.0
+            )
+        })
+        .collect();
+    fs::write(path, contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fixture::TempDir, prelude::*, OutputDest, SharedBuffer, TestOrder};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    static SECOND_RUN: AtomicBool = AtomicBool::new(false);
+
+    fn regresses() -> ExtelResult {
+        extel_assert!(!SECOND_RUN.load(Ordering::SeqCst), "broke overnight")
+    }
+
+    fn recovers() -> ExtelResult {
+        extel_assert!(SECOND_RUN.load(Ordering::SeqCst), "still broken")
+    }
+
+    fn slows_down() -> ExtelResult {
+        if SECOND_RUN.load(Ordering::SeqCst) {
+            std::thread::sleep(Duration::from_millis(150));
+        }
+        pass!()
+    }
+
+    #[test]
+    fn changes_since_previous_run_are_reported() {
+        init_test_suite!(NightlySuite as "nightly", regresses, recovers, slows_down);
+
+        let tmp = TempDir::new().unwrap();
+        let history_file = tmp.path().join("nested").join("history");
+        let run = || {
+            let output_buffer = SharedBuffer::new();
+            NightlySuite::run(
+                TestConfig::default()
+                    .output(OutputDest::Buffer(output_buffer.clone()))
+                    .colored(false)
+                    .rerun_hint(false)
+                    .history(&history_file),
+            );
+            output_buffer.to_string_lossy()
+        };
+
+        // Nothing to compare against on the first run.
+        assert!(!run().contains("since last run"));
+        assert_eq!(read_entries(&history_file).unwrap().len(), 3);
+
+        SECOND_RUN.store(true, Ordering::SeqCst);
+        let output = run();
+        assert!(output.contains(
+            "\t[since last run]\n\
+             \t  newly failing: regresses\n\
+             \t  newly passing: recovers\n\
+             \t  newly slow: slows_down ("
+        ));
+
+        let entries = read_entries(&history_file).unwrap();
+        let regresses = entries[&(String::from("nightly"), String::from("regresses"))];
+        assert!(!regresses.passed);
+    }
+
+    fn passes() -> ExtelResult {
+        pass!()
+    }
+
+    fn fails() -> ExtelResult {
+        fail!("no luck")
+    }
+
+    #[test]
+    fn failed_tests_run_first_next_time() {
+        init_test_suite!(PrioritySuite as "priority", passes, fails);
+
+        let tmp = TempDir::new().unwrap();
+        let history_file = tmp.path().join("history");
+        let run = || {
+            PrioritySuite::run(
+                TestConfig::default()
+                    .output(OutputDest::None)
+                    .history(&history_file)
+                    .order(TestOrder::FailedFirst),
+            )
+        };
+
+        assert_eq!(run()[0].test_name, "passes");
+        assert!(!previous_runs(&history_file, "priority").unwrap()["fails"].passed);
+        assert_eq!(run()[0].test_name, "fails");
+    }
+}
@@ -0,0 +1,166 @@
+//! A minimal HTTP/1.1 client for black-box testing of servers started by tests, such as with a
+//! [`Daemon`](crate::daemon::Daemon), without wiring a full HTTP client library into every
+//! project.
+//!
+//! Only plain `http://` URLs are supported. Each request is sent on its own connection, which
+//! is closed once the response has been read. Errors are reported as
+//! [`Error`](crate::errors::Error)s, so requests can be made with `?` inside a test, and the
+//! [`assert_status`] and [`assert_body_contains`] macros check a [`Response`] with a message that
+//! includes the response body.
+//!
+//! ```rust,no_run
+//! use extel::{http, prelude::*};
+//!
+//! fn health_check() -> ExtelResult {
+//!     let resp = http::get("http://127.0.0.1:8080/health")?;
+//!     assert_status!(resp, 200)?;
+//!     assert_body_contains!(resp, "ok")
+//! }
+//! ```
+//!
+//! Requests with other methods, headers, or timeouts are built with [`request`]:
+//!
+//! ```rust,no_run
+//! use extel::{http, prelude::*};
+//! use std::time::Duration;
+//!
+//! fn creates_user() -> ExtelResult {
+//!     let resp = http::request("POST", "http://127.0.0.1:8080/users")
+//!         .header("Content-Type", "application/json")
+//!         .body(r#"{"name": "ferris"}"#)
+//!         .timeout(Duration::from_secs(2))
+//!         .send()?;
+//!     assert_status!(resp, 201)
+//! }
+//! ```
+//!
+//! > *This is only available with the `http` feature enabled.*
+//!
+//! [`assert_status`]: crate::assert_status
+//! [`assert_body_contains`]: crate::assert_body_contains
+
+use crate::{
+    diff::{abbreviate, MESSAGE_LIMIT},
+    err, ExtelResult,
+};
+
+pub use crate::http_client::{get, post, request, Request, Response, DEFAULT_TIMEOUT};
+
+/// Check that `response` has the `expected` status code, failing with the response body
+/// otherwise. This is what [`assert_status`](crate::assert_status) calls.
+pub fn status_eq(response: &Response, expected: u16) -> ExtelResult {
+    match response.status == expected {
+        true => Ok(()),
+        false => Err(err!(
+            "expected status {}, got {}\n  body: {}",
+            expected,
+            response.status,
+            abbreviate(&String::from_utf8_lossy(&response.body), MESSAGE_LIMIT)
+        )),
+    }
+}
+
+/// Check that the body of `response` contains `needle`, failing with the response body
+/// otherwise. This is what [`assert_body_contains`](crate::assert_body_contains) calls.
+pub fn body_contains(response: &Response, needle: &str) -> ExtelResult {
+    let body = String::from_utf8_lossy(&response.body);
+    match body.contains(needle) {
+        true => Ok(()),
+        false => Err(err!(
+            "response body does not contain {:?}\n  body: {}",
+            needle,
+            abbreviate(&body, MESSAGE_LIMIT)
+        )),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::prelude::*;
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+        sync::mpsc,
+        thread,
+    };
+
+    /// Serve a single connection with `response`, returning the URL of the server and a receiver
+    /// for the request it got.
+    fn serve_once(response: &'static str) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let (tx, rx) = mpsc::channel();
+
+        thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut request = String::new();
+            while reader.read_line(&mut request).unwrap() > 2 || !request.ends_with("\r\n\r\n") {}
+            let length: usize = request
+                .lines()
+                .find_map(|line| line.strip_prefix("Content-Length: "))
+                .map_or(0, |length| length.parse().unwrap());
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            request.push_str(&String::from_utf8(body).unwrap());
+
+            (&stream).write_all(response.as_bytes()).unwrap();
+            tx.send(request).unwrap();
+        });
+
+        (url, rx)
+    }
+
+    #[test]
+    fn requests_are_sent_and_responses_read() {
+        let (url, request) =
+            serve_once("HTTP/1.1 201 Created\r\nContent-Length: 7\r\nX-Id: 3\r\n\r\ncreated");
+
+        let resp = super::request("post", format!("{}/users?admin=true", url))
+            .header("Content-Type", "text/plain")
+            .body("ferris")
+            .send()
+            .unwrap();
+        assert!(assert_status!(resp, 201).is_ok());
+        assert!(assert_body_contains!(resp, "create").is_ok());
+        assert_eq!(resp.header("x-id"), Some("3"));
+
+        let request = request.recv().unwrap();
+        assert!(request.starts_with("POST /users?admin=true HTTP/1.1\r\n"));
+        assert!(request.contains("\r\nContent-Type: text/plain\r\n"));
+        assert!(request.ends_with("\r\n\r\nferris"));
+    }
+
+    #[test]
+    fn chunked_and_unsized_bodies_are_read() {
+        let (url, _) = serve_once(
+            "HTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n4\r\nWiki\r\n5;ext\r\npedia\r\n0\r\n\r\n",
+        );
+        assert_eq!(get(url).unwrap().text().unwrap(), "Wikipedia");
+
+        let (url, _) = serve_once("HTTP/1.0 200 OK\r\n\r\nuntil close");
+        assert_eq!(get(url).unwrap().body, b"until close");
+    }
+
+    #[test]
+    fn mismatches_include_the_body() {
+        let resp = Response {
+            status: 404,
+            headers: Vec::new(),
+            body: b"no such user".to_vec(),
+        };
+
+        assert_eq!(
+            assert_status!(resp, 200).unwrap_err().to_string(),
+            "expected status 200, got 404\n  body: \"no such user\""
+        );
+        assert_eq!(
+            assert_body_contains!(resp, "ferris")
+                .unwrap_err()
+                .to_string(),
+            "response body does not contain \"ferris\"\n  body: \"no such user\""
+        );
+        assert!(get("https://example.com").is_err());
+    }
+}
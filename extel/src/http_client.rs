@@ -0,0 +1,225 @@
+//! The HTTP/1.1 client of the `http` module, which is always built so that
+//! [`wait_for_http`](crate::wait::wait_for_http) can send its requests without the `http` feature.
+
+#![cfg_attr(not(feature = "http"), allow(dead_code))]
+
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{TcpStream, ToSocketAddrs},
+    time::Duration,
+};
+
+use crate::{err, errors::Error};
+
+/// How long a request may take to connect, and to send or receive each chunk, by default.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// An HTTP response.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Response {
+    /// The status code, such as `200`.
+    pub status: u16,
+    /// The headers in the order they were received.
+    pub headers: Vec<(String, String)>,
+    /// The body, with any chunked transfer encoding removed.
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// The value of the first header called `name`, ignoring case.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// The body as UTF-8 text.
+    pub fn text(&self) -> Result<String, Error> {
+        Ok(String::from_utf8(self.body.clone())?)
+    }
+
+    /// The body parsed as JSON.
+    ///
+    /// > *This is only available with the `serde` feature enabled.*
+    #[cfg(feature = "serde")]
+    pub fn json(&self) -> Result<crate::json::Value, Error> {
+        crate::json::parse(&self.body)
+    }
+}
+
+/// An HTTP request that is sent with [`Request::send`].
+#[derive(Debug, Clone)]
+pub struct Request {
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+    timeout: Duration,
+}
+
+/// Start building a `method` request to `url`.
+pub fn request(method: &str, url: impl Into<String>) -> Request {
+    Request {
+        method: method.to_uppercase(),
+        url: url.into(),
+        headers: Vec::new(),
+        body: Vec::new(),
+        timeout: DEFAULT_TIMEOUT,
+    }
+}
+
+/// Send a `GET` request to `url`.
+pub fn get(url: impl Into<String>) -> Result<Response, Error> {
+    request("GET", url).send()
+}
+
+/// Send a `POST` request with `body` to `url`.
+pub fn post(url: impl Into<String>, body: impl Into<Vec<u8>>) -> Result<Response, Error> {
+    request("POST", url).body(body).send()
+}
+
+impl Request {
+    /// Add a header to the request.
+    pub fn header(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    /// Change the body of the request. A `Content-Length` header is always sent for it.
+    pub fn body(mut self, body: impl Into<Vec<u8>>) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    /// Change how long the request may take to connect, and to send or receive each chunk.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Send the request and read the whole response.
+    pub fn send(self) -> Result<Response, Error> {
+        let (authority, path) = split_url(&self.url)?;
+        let host_port = match authority.contains(':') && !authority.ends_with(']') {
+            true => authority.to_string(),
+            false => format!("{}:80", authority),
+        };
+
+        let addr = host_port
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| err!("could not resolve {}", authority))?;
+        let mut stream = TcpStream::connect_timeout(&addr, self.timeout)?;
+        stream.set_read_timeout(Some(self.timeout))?;
+        stream.set_write_timeout(Some(self.timeout))?;
+
+        let mut head = format!(
+            "{} {} HTTP/1.1\r\nHost: {}\r\nConnection: close\r\nContent-Length: {}\r\n",
+            self.method,
+            path,
+            authority,
+            self.body.len()
+        );
+        for (name, value) in &self.headers {
+            head.push_str(&format!("{}: {}\r\n", name, value));
+        }
+        head.push_str("\r\n");
+        stream.write_all(head.as_bytes())?;
+        stream.write_all(&self.body)?;
+        stream.flush()?;
+
+        read_response(BufReader::new(stream), &self.method)
+    }
+}
+
+/// Split an `http://` URL into its authority (`host[:port]`) and request target, which always
+/// starts with `/`.
+pub(crate) fn split_url(url: &str) -> Result<(&str, String), Error> {
+    let rest = url
+        .strip_prefix("http://")
+        .ok_or_else(|| err!("only http:// URLs are supported, got '{}'", url))?;
+    let (authority, target) = rest.split_at(rest.find(['/', '?', '#']).unwrap_or(rest.len()));
+    let target = target.split('#').next().unwrap_or_default();
+
+    match target.starts_with('/') {
+        true => Ok((authority, target.to_string())),
+        false => Ok((authority, format!("/{}", target))),
+    }
+}
+
+fn read_response(mut reader: impl BufRead, method: &str) -> Result<Response, Error> {
+    let mut status_line = String::new();
+    reader.read_line(&mut status_line)?;
+    let status = status_line
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .ok_or_else(|| err!("malformed HTTP status line: {:?}", status_line.trim_end()))?;
+
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    let mut response = Response {
+        status,
+        headers,
+        body: Vec::new(),
+    };
+
+    // Responses to HEAD requests, and 1xx, 204, and 304 responses, never have a body.
+    if method == "HEAD" || status / 100 == 1 || status == 204 || status == 304 {
+        return Ok(response);
+    }
+
+    let chunked = response
+        .header("Transfer-Encoding")
+        .is_some_and(|encoding| encoding.eq_ignore_ascii_case("chunked"));
+    let content_length = response
+        .header("Content-Length")
+        .and_then(|length| length.parse::<usize>().ok());
+
+    response.body = match (chunked, content_length) {
+        (true, _) => read_chunked(&mut reader)?,
+        (false, Some(length)) => {
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body)?;
+            body
+        }
+        (false, None) => {
+            let mut body = Vec::new();
+            reader.read_to_end(&mut body)?;
+            body
+        }
+    };
+    Ok(response)
+}
+
+/// Read a body sent with the chunked transfer encoding, ignoring chunk extensions and trailers.
+fn read_chunked(reader: &mut impl BufRead) -> Result<Vec<u8>, Error> {
+    let mut body = Vec::new();
+    loop {
+        let mut size_line = String::new();
+        reader.read_line(&mut size_line)?;
+        let size = size_line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16)
+            .map_err(|_| err!("malformed chunk size: {:?}", size_line.trim_end()))?;
+
+        if size == 0 {
+            return Ok(body);
+        }
+
+        let start = body.len();
+        body.resize(start + size, 0);
+        reader.read_exact(&mut body[start..])?;
+        let mut crlf = String::new();
+        reader.read_line(&mut crlf)?;
+    }
+}
@@ -0,0 +1,77 @@
+//! Restoring process-wide state that tests may change, so that one test cannot leak into the
+//! next. See [`TestConfig::isolate_env`](crate::TestConfig::isolate_env).
+
+use std::{env, ffi::OsString, path::PathBuf};
+
+/// The environment variables and working directory of the process at some point in time.
+pub(crate) struct EnvSnapshot {
+    vars: Vec<(OsString, OsString)>,
+    cwd: Option<PathBuf>,
+}
+
+impl EnvSnapshot {
+    /// Record the current environment variables and working directory.
+    pub(crate) fn take() -> Self {
+        Self {
+            vars: env::vars_os().collect(),
+            cwd: env::current_dir().ok(),
+        }
+    }
+
+    /// Put the environment variables and working directory back the way they were when the
+    /// snapshot was taken.
+    pub(crate) fn restore(self) {
+        let current: Vec<OsString> = env::vars_os().map(|(key, _)| key).collect();
+        for key in current {
+            if !self.vars.iter().any(|(k, _)| *k == key) {
+                env::remove_var(key);
+            }
+        }
+
+        for (key, value) in self.vars {
+            if env::var_os(&key).as_ref() != Some(&value) {
+                env::set_var(key, value);
+            }
+        }
+
+        // The directory may have been removed by the test, in which case there is nowhere to go
+        // back to.
+        if let Some(cwd) = self.cwd {
+            let _ = env::set_current_dir(cwd);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{prelude::*, OutputDest};
+
+    fn sets_var() -> ExtelResult {
+        std::env::set_var("EXTEL_ISOLATION_TEST", "leaked");
+        std::env::remove_var("EXTEL_ISOLATION_KEEP");
+        pass!()
+    }
+
+    fn sees_clean_env() -> ExtelResult {
+        extel_assert!(
+            std::env::var_os("EXTEL_ISOLATION_TEST").is_none()
+                && std::env::var_os("EXTEL_ISOLATION_KEEP").is_some()
+        )
+    }
+
+    #[test]
+    fn isolate_env_restores_vars() {
+        init_test_suite!(IsolatedSuite, sets_var, sees_clean_env);
+
+        std::env::set_var("EXTEL_ISOLATION_KEEP", "1");
+        let results = IsolatedSuite::run(
+            TestConfig::default()
+                .output(OutputDest::None)
+                .isolate_env(true),
+        );
+        std::env::remove_var("EXTEL_ISOLATION_KEEP");
+
+        assert!(results.iter().all(|r| r.test_result.is_ok()));
+        assert!(std::env::var_os("EXTEL_ISOLATION_TEST").is_none());
+    }
+}
@@ -0,0 +1,181 @@
+//! Structural assertions on JSON output, so that commands emitting JSON can be checked without
+//! depending on key order or whitespace.
+//!
+//! Values are compared with [`assert_json_eq`](crate::assert_json_eq), and single fields are
+//! picked out with a path such as `$.items[0].id` by
+//! [`assert_json_path`](crate::assert_json_path).
+//!
+//! ```rust
+//! use extel::{json::json, prelude::*};
+//!
+//! fn lists_items() -> ExtelResult {
+//!     let output = cmd!("echo" => [r#"{"count": 2, "items": [{"id": 3}, {"id": 4}]}"#]).output()?;
+//!
+//!     assert_json_path!(output.stdout, "$.items[0].id", 3)?;
+//!     assert_json_eq!(
+//!         output.stdout,
+//!         json!({"items": [{"id": 3}, {"id": 4}], "count": 2})
+//!     )
+//! }
+//!
+//! assert!(lists_items().is_ok());
+//! ```
+//!
+//! > *This is only available with the `serde` feature enabled.*
+
+use crate::{err, errors::Error, ExtelResult};
+
+pub use serde_json::{json, Value};
+
+/// Parse `actual`, such as the stdout of a command, as JSON.
+pub fn parse(actual: impl AsRef<[u8]>) -> Result<Value, Error> {
+    Ok(serde_json::from_slice(actual.as_ref())?)
+}
+
+/// Check that `actual` parses to the same JSON value as `expected`, failing with every path at
+/// which they differ. Used by [`assert_json_eq`](crate::assert_json_eq).
+pub fn json_eq(actual: impl AsRef<[u8]>, expected: Value) -> ExtelResult {
+    let actual = parse(actual)?;
+    let mut differences = Vec::new();
+    diff("$", &expected, &actual, &mut differences);
+
+    match differences.is_empty() {
+        true => Ok(()),
+        false => Err(err!("JSON does not match\n  {}", differences.join("\n  "))),
+    }
+}
+
+/// Check that the value at `path` in `actual` equals `expected`. Used by
+/// [`assert_json_path`](crate::assert_json_path).
+pub fn json_path_eq(actual: impl AsRef<[u8]>, path: &str, expected: Value) -> ExtelResult {
+    let actual = parse(actual)?;
+    match lookup(&actual, path)? {
+        Some(value) if *value == expected => Ok(()),
+        Some(value) => Err(err!("{}: expected {}, got {}", path, expected, value)),
+        None => Err(err!(
+            "{}: expected {}, but there is no such value",
+            path,
+            expected
+        )),
+    }
+}
+
+/// Find the value at `path` in `value`, where `path` starts at `$` and selects object keys with
+/// `.key` or `["key"]` and array elements with `[index]`. Returns `None` if there is no value at
+/// `path`, and an error if `path` is malformed.
+///
+/// # Example
+/// ```rust
+/// use extel::json::{json, lookup};
+///
+/// let value = json!({"items": [{"id": 3}], "a.b": true});
+/// assert_eq!(lookup(&value, "$.items[0].id").unwrap(), Some(&json!(3)));
+/// assert_eq!(lookup(&value, "$[\"a.b\"]").unwrap(), Some(&json!(true)));
+/// assert_eq!(lookup(&value, "$.items[1]").unwrap(), None);
+/// assert!(lookup(&value, "items").is_err());
+/// ```
+pub fn lookup<'v>(value: &'v Value, path: &str) -> Result<Option<&'v Value>, Error> {
+    let invalid = || err!("invalid JSON path {:?}", path);
+    let mut rest = path.strip_prefix('$').ok_or_else(invalid)?;
+    let mut current = value;
+
+    while !rest.is_empty() {
+        let next = match rest.as_bytes()[0] {
+            b'.' => {
+                let end = rest[1..].find(['.', '[']).map_or(rest.len(), |i| i + 1);
+                let key = &rest[1..end];
+                if key.is_empty() {
+                    return Err(invalid());
+                }
+                rest = &rest[end..];
+                current.get(key)
+            }
+            b'[' => {
+                let end = rest.find(']').ok_or_else(invalid)?;
+                let selector = &rest[1..end];
+                rest = &rest[end + 1..];
+                match selector.strip_prefix('"').and_then(|s| s.strip_suffix('"')) {
+                    Some(key) => current.get(key),
+                    None => current.get(selector.parse::<usize>().map_err(|_| invalid())?),
+                }
+            }
+            _ => return Err(invalid()),
+        };
+
+        match next {
+            Some(value) => current = value,
+            None => return Ok(None),
+        }
+    }
+
+    Ok(Some(current))
+}
+
+/// Append a description of every difference between `expected` and `actual` at `path`.
+fn diff(path: &str, expected: &Value, actual: &Value, differences: &mut Vec<String>) {
+    match (expected, actual) {
+        (Value::Object(expected), Value::Object(actual)) => {
+            for (key, expected) in expected {
+                let path = format!("{}.{}", path, key);
+                match actual.get(key) {
+                    Some(actual) => diff(&path, expected, actual, differences),
+                    None => differences.push(format!("{}: missing, expected {}", path, expected)),
+                }
+            }
+            for (key, actual) in actual {
+                if !expected.contains_key(key) {
+                    differences.push(format!("{}.{}: unexpected {}", path, key, actual));
+                }
+            }
+        }
+        (Value::Array(expected), Value::Array(actual)) if expected.len() == actual.len() => {
+            for (i, (expected, actual)) in expected.iter().zip(actual).enumerate() {
+                diff(&format!("{}[{}]", path, i), expected, actual, differences);
+            }
+        }
+        (expected, actual) if expected != actual => {
+            differences.push(format!("{}: expected {}, got {}", path, expected, actual));
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_eq_reports_every_difference() {
+        let err = json_eq(
+            r#"{"name": "extel", "items": [1, 5], "extra": null}"#,
+            json!({"name": "extel", "items": [1, 2], "version": 2}),
+        )
+        .unwrap_err();
+
+        assert_eq!(
+            err.to_string(),
+            "JSON does not match\n  \
+             $.items[1]: expected 2, got 5\n  \
+             $.version: missing, expected 2\n  \
+             $.extra: unexpected null"
+        );
+    }
+
+    #[test]
+    fn json_path_eq_checks_one_value() {
+        let output = br#"{"items": [{"id": 3}]}"#;
+
+        assert!(json_path_eq(output, "$.items[0].id", json!(3)).is_ok());
+        assert_eq!(
+            json_path_eq(output, "$.items[0].id", json!(4))
+                .unwrap_err()
+                .to_string(),
+            "$.items[0].id: expected 4, got 3"
+        );
+        assert!(json_path_eq(output, "$.items[2]", json!(3)).is_err());
+        assert!(matches!(
+            json_path_eq("not json", "$", json!(3)),
+            Err(Error::Json(_))
+        ));
+    }
+}
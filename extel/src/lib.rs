@@ -11,8 +11,8 @@
 //! then chose where your test output goes, too!
 //!
 //! ## Usage
-//! Extel is intended to function on zero argument or single argument functions (the former being
-//! called *single* tests and the latter *parameterized* tests). After creating your test function,
+//! Extel is intended to function on zero argument functions (called *single* tests) and on
+//! functions with arguments run once per case (called *parameterized* tests). After creating your test function,
 //! you can register it using the [`init_test_suite`] macro. This will scaffold a struct containing
 //! pointers to the functions passed in. Calling the [`run`](RunnableTestSet::run) function on the
 //! generated struct will immediately go through all tests and collect the results into a vector
@@ -44,17 +44,17 @@
 //! }
 //!
 //! fn main() {
-//!     init_test_suite!(ExtelDemo, single_test, param_test);
+//!     init_test_suite!(ExtelDemo, single_test, param_test_cases);
 //!     ExtelDemo::run(TestConfig::default());
 //! }
 
-/// Convert a *single argument function* into a parameterized function. The expected function
-/// signature is a single argument function (can be any type) that returns an
-/// [`ExtelResult`](crate::ExtelResult).
+/// Generate a parameterized `<name>_cases` function from a function with arguments. The expected
+/// function signature takes one or more arguments (of any type) and returns an
+/// [`ExtelResult`](crate::ExtelResult). The original function is left untouched, so it can still
+/// be called directly with an argument.
 ///
-/// While technically possible, this macro is not intended to be used to run tests
-/// manually. This macro is specifically for the purpose of helping the [test
-/// initializer](crate::init_test_suite) prepare parameterized tests.
+/// The generated `<name>_cases` function is what gets registered with the [test
+/// initializer](crate::init_test_suite).
 ///
 /// # Example
 /// ```rust
@@ -67,7 +67,7 @@
 /// }
 ///
 /// assert!(matches!(
-///     &less_than_3()[..],
+///     &less_than_3_cases()[..],
 ///     [
 ///         Ok(_),
 ///         Err(Error::TestFailed(_))
@@ -78,19 +78,69 @@
 #[cfg(feature = "parameterized")]
 pub use extel_parameterized::parameters;
 
+/// Inject [fixtures](crate::fixture) into a test function. Every argument of the function must
+/// implement [`Fixture`](crate::fixture::Fixture); each one is set up before the test body runs
+/// and torn down once it returns. The resulting function takes no arguments and can be registered
+/// with the [test initializer](crate::init_test_suite) like any other single test.
+///
+/// > *This is only available with the `fixtures` feature enabled.*
+#[cfg(feature = "fixtures")]
+pub use extel_parameterized::fixtures;
+
+/// Only run a test on platforms matching a `cfg` predicate, such as `#[extel_cfg(unix)]`, and
+/// [skip](crate::errors::Error::Skipped) it elsewhere with the predicate as the reason, rather than
+/// compiling it out of the suite.
+///
+/// # Example
+/// ```rust
+/// use extel::{extel_cfg, prelude::*};
+///
+/// #[extel_cfg(target_os = "linux")]
+/// fn reads_proc() -> ExtelResult {
+///     extel_assert!(std::path::Path::new("/proc/self").exists())
+/// }
+///
+/// init_test_suite!(LinuxSuite, reads_proc);
+/// let results = LinuxSuite::run(TestConfig::default());
+/// assert!(results[0].test_result.is_ok() || results[0].test_result.is_skipped());
+/// ```
+pub use extel_parameterized::extel_cfg;
+
 pub mod prelude {
     pub use crate::{
-        cmd, err, errors::Error, extel_assert, fail, init_test_suite, pass, ExtelResult,
-        RunnableTestSet, TestConfig,
+        assert_approx_eq, assert_bytes_snapshot, assert_cmd_matches, assert_contains,
+        assert_csv_cell, assert_csv_column, assert_csv_rows, assert_dir_snapshot, assert_ends_with,
+        assert_file_eq, assert_file_exists, assert_matches_file, assert_relative_eq,
+        assert_starts_with, assert_stderr_bytes_eq, assert_stdout_bytes_eq, assert_that, cmd,
+        cmd_template,
+        command::CommandExt,
+        compose_suites, err,
+        errors::{Error, IntoExtelResult},
+        expect::Expectation,
+        expect_exit, extel_assert, extel_assert_eq, extel_assert_ne, fail, init_test_suite, pass,
+        require_binary, require_env, require_port_free, skip, step, ExtelResult, RunnableTestSet,
+        TestConfig,
     };
 
-    /// Convert a *single argument function* into a parameterized function. The expected function
-    /// signature is a single argument function (can be any type) that returns an
-    /// [`ExtelResult`](crate::ExtelResult).
+    /// > *This is only available with the `serde` feature enabled.*
+    #[cfg(feature = "serde")]
+    pub use crate::{assert_json_eq, assert_json_path};
+
+    /// > *This is only available with the `http` feature enabled.*
+    #[cfg(feature = "http")]
+    pub use crate::{assert_body_contains, assert_status};
+
+    /// > *This is only available with the `ssh` feature enabled.*
+    #[cfg(feature = "ssh")]
+    pub use crate::remote_cmd;
+
+    /// Generate a parameterized `<name>_cases` function from a function with arguments. The expected
+    /// function signature takes one or more arguments (of any type) and returns an
+    /// [`ExtelResult`](crate::ExtelResult). The original function is left untouched, so it can still
+    /// be called directly with an argument.
     ///
-    /// While technically possible, this macro is not intended to be used to run tests
-    /// manually. This macro is specifically for the purpose of helping the [test
-    /// initializer](crate::init_test_suite) prepare parameterized tests.
+    /// The generated `<name>_cases` function is what gets registered with the [test
+    /// initializer](crate::init_test_suite).
     ///
     /// # Example
     /// ```rust
@@ -103,7 +153,7 @@ pub mod prelude {
     /// }
     ///
     /// assert!(matches!(
-    ///     &less_than_3()[..],
+    ///     &less_than_3_cases()[..],
     ///     [
     ///         Ok(_),
     ///         Err(Error::TestFailed(_))
@@ -113,16 +163,121 @@ pub mod prelude {
     /// > *This is only available with the `parameterized` feature enabled.*
     #[cfg(feature = "parameterized")]
     pub use extel_parameterized::parameters;
+
+    /// Inject [fixtures](crate::fixture) into a test function. See [`fixtures`](crate::fixtures).
+    ///
+    /// > *This is only available with the `fixtures` feature enabled.*
+    #[cfg(feature = "fixtures")]
+    pub use extel_parameterized::fixtures;
 }
 
 use errors::Error;
-use std::io::{BufWriter, Write};
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    fmt,
+    io::{BufWriter, IsTerminal, Write},
+    path::PathBuf,
+    sync::{Arc, Mutex, MutexGuard, Once},
+    time::{Duration, Instant},
+};
+use style::Style;
 
+pub mod allure;
+pub mod artifacts;
+pub mod assertion;
+pub mod bytes;
+#[cfg(all(feature = "capture", unix))]
+pub mod capture;
+pub mod cargo;
+pub mod cases;
+pub mod cassette;
+pub mod cli;
+pub mod clock;
+pub mod command;
+pub mod compose;
+#[cfg(feature = "config-file")]
+pub mod config;
+pub mod context;
+pub mod csv;
+pub mod daemon;
+mod deadline;
+pub mod diff;
+#[cfg(feature = "docker")]
+pub mod docker;
 pub mod errors;
+pub mod executor;
+pub mod expect;
+pub mod fixture;
+pub mod fs;
+pub mod golden;
+#[cfg(feature = "grpc")]
+pub mod grpc;
+pub mod harness;
+pub mod history;
+#[cfg(feature = "http")]
+pub mod http;
+mod http_client;
+mod isolation;
+#[cfg(feature = "serde")]
+pub mod json;
+pub mod libtest;
+#[cfg(any(feature = "log", feature = "tracing"))]
+pub mod logs;
+pub mod manifest;
+pub mod net;
+pub mod normalize;
+pub mod order;
+pub mod outcome;
+#[cfg(all(feature = "pty", unix))]
+pub mod pty;
+pub mod report;
+pub mod require;
+pub mod results;
+pub mod run_info;
+pub mod runner;
+pub mod shard;
+#[cfg(feature = "ssh")]
+pub mod ssh;
+pub mod state;
+pub mod step;
+pub mod style;
+mod subprocess;
+pub mod suite;
+pub mod usage;
+pub mod wait;
+#[cfg(feature = "watch")]
+pub mod watch;
+
+#[cfg(all(
+    feature = "sandbox",
+    target_os = "linux",
+    any(target_arch = "x86_64", target_arch = "aarch64")
+))]
+pub mod sandbox;
+pub mod session;
+
+pub use order::TestOrder;
+pub use outcome::{CaseResult, OmittedCases, TestOutcome};
+pub use report::{JsonReporter, ProgressReporter, Reporter, TextReporter};
+pub use results::{SuiteCounts, SuiteReport, SuiteResults};
+pub use suite::SuiteBuilder;
+
+#[cfg(feature = "http-mock")]
+pub mod mock;
 
 #[doc(hidden)]
 pub mod macros;
 
+/// The environment variable used as the default [`TestConfig::filter`]. Only tests whose name
+/// contains its value are run.
+pub const FILTER_ENV: &str = "EXTEL_FILTER";
+
+/// The environment variable used as the default [`TestConfig::exact`]. Only the test it names,
+/// as `suite::test` or `suite::test#case`, is run.
+pub const EXACT_ENV: &str = "EXTEL_EXACT";
+
 /// The expected return type of extel test functions. This type is represented as a result type to
 /// allow error propogation.
 ///
@@ -152,233 +307,1993 @@ pub mod macros;
 /// ```
 pub type ExtelResult = Result<(), Error>;
 
-#[derive(Debug)]
-/// A test result variant that helps distinguish between standard, or single, tests and
-/// parameterized tests. Both the `Single` and `Parameterized` variants contain one or more
-/// [`ExtelResult`].
-pub enum TestStatus {
-    Single(ExtelResult),
-    Parameterized(Vec<ExtelResult>),
-}
-
-/// Represents a generic test result. The test result can be extracted into a [`TestStatus`] to
-/// determine if the result came from a parameterized or single test.
-pub trait GenericTestResult {
-    fn get_test_result(self: Box<Self>) -> TestStatus;
+/// A function that can be registered as a test: either `fn() -> R` or `fn(&TestContext) -> R`,
+/// where `R` converts into a [`TestOutcome`], such as an [`ExtelResult`] or the
+/// `Vec<ExtelResult>` of a parameterized test. The `Args` parameter only exists to tell the two
+/// signatures apart.
+pub trait TestFn<Args> {
+    /// Call the test function, giving it `ctx` if it asks for one.
+    fn call_test(self, ctx: &context::TestContext) -> TestOutcome;
 }
 
-impl GenericTestResult for ExtelResult {
-    fn get_test_result(self: Box<Self>) -> TestStatus {
-        TestStatus::Single(*self)
+impl<F, R> TestFn<()> for F
+where
+    F: FnOnce() -> R,
+    R: Into<TestOutcome>,
+{
+    fn call_test(self, _ctx: &context::TestContext) -> TestOutcome {
+        self().into()
     }
 }
 
-impl GenericTestResult for Vec<ExtelResult> {
-    fn get_test_result(self: Box<Self>) -> TestStatus {
-        TestStatus::Parameterized(*self)
+impl<F, R> TestFn<(context::TestContext,)> for F
+where
+    F: FnOnce(&context::TestContext) -> R,
+    R: Into<TestOutcome>,
+{
+    fn call_test(self, ctx: &context::TestContext) -> TestOutcome {
+        self(ctx).into()
     }
 }
 
+/// The function of a [`Test`], boxed so that tests built at runtime can capture their inputs.
+pub type BoxedTestFn = Box<dyn FnOnce() -> TestOutcome>;
+
+/// A function that describes the environment of a failed test, registered with
+/// [`TestConfig::on_failure`].
+pub type FailureHook = Arc<dyn Fn(&TestResult) -> String + Send + Sync>;
+
 /// A test instance that contains the test name and the test function that will be run.
 pub struct Test {
+    pub test_name: Cow<'static, str>,
+    pub test_fn: BoxedTestFn,
+    pub tags: &'static [&'static str],
+    /// The path of the module the test was registered in, or an empty string if it is not known.
+    /// See [`TestConfig::qualified_names`].
+    pub module_path: &'static str,
+}
+
+/// A test stored in the static table of a suite created with [`init_test_suite`], available as
+/// the suite's `TESTS` associated constant. Unlike a [`Test`], it needs no allocation, so a custom
+/// runner can go through the table without using the heap at startup, and the size of a suite can
+/// be checked at compile time. [`RunnableTestSet::run`] does allocate, as it converts each entry
+/// into a [`Test`] before running it.
+///
+/// # Example
+/// ```rust
+/// use extel::prelude::*;
+///
+/// fn always_succeed() -> ExtelResult {
+///     pass!()
+/// }
+///
+/// fn always_fail() -> ExtelResult {
+///     fail!("no luck")
+/// }
+///
+/// init_test_suite!(StaticTestSuite, always_succeed, always_fail);
+/// const _: () = assert!(StaticTestSuite::TESTS.len() == 2);
+///
+/// for test in StaticTestSuite::TESTS {
+///     let passed = (test.test_fn)().is_ok();
+///     assert_eq!(passed, test.test_name == "always_succeed");
+/// }
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct StaticTest {
     pub test_name: &'static str,
-    pub test_fn: fn() -> Box<dyn GenericTestResult>,
+    pub test_fn: fn() -> TestOutcome,
+    pub tags: &'static [&'static str],
+    pub module_path: &'static str,
+}
+
+impl From<StaticTest> for Test {
+    fn from(test: StaticTest) -> Self {
+        Self {
+            test_name: Cow::Borrowed(test.test_name),
+            test_fn: Box::new(test.test_fn),
+            tags: test.tags,
+            module_path: test.module_path,
+        }
+    }
 }
 
 impl Test {
-    /// Run a test function, returning the name of the test and the result of it in a [`GenericTestResult`].
+    /// A test called `test_name` running `test`, which may take a
+    /// [`TestContext`](context::TestContext) like the tests of a [`SuiteBuilder`]. Use this to
+    /// build the tests of a hand-written [`RunnableTestSet`]; see [`runner`].
+    pub fn new<Args>(
+        test_name: impl Into<Cow<'static, str>>,
+        test: impl TestFn<Args> + 'static,
+    ) -> Self {
+        Self {
+            test_name: test_name.into(),
+            test_fn: Box::new(move || test.call_test(&context::TestContext::current())),
+            tags: &[],
+            module_path: "",
+        }
+    }
+
+    /// Prefix the name of the test with its module path, unless it is unknown.
+    fn qualify(&mut self) {
+        if !self.module_path.is_empty() {
+            self.test_name = format!("{}::{}", self.module_path, self.test_name).into();
+        }
+    }
+
+    /// Run a test function, returning the name of the test and its [`TestOutcome`]. A panicking
+    /// test results in an [`Error::Panicked`] rather than unwinding into the runner.
     pub fn run_test(self) -> TestResult {
+        let test_result = catch_panic(self.test_fn).unwrap_or_else(|e| TestOutcome::single(Err(e)));
+
         TestResult {
             test_name: self.test_name,
-            test_result: (self.test_fn)().get_test_result(),
+            test_result,
+            tags: self.tags,
+            artifacts: Vec::new(),
+            resource_usage: Vec::new(),
+            metadata: HashMap::new(),
+            duration: Default::default(),
+            commands: Vec::new(),
+            logs: Vec::new(),
+            steps: Vec::new(),
+            failure_context: Vec::new(),
+            #[cfg(all(feature = "capture", unix))]
+            captured: None,
         }
     }
 }
 
+thread_local! {
+    /// How many calls to [`catch_panic`] are running on this thread.
+    static CATCHING: Cell<usize> = const { Cell::new(0) };
+    /// Where the last panic caught by [`catch_panic`] on this thread happened.
+    static PANIC_LOCATION: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+/// Run `f`, converting a panic into an [`Error::Panicked`] containing the panic message and where
+/// it happened. The panic is not printed to stderr, as it is reported with the failure instead.
+///
+/// # Example
+/// ```rust
+/// use extel::{catch_panic, errors::Error};
+///
+/// let result = catch_panic(|| -> usize { panic!("oh no") });
+/// assert!(matches!(result, Err(Error::Panicked(msg)) if msg.starts_with("oh no at src/lib.rs:")));
+/// assert_eq!(catch_panic(|| 5).unwrap(), 5);
+/// ```
+pub fn catch_panic<T>(f: impl FnOnce() -> T) -> Result<T, Error> {
+    install_panic_hook();
+    CATCHING.with(|catching| catching.set(catching.get() + 1));
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(f));
+    CATCHING.with(|catching| catching.set(catching.get() - 1));
+
+    result.map_err(|payload| {
+        let msg = match (
+            payload.downcast_ref::<&str>(),
+            payload.downcast_ref::<String>(),
+        ) {
+            (Some(msg), _) => msg.to_string(),
+            (_, Some(msg)) => msg.clone(),
+            _ => String::from("<non-string panic payload>"),
+        };
+        match PANIC_LOCATION.with(|location| location.borrow_mut().take()) {
+            Some(location) => Error::Panicked(format!("{} at {}", msg, location)),
+            None => Error::Panicked(msg),
+        }
+    })
+}
+
+/// Install a panic hook, once, that records where panics caught by [`catch_panic`] happened
+/// instead of printing them. Every other panic is left to the hook that was installed before.
+fn install_panic_hook() {
+    static INSTALLED: Once = Once::new();
+    INSTALLED.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| match CATCHING.with(Cell::get) > 0 {
+            true => PANIC_LOCATION
+                .with(|location| *location.borrow_mut() = info.location().map(ToString::to_string)),
+            false => previous(info),
+        }));
+    });
+}
+
 /// A test result item that contains the name of the test and a result value. The value can either
 /// be a success or a failure. If a failure, there will be an underlying message as well to explain
 /// the context of the failure.
 #[derive(Debug)]
 pub struct TestResult {
-    pub test_name: &'static str,
-    pub test_result: TestStatus,
+    pub test_name: Cow<'static, str>,
+    pub test_result: TestOutcome,
+    pub tags: &'static [&'static str],
+    /// The [artifact](artifacts::artifact_path) paths requested by the test.
+    pub artifacts: Vec<PathBuf>,
+    /// The [resource usage](usage::ResourceUsage) of every child process measured by the test.
+    pub resource_usage: Vec<usage::ResourceUsage>,
+    /// Key/value pairs attached by the test through its [`TestContext`](context::TestContext).
+    pub metadata: HashMap<String, String>,
+    /// How long the test took to run.
+    pub duration: Duration,
+    /// Every [command](command::CommandRecord) the test ran through
+    /// [`CommandExt`](command::CommandExt).
+    pub commands: Vec<command::CommandRecord>,
+    /// The [log records](logs) emitted by the test, if log capturing was enabled.
+    pub logs: Vec<String>,
+    /// The [steps](step) the test ran, in the order they finished.
+    pub steps: Vec<step::StepRecord>,
+    /// The name and output of every [failure hook](TestConfig::on_failure) run after the test
+    /// failed.
+    pub failure_context: Vec<(String, String)>,
+    /// What the test wrote to stdout/stderr, if output capturing was enabled.
+    #[cfg(all(feature = "capture", unix))]
+    pub captured: Option<capture::CapturedOutput>,
 }
 
-/// The output method for logging test results.
-#[derive(Debug)]
-pub enum OutputDest<'a> {
+/// The output method for logging test results. An output file is truncated the first time a
+/// suite writes to it and appended to by the suites that run after it in the same process.
+#[derive(Debug, Clone)]
+pub enum OutputDest {
     Stdout,
-    File(&'static str),
-    Buffer(&'a mut Vec<u8>),
+    /// Write [JSON Lines events](JsonReporter) to stdout instead of text.
+    Json,
+    File(PathBuf),
+    Buffer(SharedBuffer),
     None,
 }
 
+impl OutputDest {
+    /// Parse an output destination given as text, where `stdout`, `json`, and `none` name the
+    /// matching variants and anything else is a file path. A file called `json` can still be
+    /// written to as `./json`.
+    pub(crate) fn parse(value: &str) -> Self {
+        match value {
+            "stdout" => OutputDest::Stdout,
+            "json" => OutputDest::Json,
+            "none" => OutputDest::None,
+            path => OutputDest::File(PathBuf::from(path)),
+        }
+    }
+}
+
+/// An in-memory buffer that test results can be written to with [`OutputDest::Buffer`] and read
+/// back from once the suite has run. Clones share the same contents, so a config holding one
+/// can be stored, moved to another thread, or reused without borrowing the buffer.
+///
+/// # Example
+/// ```rust
+/// use extel::{prelude::*, OutputDest, SharedBuffer};
+///
+/// fn always_succeed() -> ExtelResult {
+///     pass!()
+/// }
+///
+/// let output = SharedBuffer::new();
+/// init_test_suite!(BufferedSuite as "buffered", always_succeed);
+/// BufferedSuite::run(
+///     TestConfig::default()
+///         .output(OutputDest::Buffer(output.clone()))
+///         .colored(false),
+/// );
+///
+/// assert_eq!(
+///     output.to_string_lossy(),
+///     "[buffered]\n\tTest #1 (always_succeed) ... ok\n"
+/// );
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl SharedBuffer {
+    /// Create an empty buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A copy of everything written to the buffer so far.
+    pub fn contents(&self) -> Vec<u8> {
+        self.lock().clone()
+    }
+
+    /// Everything written to the buffer so far as text, replacing invalid UTF-8.
+    pub fn to_string_lossy(&self) -> String {
+        String::from_utf8_lossy(&self.lock()).into_owned()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Vec<u8>> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.lock().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
 /// A test configuration type that determines what features will be enabled on the tests.
-#[derive(Debug)]
-pub struct TestConfig<'a> {
-    pub output: OutputDest<'a>,
-    pub colored: bool,
+///
+/// Configs can be cloned, so a preset such as one for CI can be built once and reused for every
+/// suite. Presets can also be kept in a [config file](TestConfig::from_file_preset).
+///
+/// # Example
+/// ```rust
+/// use extel::{prelude::*, OutputDest};
+///
+/// fn always_succeed() -> ExtelResult {
+///     pass!()
+/// }
+///
+/// let ci = TestConfig::default()
+///     .output(OutputDest::None)
+///     .colored(false);
+///
+/// init_test_suite!(FirstSuite, always_succeed);
+/// init_test_suite!(SecondSuite, always_succeed);
+/// assert!(FirstSuite::run(ci.clone())[0].test_result.is_ok());
+/// assert!(SecondSuite::run(ci.verbose(true))[0].test_result.is_ok());
+/// ```
+#[derive(Clone)]
+pub struct TestConfig {
+    pub output: OutputDest,
+    pub colored: Option<bool>,
+    pub reporter: Option<Arc<Mutex<dyn Reporter + Send>>>,
+    pub filter: Option<String>,
+    pub exact: Option<String>,
+    pub tags: Vec<String>,
+    pub rerun_hint: bool,
+    pub progress: bool,
+    pub order: TestOrder,
+    pub state_file: Option<PathBuf>,
+    pub rerun_failed: bool,
+    pub artifacts_dir: Option<PathBuf>,
+    pub metadata: HashMap<String, String>,
+    pub verbose: bool,
+    pub vars: HashMap<String, String>,
+    pub list: bool,
+    pub isolate_env: bool,
+    pub isolate_process: bool,
+    pub wrapper: Option<command::CommandWrapper>,
+    pub executor: Option<Arc<dyn executor::CommandExecutor>>,
+    pub cassette: Option<cassette::Cassette>,
+    pub dry_run: bool,
+    pub qualified_names: bool,
+    pub command_args: HashMap<String, Vec<String>>,
+    pub max_failure_len: Option<usize>,
+    pub tee_commands: bool,
+    pub manifest: Option<PathBuf>,
+    pub run_info: bool,
+    pub report_env: Vec<String>,
+    pub allure_dir: Option<PathBuf>,
+    pub history: Option<PathBuf>,
+    pub slow_threshold: f64,
+    pub shard: Option<shard::Shard>,
+    pub style: Style,
+    pub align: bool,
+    pub cluster_failures: bool,
+    pub case_jobs: usize,
+    pub summarize_cases: Option<usize>,
+    pub normalizer: Option<normalize::Normalizer>,
+    pub suite_timeout: Option<Duration>,
+    pub failure_hooks: Vec<(String, FailureHook)>,
+    #[cfg(all(feature = "capture", unix))]
+    pub capture: bool,
+    #[cfg(any(feature = "log", feature = "tracing"))]
+    pub capture_logs: bool,
+    #[cfg(all(
+        feature = "sandbox",
+        target_os = "linux",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    ))]
+    pub sandbox: Option<sandbox::SandboxProfile>,
 }
 
-impl<'a> TestConfig<'a> {
+impl TestConfig {
     /// Change the output destination.
-    pub fn output(mut self, output_style: OutputDest<'a>) -> Self {
+    pub fn output(mut self, output_style: OutputDest) -> Self {
         self.output = output_style;
         self
     }
 
-    /// Change whether or not the logging should output with ANSI color codes.
+    /// Change whether or not the logging should output with ANSI color codes. By default, colors
+    /// are only used when writing to a terminal, as decided by [`color_enabled`].
     pub fn colored(mut self, yes: bool) -> Self {
-        self.colored = yes;
+        self.colored = Some(yes);
         self
     }
-}
 
-impl<'a> Default for TestConfig<'a> {
-    fn default() -> Self {
-        Self {
-            output: OutputDest::Stdout,
-            colored: true,
-        }
+    /// Use a custom [`Reporter`] instead of the default [`TextReporter`]. When a reporter is set,
+    /// the `output` and `colored` options are ignored. Clones of the config share the reporter.
+    pub fn reporter(mut self, reporter: impl Reporter + Send + 'static) -> Self {
+        self.reporter = Some(Arc::new(Mutex::new(reporter)));
+        self
     }
-}
 
-/// A test set that produces a list of test results.
-pub trait RunnableTestSet {
-    /// Run a test set with the provided configuration to create a list of test results. The test
-    /// suite can contain both single, or standard, tests and parameterized tests. The results of
-    /// the parameterized tests will be flattened into the resulting vec.
-    fn run(cfg: TestConfig) -> Vec<TestResult>;
-}
+    /// Only run tests whose name contains `pattern`. By default, the filter is read from the
+    /// [`FILTER_ENV`] environment variable.
+    pub fn filter(mut self, pattern: impl Into<String>) -> Self {
+        self.filter = Some(pattern.into());
+        self
+    }
 
-/// Output the test results to the desired stream. This function is public only to give
-/// availability to the [test initializer](crate::init_test_suite). If you wish to generate test
-/// output, consider [`RunnableTestSet::run`].
-pub fn output_test_result<T: Write>(
-    stream: T,
-    result: &TestResult,
-    test_num: usize,
-    colored: bool,
-) {
-    // Kinda bogus but it'll work :V
-    let color_terminator = match colored {
-        true => "\x1b[0m",
-        false => "",
-    };
-    let ok_color = match colored {
-        true => "\x1b[32m",
-        false => "",
-    };
-    let fail_color = match colored {
-        true => "\x1b[31m",
-        false => "",
-    };
+    /// Only run the test named by `selector`, written as `suite::test`, or only its case number
+    /// `N` (from 1), written as `suite::test#N`. Suite names are as given to
+    /// [`Reporter::on_suite_start`], and the rerun commands printed under failing tests use this
+    /// selector. By default, the selector is read from the [`EXACT_ENV`] environment variable.
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::{prelude::*, OutputDest};
+    /// use extel_parameterized::parameters;
+    ///
+    /// #[parameters(1, 2, 3)]
+    /// fn small(n: u32) -> ExtelResult {
+    ///     extel_assert!(n < 3)
+    /// }
+    ///
+    /// fn other() -> ExtelResult {
+    ///     pass!()
+    /// }
+    ///
+    /// init_test_suite!(ExactSuite, small_cases, other);
+    /// let cfg = TestConfig::default().output(OutputDest::None);
+    ///
+    /// let results = ExactSuite::run(
+    ///     cfg.clone()
+    ///         .exact(format!("{}::small_cases", ExactSuite::suite_name())),
+    /// );
+    /// assert_eq!(results.len(), 1);
+    /// assert_eq!(results[0].test_result.cases.len(), 3);
+    ///
+    /// let results = ExactSuite::run(cfg.exact(format!("{}::small_cases#3", ExactSuite::suite_name())));
+    /// assert_eq!(results[0].test_result.cases.len(), 1);
+    /// assert_eq!(results[0].test_result.cases[0].name.as_deref(), Some("case 3"));
+    /// assert!(!results[0].test_result.is_ok());
+    /// ```
+    pub fn exact(mut self, selector: impl Into<String>) -> Self {
+        self.exact = Some(selector.into());
+        self
+    }
 
-    let fmt_output = match &result.test_result {
-        TestStatus::Single(status) => match status {
-            Ok(()) => format!(
-                "\tTest #{} ({}) ... {ok_color}ok{color_terminator}\n",
-                test_num, result.test_name
-            ),
-            Err(err_msg) => format!(
-                "\tTest #{} ({}) ... {fail_color}FAILED{color_terminator}\n\t  [x] {}\n",
-                test_num, result.test_name, err_msg
-            ),
-        },
-        TestStatus::Parameterized(statuses) => statuses
-            .iter()
-            .enumerate()
-            .map(|(idx, status)| match status {
-                Ok(()) => {
-                    format!(
-                        "\tTest #{}.{} ({}) ... {ok_color}ok{color_terminator}\n",
-                        test_num, idx, result.test_name
-                    )
-                }
-                Err(err_msg) => format!(
-                    "\tTest #{}.{} ({}) ... {fail_color}FAILED{color_terminator}\n\t  [x] {}\n",
-                    test_num,
-                    idx + 1,
-                    result.test_name,
-                    err_msg
-                ),
-            })
-            .collect::<String>(),
-    };
+    /// Only run tests tagged with at least one of `tags`. If no tags are given, every test is run
+    /// regardless of its tags.
+    pub fn tags<I, S>(mut self, tags: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.tags = tags.into_iter().map(Into::into).collect();
+        self
+    }
 
-    let mut writer: BufWriter<T> = BufWriter::new(stream);
-    writer
-        .write_all(fmt_output.as_bytes())
-        .expect("stream could not be written to");
-}
+    /// Change whether or not everything each test writes to stdout/stderr is captured and attached
+    /// to its [`TestResult`]. The default [`TextReporter`] prints captured output only for failing
+    /// tests. Capturing is disabled by default.
+    ///
+    /// > *This is only available with the `capture` feature enabled, on Unix platforms.*
+    #[cfg(all(feature = "capture", unix))]
+    pub fn capture(mut self, yes: bool) -> Self {
+        self.capture = yes;
+        self
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use Error as XE;
-    use TestStatus as TRT;
+    /// Change whether or not `log` records and `tracing` events emitted by each test are
+    /// [captured](logs) and attached to its [`TestResult`]. The default [`TextReporter`] prints
+    /// captured logs for failing tests, or for every test in [verbose](TestConfig::verbose) mode.
+    ///
+    /// > *This is only available with the `log` or `tracing` feature enabled.*
+    #[cfg(any(feature = "log", feature = "tracing"))]
+    pub fn capture_logs(mut self, yes: bool) -> Self {
+        self.capture_logs = yes;
+        self
+    }
 
-    #[test]
-    fn write_test_output_no_color() {
-        let ok_test = TestResult {
-            test_name: "this_test_passes",
-            test_result: TRT::Single(Ok(())),
-        };
+    /// Change whether or not a command to rerun each failing test is printed under the failure.
+    pub fn rerun_hint(mut self, yes: bool) -> Self {
+        self.rerun_hint = yes;
+        self
+    }
 
-        let fail_test = TestResult {
-            test_name: "this_test_fails",
-            test_result: TRT::Single(Err(XE::TestFailed(format!(
-                "test failed after {}",
-                ok_test.test_name
-            )))),
-        };
+    /// Run tests in a pseudo-random order. The seed is printed before the suite runs so that a
+    /// failing order can be reproduced by passing the same seed again; if no seed is given, a
+    /// random one is picked.
+    pub fn shuffle(mut self, seed: Option<u64>) -> Self {
+        self.order = TestOrder::Shuffled(seed.unwrap_or_else(order::random_seed));
+        self
+    }
 
-        let mut ok_result_buffer: Vec<u8> = Vec::new();
-        let mut fail_result_buffer: Vec<u8> = Vec::new();
+    /// Run tests sorted by name instead of in the order they were declared.
+    pub fn sort_by_name(mut self) -> Self {
+        self.order = TestOrder::Alphabetical;
+        self
+    }
+
+    /// Change the order tests are run in. The orders that prioritize tests based on previous runs,
+    /// such as [`TestOrder::FailedFirst`], need a [history file](TestConfig::history).
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::{TestConfig, TestOrder};
+    ///
+    /// let cfg = TestConfig::default()
+    ///     .history("target/extel-history")
+    ///     .order(TestOrder::FailedFirst);
+    /// assert_eq!(cfg.order, TestOrder::FailedFirst);
+    /// ```
+    pub fn order(mut self, order: TestOrder) -> Self {
+        self.order = order;
+        self
+    }
 
-        output_test_result(&mut ok_result_buffer, &ok_test, 1, false);
-        output_test_result(&mut fail_result_buffer, &fail_test, 2, false);
+    /// Change where the names of failing tests are persisted between runs, or stop persisting them
+    /// with `None`. By default, nothing is persisted, so that running a suite leaves nothing
+    /// behind in the working directory; [`state::DEFAULT_STATE_FILE`] is the conventional
+    /// location when it is needed.
+    pub fn state_file(mut self, path: Option<PathBuf>) -> Self {
+        self.state_file = path;
+        self
+    }
 
-        assert_eq!(
-            String::from_utf8_lossy(&ok_result_buffer),
-            "\tTest #1 (this_test_passes) ... ok\n"
-        );
+    /// Only run the tests that failed the last time they ran, as recorded in the
+    /// [state file](TestConfig::state_file), which must be set for the previous run too. If no
+    /// failures were recorded, or there is no state file, every test is run.
+    pub fn rerun_failed(mut self, yes: bool) -> Self {
+        self.rerun_failed = yes;
+        self
+    }
 
-        assert_eq!(
-            String::from_utf8_lossy(&fail_result_buffer),
-            "\tTest #2 (this_test_fails) ... FAILED\n\t  [x] test failed after this_test_passes\n"
-        );
+    /// Change the run-scoped directory that [artifacts](artifacts::artifact_path) are written
+    /// under. Each test gets its own `<suite>/<test>` subdirectory. Defaults to
+    /// [`artifacts::default_dir`].
+    pub fn artifacts_dir(mut self, path: impl Into<PathBuf>) -> Self {
+        self.artifacts_dir = Some(path.into());
+        self
     }
 
-    #[test]
-    fn write_test_output_with_color() {
-        let ok_test = TestResult {
-            test_name: "this_test_passes",
-            test_result: TRT::Single(Ok(())),
-        };
+    /// Attach a suite-level key/value pair, such as the version of the binary under test, that is
+    /// reported once at the start of the suite.
+    pub fn metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.metadata.insert(key.into(), value.into());
+        self
+    }
 
-        let fail_test = TestResult {
-            test_name: "this_test_fails",
-            test_result: TRT::Single(Err(XE::TestFailed(format!(
-                "test failed after {}",
-                ok_test.test_name
-            )))),
-        };
+    /// Change whether or not tests are told to be verbose through
+    /// [`TestContext::verbose`](context::TestContext::verbose).
+    pub fn verbose(mut self, yes: bool) -> Self {
+        self.verbose = yes;
+        self
+    }
 
-        let mut ok_result_buffer: Vec<u8> = Vec::new();
-        let mut fail_result_buffer: Vec<u8> = Vec::new();
+    /// Set a configuration value that tests can read through
+    /// [`TestContext::var`](context::TestContext::var).
+    pub fn var(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.vars.insert(key.into(), value.into());
+        self
+    }
 
-        output_test_result(&mut ok_result_buffer, &ok_test, 1, true);
-        output_test_result(&mut fail_result_buffer, &fail_test, 2, true);
+    /// Change whether or not a single, live progress line is shown instead of one line per test.
+    /// This only takes effect when writing to [`OutputDest::Stdout`] and stdout is a terminal;
+    /// otherwise the plain per-test output is used.
+    pub fn progress(mut self, yes: bool) -> Self {
+        self.progress = yes;
+        self
+    }
 
-        assert_eq!(
-            String::from_utf8_lossy(&ok_result_buffer),
-            "\tTest #1 (this_test_passes) ... \x1b[32mok\x1b[0m\n"
-        );
+    /// Change whether or not the selected tests are only listed, in the order they would run,
+    /// instead of being run. A listed suite returns no results.
+    pub fn list(mut self, yes: bool) -> Self {
+        self.list = yes;
+        self
+    }
 
-        assert_eq!(
-            String::from_utf8_lossy(&fail_result_buffer),
-            "\tTest #2 (this_test_fails) ... \x1b[31mFAILED\x1b[0m\n\t  [x] test failed after this_test_passes\n"
-        );
+    /// Change whether or not environment variables and the working directory are restored after
+    /// each test, so that tests calling [`std::env::set_var`] or [`std::env::set_current_dir`]
+    /// cannot affect the tests that run after them.
+    pub fn isolate_env(mut self, yes: bool) -> Self {
+        self.isolate_env = yes;
+        self
+    }
+
+    /// Change whether or not each test runs in its own process, started by re-running the
+    /// current executable with the same arguments. A test that crashes, aborts, or leaks state in
+    /// its process then cannot take down or affect the rest of the run.
+    ///
+    /// The executable must run the suite the same way in every process, so `main` should only
+    /// build its configuration from its arguments, environment, and [files](TestConfig::from_file).
+    pub fn isolate_process(mut self, yes: bool) -> Self {
+        self.isolate_process = yes;
+        self
+    }
+
+    /// Run every command built with [`cmd!`] during a test under `command_line`, such as
+    /// `valgrind --error-exitcode=99`. A wrapped command exiting with the wrapper's
+    /// `--error-exitcode` fails with an [`Error::WrapperFailed`] when run through
+    /// [`CommandExt`](command::CommandExt). See [`CommandWrapper`](command::CommandWrapper).
+    pub fn wrap_command(mut self, command_line: &str) -> Self {
+        self.wrapper = Some(command::CommandWrapper::parse(command_line));
+        self
+    }
+
+    /// Add `args` right after the program of every command built with [`cmd!`] during a test that
+    /// runs `program`, such as `--config test.toml`. The program is matched by its file name, and
+    /// `*` matches every program. To put a program such as `stdbuf -o0` in front of every
+    /// command instead, use [`wrap_command`](Self::wrap_command).
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::{prelude::*, OutputDest};
+    ///
+    /// fn uses_test_config() -> ExtelResult {
+    ///     let command = cmd!("target/debug/app migrate");
+    ///     let args: Vec<_> = command.get_args().collect();
+    ///     extel_assert_eq!(args, ["--config", "test.toml", "migrate"])
+    /// }
+    ///
+    /// init_test_suite!(AppSuite, uses_test_config);
+    /// let cfg = TestConfig::default()
+    ///     .output(OutputDest::None)
+    ///     .command_args("app", ["--config", "test.toml"]);
+    /// assert!(AppSuite::run(cfg)[0].test_result.is_ok());
+    /// ```
+    pub fn command_args<I, S>(mut self, program: impl Into<String>, args: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.command_args
+            .entry(program.into())
+            .or_default()
+            .extend(args.into_iter().map(Into::into));
+        self
+    }
+
+    /// Run every command built with [`cmd!`] during a test with `executor`, such as in a
+    /// [container](executor::DockerExec) or on a [remote host](executor), instead of on this
+    /// machine. A [wrapper](Self::wrap_command) runs under the executor too.
+    pub fn executor(mut self, executor: impl executor::CommandExecutor + 'static) -> Self {
+        self.executor = Some(Arc::new(executor));
+        self
+    }
+
+    /// Run every command built with [`cmd!`] during a test inside `profile`, and fail the test
+    /// with an [`Error::SandboxViolation`] when a checked run breaks out of it. See [`sandbox`].
+    ///
+    /// > *This is only available with the `sandbox` feature enabled, on x86_64 and aarch64
+    /// > Linux.*
+    #[cfg(all(
+        feature = "sandbox",
+        target_os = "linux",
+        any(target_arch = "x86_64", target_arch = "aarch64")
+    ))]
+    pub fn sandbox(mut self, profile: sandbox::SandboxProfile) -> Self {
+        self.sandbox = Some(profile);
+        self
+    }
+
+    /// Record the results of the commands tests run through [`CommandExt`](command::CommandExt)
+    /// into the cassette at `path`, or replay them from it instead of running the commands,
+    /// depending on `mode`. See [`cassette`].
+    pub fn cassette(mut self, path: impl Into<PathBuf>, mode: cassette::CassetteMode) -> Self {
+        self.cassette = Some(cassette::Cassette {
+            path: path.into(),
+            mode,
+        });
+        self
+    }
+
+    /// Change whether or not tests only print the commands they would run through
+    /// [`CommandExt`](command::CommandExt), with their working directory and environment, instead
+    /// of running them. Every command is treated as having succeeded with no output, and every
+    /// test is reported as skipped. See [`command_line`](command::command_line).
+    pub fn dry_run(mut self, yes: bool) -> Self {
+        self.dry_run = yes;
+        self
+    }
+
+    /// Change whether or not test names are prefixed with the path of the module the tests were
+    /// registered in by [`init_test_suite`], such as `my_crate::smoke::smoke_test` rather than
+    /// `smoke_test`, so that tests with the same name from different modules do not collide in
+    /// combined reports, history, or cassettes. Filters and reruns then match the qualified names.
+    /// Tests added to a [`SuiteBuilder`] keep their names. Qualified names are disabled by default.
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::{prelude::*, OutputDest};
+    ///
+    /// mod smoke {
+    ///     use extel::prelude::*;
+    ///
+    ///     pub fn smoke_test() -> ExtelResult {
+    ///         pass!()
+    ///     }
+    /// }
+    ///
+    /// init_test_suite!(SmokeSuite, smoke::smoke_test);
+    /// let results = SmokeSuite::run(
+    ///     TestConfig::default().output(OutputDest::None).qualified_names(true),
+    /// );
+    /// assert!(results[0].test_name.ends_with("::smoke::smoke_test"));
+    /// ```
+    pub fn qualified_names(mut self, yes: bool) -> Self {
+        self.qualified_names = yes;
+        self
+    }
+
+    /// Change whether or not the output of commands run by tests through
+    /// [`CommandExt`](command::CommandExt), [daemons](daemon::Daemon), and [sessions](session) is
+    /// streamed to the console line by line as it arrives, prefixed with the name of the test.
+    /// The output is still captured for assertions. Teeing is disabled by default.
+    pub fn tee_commands(mut self, yes: bool) -> Self {
+        self.tee_commands = yes;
+        self
+    }
+
+    /// Limit failure messages printed by the default reporter to `max_len` bytes, such as when a
+    /// failing command dumps megabytes of output. The full message of a longer failure is written
+    /// to a `failure.txt` [artifact](artifacts) of the test, which the truncated message points
+    /// to. Failure messages are not truncated by default.
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::{prelude::*, SharedBuffer, TextReporter};
+    ///
+    /// fn floods() -> ExtelResult {
+    ///     fail!("{}", "x".repeat(1 << 20))
+    /// }
+    ///
+    /// init_test_suite!(FloodSuite, floods);
+    /// let output = SharedBuffer::new();
+    /// let results = FloodSuite::run(
+    ///     TestConfig::default()
+    ///         .reporter(TextReporter::new(output.clone(), false).max_failure_len(Some(10)))
+    ///         .max_failure_len(Some(10)),
+    /// );
+    ///
+    /// let artifact = &results[0].artifacts[0];
+    /// assert_eq!(std::fs::read_to_string(artifact).unwrap().len(), 1 << 20);
+    /// assert!(output.to_string_lossy().contains(&format!(
+    ///     "[x] xxxxxxxxxx... [truncated, full output in {}]",
+    ///     artifact.display()
+    /// )));
+    /// ```
+    pub fn max_failure_len(mut self, max_len: Option<usize>) -> Self {
+        self.max_failure_len = max_len;
+        self
+    }
+
+    /// Change the words and colors used to show test statuses. See [`Style`].
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Change whether or not test names and statuses are lined up in columns, with long failure
+    /// messages wrapped. See [`TextReporter::align`].
+    pub fn align(mut self, yes: bool) -> Self {
+        self.align = yes;
+        self
+    }
+
+    /// Change whether or not the cases of a parameterized test that fail with the same message
+    /// pattern are shown as a single line. See [`TextReporter::cluster_failures`].
+    pub fn cluster_failures(mut self, yes: bool) -> Self {
+        self.cluster_failures = yes;
+        self
+    }
+
+    /// Run up to `jobs` cases of each parameterized test at the same time, each on its own
+    /// thread. Cases are still reported in the order they were declared. A value of `0` or `1`
+    /// runs cases one after another, which is the default. See [`cases::run`].
+    pub fn case_jobs(mut self, jobs: usize) -> Self {
+        self.case_jobs = jobs;
+        self
+    }
+
+    /// Keep only a summary of the cases of each parameterized test: the number of cases that
+    /// passed, failed, and were skipped, and the first `max_failures` failing cases, so that tests
+    /// with a huge number of cases do not hold every result in memory. The cases that were not
+    /// kept are counted in [`TestOutcome::omitted`], and every case is sent to
+    /// [`Reporter::on_case_finish`] as it finishes. Kept cases are named after their position
+    /// among all of the cases, such as `case 4711`, unless they already have a name.
+    ///
+    /// Only the cases run by `#[parameters(...)]` and [`SuiteBuilder::add_param_test`] are
+    /// summarized.
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::{prelude::*, OutputDest, SuiteBuilder};
+    ///
+    /// let results = SuiteBuilder::new("Fuzz")
+    ///     .add_param_test("even", 0..100_000u32, |n| extel_assert!(n % 2 == 0, "{} is odd", n))
+    ///     .run(TestConfig::default().output(OutputDest::None).summarize_cases(2));
+    ///
+    /// let outcome = &results[0].test_result;
+    /// assert_eq!((outcome.passed(), outcome.failed()), (50_000, 50_000));
+    /// assert_eq!(outcome.cases.len(), 2);
+    /// assert_eq!(outcome.cases[0].name.as_deref(), Some("case 2"));
+    /// ```
+    pub fn summarize_cases(mut self, max_failures: usize) -> Self {
+        self.summarize_cases = Some(max_failures);
+        self
+    }
+
+    /// Normalize the actual output compared by [`assert_matches_file`] and [`assert_cmd_matches`]
+    /// during a test with `normalizer`, such as to strip ANSI codes or mask timestamps, so the same
+    /// golden files work on every platform. See [`normalize`].
+    pub fn normalize(mut self, normalizer: normalize::Normalizer) -> Self {
+        self.normalizer = Some(normalizer);
+        self
+    }
+
+    /// Limit how long the whole suite may take to run. Once `timeout` runs out, the tests that
+    /// have not started yet are reported as failed with [`Error::NotRun`] instead of running. If
+    /// a test is still running at that point, it is reported as failed and the rest as not run,
+    /// the suite is finished as usual (summary, state file, history, manifest, Allure results,
+    /// and cassette), the tests involved are printed to stderr, and the process exits with status
+    /// 124, so that a hung test cannot use up a CI job's whole wall-clock limit without reporting
+    /// anything. Suites that would have run after it in the same process are not run.
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::{errors::Error, prelude::*, OutputDest};
+    /// use std::time::Duration;
+    ///
+    /// fn always_succeed() -> ExtelResult {
+    ///     pass!()
+    /// }
+    ///
+    /// init_test_suite!(BudgetSuite, always_succeed);
+    /// let results = BudgetSuite::run(
+    ///     TestConfig::default()
+    ///         .output(OutputDest::None)
+    ///         .suite_timeout(Duration::ZERO),
+    /// );
+    ///
+    /// assert!(matches!(
+    ///     results[0].test_result.cases[0].result,
+    ///     Err(Error::NotRun(_))
+    /// ));
+    /// ```
+    pub fn suite_timeout(mut self, timeout: Duration) -> Self {
+        self.suite_timeout = Some(timeout);
+        self
+    }
+
+    /// Run `hook` after every test that fails, such as to dump `docker ps`, server logs, or the
+    /// tail of `dmesg`. Its output is reported under `name` with the rest of the failure, and kept
+    /// on the [result](TestResult::failure_context). Hooks run in the order they were added, and
+    /// a panicking hook reports its panic instead.
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::{prelude::*, OutputDest};
+    ///
+    /// fn always_fail() -> ExtelResult {
+    ///     fail!("server did not respond")
+    /// }
+    ///
+    /// init_test_suite!(HookedSuite, always_fail);
+    /// let results = HookedSuite::run(
+    ///     TestConfig::default()
+    ///         .output(OutputDest::None)
+    ///         .on_failure("uptime", |_| {
+    ///             cmd!("echo up 3 days")
+    ///                 .output()
+    ///                 .map(|output| String::from_utf8_lossy(&output.stdout).into_owned())
+    ///                 .unwrap_or_else(|e| e.to_string())
+    ///         }),
+    /// );
+    ///
+    /// assert_eq!(
+    ///     results[0].failure_context,
+    ///     [(String::from("uptime"), String::from("up 3 days\n"))]
+    /// );
+    /// ```
+    pub fn on_failure(
+        mut self,
+        name: impl Into<String>,
+        hook: impl Fn(&TestResult) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.failure_hooks.push((name.into(), Arc::new(hook)));
+        self
+    }
+
+    /// Append a [run manifest](manifest) of every test and command that ran to `path`, for
+    /// external tooling to consume. The file is truncated the first time it is written to by the
+    /// current process.
+    pub fn manifest(mut self, path: impl Into<PathBuf>) -> Self {
+        self.manifest = Some(path.into());
+        self
+    }
+
+    /// Change whether or not the [run ID, start time, host, and operating system](run_info) of
+    /// the run are shown at the top of the console output, along with the environment variables
+    /// selected with [`report_env`](TestConfig::report_env). They are always written to the
+    /// [manifest](TestConfig::manifest) and the [Allure results](TestConfig::allure_dir). Run
+    /// information is hidden by default.
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::{prelude::*, OutputDest, SharedBuffer};
+    ///
+    /// fn always_succeed() -> ExtelResult {
+    ///     pass!()
+    /// }
+    ///
+    /// let output = SharedBuffer::new();
+    /// init_test_suite!(RunInfoSuite as "run info", always_succeed);
+    /// RunInfoSuite::run(
+    ///     TestConfig::default()
+    ///         .output(OutputDest::Buffer(output.clone()))
+    ///         .run_info(true)
+    ///         .report_env(["PATH"]),
+    /// );
+    ///
+    /// let output = output.to_string_lossy();
+    /// assert!(output.starts_with(&format!("[run info]\n\t[run {} on ", extel::run_info::run_id())));
+    /// assert!(output.contains("\t[env PATH="));
+    /// ```
+    pub fn run_info(mut self, yes: bool) -> Self {
+        self.run_info = yes;
+        self
+    }
+
+    /// Record the values of the environment variables named in `names` with the
+    /// [run information](run_info), such as the commit or the CI job being tested. Variables that
+    /// are not set are left out.
+    pub fn report_env<I, S>(mut self, names: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.report_env.extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    /// Run only the tests belonging to the [shard](shard::Shard) at the 0-based `index` out of
+    /// `total`, so that a suite can be split between `total` CI jobs that each pass a different
+    /// `index`.
+    ///
+    /// # Panics
+    /// Panics unless `index < total`.
+    pub fn shard(mut self, index: usize, total: usize) -> Self {
+        self.shard = Some(shard::Shard::new(index, total).expect("invalid shard"));
+        self
+    }
+
+    /// Compare every run against the previous one recorded in the [history file](history) at
+    /// `path`, reporting tests that are newly failing, newly passing, or newly slow, and then
+    /// record the run for the next comparison.
+    pub fn history(mut self, path: impl Into<PathBuf>) -> Self {
+        self.history = Some(path.into());
+        self
+    }
+
+    /// Flag a test as newly slow when it takes more than `ratio` times as long as it did in the
+    /// previous run recorded in the [history file](TestConfig::history). Defaults to
+    /// [`history::DEFAULT_SLOW_THRESHOLD`].
+    pub fn slow_threshold(mut self, ratio: f64) -> Self {
+        self.slow_threshold = ratio;
+        self
+    }
+
+    /// Write [Allure](allure) result files for every test that ran into the directory at `path`,
+    /// so that runs can be shown in an Allure dashboard.
+    pub fn allure_dir(mut self, path: impl Into<PathBuf>) -> Self {
+        self.allure_dir = Some(path.into());
+        self
+    }
+
+    /// Take the reporter that test events should be sent to, if any: the one attached with
+    /// [`reporter`](TestConfig::reporter), or else the default reporter writing to the
+    /// [output destination](TestConfig::output). This leaves the output destination as
+    /// [`OutputDest::None`], so taking the reporter again returns `None` unless one was attached.
+    pub fn take_reporter(&mut self) -> Option<Arc<Mutex<dyn Reporter + Send>>> {
+        if let Some(reporter) = self.reporter.take() {
+            return Some(reporter);
+        }
+
+        let is_terminal =
+            matches!(self.output, OutputDest::Stdout) && std::io::stdout().is_terminal();
+        let colored = self.colored.unwrap_or_else(|| {
+            color_enabled(
+                std::env::var_os("NO_COLOR"),
+                std::env::var_os("CLICOLOR_FORCE"),
+                is_terminal,
+            )
+        });
+        let rerun_hint = self.rerun_hint;
+        let style = self.style.clone();
+        let align = self.align;
+        let cluster_failures = self.cluster_failures;
+        let verbose = self.verbose;
+        let max_failure_len = self.max_failure_len;
+        let text_reporter = |writer: Box<dyn Write + Send>| -> Arc<Mutex<dyn Reporter + Send>> {
+            Arc::new(Mutex::new(
+                TextReporter::new(writer, colored)
+                    .rerun_hint(rerun_hint)
+                    .style(style.clone())
+                    .align(align)
+                    .cluster_failures(cluster_failures)
+                    .max_failure_len(max_failure_len)
+                    .verbose(verbose),
+            ))
+        };
+
+        match std::mem::replace(&mut self.output, OutputDest::None) {
+            OutputDest::Stdout if self.progress && is_terminal => Some(Arc::new(Mutex::new(
+                ProgressReporter::new(std::io::stdout(), colored)
+                    .rerun_hint(rerun_hint)
+                    .style(style.clone()),
+            ))),
+            OutputDest::Stdout => Some(text_reporter(Box::new(std::io::stdout()))),
+            OutputDest::Json => Some(Arc::new(Mutex::new(JsonReporter::new(std::io::stdout())))),
+            OutputDest::File(file_name) => match open_output_file(&file_name) {
+                Ok(file_handle) => Some(text_reporter(Box::new(file_handle))),
+                Err(err) => {
+                    eprintln!(
+                        "warning: could not open output file {}, results are not reported: {}",
+                        file_name.display(),
+                        err
+                    );
+                    None
+                }
+            },
+            OutputDest::Buffer(buffer) => Some(text_reporter(Box::new(buffer))),
+            OutputDest::None => None,
+        }
+    }
+}
+
+/// Open the output file `file_name`, truncating it the first time this process opens it and
+/// appending to it afterwards.
+fn open_output_file(file_name: &std::path::Path) -> std::io::Result<std::fs::File> {
+    static OPENED: Mutex<Vec<PathBuf>> = Mutex::new(Vec::new());
+    let mut opened = OPENED
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let first_open = !opened.iter().any(|opened| opened == file_name);
+    if first_open {
+        opened.push(file_name.to_path_buf());
+    }
+
+    std::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(!first_open)
+        .truncate(first_open)
+        .open(file_name)
+}
+
+impl Default for TestConfig {
+    fn default() -> Self {
+        Self {
+            output: OutputDest::Stdout,
+            colored: None,
+            reporter: None,
+            filter: std::env::var(FILTER_ENV).ok(),
+            exact: std::env::var(EXACT_ENV).ok(),
+            tags: Vec::new(),
+            rerun_hint: true,
+            progress: false,
+            order: TestOrder::Declared,
+            state_file: None,
+            rerun_failed: false,
+            artifacts_dir: None,
+            metadata: HashMap::new(),
+            verbose: false,
+            vars: HashMap::new(),
+            list: false,
+            isolate_env: false,
+            isolate_process: false,
+            wrapper: None,
+            executor: None,
+            cassette: None,
+            dry_run: false,
+            qualified_names: false,
+            command_args: HashMap::new(),
+            max_failure_len: None,
+            tee_commands: false,
+            manifest: None,
+            run_info: false,
+            report_env: Vec::new(),
+            allure_dir: None,
+            history: None,
+            slow_threshold: history::DEFAULT_SLOW_THRESHOLD,
+            shard: None,
+            style: Style::default(),
+            align: false,
+            cluster_failures: false,
+            case_jobs: 1,
+            summarize_cases: None,
+            normalizer: None,
+            suite_timeout: None,
+            failure_hooks: Vec::new(),
+            #[cfg(all(feature = "capture", unix))]
+            capture: false,
+            #[cfg(any(feature = "log", feature = "tracing"))]
+            capture_logs: false,
+            #[cfg(all(
+                feature = "sandbox",
+                target_os = "linux",
+                any(target_arch = "x86_64", target_arch = "aarch64")
+            ))]
+            sandbox: None,
+        }
+    }
+}
+
+impl fmt::Debug for TestConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut f = f.debug_struct("TestConfig");
+        f.field("output", &self.output)
+            .field("colored", &self.colored)
+            .field("reporter", &self.reporter.as_ref().map(|_| "dyn Reporter"))
+            .field("filter", &self.filter)
+            .field("exact", &self.exact)
+            .field("tags", &self.tags)
+            .field("rerun_hint", &self.rerun_hint)
+            .field("progress", &self.progress)
+            .field("order", &self.order)
+            .field("state_file", &self.state_file)
+            .field("rerun_failed", &self.rerun_failed)
+            .field("artifacts_dir", &self.artifacts_dir)
+            .field("metadata", &self.metadata)
+            .field("verbose", &self.verbose)
+            .field("vars", &self.vars)
+            .field("list", &self.list)
+            .field("isolate_env", &self.isolate_env)
+            .field("isolate_process", &self.isolate_process)
+            .field("wrapper", &self.wrapper)
+            .field("executor", &self.executor)
+            .field("cassette", &self.cassette)
+            .field("dry_run", &self.dry_run)
+            .field("qualified_names", &self.qualified_names)
+            .field("command_args", &self.command_args)
+            .field("max_failure_len", &self.max_failure_len)
+            .field("tee_commands", &self.tee_commands)
+            .field("manifest", &self.manifest)
+            .field("run_info", &self.run_info)
+            .field("report_env", &self.report_env)
+            .field("allure_dir", &self.allure_dir)
+            .field("history", &self.history)
+            .field("slow_threshold", &self.slow_threshold)
+            .field("shard", &self.shard)
+            .field("style", &self.style)
+            .field("align", &self.align)
+            .field("cluster_failures", &self.cluster_failures)
+            .field("case_jobs", &self.case_jobs)
+            .field("summarize_cases", &self.summarize_cases)
+            .field("normalizer", &self.normalizer)
+            .field("suite_timeout", &self.suite_timeout)
+            .field(
+                "failure_hooks",
+                &self
+                    .failure_hooks
+                    .iter()
+                    .map(|(name, _)| name)
+                    .collect::<Vec<_>>(),
+            );
+        #[cfg(all(feature = "capture", unix))]
+        f.field("capture", &self.capture);
+        #[cfg(any(feature = "log", feature = "tracing"))]
+        f.field("capture_logs", &self.capture_logs);
+        #[cfg(all(
+            feature = "sandbox",
+            target_os = "linux",
+            any(target_arch = "x86_64", target_arch = "aarch64")
+        ))]
+        f.field("sandbox", &self.sandbox);
+        f.finish()
+    }
+}
+
+/// Decide whether output should be colored when no explicit choice was made, following the
+/// [`NO_COLOR`](https://no-color.org) and
+/// [`CLICOLOR_FORCE`](https://bixense.com/clicolors) conventions. A non-empty `NO_COLOR` always
+/// disables colors, a `CLICOLOR_FORCE` other than `0` enables them even when not writing to a
+/// terminal, and otherwise colors are used only when writing to a terminal.
+///
+/// # Example
+/// ```rust
+/// use extel::color_enabled;
+///
+/// assert!(color_enabled(None, None, true));
+/// assert!(!color_enabled(Some("1".into()), None, true));
+/// assert!(color_enabled(None, Some("1".into()), false));
+/// ```
+pub fn color_enabled(
+    no_color: Option<std::ffi::OsString>,
+    clicolor_force: Option<std::ffi::OsString>,
+    is_terminal: bool,
+) -> bool {
+    if no_color.is_some_and(|value| !value.is_empty()) {
+        return false;
+    }
+
+    match clicolor_force {
+        Some(value) if !value.is_empty() && value != "0" => true,
+        _ => is_terminal,
+    }
+}
+
+/// A test set that produces a list of test results.
+pub trait RunnableTestSet {
+    /// Run a test set with the provided configuration to create a list of test results. The test
+    /// suite can contain both single, or standard, tests and parameterized tests. The results of
+    /// the parameterized tests will be flattened into the resulting vec.
+    fn run(cfg: TestConfig) -> Vec<TestResult>;
+
+    /// The name the suite reports its results under. Defaults to the full path of the
+    /// implementing type, like the suites of [`init_test_suite`].
+    fn suite_name() -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Run a test set like [`run`](RunnableTestSet::run), collecting the results into a
+    /// [`SuiteReport`] with the name, duration, and totals of the run.
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::{prelude::*, OutputDest};
+    ///
+    /// fn always_succeed() -> ExtelResult {
+    ///     pass!()
+    /// }
+    ///
+    /// fn always_fail() -> ExtelResult {
+    ///     fail!("no luck")
+    /// }
+    ///
+    /// init_test_suite!(ReportedSuite as "Reported", always_succeed, always_fail);
+    /// let report = ReportedSuite::run_report(TestConfig::default().output(OutputDest::None));
+    ///
+    /// assert_eq!(report.name, "Reported");
+    /// assert_eq!((report.counts.passed, report.counts.failed), (1, 1));
+    /// assert_eq!(report.results.failures().next().unwrap().test_name, "always_fail");
+    /// ```
+    fn run_report(cfg: TestConfig) -> SuiteReport {
+        let start = Instant::now();
+        let results = Self::run(cfg);
+        SuiteReport::new(Self::suite_name(), results, start.elapsed())
+    }
+}
+
+/// Run a list of tests, sending each result to the reporter described by `cfg`. This is the loop
+/// behind every suite created with the [test initializer](crate::init_test_suite), and the one a
+/// hand-written [`RunnableTestSet`] should call; see [`runner`]. If you wish to run tests,
+/// consider [`RunnableTestSet::run`].
+pub fn run_tests(suite_name: &str, mut tests: Vec<Test>, mut cfg: TestConfig) -> Vec<TestResult> {
+    if cfg.qualified_names {
+        tests.iter_mut().for_each(Test::qualify);
+    }
+
+    let artifacts_base = cfg
+        .artifacts_dir
+        .clone()
+        .unwrap_or_else(artifacts::default_dir);
+
+    #[cfg(any(feature = "log", feature = "tracing"))]
+    if cfg.capture_logs {
+        logs::install();
+    }
+
+    let tape = cfg
+        .cassette
+        .as_ref()
+        .map(|cassette| Arc::new(cassette::Tape::open(cassette, suite_name)));
+
+    if let Some(request) = subprocess::ChildRequest::from_env() {
+        if request.suite_name != suite_name {
+            return Vec::new();
+        }
+
+        cfg.isolate_process = false;
+        return match tests.into_iter().find(|t| t.test_name == request.test_name) {
+            Some(test) => {
+                let result =
+                    run_scoped(suite_name, test, &cfg, &artifacts_base, tape.as_ref(), None);
+                if let (Some(tape), false) = (&tape, cfg.dry_run) {
+                    let _ = tape.save(std::slice::from_ref(&result));
+                }
+                request.finish(&result)
+            }
+            None => Vec::new(),
+        };
+    }
+
+    let mut reporter = cfg.take_reporter();
+    let mut run_info = run_info::RunInfo::collect(&cfg.report_env);
+
+    if let Some(r) = reporter.as_mut() {
+        r.on_suite_start(suite_name);
+        if cfg.run_info {
+            r.on_run_info(&run_info);
+        }
+        if !cfg.metadata.is_empty() {
+            r.on_metadata(&cfg.metadata);
+        }
+    }
+
+    let last_failures = match (&cfg.state_file, cfg.rerun_failed) {
+        (Some(path), true) => state::last_failures(path, suite_name).unwrap_or_default(),
+        _ => Default::default(),
+    };
+
+    let mut selected: Vec<(usize, Test)> = tests
+        .into_iter()
+        .enumerate()
+        .filter(|(_, test)| match &cfg.filter {
+            Some(pattern) => test.test_name.contains(pattern.as_str()),
+            None => true,
+        })
+        .filter(|(_, test)| match &cfg.exact {
+            Some(selector) => report::exact_match(selector, suite_name, &test.test_name).is_some(),
+            None => true,
+        })
+        .filter(|(_, test)| {
+            cfg.tags.is_empty() || cfg.tags.iter().any(|tag| test.tags.contains(&tag.as_str()))
+        })
+        .filter(|(_, test)| {
+            last_failures.is_empty() || last_failures.contains(test.test_name.as_ref())
+        })
+        .filter(|(_, test)| match &cfg.shard {
+            Some(shard) => shard.contains(suite_name, &test.test_name),
+            None => true,
+        })
+        .collect();
+
+    let previous = match (&cfg.history, cfg.order) {
+        (Some(path), TestOrder::FailedFirst | TestOrder::SlowestFirst) => {
+            history::previous_runs(path, suite_name).unwrap_or_default()
+        }
+        _ => Default::default(),
+    };
+    cfg.order
+        .apply_with_history(&mut selected, |(_, test)| &test.test_name, &previous);
+
+    if cfg.list {
+        if let Some(r) = reporter.as_mut() {
+            for (_, test) in &selected {
+                r.on_list(&test.test_name);
+            }
+        }
+        return Vec::new();
+    }
+
+    if let Some(r) = reporter.as_mut() {
+        if let TestOrder::Shuffled(seed) = cfg.order {
+            r.on_shuffle(seed);
+        }
+        r.on_plan(selected.len());
+
+        let test_names: Vec<&str> = selected
+            .iter()
+            .map(|(_, test)| test.test_name.as_ref())
+            .collect();
+        r.on_selected(&test_names);
+    }
+
+    // Finished results are shared with the watchdog, so that it can still report them if a test
+    // hangs past the suite timeout.
+    let finished: Arc<Mutex<Vec<TestResult>>> = Default::default();
+    let watchdog = cfg.suite_timeout.map(|timeout| {
+        let pending = selected
+            .iter()
+            .map(|(_, test)| test.test_name.to_string())
+            .collect();
+        let planned: Vec<(usize, String, &'static [&'static str])> = selected
+            .iter()
+            .map(|(test_id, test)| (test_id + 1, test.test_name.to_string(), test.tags))
+            .collect();
+        let (name, cfg, reporter, finished, tape, mut run_info) = (
+            suite_name.to_string(),
+            cfg.clone(),
+            reporter.clone(),
+            Arc::clone(&finished),
+            tape.clone(),
+            run_info.clone(),
+        );
+
+        // Tests run in the order they were planned, so the first test without a result is the one
+        // still running.
+        deadline::Watchdog::start(suite_name, timeout, pending, move |reason| {
+            let mut results = std::mem::take(
+                &mut *finished
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner()),
+            );
+            let running = results.len();
+            for (idx, (test_num, test_name, tags)) in planned.into_iter().enumerate().skip(running)
+            {
+                let error = match idx == running {
+                    true => err!("{} while the test was running", reason),
+                    false => Error::NotRun(reason.to_string()),
+                };
+                let mut test_result = subprocess::blank_result(Test {
+                    test_name: test_name.into(),
+                    test_fn: Box::new(|| TestOutcome::single(Ok(()))),
+                    tags,
+                    module_path: "",
+                });
+                test_result.test_result = TestOutcome::single(Err(error));
+                if let Some(r) = reporter.as_ref() {
+                    report::lock(r).on_test_finish(&test_result, test_num);
+                }
+                results.push(test_result);
+            }
+            finish_suite(
+                &name,
+                &cfg,
+                reporter.as_ref(),
+                &results,
+                &mut run_info,
+                tape.as_ref(),
+            );
+        })
+    });
+
+    for (test_id, test) in selected {
+        if let Some(watchdog) = watchdog.as_ref().filter(|w| w.expired()) {
+            let mut test_result = subprocess::blank_result(test);
+            test_result.test_result =
+                TestOutcome::single(Err(Error::NotRun(watchdog.not_run_reason())));
+            if let Some(r) = reporter.as_mut() {
+                r.on_test_finish(&test_result, test_id + 1);
+            }
+            finished
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .push(test_result);
+            continue;
+        }
+
+        if let Some(watchdog) = &watchdog {
+            watchdog.enter(&test.test_name);
+        }
+
+        let started = Instant::now();
+        let mut test_result = match cfg.isolate_process {
+            true => subprocess::run_in_child(suite_name, test),
+            false => run_scoped(
+                suite_name,
+                test,
+                &cfg,
+                &artifacts_base,
+                tape.as_ref(),
+                reporter.as_ref(),
+            ),
+        };
+        test_result.duration = started.elapsed();
+
+        if let Some(watchdog) = &watchdog {
+            watchdog.exit();
+        }
+
+        if let Some(r) = reporter.as_mut() {
+            r.on_test_finish(&test_result, test_id + 1);
+        }
+
+        finished
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(test_result);
+    }
+
+    drop(watchdog);
+
+    let results = std::mem::take(
+        &mut *finished
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner()),
+    );
+    finish_suite(
+        suite_name,
+        &cfg,
+        reporter.as_ref(),
+        &results,
+        &mut run_info,
+        tape.as_ref(),
+    );
+    results
+}
+
+/// Report the end of a run of `suite_name` that produced `results`, and record them wherever
+/// `cfg` asks for.
+fn finish_suite(
+    suite_name: &str,
+    cfg: &TestConfig,
+    reporter: Option<&Arc<Mutex<dyn Reporter + Send>>>,
+    results: &[TestResult],
+    run_info: &mut run_info::RunInfo,
+    tape: Option<&Arc<cassette::Tape>>,
+) {
+    if let Some(path) = &cfg.history {
+        let comparison = history::compare(path, suite_name, results, cfg.slow_threshold);
+        if let (Some(r), Ok(comparison)) = (reporter, comparison) {
+            report::lock(r).on_comparison(&comparison);
+        }
+        let _ = history::record_results(path, suite_name, results);
+    }
+
+    run_info.finished = Some(std::time::SystemTime::now());
+    if let Some(r) = reporter {
+        report::lock(r).on_suite_end(results);
+    }
+
+    // Failing to persist state should never fail the run itself.
+    if let Some(path) = &cfg.state_file {
+        let _ = state::record_results(path, suite_name, results);
+    }
+
+    if let Some(path) = &cfg.manifest {
+        let _ = manifest::record_suite(path, suite_name, results, run_info);
+    }
+
+    // Isolated tests save their own recordings from their processes, and dry runs record nothing.
+    if let (Some(tape), false) = (tape, cfg.isolate_process || cfg.dry_run) {
+        let _ = tape.save(results);
+    }
+
+    if let Some(dir) = &cfg.allure_dir {
+        let _ = allure::write_results(dir, suite_name, results, std::time::SystemTime::now());
+        let _ = allure::write_environment(dir, run_info);
+    }
+}
+
+/// Run a single test with a [`TestContext`](context::TestContext) scoped to it, collecting what
+/// it recorded through the context onto its result.
+fn run_scoped(
+    suite_name: &str,
+    test: Test,
+    cfg: &TestConfig,
+    artifacts_base: &std::path::Path,
+    tape: Option<&Arc<cassette::Tape>>,
+    reporter: Option<&Arc<Mutex<dyn Reporter + Send>>>,
+) -> TestResult {
+    let artifact_dir = artifacts::test_dir(artifacts_base, suite_name, &test.test_name);
+    let only_case = cfg
+        .exact
+        .as_deref()
+        .and_then(|selector| report::exact_match(selector, suite_name, &test.test_name))
+        .flatten();
+    let case_stream = cfg.summarize_cases.map(|max_failures| {
+        Arc::new(cases::CaseStream::new(
+            test.test_name.to_string(),
+            reporter.cloned(),
+            max_failures,
+        ))
+    });
+    let scope = context::Scope {
+        suite_name: suite_name.to_string(),
+        test_name: test.test_name.to_string(),
+        artifact_dir: artifact_dir.clone(),
+        verbose: cfg.verbose,
+        vars: cfg.vars.clone(),
+        wrapper: cfg.wrapper.clone(),
+        executor: cfg.executor.clone(),
+        tape: tape.cloned(),
+        dry_run: cfg.dry_run,
+        command_args: cfg.command_args.clone(),
+        tee_commands: cfg.tee_commands,
+        case_jobs: cfg.case_jobs,
+        case_stream: case_stream.clone(),
+        only_case,
+        normalizer: cfg.normalizer.clone(),
+        #[cfg(all(
+            feature = "sandbox",
+            target_os = "linux",
+            any(target_arch = "x86_64", target_arch = "aarch64")
+        ))]
+        sandbox: cfg.sandbox.clone(),
+        recorded: Default::default(),
+    };
+
+    let (mut test_result, recorded) = context::scoped(scope, || execute_test(test, cfg));
+    if let Some(stream) = case_stream {
+        stream.finish(&mut test_result.test_result);
+    }
+    if let (Some(case_num), [case]) = (only_case, test_result.test_result.cases.as_mut_slice()) {
+        if test_result.test_result.parameterized {
+            case.name
+                .get_or_insert_with(|| format!("case {}", case_num));
+        }
+    }
+    if cfg.dry_run {
+        for case in &mut test_result.test_result.cases {
+            case.result = Err(Error::Skipped(command::DRY_RUN_REASON.to_string()));
+        }
+    }
+    test_result.artifacts = recorded.artifacts;
+    test_result.resource_usage = recorded.resource_usage;
+    test_result.metadata = recorded.metadata;
+    test_result.commands = recorded.commands;
+    test_result.logs = recorded.logs;
+    test_result.steps = recorded.steps;
+    if let Some(max_len) = cfg.max_failure_len {
+        artifacts::write_long_failures(&mut test_result, &artifact_dir, max_len);
+    }
+    if !test_result.test_result.is_ok() {
+        for (name, hook) in &cfg.failure_hooks {
+            let context = match catch_panic(|| hook(&test_result)) {
+                Ok(context) => context,
+                Err(Error::Panicked(msg)) => format!("failure hook panicked: {}", msg),
+                Err(e) => e.to_string(),
+            };
+            test_result.failure_context.push((name.clone(), context));
+        }
+    }
+    test_result
+}
+
+/// Run a single test, restoring the environment afterwards if enabled in `cfg`.
+fn execute_test(test: Test, cfg: &TestConfig) -> TestResult {
+    let snapshot = match cfg.isolate_env {
+        true => Some(isolation::EnvSnapshot::take()),
+        false => None,
+    };
+
+    let test_result = capture_test(test, cfg);
+    if let Some(snapshot) = snapshot {
+        snapshot.restore();
+    }
+
+    test_result
+}
+
+/// Run a single test, capturing its output if enabled in `cfg`.
+#[cfg_attr(not(all(feature = "capture", unix)), allow(unused_variables))]
+fn capture_test(test: Test, cfg: &TestConfig) -> TestResult {
+    #[cfg(all(feature = "capture", unix))]
+    if cfg.capture {
+        let (test_name, tags) = (test.test_name.clone(), test.tags);
+        return match capture::capture_output(|| test.run_test()) {
+            Ok((mut test_result, output)) => {
+                if let [case] = &mut test_result.test_result.cases[..] {
+                    case.captured = Some(output.clone());
+                }
+                test_result.captured = Some(output);
+                test_result
+            }
+            // Fail only this test, so that running out of file descriptors does not stop the run.
+            Err(e) => {
+                eprintln!(
+                    "warning: could not capture the output of {}: {}",
+                    test_name, e
+                );
+                Test {
+                    test_name,
+                    test_fn: Box::new(move || TestOutcome::single(Err(Error::Io(e)))),
+                    tags,
+                    module_path: "",
+                }
+                .run_test()
+            }
+        };
+    }
+
+    test.run_test()
+}
+
+/// Output the test results to the desired stream. This function is used by the default
+/// [`TextReporter`]. If you wish to generate test output, consider [`RunnableTestSet::run`].
+///
+/// # Errors
+/// Returns any error from writing to `stream`, such as on a full disk or a closed pipe.
+pub fn output_test_result<T: Write>(
+    stream: T,
+    result: &TestResult,
+    test_num: usize,
+    colored: bool,
+) -> std::io::Result<()> {
+    output_styled_test_result(stream, result, test_num, colored, &Style::default())
+}
+
+/// Output the test results to the desired stream like [`output_test_result`], showing statuses
+/// in the given [`Style`].
+pub fn output_styled_test_result<T: Write>(
+    stream: T,
+    result: &TestResult,
+    test_num: usize,
+    colored: bool,
+    style: &Style,
+) -> std::io::Result<()> {
+    let fmt_output =
+        report::format_test_result(result, test_num, colored, style, &report::Layout::default());
+
+    let mut writer: BufWriter<T> = BufWriter::new(stream);
+    writer.write_all(fmt_output.as_bytes())?;
+    writer.flush()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use Error as XE;
+
+    #[test]
+    fn write_test_output_no_color() {
+        let ok_test = TestResult {
+            test_name: "this_test_passes".into(),
+            test_result: TestOutcome::single(Ok(())),
+            tags: &[],
+            artifacts: Vec::new(),
+            resource_usage: Vec::new(),
+            metadata: HashMap::new(),
+            duration: Default::default(),
+            commands: Vec::new(),
+            logs: Vec::new(),
+            steps: Vec::new(),
+            failure_context: Vec::new(),
+            #[cfg(all(feature = "capture", unix))]
+            captured: None,
+        };
+
+        let fail_test = TestResult {
+            test_name: "this_test_fails".into(),
+            test_result: TestOutcome::single(Err(XE::TestFailed(format!(
+                "test failed after {}",
+                ok_test.test_name
+            )))),
+            tags: &[],
+            artifacts: Vec::new(),
+            resource_usage: Vec::new(),
+            metadata: HashMap::new(),
+            duration: Default::default(),
+            commands: Vec::new(),
+            logs: Vec::new(),
+            steps: Vec::new(),
+            failure_context: Vec::new(),
+            #[cfg(all(feature = "capture", unix))]
+            captured: None,
+        };
+
+        let mut ok_result_buffer: Vec<u8> = Vec::new();
+        let mut fail_result_buffer: Vec<u8> = Vec::new();
+
+        output_test_result(&mut ok_result_buffer, &ok_test, 1, false).unwrap();
+        output_test_result(&mut fail_result_buffer, &fail_test, 2, false).unwrap();
+
+        assert_eq!(
+            String::from_utf8_lossy(&ok_result_buffer),
+            "\tTest #1 (this_test_passes) ... ok\n"
+        );
+
+        assert_eq!(
+            String::from_utf8_lossy(&fail_result_buffer),
+            "\tTest #2 (this_test_fails) ... FAILED\n\t  [x] test failed after this_test_passes\n"
+        );
+    }
+
+    #[test]
+    fn write_test_output_returns_write_errors() {
+        let result = TestResult {
+            test_name: "this_test_passes".into(),
+            test_result: TestOutcome::single(Ok(())),
+            tags: &[],
+            artifacts: Vec::new(),
+            resource_usage: Vec::new(),
+            metadata: HashMap::new(),
+            duration: Default::default(),
+            commands: Vec::new(),
+            logs: Vec::new(),
+            steps: Vec::new(),
+            failure_context: Vec::new(),
+            #[cfg(all(feature = "capture", unix))]
+            captured: None,
+        };
+
+        let mut full: &mut [u8] = &mut [0; 4];
+        let err = output_test_result(&mut full, &result, 1, false).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::WriteZero);
+    }
+
+    #[test]
+    fn write_test_output_with_color() {
+        let ok_test = TestResult {
+            test_name: "this_test_passes".into(),
+            test_result: TestOutcome::single(Ok(())),
+            tags: &[],
+            artifacts: Vec::new(),
+            resource_usage: Vec::new(),
+            metadata: HashMap::new(),
+            duration: Default::default(),
+            commands: Vec::new(),
+            logs: Vec::new(),
+            steps: Vec::new(),
+            failure_context: Vec::new(),
+            #[cfg(all(feature = "capture", unix))]
+            captured: None,
+        };
+
+        let fail_test = TestResult {
+            test_name: "this_test_fails".into(),
+            test_result: TestOutcome::single(Err(XE::TestFailed(format!(
+                "test failed after {}",
+                ok_test.test_name
+            )))),
+            tags: &[],
+            artifacts: Vec::new(),
+            resource_usage: Vec::new(),
+            metadata: HashMap::new(),
+            duration: Default::default(),
+            commands: Vec::new(),
+            logs: Vec::new(),
+            steps: Vec::new(),
+            failure_context: Vec::new(),
+            #[cfg(all(feature = "capture", unix))]
+            captured: None,
+        };
+
+        let mut ok_result_buffer: Vec<u8> = Vec::new();
+        let mut fail_result_buffer: Vec<u8> = Vec::new();
+
+        output_test_result(&mut ok_result_buffer, &ok_test, 1, true).unwrap();
+        output_test_result(&mut fail_result_buffer, &fail_test, 2, true).unwrap();
+
+        assert_eq!(
+            String::from_utf8_lossy(&ok_result_buffer),
+            "\tTest #1 (this_test_passes) ... \x1b[32mok\x1b[0m\n"
+        );
+
+        assert_eq!(
+            String::from_utf8_lossy(&fail_result_buffer),
+            "\tTest #2 (this_test_fails) ... \x1b[31mFAILED\x1b[0m\n\t  [x] test failed after this_test_passes\n"
+        );
+    }
+
+    #[test]
+    fn run_tests_accepts_computed_names() {
+        fn always_succeed() -> ExtelResult {
+            Ok(())
+        }
+
+        let tests = (1..=2)
+            .map(|n| Test {
+                test_name: format!("generated_{}", n).into(),
+                test_fn: Box::new(|| always_succeed().into()),
+                tags: &[],
+                module_path: "",
+            })
+            .collect();
+
+        let results = run_tests(
+            "generated",
+            tests,
+            TestConfig::default()
+                .output(OutputDest::None)
+                .filter("generated_2"),
+        );
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].test_name, "generated_2");
+    }
+
+    #[test]
+    fn hand_written_suites_are_named_like_generated_ones() {
+        struct HandWritten;
+
+        impl RunnableTestSet for HandWritten {
+            fn run(cfg: TestConfig) -> Vec<TestResult> {
+                run_tests(Self::suite_name(), Vec::new(), cfg)
+            }
+        }
+
+        fn always_succeed() -> ExtelResult {
+            Ok(())
+        }
+
+        init_test_suite!(Generated, always_succeed);
+        assert_eq!(
+            HandWritten::suite_name(),
+            "extel::tests::hand_written_suites_are_named_like_generated_ones::HandWritten"
+        );
+        assert_eq!(
+            Generated::suite_name(),
+            "extel::tests::hand_written_suites_are_named_like_generated_ones::Generated"
+        );
+    }
+
+    mod api {
+        pub fn smoke_test() -> crate::ExtelResult {
+            crate::pass!()
+        }
+    }
+
+    mod cli {
+        pub fn smoke_test() -> crate::ExtelResult {
+            crate::fail!("cli smoke test failed")
+        }
+    }
+
+    #[test]
+    fn configs_can_be_moved_to_other_threads() {
+        fn always_succeed() -> ExtelResult {
+            pass!()
+        }
+
+        init_test_suite!(ThreadedSuite as "threaded", always_succeed);
+        let output = SharedBuffer::new();
+        let cfg = TestConfig::default()
+            .output(OutputDest::Buffer(output.clone()))
+            .colored(false);
+
+        let results = std::thread::spawn(move || ThreadedSuite::run(cfg))
+            .join()
+            .unwrap();
+        assert!(results[0].test_result.is_ok());
+        assert_eq!(
+            output.to_string_lossy(),
+            "[threaded]\n\tTest #1 (always_succeed) ... ok\n"
+        );
+    }
+
+    #[test]
+    fn qualified_names_keep_same_named_tests_apart() {
+        init_test_suite!(SmokeSuite, api::smoke_test, cli::smoke_test);
+        let cfg = || TestConfig::default().output(OutputDest::None);
+
+        let results = SmokeSuite::run(cfg().qualified_names(true).filter("tests::cli::"));
+        let names: Vec<_> = results.iter().map(|r| r.test_name.as_ref()).collect();
+        assert_eq!(names, ["extel::tests::cli::smoke_test"]);
+
+        let results = SmokeSuite::run(cfg());
+        assert_eq!(results[0].test_name, "api::smoke_test");
+    }
+
+    #[test]
+    fn failure_hooks_only_run_for_failed_tests() {
+        fn always_succeed() -> ExtelResult {
+            Ok(())
+        }
+
+        fn always_fail() -> ExtelResult {
+            fail!("no luck")
+        }
+
+        init_test_suite!(HookedSuite, always_succeed, always_fail);
+        let output = SharedBuffer::new();
+        let results = HookedSuite::run(
+            TestConfig::default()
+                .output(OutputDest::Buffer(output.clone()))
+                .colored(false)
+                .rerun_hint(false)
+                .on_failure("server log", |result| {
+                    format!("GET /{}\n500 Internal Server Error", result.test_name)
+                })
+                .on_failure("broken", |_| panic!("no docker")),
+        );
+
+        assert!(results[0].failure_context.is_empty());
+        let (name, context) = &results[1].failure_context[1];
+        assert_eq!(name, "broken");
+        assert!(
+            context.starts_with("failure hook panicked: no docker at src/lib.rs:"),
+            "{}",
+            context
+        );
+        assert!(output.to_string_lossy().contains(
+            "\t  [x] no luck\n\
+             \t  [server log]\n\
+             \t    GET /always_fail\n\
+             \t    500 Internal Server Error\n\
+             \t  [broken]\n\
+             \t    failure hook panicked: no docker at src/lib.rs:"
+        ));
+    }
+
+    #[test]
+    fn color_enabled_follows_env_conventions() {
+        assert!(!color_enabled(None, None, false));
+        assert!(color_enabled(Some("".into()), None, true));
+        assert!(!color_enabled(Some("1".into()), Some("1".into()), true));
+        assert!(!color_enabled(None, Some("0".into()), false));
+        assert!(color_enabled(None, Some("yes".into()), false));
     }
 }
@@ -0,0 +1,84 @@
+//! Running Extel tests under `cargo test`, so that a suite shows up in the standard test tooling
+//! alongside regular `#[test]` functions. See [`libtest_bridge`](crate::libtest_bridge).
+//!
+//! Each bridged test runs through the Extel runner as usual, and its report is printed to stdout,
+//! which libtest shows when the test fails or when run with `--nocapture`. Selecting tests is
+//! left to libtest, so [`FILTER_ENV`](crate::FILTER_ENV) is ignored, and no
+//! [state file](crate::TestConfig::state_file) is written.
+
+use crate::{
+    OutputDest, RunnableTestSet, SharedBuffer, SuiteBuilder, SuiteReport, TestConfig, TestFn,
+};
+
+/// Run a single Extel test, panicking if it fails. This is what each test generated by
+/// [`libtest_bridge`](crate::libtest_bridge) calls.
+pub fn run_test<Args>(
+    suite_name: &str,
+    test_name: &'static str,
+    test: impl TestFn<Args> + 'static,
+) {
+    let output = SharedBuffer::new();
+    let report = SuiteBuilder::new(suite_name)
+        .add_test(test_name, test)
+        .run_report(config(output.clone()));
+    finish(report, &output);
+}
+
+/// Run every test of a suite, panicking if any of them fails.
+pub fn run_suite<S: RunnableTestSet>() {
+    let output = SharedBuffer::new();
+    let report = S::run_report(config(output.clone()));
+    finish(report, &output);
+}
+
+fn config(output: SharedBuffer) -> TestConfig {
+    let mut cfg = TestConfig::default()
+        .output(OutputDest::Buffer(output))
+        .colored(false)
+        .rerun_hint(false);
+    cfg.filter = None;
+    cfg
+}
+
+fn finish(report: SuiteReport, output: &SharedBuffer) {
+    print!("{}", output.to_string_lossy());
+    if !report.all_passed() {
+        panic!(
+            "{} of {} test(s) failed in suite '{}'",
+            report.counts.failed, report.counts.total, report.name
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::prelude::*;
+
+    fn always_succeed() -> ExtelResult {
+        pass!()
+    }
+
+    fn always_fail() -> ExtelResult {
+        fail!("no luck")
+    }
+
+    crate::libtest_bridge!(bridged, always_succeed);
+
+    init_test_suite!(FirstPassing, always_succeed);
+    init_test_suite!(SecondPassing, always_succeed);
+    compose_suites!(ComposedPassing, FirstPassing, SecondPassing);
+    crate::libtest_bridge!(ComposedPassing);
+
+    #[test]
+    #[should_panic(expected = "1 of 1 test(s) failed in suite 'bridged'")]
+    fn failing_test_panics() {
+        super::run_test("bridged", "always_fail", always_fail);
+    }
+
+    #[test]
+    #[should_panic(expected = "1 of 2 test(s) failed in suite 'Mixed'")]
+    fn failing_suite_panics() {
+        init_test_suite!(MixedSuite as "Mixed", always_succeed, always_fail);
+        super::run_suite::<MixedSuite>();
+    }
+}
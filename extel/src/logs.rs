@@ -0,0 +1,228 @@
+//! Capturing [`log`](https://docs.rs/log) records and [`tracing`](https://docs.rs/tracing) events
+//! emitted while a test runs, so that library-level tests can keep diagnostics without
+//! polluting the report. See [`TestConfig::capture_logs`](crate::TestConfig::capture_logs).
+//!
+//! Captured lines are attached to the [`TestResult`](crate::TestResult) of the test that emitted
+//! them and are printed by the default reporter under failing tests, or under every test in
+//! [verbose](crate::TestConfig::verbose) mode. Anything emitted outside of a test is dropped.
+//!
+//! > *This is only available with the `log` or `tracing` feature enabled.*
+
+use std::fmt::Display;
+
+use crate::context;
+
+/// Attach a captured line to the current test, if any.
+fn record(level: impl Display, target: &str, message: impl Display) {
+    context::with_scope(|scope| {
+        if let Some(scope) = scope {
+            scope
+                .recorded
+                .logs
+                .push(format!("{:<5} {}: {}", level.to_string(), target, message));
+        }
+    });
+}
+
+/// Install the capturing [`log`] logger and [`tracing`] subscriber for every enabled feature.
+/// Returns `false` if another logger or subscriber was already installed, in which case records
+/// are not captured. Installing more than once is harmless.
+///
+/// # Example
+/// ```rust
+/// use extel::{prelude::*, OutputDest};
+///
+/// fn noisy() -> ExtelResult {
+///     # #[cfg(feature = "log")]
+///     log::warn!("retrying connection");
+///     fail!("could not connect")
+/// }
+///
+/// init_test_suite!(LoggingSuite, noisy);
+/// let results = LoggingSuite::run(
+///     TestConfig::default().output(OutputDest::None).capture_logs(true),
+/// );
+///
+/// # #[cfg(feature = "log")]
+/// assert_eq!(results[0].logs, ["WARN  rust_out: retrying connection"]);
+/// ```
+pub fn install() -> bool {
+    #[allow(unused_mut)]
+    let mut installed = true;
+
+    #[cfg(feature = "log")]
+    {
+        installed &= log_capture::install();
+    }
+
+    #[cfg(feature = "tracing")]
+    {
+        installed &= tracing_capture::install();
+    }
+
+    installed
+}
+
+#[cfg(feature = "log")]
+mod log_capture {
+    use std::sync::OnceLock;
+
+    /// A [`log::Log`] that attaches every record to the current test.
+    struct CaptureLogger;
+
+    impl log::Log for CaptureLogger {
+        fn enabled(&self, _metadata: &log::Metadata) -> bool {
+            true
+        }
+
+        fn log(&self, record: &log::Record) {
+            super::record(record.level(), record.target(), record.args());
+        }
+
+        fn flush(&self) {}
+    }
+
+    static LOGGER: CaptureLogger = CaptureLogger;
+    static INSTALLED: OnceLock<bool> = OnceLock::new();
+
+    pub(super) fn install() -> bool {
+        *INSTALLED.get_or_init(|| {
+            let installed = log::set_logger(&LOGGER).is_ok();
+            if installed {
+                log::set_max_level(log::LevelFilter::Trace);
+            }
+            installed
+        })
+    }
+}
+
+#[cfg(feature = "tracing")]
+mod tracing_capture {
+    use std::{
+        fmt::{self, Write},
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            OnceLock,
+        },
+    };
+
+    use tracing::{
+        field::{Field, Visit},
+        span, Event, Metadata, Subscriber,
+    };
+
+    /// A [`Subscriber`] that attaches every event to the current test. Spans are accepted but
+    /// not recorded.
+    struct CaptureSubscriber {
+        next_span: AtomicU64,
+    }
+
+    /// Formats the fields of an event as its message followed by `name=value` pairs.
+    #[derive(Default)]
+    struct EventFormatter {
+        message: String,
+        fields: String,
+    }
+
+    impl Visit for EventFormatter {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            match field.name() {
+                "message" => {
+                    let _ = write!(self.message, "{:?}", value);
+                }
+                name => {
+                    let _ = write!(self.fields, " {}={:?}", name, value);
+                }
+            }
+        }
+
+        fn record_str(&mut self, field: &Field, value: &str) {
+            match field.name() {
+                "message" => self.message.push_str(value),
+                name => {
+                    let _ = write!(self.fields, " {}={}", name, value);
+                }
+            }
+        }
+    }
+
+    impl Subscriber for CaptureSubscriber {
+        fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, _span: &span::Attributes<'_>) -> span::Id {
+            span::Id::from_u64(self.next_span.fetch_add(1, Ordering::Relaxed))
+        }
+
+        fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+        fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut formatter = EventFormatter::default();
+            event.record(&mut formatter);
+
+            let metadata = event.metadata();
+            super::record(
+                metadata.level(),
+                metadata.target(),
+                formatter.message + &formatter.fields,
+            );
+        }
+
+        fn enter(&self, _span: &span::Id) {}
+
+        fn exit(&self, _span: &span::Id) {}
+    }
+
+    static INSTALLED: OnceLock<bool> = OnceLock::new();
+
+    pub(super) fn install() -> bool {
+        *INSTALLED.get_or_init(|| {
+            tracing::subscriber::set_global_default(CaptureSubscriber {
+                next_span: AtomicU64::new(1),
+            })
+            .is_ok()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{prelude::*, OutputDest, SharedBuffer};
+
+    fn logs_and_fails() -> ExtelResult {
+        #[cfg(feature = "log")]
+        log::info!("from log");
+        #[cfg(feature = "tracing")]
+        tracing::info!(attempt = 2, "from tracing");
+        fail!("gave up")
+    }
+
+    #[test]
+    fn logs_are_shown_under_failing_tests() {
+        init_test_suite!(LogSuite as "logs", logs_and_fails);
+
+        let output_buffer = SharedBuffer::new();
+        let results = LogSuite::run(
+            TestConfig::default()
+                .output(OutputDest::Buffer(output_buffer.clone()))
+                .colored(false)
+                .rerun_hint(false)
+                .capture_logs(true),
+        );
+
+        let output = output_buffer.to_string_lossy();
+        assert!(!results[0].logs.is_empty());
+        #[cfg(feature = "log")]
+        {
+            assert!(results[0]
+                .logs
+                .contains(&String::from("INFO  extel::logs::tests: from log")));
+            assert!(output.contains("\t  [log] INFO  extel::logs::tests: from log\n"));
+        }
+        #[cfg(feature = "tracing")]
+        assert!(output.contains("\t  [log] INFO  extel::logs::tests: from tracing attempt=2\n"));
+    }
+}
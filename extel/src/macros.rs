@@ -3,16 +3,14 @@
 #[cfg(not(doc))]
 #[macro_export]
 macro_rules! __extel_init_tests {
-    ($($test:expr),*) => {{
-        #[allow(unused_mut)]
-        let mut v: Vec<$crate::Test> = Vec::new();
-
-        $(let test_name: &'static str = stringify!($test);
-        let test_fn: fn() -> Box<dyn $crate::GenericTestResult> = || Box::new($test());
-        v.push($crate::Test { test_name, test_fn });)*
-
-        v
-    }};
+    ($($test:expr $(=> [$($tag:literal),*])?),*) => {
+        [$($crate::StaticTest {
+            test_name: ::core::stringify!($test),
+            test_fn: || $crate::TestFn::call_test($test, &$crate::context::TestContext::current()),
+            tags: &[$($($tag),*)?],
+            module_path: ::core::module_path!(),
+        }),*]
+    };
 }
 
 /// A macro to create a passing [`ExtelResult`](crate::ExtelResult).
@@ -111,6 +109,104 @@ macro_rules! err {
     ($fmt:expr) => { $crate::errors::Error::TestFailed(format!($fmt)) }
 }
 
+/// A macro to create a skipped [`ExtelResult`](crate::ExtelResult), for a test that cannot run
+/// on the current machine. A skipped test is neither passed nor failed, and does not fail the run.
+///
+/// If this macro is called the underlying error will be of type
+/// [`Error::Skipped`](crate::errors::Error::Skipped). See [`require`](crate::require) for
+/// ready-made guards.
+///
+/// # Example
+/// ```rust
+/// use extel::{prelude::*, skip};
+///
+/// fn linux_only() -> ExtelResult {
+///     if !cfg!(target_os = "linux") {
+///         return skip!("{} is not supported", std::env::consts::OS);
+///     }
+///     pass!()
+/// }
+///
+/// assert!(!matches!(linux_only(), Err(Error::TestFailed(_))));
+/// ```
+#[macro_export]
+macro_rules! skip {
+    ($fmt:expr, $($arg:expr),*) => {
+        Result::<(), $crate::errors::Error>::Err($crate::errors::Error::Skipped(format!($fmt, $($arg),*)))
+    };
+
+    ($fmt:expr) => {
+        Result::<(), $crate::errors::Error>::Err($crate::errors::Error::Skipped(format!($fmt)))
+    };
+}
+
+/// Skip the test unless the executable `name` is on the `PATH`, evaluating to its path otherwise.
+/// This returns from the enclosing function. See [`require::binary`](crate::require::binary).
+///
+/// # Example
+/// ```rust
+/// use extel::prelude::*;
+///
+/// fn runs_docker() -> ExtelResult {
+///     let docker = require_binary!("extel-missing-docker");
+///     cmd!("{} info", docker.display()).output_checked()?;
+///     pass!()
+/// }
+///
+/// assert!(matches!(runs_docker(), Err(Error::Skipped(_))));
+/// ```
+#[macro_export]
+macro_rules! require_binary {
+    ($name:expr) => {
+        $crate::require::binary($name)?
+    };
+}
+
+/// Skip the test unless the environment variable `key` is set to a non-empty value, evaluating
+/// to the value otherwise. This returns from the enclosing function. See
+/// [`require::env`](crate::require::env).
+///
+/// # Example
+/// ```rust
+/// use extel::prelude::*;
+///
+/// fn calls_api() -> ExtelResult {
+///     let api_key = require_env!("EXTEL_MISSING_API_KEY");
+///     extel_assert!(!api_key.is_empty())
+/// }
+///
+/// assert!(matches!(calls_api(), Err(Error::Skipped(_))));
+/// ```
+#[macro_export]
+macro_rules! require_env {
+    ($key:expr) => {
+        $crate::require::env($key)?
+    };
+}
+
+/// Skip the test if something is already listening on the local TCP `port`. This returns from
+/// the enclosing function. See [`require::port_free`](crate::require::port_free).
+///
+/// # Example
+/// ```rust
+/// use extel::prelude::*;
+/// use std::net::TcpListener;
+///
+/// fn serves_on_8080() -> ExtelResult {
+///     require_port_free!(8080);
+///     let _listener = TcpListener::bind("127.0.0.1:8080")?;
+///     pass!()
+/// }
+///
+/// assert!(!matches!(serves_on_8080(), Err(Error::TestFailed(_))));
+/// ```
+#[macro_export]
+macro_rules! require_port_free {
+    ($port:expr) => {
+        $crate::require::port_free($port)?
+    };
+}
+
 /// Assert if a given condition is true/false. If the condition is true, call the [`pass`] macro,
 /// else call the [`fail`] macro.
 ///
@@ -156,9 +252,341 @@ macro_rules! extel_assert {
     }
 }
 
+/// Assert that two values are equal, like Rust's [`assert_eq`] macro. On failure, both values are
+/// shown using their [`Debug`](std::fmt::Debug) representations, followed by the custom message
+/// if one was given.
+///
+/// Like [`extel_assert`], this macro does not panic and instead returns an
+/// [`ExtelResult`](crate::ExtelResult).
+///
+/// # Example
+/// ```rust
+/// use extel::extel_assert_eq;
+///
+/// let (x, y) = (1, 2);
+///
+/// assert!(extel_assert_eq!(x, 1).is_ok());
+/// assert_eq!(
+///     extel_assert_eq!(x, y).unwrap_err().to_string(),
+///     "[x == y] assertion failed\n  left: 1\n right: 2"
+/// );
+/// assert_eq!(
+///     extel_assert_eq!(x, y, "x was {}", x).unwrap_err().to_string(),
+///     "[x == y] assertion failed: x was 1\n  left: 1\n right: 2"
+/// );
+/// ```
+#[macro_export]
+macro_rules! extel_assert_eq {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::__extel_assert_cmp!($left, ==, $right, "")
+    };
+
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        $crate::__extel_assert_cmp!($left, ==, $right, format!(": {}", format_args!($($arg)+)))
+    };
+}
+
+/// Check `actual` with a custom [`Assertion`](crate::assertion::Assertion), such as one provided
+/// by another crate, failing with a message in the style of the built-in assertions. An optional
+/// format string and arguments are appended to the message.
+///
+/// # Example
+/// ```rust
+/// use extel::{assert_that, assertion::{Assertion, Mismatch}, prelude::*};
+///
+/// struct Sorted;
+///
+/// impl Assertion<[i32]> for Sorted {
+///     fn description(&self) -> String {
+///         String::from("sorted")
+///     }
+///
+///     fn check(&self, actual: &[i32]) -> Result<(), Mismatch> {
+///         match actual.windows(2).position(|pair| pair[0] > pair[1]) {
+///             None => Ok(()),
+///             Some(i) => Err(Mismatch::new(format!("out of order at index {}", i + 1))),
+///         }
+///     }
+/// }
+///
+/// let ids = vec![1, 3, 2];
+/// assert!(assert_that!(ids[..2], Sorted).is_ok());
+/// assert!(assert_that!(ids[..], Sorted, "ids from {}", "the API").is_err());
+/// ```
+#[macro_export]
+macro_rules! assert_that {
+    ($actual:expr, $assertion:expr $(,)?) => {
+        $crate::assertion::assert_that(&$actual, &$assertion, stringify!($actual), "")
+    };
+
+    ($actual:expr, $assertion:expr, $($arg:tt)+) => {
+        $crate::assertion::assert_that(
+            &$actual,
+            &$assertion,
+            stringify!($actual),
+            &format!(": {}", format_args!($($arg)+)),
+        )
+    };
+}
+
+/// Assert that two values are not equal, like Rust's [`assert_ne`] macro. On failure, both values
+/// are shown using their [`Debug`](std::fmt::Debug) representations, followed by the custom
+/// message if one was given.
+///
+/// Like [`extel_assert`], this macro does not panic and instead returns an
+/// [`ExtelResult`](crate::ExtelResult).
+///
+/// # Example
+/// ```rust
+/// use extel::extel_assert_ne;
+///
+/// let (x, y) = (1, 1);
+///
+/// assert!(extel_assert_ne!(x, 2).is_ok());
+/// assert_eq!(
+///     extel_assert_ne!(x, y).unwrap_err().to_string(),
+///     "[x != y] assertion failed\n  left: 1\n right: 1"
+/// );
+/// ```
+#[macro_export]
+macro_rules! extel_assert_ne {
+    ($left:expr, $right:expr $(,)?) => {
+        $crate::__extel_assert_cmp!($left, !=, $right, "")
+    };
+
+    ($left:expr, $right:expr, $($arg:tt)+) => {
+        $crate::__extel_assert_cmp!($left, !=, $right, format!(": {}", format_args!($($arg)+)))
+    };
+}
+
+/// Assert that a string contains a substring. On failure, both strings are shown, with long
+/// strings [abbreviated](crate::diff::abbreviate) to keep the message readable.
+///
+/// Both arguments can be anything that implements `AsRef<str>`. Like [`extel_assert`], this macro
+/// does not panic and instead returns an [`ExtelResult`](crate::ExtelResult).
+///
+/// # Example
+/// ```rust
+/// use extel::{assert_contains, cmd, ExtelResult};
+///
+/// fn greets() -> ExtelResult {
+///     let output = cmd!("echo -n \"hello world\"").output()?;
+///     assert_contains!(String::from_utf8(output.stdout)?, "lo wo")
+/// }
+///
+/// assert!(greets().is_ok());
+/// assert_eq!(
+///     assert_contains!("hello", "bye").unwrap_err().to_string(),
+///     "[\"hello\" contains \"bye\"] assertion failed\n  haystack: \"hello\"\n    needle: \"bye\""
+/// );
+/// ```
+#[macro_export]
+macro_rules! assert_contains {
+    ($haystack:expr, $needle:expr $(,)?) => {
+        $crate::__extel_assert_str!($haystack, contains, $needle, $crate::diff::abbreviate)
+    };
+}
+
+/// Assert that a string starts with a prefix. On failure, both strings are shown, with long
+/// strings [abbreviated](crate::diff::abbreviate) to their beginning.
+///
+/// Both arguments can be anything that implements `AsRef<str>`. Like [`extel_assert`], this macro
+/// does not panic and instead returns an [`ExtelResult`](crate::ExtelResult).
+///
+/// # Example
+/// ```rust
+/// use extel::assert_starts_with;
+///
+/// assert!(assert_starts_with!("error: file not found", "error:").is_ok());
+/// assert!(assert_starts_with!("warning: unused", "error:").is_err());
+/// ```
+#[macro_export]
+macro_rules! assert_starts_with {
+    ($haystack:expr, $prefix:expr $(,)?) => {
+        $crate::__extel_assert_str!($haystack, starts_with, $prefix, $crate::diff::abbreviate)
+    };
+}
+
+/// Assert that a string ends with a suffix. On failure, both strings are shown, with long strings
+/// [abbreviated](crate::diff::abbreviate_tail) to their end.
+///
+/// Both arguments can be anything that implements `AsRef<str>`. Like [`extel_assert`], this macro
+/// does not panic and instead returns an [`ExtelResult`](crate::ExtelResult).
+///
+/// # Example
+/// ```rust
+/// use extel::assert_ends_with;
+///
+/// assert!(assert_ends_with!("build finished\n", "finished\n").is_ok());
+/// assert!(assert_ends_with!("build failed\n", "finished\n").is_err());
+/// ```
+#[macro_export]
+macro_rules! assert_ends_with {
+    ($haystack:expr, $suffix:expr $(,)?) => {
+        $crate::__extel_assert_str!($haystack, ends_with, $suffix, $crate::diff::abbreviate_tail)
+    };
+}
+
+/// Assert that two numbers differ by no more than `epsilon`. On failure, both values are shown
+/// along with their difference, followed by the custom message if one was given.
+///
+/// The values can be of any primitive numeric type, and are compared as [`f64`]. A `NaN` on either
+/// side always fails. Like [`extel_assert`], this macro does not panic and instead returns an
+/// [`ExtelResult`](crate::ExtelResult).
+///
+/// # Example
+/// ```rust
+/// use extel::assert_approx_eq;
+///
+/// let elapsed = 0.1 + 0.2;
+///
+/// assert!(assert_approx_eq!(elapsed, 0.3, 1e-9).is_ok());
+/// assert_eq!(
+///     assert_approx_eq!(elapsed, 0.5, 0.1, "took too long").unwrap_err().to_string(),
+///     "[elapsed ~= 0.5] assertion failed: took too long\n  left: 0.30000000000000004\n right: 0.5\n  \
+///      diff: 0.19999999999999996 (epsilon 0.1)"
+/// );
+/// ```
+#[macro_export]
+macro_rules! assert_approx_eq {
+    ($left:expr, $right:expr, $epsilon:expr $(,)?) => {
+        $crate::__extel_assert_approx!($left, $right, $epsilon, false, "")
+    };
+
+    ($left:expr, $right:expr, $epsilon:expr, $($arg:tt)+) => {
+        $crate::__extel_assert_approx!(
+            $left,
+            $right,
+            $epsilon,
+            false,
+            format!(": {}", format_args!($($arg)+))
+        )
+    };
+}
+
+/// Assert that two numbers differ by no more than `tolerance` relative to the larger of their
+/// magnitudes, so that `0.05` allows a difference of 5%. This suits values whose scale is not
+/// known up front, such as timings and sizes. On failure, both values are shown along with their
+/// relative difference, followed by the custom message if one was given.
+///
+/// The values can be of any primitive numeric type, and are compared as [`f64`]. A `NaN` on either
+/// side always fails. Like [`extel_assert`], this macro does not panic and instead returns an
+/// [`ExtelResult`](crate::ExtelResult).
+///
+/// # Example
+/// ```rust
+/// use extel::assert_relative_eq;
+///
+/// let bytes_written: u64 = 10_300;
+///
+/// assert!(assert_relative_eq!(bytes_written, 10_000, 0.05).is_ok());
+/// assert_eq!(
+///     assert_relative_eq!(bytes_written, 12_000, 0.05).unwrap_err().to_string(),
+///     "[bytes_written ~= 12_000] assertion failed\n  left: 10300\n right: 12000\n  \
+///      diff: 0.14166666666666666 relative (tolerance 0.05)"
+/// );
+/// ```
+#[macro_export]
+macro_rules! assert_relative_eq {
+    ($left:expr, $right:expr, $tolerance:expr $(,)?) => {
+        $crate::__extel_assert_approx!($left, $right, $tolerance, true, "")
+    };
+
+    ($left:expr, $right:expr, $tolerance:expr, $($arg:tt)+) => {
+        $crate::__extel_assert_approx!(
+            $left,
+            $right,
+            $tolerance,
+            true,
+            format!(": {}", format_args!($($arg)+))
+        )
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __extel_assert_approx {
+    ($left:expr, $right:expr, $tolerance:expr, $relative:expr, $msg:expr) => {
+        match (&$left, &$right, &$tolerance) {
+            (left, right, tolerance) => {
+                #[allow(clippy::unnecessary_cast)]
+                let (left, right, tolerance) = (*left as f64, *right as f64, *tolerance as f64);
+                let diff = match $relative {
+                    true => (left - right).abs() / left.abs().max(right.abs()),
+                    false => (left - right).abs(),
+                };
+                // Identical values pass even when relative to zero, where the difference is NaN.
+                match left == right || diff <= tolerance {
+                    true => $crate::pass!(),
+                    false => $crate::fail!(
+                        "[{} ~= {}] assertion failed{}\n  left: {}\n right: {}\n  diff: {}",
+                        stringify!($left),
+                        stringify!($right),
+                        $msg,
+                        left,
+                        right,
+                        match $relative {
+                            true => format!("{} relative (tolerance {})", diff, tolerance),
+                            false => format!("{} (epsilon {})", diff, tolerance),
+                        }
+                    ),
+                }
+            }
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __extel_assert_str {
+    ($haystack:expr, $method:ident, $needle:expr, $abbreviate:path) => {
+        match (&$haystack, &$needle) {
+            (haystack, needle) => {
+                let (haystack, needle): (&str, &str) = (haystack.as_ref(), needle.as_ref());
+                match haystack.$method(needle) {
+                    true => $crate::pass!(),
+                    false => $crate::fail!(
+                        "[{} {} {}] assertion failed\n  haystack: {}\n    needle: {}",
+                        stringify!($haystack),
+                        stringify!($method),
+                        stringify!($needle),
+                        $abbreviate(haystack, $crate::diff::MESSAGE_LIMIT),
+                        $abbreviate(needle, $crate::diff::MESSAGE_LIMIT)
+                    ),
+                }
+            }
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __extel_assert_cmp {
+    ($left:expr, $op:tt, $right:expr, $msg:expr) => {
+        match (&$left, &$right) {
+            (left, right) => match *left $op *right {
+                true => $crate::pass!(),
+                false => $crate::fail!(
+                    "[{} {} {}] assertion failed{}\n  left: {:?}\n right: {:?}",
+                    stringify!($left),
+                    stringify!($op),
+                    stringify!($right),
+                    $msg,
+                    left,
+                    right
+                ),
+            },
+        }
+    };
+}
+
 /// Constructs a [`Command`](std::process::Command) as if receiving the command directly from the
 /// CLI. Arguments wrapped in single or double quotes are treated as single arguments, allowing
-/// multiple tokens to be passed as a single argument to a command.
+/// multiple tokens to be passed as a single argument to a command. See
+/// [`split_command_line`](crate::command::split_command_line) for the exact rules.
+///
+/// # Panics
+/// Panics if the command line is empty or has a quote that is never closed.
 ///
 /// # Example
 /// ```rust
@@ -196,61 +624,14 @@ macro_rules! extel_assert {
 ///     String::from_utf8_lossy(&cmd_output.stdout),
 ///     String::from_utf8_lossy(&cmd_output_path.stdout)
 /// )
-
 /// ```
 #[macro_export]
 macro_rules! cmd {
     ($cmd_str:expr) => {{
-        // First, extract tokens by spliting them by spaces, but keep
-        // together tokens that are wrapped in single/double quotes.
-        let mut cmd_str_tokens = $cmd_str.trim().split(' ');
-        let command = cmd_str_tokens.next().expect("no command was provided");
-        let mut args = cmd_str_tokens.map(String::from);
-        let mut final_args: Vec<String> = Vec::new();
-
-        while let Some(token) = args.next() {
-            // Get a token, check if quotes are present. If so, begin iterating
-            // until a closing quote is found. If a closing quote is not found,
-            // panic.
-            let tok_chars = token.chars().collect::<Vec<_>>();
-            let first_char = tok_chars[0];
-            if ['"', '\''].contains(&first_char) {
-                // Verify that the last token for this string is not also a quote.
-                if *tok_chars.last().unwrap() == first_char {
-                    final_args.push(tok_chars[1..tok_chars.len()-1].into_iter().collect());
-                    break;
-                }
-
-                // Iterate until the next is found.
-                let mut quoted_arg = vec![token.chars().skip(1).collect::<String>()];
-
-                loop {
-                    let Some(token) = args.next() else {
-                        break;
-                    };
-
-                    // Check if the last char is a matching quote
-                    let tok_chars = token.chars().collect::<Vec<_>>();
-                    if *tok_chars.last().unwrap() == first_char {
-                        quoted_arg.push(
-                            // Assumes UTF-8
-                            tok_chars[0..tok_chars.len()-1].into_iter().collect()
-                        );
-                    } else {
-                        quoted_arg.push(token);
-                    }
-                }
-                final_args.extend(quoted_arg);
-            } else {
-                final_args.push(token);
-            }
-        }
-
-        let mut command = ::std::process::Command::new(command);
-        if !final_args.is_empty() {
-            command.args(final_args);
-        }
-        command
+        let (command, args) =
+            $crate::command::split_command_line(::core::convert::AsRef::<str>::as_ref(&$cmd_str))
+                .unwrap_or_else(|e| ::core::panic!("{}", e));
+        $crate::command::build(command, args)
     }};
 
     ($cmd_str:literal, $($arg:expr),*) => {{
@@ -259,99 +640,832 @@ macro_rules! cmd {
     }};
 
     /* Arms to handle empty expression blocks */
-    ($cmd:expr => []) => { ::std::process::Command::new($cmd) };
-    ($cmd:expr => {}) => { ::std::process::Command::new($cmd) };
-    ($cmd:expr => ()) => { ::std::process::Command::new($cmd) };
+    ($cmd:expr => []) => { $crate::command::wrapped($cmd) };
+    ($cmd:expr => {}) => { $crate::command::wrapped($cmd) };
+    ($cmd:expr => ()) => { $crate::command::wrapped($cmd) };
     /* End empty expression blocks */
 
-    ($cmd:expr => $args:expr) => { ::std::process::Command::new($cmd).args($args) };
+    ($cmd:expr => $args:expr) => { &mut $crate::command::build($cmd, $args) };
 }
 
-/// The test suite initializer that constructs test suits based on the provided name (first
-/// parameter) and the provided functions (the comma-delimited list afterwards). Every function
-/// that is provided is expected *only* to return type [`ExtelResult`](crate::ExtelResult), and
-/// should have *no* parameters.
+/// Create a [`CommandTemplate`](crate::command::CommandTemplate) from a command line, split into
+/// the program and its arguments like [`cmd!`]. Like [`cmd!`], the command line may be formatted
+/// with arguments, or a program and a list of arguments can be given instead.
 ///
-/// These tests are stateless in nature, relying on their environment and hard-coded CLI args to
-/// handle configuration and valid setup.
+/// # Panics
+/// Panics if the command line is empty or has a quote that is never closed.
 ///
 /// # Example
 /// ```rust
-/// use std::process::Command;
 /// use extel::prelude::*;
+/// use std::path::Path;
 ///
-/// /// Run end-to-end test of application.
-/// fn echo_no_arg_e2e() -> ExtelResult {
-///     match Command::new("echo").status() {
-///         Ok(exit_code) => {
-///             let code: i32 = exit_code.code().unwrap_or(-1);
-///             extel_assert!(code == 0, "failed with exit code: {}", code)
-///         },
-///         Err(msg) => {
-///             fail!("failed to execute with error: {}", msg)
-///         }
-///     }
-/// }
+/// let bin_dir = Path::new("target/debug");
+/// let app = cmd_template!("{}/app --color never", bin_dir.display()).env("APP_LOG", "debug");
+/// let version = app.build(["--version"]);
 ///
-/// // Outputs:
-/// //  Test #1 (echo_no_arg_e2e) ... ok
-/// init_test_suite!(EchoTestSuite, echo_no_arg_e2e);
-/// EchoTestSuite::run(TestConfig::default());
+/// assert_eq!(version.get_program(), "target/debug/app");
+/// assert_eq!(version.get_args().collect::<Vec<_>>(), ["--color", "never", "--version"]);
+///
+/// let app = cmd_template!(bin_dir.join("app") => ["--color", "never"]);
+/// assert_eq!(app.build(["--version"]).get_args().count(), 3);
 /// ```
 #[macro_export]
-macro_rules! init_test_suite {
-    ($test_suite:ident) => {
-        init_test_suite!($test_suite,)
-    };
-
-    ($test_suite:ident, $($test_name:expr),*) => {
-        #[allow(non_camel_case_types)]
-        pub struct $test_suite {
-            tests: Vec<$crate::Test>,
-        }
+macro_rules! cmd_template {
+    ($cmd_str:expr) => {{
+        let (command, args) =
+            $crate::command::split_command_line(::core::convert::AsRef::<str>::as_ref(&$cmd_str))
+                .unwrap_or_else(|e| ::core::panic!("{}", e));
+        $crate::command::CommandTemplate::new(command).args(args)
+    }};
 
-        impl $crate::RunnableTestSet for $test_suite {
-            fn run(cfg: $crate::TestConfig) -> Vec<$crate::TestResult> {
-                let test_set = $test_suite { tests: $crate::__extel_init_tests!($($test_name),*) };
-                let mut writer: Option<Box<dyn ::std::io::Write>> = match cfg.output {
-                    $crate::OutputDest::Stdout => Some(Box::new(::std::io::stdout())),
-                    $crate::OutputDest::File(file_name) => {
-                        let file_handle = ::std::fs::File::create(file_name).expect("could not open output file");
-                        Some(Box::new(file_handle))
-                    },
-                    $crate::OutputDest::Buffer(buffer) => Some(Box::new(buffer)),
-                    $crate::OutputDest::None => None
-                };
+    ($cmd_str:literal, $($arg:expr),*) => {{
+        let fmt = ::std::format!($cmd_str, $($arg),*);
+        $crate::cmd_template!(fmt)
+    }};
 
-                if let Some(w) = writer.as_mut() {
-                    write!(w, "[{}]\n", std::any::type_name::<$test_suite>()).expect("buffer could not be written to");
-                }
+    ($cmd:expr => $args:expr) => {
+        $crate::command::CommandTemplate::new($cmd).args($args)
+    };
+}
 
-                // Begin running tests and logging to the desired writer
-                test_set
-                    .tests
-                    .into_iter()
-                    .enumerate()
-                    .map(|(test_id, test)| {
-                        let test_result = test.run_test();
+/// Create a [`Command`](std::process::Command) that runs a command line on a
+/// [remote host](crate::ssh::Remote) over SSH. Like [`cmd!`], the command line may be formatted
+/// with arguments. It is interpreted by the remote shell, so quoting follows shell rules.
+///
+/// A program and a list of arguments can be given instead, in which case every argument is
+/// quoted so that it reaches the remote program verbatim. See
+/// [`Remote::command_args`](crate::ssh::Remote::command_args).
+///
+/// # Example
+/// ```rust
+/// use extel::{remote_cmd, ssh::Remote};
+///
+/// let board = Remote::new("lab-board-3");
+/// let version = remote_cmd!(board, "fwctl --version");
+/// let flashed = remote_cmd!(board, "fwctl flash --slot {}", 2);
+/// let quoted = remote_cmd!(board, "fwctl" => ["set", "name", "board 3"]);
+///
+/// assert_eq!(flashed.get_args().last().unwrap(), "fwctl flash --slot 2");
+/// assert_eq!(quoted.get_args().last().unwrap(), "'fwctl' 'set' 'name' 'board 3'");
+/// ```
+///
+/// > *This is only available with the `ssh` feature enabled.*
+#[cfg(feature = "ssh")]
+#[macro_export]
+macro_rules! remote_cmd {
+    ($remote:expr, $cmd_str:expr) => {
+        $crate::ssh::Remote::command(&$remote, &$cmd_str)
+    };
 
-                        if let Some(w) = writer.as_mut() {
-                           $crate::output_test_result(w, &test_result, test_id + 1, cfg.colored);
-                        }
+    ($remote:expr, $cmd_str:literal, $($arg:expr),*) => {
+        $crate::ssh::Remote::command(&$remote, &format!($cmd_str, $($arg),*))
+    };
 
-                        test_result
-                    })
-                    .collect()
-            }
-        }
+    ($remote:expr, $cmd:expr => $args:expr) => {
+        $crate::ssh::Remote::command_args(&$remote, $cmd, $args)
     };
 }
 
-#[cfg(test)]
+/// Compare a string against the contents of a checked-in golden file. On mismatch, the resulting
+/// [`Error::TestFailed`](crate::errors::Error::TestFailed) contains a line diff between the golden
+/// file and the actual value. This macro returns an [`ExtelResult`](crate::ExtelResult).
+///
+/// Setting the [`EXTEL_UPDATE_GOLDEN`](crate::golden::UPDATE_ENV) environment variable rewrites
+/// the golden file with the actual value instead of comparing against it.
+///
+/// # Example
+/// ```rust
+/// use extel::{fs::temp_file_with, prelude::*};
+///
+/// fn echo_matches_golden() -> ExtelResult {
+///     let golden = temp_file_with("hello world\n")?;
+///     let output = String::from_utf8(cmd!("echo hello world").output()?.stdout)?;
+///     assert_matches_file!(output, &golden)
+/// }
+///
+/// assert!(echo_matches_golden().is_ok());
+/// ```
+#[macro_export]
+macro_rules! assert_matches_file {
+    ($actual:expr, $path:expr) => {
+        $crate::golden::matches_file(&$actual, $path)
+    };
+}
+
+/// Compare bytes, such as the output of a binary encoder, against the contents of a checked-in
+/// golden file. On mismatch, the resulting
+/// [`Error::TestFailed`](crate::errors::Error::TestFailed) contains the offset of the first
+/// difference and a hexdump of the first differing rows of both. This macro returns an
+/// [`ExtelResult`](crate::ExtelResult).
+///
+/// Like [`assert_matches_file`], setting the [`EXTEL_UPDATE_GOLDEN`](crate::golden::UPDATE_ENV)
+/// environment variable rewrites the golden file with the actual bytes instead.
+///
+/// # Example
+/// ```rust
+/// use extel::{fs::temp_file_with, prelude::*};
+///
+/// fn encoder_matches_golden() -> ExtelResult {
+///     let golden = temp_file_with(b"\x00\x01hi\xff")?;
+///     let output = cmd!("printf" => [r"\000\001hi\377"]).output()?;
+///     assert_bytes_snapshot!(output.stdout, &golden)
+/// }
+///
+/// assert!(encoder_matches_golden().is_ok());
+/// ```
+#[macro_export]
+macro_rules! assert_bytes_snapshot {
+    ($actual:expr, $path:expr) => {
+        $crate::golden::matches_bytes_file(&$actual, $path)
+    };
+}
+
+/// Check that a cell of CSV output, given by its row number after the header and its column name
+/// or index, is the expected text. This macro returns an [`ExtelResult`](crate::ExtelResult). See
+/// the [`csv`](crate::csv) module for TSV output and more.
+///
+/// # Example
+/// ```rust
+/// use extel::prelude::*;
+///
+/// let output = b"city,population\nLyon,522250\n";
+/// assert!(assert_csv_cell!(output, 0, "population", "522250").is_ok());
+/// assert!(assert_csv_cell!(output, 0, 0, "Paris").is_err());
+/// ```
+#[macro_export]
+macro_rules! assert_csv_cell {
+    ($output:expr, $row:expr, $column:expr, $expected:expr) => {
+        $crate::csv::Table::csv(&$output).and_then(|table| {
+            table.expect_cell($row, $column, ::core::convert::AsRef::as_ref(&$expected))
+        })
+    };
+}
+
+/// Check that CSV output has the expected number of rows after its header. This macro returns an
+/// [`ExtelResult`](crate::ExtelResult).
+///
+/// # Example
+/// ```rust
+/// use extel::prelude::*;
+///
+/// assert!(assert_csv_rows!("id\n1\n2\n", 2).is_ok());
+/// ```
+#[macro_export]
+macro_rules! assert_csv_rows {
+    ($output:expr, $expected:expr) => {
+        $crate::csv::Table::csv(&$output).and_then(|table| table.expect_rows($expected))
+    };
+}
+
+/// Check that the header of CSV output has a column with the expected name. This macro returns an
+/// [`ExtelResult`](crate::ExtelResult).
+///
+/// # Example
+/// ```rust
+/// use extel::prelude::*;
+///
+/// assert!(assert_csv_column!("id,name\n", "name").is_ok());
+/// assert!(assert_csv_column!("id,name\n", "email").is_err());
+/// ```
+#[macro_export]
+macro_rules! assert_csv_column {
+    ($output:expr, $name:expr) => {
+        $crate::csv::Table::csv(&$output).and_then(|table| table.expect_column($name))
+    };
+}
+
+/// Assert that a file exists. Like [`extel_assert`], this macro does not panic and instead returns
+/// an [`ExtelResult`](crate::ExtelResult).
+///
+/// # Example
+/// ```rust
+/// use extel::{fs::temp_dir, prelude::*};
+///
+/// fn creates_file() -> ExtelResult {
+///     let dir = temp_dir()?;
+///     cmd!("touch" => ["created.txt"]).current_dir(&dir).output_checked()?;
+///     assert_file_exists!(dir.join("created.txt"))
+/// }
+///
+/// assert!(creates_file().is_ok());
+/// ```
+#[macro_export]
+macro_rules! assert_file_exists {
+    ($path:expr $(,)?) => {
+        $crate::fs::file_exists(&$path)
+    };
+}
+
+/// Assert that a file contains exactly the expected contents, which can be anything that
+/// implements `AsRef<[u8]>`. On mismatch, the resulting
+/// [`Error::TestFailed`](crate::errors::Error::TestFailed) contains a line diff between the
+/// expected and actual contents. This macro returns an [`ExtelResult`](crate::ExtelResult).
+///
+/// # Example
+/// ```rust
+/// use extel::{fs::temp_dir, prelude::*};
+///
+/// fn writes_greeting() -> ExtelResult {
+///     let dir = temp_dir()?;
+///     cmd!("sh" => ["-c", "echo hello > greeting.txt"]).current_dir(&dir).output_checked()?;
+///     assert_file_eq!(dir.join("greeting.txt"), "hello\n")
+/// }
+///
+/// assert!(writes_greeting().is_ok());
+/// ```
+#[macro_export]
+macro_rules! assert_file_eq {
+    ($path:expr, $expected:expr $(,)?) => {
+        $crate::fs::file_eq(&$path, &$expected)
+    };
+}
+
+/// Compare a sorted [listing](crate::fs::dir_listing) of a directory tree, with a hash of every
+/// file, against a golden file. Like [`assert_matches_file`], setting the
+/// [`EXTEL_UPDATE_GOLDEN`](crate::golden::UPDATE_ENV) environment variable rewrites the golden
+/// file instead. This macro returns an [`ExtelResult`](crate::ExtelResult).
+///
+/// # Example
+/// ```rust
+/// use extel::{fs::{temp_dir, temp_file_with}, prelude::*};
+///
+/// fn scaffolds_project() -> ExtelResult {
+///     let dir = temp_dir()?;
+///     cmd!("mkdir" => ["-p", "src"]).current_dir(&dir).output_checked()?;
+///     cmd!("touch" => ["src/lib.rs"]).current_dir(&dir).output_checked()?;
+///
+///     let golden = temp_file_with("src/\ncbf29ce484222325  src/lib.rs\n")?;
+///     assert_dir_snapshot!(dir, &golden)
+/// }
+///
+/// assert!(scaffolds_project().is_ok());
+/// ```
+#[macro_export]
+macro_rules! assert_dir_snapshot {
+    ($dir:expr, $golden:expr $(,)?) => {
+        $crate::fs::dir_snapshot(&$dir, $golden)
+    };
+}
+
+/// Check the stdout, stderr, and exit code of a command against an
+/// [`Expectation`](crate::expect::Expectation) in one assertion. The command is run to completion,
+/// and every channel that does not match is described in the resulting
+/// [`Error::TestFailed`](crate::errors::Error::TestFailed). This macro returns an
+/// [`ExtelResult`](crate::ExtelResult).
+///
+/// # Example
+/// ```rust
+/// use extel::prelude::*;
+///
+/// fn greets() -> ExtelResult {
+///     assert_cmd_matches!(
+///         cmd!("echo hello"),
+///         Expectation::new().stdout("hello\n").stderr("").exit(0)
+///     )
+/// }
+///
+/// assert!(greets().is_ok());
+/// ```
+#[macro_export]
+macro_rules! assert_cmd_matches {
+    ($cmd:expr, $expectation:expr) => {
+        $crate::expect::Expectation::verify(&$expectation, &mut $cmd)
+    };
+}
+
+/// Run a command to completion and check that it exits with the expected code, such as a usage
+/// error code for invalid arguments. On mismatch, the resulting
+/// [`Error::TestFailed`](crate::errors::Error::TestFailed) shows the actual code and the end of
+/// the command's stderr. This macro returns an [`ExtelResult`](crate::ExtelResult). See
+/// [`CommandExt::expect_exit`](crate::command::CommandExt::expect_exit).
+///
+/// # Example
+/// ```rust
+/// use extel::prelude::*;
+///
+/// fn rejects_unknown_flag() -> ExtelResult {
+///     expect_exit!(cmd!("sh" => ["-c", "echo 'unknown flag' >&2; exit 2"]), 2)
+/// }
+///
+/// fn accepts_no_flags() -> ExtelResult {
+///     expect_exit!(cmd!("sh" => ["-c", "echo 'missing input' >&2; exit 1"]), 0)
+/// }
+///
+/// assert!(rejects_unknown_flag().is_ok());
+/// assert_eq!(
+///     accepts_no_flags().unwrap_err().to_string(),
+///     "expected exit code 0, got 1\n  stderr: \"missing input\\n\""
+/// );
+/// ```
+#[macro_export]
+macro_rules! expect_exit {
+    ($cmd:expr, $code:expr) => {{
+        use $crate::command::CommandExt as _;
+        $cmd.expect_exit($code)
+    }};
+}
+
+/// Run a closure returning a `Result` as a named step of the current test, recording whether it
+/// succeeded and how long it took. The steps of a failing test are listed in its report, showing
+/// how far it got. This macro returns the result of the closure. See the [`step`](crate::step)
+/// module.
+///
+/// # Example
+/// ```rust
+/// use extel::prelude::*;
+///
+/// fn migrates() -> ExtelResult {
+///     step!("create schema", || cmd!("true").output_checked())?;
+///     step!("apply migrations", || fail!("migration 0042 failed"))
+/// }
+///
+/// assert!(migrates().is_err());
+/// ```
+#[macro_export]
+macro_rules! step {
+    ($name:expr, $f:expr $(,)?) => {
+        $crate::step::run($name, $f)
+    };
+}
+
+/// Check that the stdout of a command's [`Output`](std::process::Output) is exactly the expected
+/// bytes, without requiring either to be valid UTF-8. On mismatch, the resulting
+/// [`Error::TestFailed`](crate::errors::Error::TestFailed) shows where the bytes first differ. See
+/// [`bytes::bytes_eq`](crate::bytes::bytes_eq). This macro returns an
+/// [`ExtelResult`](crate::ExtelResult).
+///
+/// # Example
+/// ```rust
+/// use extel::prelude::*;
+///
+/// fn writes_header() -> ExtelResult {
+///     let output = cmd!("printf" => ["\\211PNG"]).output()?;
+///     assert_stdout_bytes_eq!(output, b"\x89PNG")
+/// }
+///
+/// assert!(writes_header().is_ok());
+/// ```
+#[macro_export]
+macro_rules! assert_stdout_bytes_eq {
+    ($output:expr, $expected:expr $(,)?) => {
+        $crate::bytes::bytes_eq(&$output.stdout, $expected)
+    };
+}
+
+/// Check that the stderr of a command's [`Output`](std::process::Output) is exactly the expected
+/// bytes, like [`assert_stdout_bytes_eq`]. This macro returns an
+/// [`ExtelResult`](crate::ExtelResult).
+///
+/// # Example
+/// ```rust
+/// use extel::prelude::*;
+///
+/// fn warns() -> ExtelResult {
+///     let output = cmd!("sh" => ["-c", "printf 'warn\\n' >&2"]).output()?;
+///     assert_stderr_bytes_eq!(output, b"warn\n")
+/// }
+///
+/// assert!(warns().is_ok());
+/// ```
+#[macro_export]
+macro_rules! assert_stderr_bytes_eq {
+    ($output:expr, $expected:expr $(,)?) => {
+        $crate::bytes::bytes_eq(&$output.stderr, $expected)
+    };
+}
+
+/// Compare output, such as a command's stdout, against an expected JSON value, ignoring key order
+/// and whitespace. The expected value is anything accepted by [`json!`](crate::json::json). On a
+/// mismatch, every path at which the values differ is listed in the resulting
+/// [`Error::TestFailed`](crate::errors::Error::TestFailed), and output that is not valid JSON is an
+/// [`Error::Json`](crate::errors::Error::Json). This macro returns an
+/// [`ExtelResult`](crate::ExtelResult).
+///
+/// # Example
+/// ```rust
+/// use extel::{json::json, prelude::*};
+///
+/// fn reports_version() -> ExtelResult {
+///     let output = cmd!("echo" => [r#"{"name": "extel", "version": [0, 3]}"#]).output()?;
+///     assert_json_eq!(output.stdout, json!({"version": [0, 3], "name": "extel"}))
+/// }
+///
+/// assert!(reports_version().is_ok());
+/// ```
+///
+/// > *This is only available with the `serde` feature enabled.*
+#[cfg(feature = "serde")]
+#[macro_export]
+macro_rules! assert_json_eq {
+    ($actual:expr, $($expected:tt)+) => {
+        $crate::json::json_eq(&$actual, $crate::json::json!($($expected)+))
+    };
+}
+
+/// Check a single value in JSON output, selected by a [path](crate::json::lookup) such as
+/// `$.items[0].id`. The expected value is anything accepted by [`json!`](crate::json::json). This
+/// macro returns an [`ExtelResult`](crate::ExtelResult).
+///
+/// # Example
+/// ```rust
+/// use extel::prelude::*;
+///
+/// fn first_item() -> ExtelResult {
+///     let output = cmd!("echo" => [r#"{"items": [{"id": 3, "tags": ["a"]}]}"#]).output()?;
+///     assert_json_path!(output.stdout, "$.items[0].id", 3)?;
+///     assert_json_path!(output.stdout, "$.items[0].tags", ["a"])
+/// }
+///
+/// assert!(first_item().is_ok());
+/// ```
+///
+/// > *This is only available with the `serde` feature enabled.*
+#[cfg(feature = "serde")]
+#[macro_export]
+macro_rules! assert_json_path {
+    ($actual:expr, $path:expr, $($expected:tt)+) => {
+        $crate::json::json_path_eq(&$actual, $path, $crate::json::json!($($expected)+))
+    };
+}
+
+/// Check that an [HTTP response](crate::http::Response) has the expected status code. The
+/// failure message includes the response body. This macro returns an
+/// [`ExtelResult`](crate::ExtelResult).
+///
+/// # Example
+/// ```rust
+/// use extel::{http::Response, prelude::*};
+///
+/// let resp = Response { status: 503, headers: Vec::new(), body: b"starting up".to_vec() };
+/// assert!(assert_status!(resp, 200).is_err());
+/// ```
+///
+/// > *This is only available with the `http` feature enabled.*
+#[cfg(feature = "http")]
+#[macro_export]
+macro_rules! assert_status {
+    ($response:expr, $status:expr) => {
+        $crate::http::status_eq(&$response, $status)
+    };
+}
+
+/// Check that the body of an [HTTP response](crate::http::Response) contains a string. The
+/// failure message includes the response body. This macro returns an
+/// [`ExtelResult`](crate::ExtelResult).
+///
+/// # Example
+/// ```rust
+/// use extel::{http::Response, prelude::*};
+///
+/// let resp = Response { status: 200, headers: Vec::new(), body: b"{\"ok\":true}".to_vec() };
+/// assert!(assert_body_contains!(resp, "\"ok\"").is_ok());
+/// ```
+///
+/// > *This is only available with the `http` feature enabled.*
+#[cfg(feature = "http")]
+#[macro_export]
+macro_rules! assert_body_contains {
+    ($response:expr, $needle:expr) => {
+        $crate::http::body_contains(&$response, $needle)
+    };
+}
+
+/// The test suite initializer that constructs test suits based on the provided name (first
+/// parameter) and the provided functions (the comma-delimited list afterwards). Every function
+/// that is provided is expected *only* to return type [`ExtelResult`](crate::ExtelResult), and
+/// should have *no* parameters, or a single
+/// [`&TestContext`](crate::context::TestContext) parameter.
+///
+/// These tests are stateless in nature, relying on their environment and hard-coded CLI args to
+/// handle configuration and valid setup. Runtime information, such as where to put files, is
+/// available through the [test context](crate::context).
+///
+/// # Example
+/// ```rust
+/// use std::process::Command;
+/// use extel::prelude::*;
+///
+/// /// Run end-to-end test of application.
+/// fn echo_no_arg_e2e() -> ExtelResult {
+///     match Command::new("echo").status() {
+///         Ok(exit_code) => {
+///             let code: i32 = exit_code.code().unwrap_or(-1);
+///             extel_assert!(code == 0, "failed with exit code: {}", code)
+///         },
+///         Err(msg) => {
+///             fail!("failed to execute with error: {}", msg)
+///         }
+///     }
+/// }
+///
+/// // Outputs:
+/// //  Test #1 (echo_no_arg_e2e) ... ok
+/// init_test_suite!(EchoTestSuite, echo_no_arg_e2e);
+/// EchoTestSuite::run(TestConfig::default());
+/// ```
+///
+/// Tests can be tagged by following them with `=> [...]`. Tags can then be used to select which
+/// tests run with [`TestConfig::tags`](crate::TestConfig::tags).
+///
+/// ```rust
+/// use extel::prelude::*;
+///
+/// fn smoke() -> ExtelResult {
+///     pass!()
+/// }
+///
+/// fn slow_network() -> ExtelResult {
+///     pass!()
+/// }
+///
+/// init_test_suite!(TaggedTestSuite, smoke => ["smoke"], slow_network => ["slow", "network"]);
+/// let results = TaggedTestSuite::run(TestConfig::default().tags(["smoke"]));
+/// assert_eq!(results.len(), 1);
+/// ```
+///
+/// By default, the suite is reported under its fully qualified type name. A human-readable name
+/// can be given instead with `as`:
+///
+/// ```rust
+/// use extel::prelude::*;
+///
+/// fn always_succeed() -> ExtelResult {
+///     pass!()
+/// }
+///
+/// // Outputs:
+/// //  [Always succeeding tests]
+/// //      Test #1 (always_succeed) ... ok
+/// init_test_suite!(SucceedTestSuite as "Always succeeding tests", always_succeed);
+/// SucceedTestSuite::run(TestConfig::default());
+/// ```
+///
+/// The tests of a suite are also available as a `TESTS` array of
+/// [`StaticTest`](crate::StaticTest) on the suite type, whose length can be checked at compile
+/// time. Running the suite turns each of them into a [`Test`](crate::Test).
+///
+/// Listing the same test more than once is usually a copy-paste mistake that skews the results, so
+/// it fails to compile:
+///
+/// ```rust,compile_fail
+/// use extel::prelude::*;
+///
+/// fn always_succeed() -> ExtelResult {
+///     pass!()
+/// }
+///
+/// init_test_suite!(RepeatedTestSuite, always_succeed, always_succeed);
+/// ```
+///
+/// To repeat a test on purpose, such as to catch flakiness, start the list with
+/// `allow_duplicates;`:
+///
+/// ```rust
+/// use extel::prelude::*;
+///
+/// fn always_succeed() -> ExtelResult {
+///     pass!()
+/// }
+///
+/// init_test_suite!(RepeatedTestSuite, allow_duplicates; always_succeed, always_succeed);
+/// let results = RepeatedTestSuite::run(TestConfig::default());
+/// assert_eq!(results.len(), 2);
+/// ```
+#[macro_export]
+macro_rules! init_test_suite {
+    (@define $test_suite:ident, $suite_name:expr, $unique:literal, $($test_name:expr $(=> [$($tag:literal),*])?),*) => {
+        const _: () = match $unique {
+            true => $crate::macros::check_unique_tests(&[$(::core::stringify!($test_name)),*]),
+            false => (),
+        };
+
+        #[allow(non_camel_case_types)]
+        pub struct $test_suite {}
+
+        impl $test_suite {
+            /// The tests of the suite, in the order they were listed.
+            #[allow(dead_code)]
+            pub const TESTS: [$crate::StaticTest; [$(::core::stringify!($test_name)),*].len()] =
+                $crate::__extel_init_tests!($($test_name $(=> [$($tag),*])?),*);
+        }
+
+        impl $crate::RunnableTestSet for $test_suite {
+            fn run(cfg: $crate::TestConfig) -> ::std::vec::Vec<$crate::TestResult> {
+                let tests = Self::TESTS.into_iter().map($crate::Test::from).collect();
+                $crate::run_tests($suite_name, tests, cfg)
+            }
+
+            fn suite_name() -> &'static str {
+                $suite_name
+            }
+        }
+    };
+
+    ($test_suite:ident as $suite_name:literal) => {
+        $crate::init_test_suite!($test_suite as $suite_name,)
+    };
+
+    ($test_suite:ident as $suite_name:literal, allow_duplicates; $($test_name:expr $(=> [$($tag:literal),*])?),*) => {
+        $crate::init_test_suite!(@define $test_suite, $suite_name, false, $($test_name $(=> [$($tag),*])?),*);
+    };
+
+    ($test_suite:ident as $suite_name:literal, $($test_name:expr $(=> [$($tag:literal),*])?),*) => {
+        $crate::init_test_suite!(@define $test_suite, $suite_name, true, $($test_name $(=> [$($tag),*])?),*);
+    };
+
+    ($test_suite:ident) => {
+        $crate::init_test_suite!($test_suite,)
+    };
+
+    ($test_suite:ident, allow_duplicates; $($test_name:expr $(=> [$($tag:literal),*])?),*) => {
+        $crate::init_test_suite!(
+            @define $test_suite,
+            ::std::any::type_name::<$test_suite>(),
+            false,
+            $($test_name $(=> [$($tag),*])?),*
+        );
+    };
+
+    ($test_suite:ident, $($test_name:expr $(=> [$($tag:literal),*])?),*) => {
+        $crate::init_test_suite!(
+            @define $test_suite,
+            ::std::any::type_name::<$test_suite>(),
+            true,
+            $($test_name $(=> [$($tag),*])?),*
+        );
+    };
+}
+
+/// Combine existing suites into a single suite that runs each of them in order, with a clone of its
+/// configuration, and then reports the totals of every suite and of the whole run. The results
+/// of every suite are returned together.
+///
+/// As with [`init_test_suite`], the combined suite is reported under its type name unless a
+/// name is given with `as`.
+///
+/// # Example
+/// ```rust
+/// use extel::{prelude::*, OutputDest, SharedBuffer};
+///
+/// fn always_succeed() -> ExtelResult {
+///     pass!()
+/// }
+///
+/// fn always_fail() -> ExtelResult {
+///     fail!("no luck")
+/// }
+///
+/// init_test_suite!(MathTestSuite as "math", always_succeed);
+/// init_test_suite!(CommandTestSuite as "command", always_succeed, always_fail);
+///
+/// // Outputs:
+/// //  [math]
+/// //      Test #1 (always_succeed) ... ok
+/// //  [command]
+/// //      Test #1 (always_succeed) ... ok
+/// //      Test #2 (always_fail) ... FAILED
+/// //        [x] no luck
+/// //  [all tests]
+/// //      math: 1 passed; 0 failed
+/// //      command: 1 passed; 1 failed
+/// //      total: 2 passed; 1 failed
+/// compose_suites!(AllTests as "all tests", MathTestSuite, CommandTestSuite);
+///
+/// let results = AllTests::run(TestConfig::default().rerun_hint(false));
+/// assert_eq!(results.len(), 3);
+/// ```
+#[macro_export]
+macro_rules! compose_suites {
+    (@define $composed:ident, $suite_name:expr, $($suite:path),+) => {
+        #[allow(non_camel_case_types)]
+        pub struct $composed {}
+
+        impl $crate::RunnableTestSet for $composed {
+            fn run(cfg: $crate::TestConfig) -> ::std::vec::Vec<$crate::TestResult> {
+                $crate::compose::run(
+                    $suite_name,
+                    &[$(<$suite as $crate::RunnableTestSet>::run_report),+],
+                    cfg,
+                )
+            }
+
+            fn suite_name() -> &'static str {
+                $suite_name
+            }
+        }
+    };
+
+    ($composed:ident as $suite_name:literal, $($suite:path),+ $(,)?) => {
+        $crate::compose_suites!(@define $composed, $suite_name, $($suite),+);
+    };
+
+    ($composed:ident, $($suite:path),+ $(,)?) => {
+        $crate::compose_suites!(
+            @define $composed,
+            ::std::any::type_name::<$composed>(),
+            $($suite),+
+        );
+    };
+}
+
+/// Fail to compile if a test is listed more than once in [`init_test_suite`].
+#[doc(hidden)]
+pub const fn check_unique_tests(test_names: &[&str]) {
+    let mut i = 0;
+    while i < test_names.len() {
+        let mut j = i + 1;
+        while j < test_names.len() {
+            if str_eq(test_names[i], test_names[j]) {
+                panic!(
+                    "init_test_suite! lists the same test more than once; start the list with \
+                     `allow_duplicates;` to repeat a test on purpose"
+                );
+            }
+            j += 1;
+        }
+        i += 1;
+    }
+}
+
+const fn str_eq(left: &str, right: &str) -> bool {
+    let (left, right) = (left.as_bytes(), right.as_bytes());
+    if left.len() != right.len() {
+        return false;
+    }
+
+    let mut i = 0;
+    while i < left.len() {
+        if left[i] != right[i] {
+            return false;
+        }
+        i += 1;
+    }
+    true
+}
+
+/// Make Extel tests runnable with `cargo test`, generating one libtest test for each of them. The
+/// tests are placed in a module named after the first argument, so they are listed as
+/// `suite::test_name` and can be selected with the usual `cargo test` filters. A failing test
+/// panics, and its Extel report is shown along with the panic.
+///
+/// Given only the name of a suite created with [`init_test_suite`], a single libtest test runs the
+/// whole suite instead.
+///
+/// See the [`libtest`](crate::libtest) module for how the bridged tests are configured.
+///
+/// # Example
+/// In an integration test such as `tests/cli.rs`:
+/// ```rust
+/// use extel::prelude::*;
+///
+/// fn prints_version() -> ExtelResult {
+///     let output = cmd!("echo 1.0.0").output_checked()?;
+///     assert_starts_with!(String::from_utf8(output.stdout)?, "1.")
+/// }
+///
+/// fn prints_help() -> ExtelResult {
+///     assert_cmd_matches!(cmd!("echo usage"), Expectation::new().stdout_contains("usage"))
+/// }
+///
+/// // Listed by `cargo test` as `cli::prints_version` and `cli::prints_help`.
+/// extel::libtest_bridge!(cli, prints_version, prints_help);
+///
+/// // Listed by `cargo test` as `CliSuite`.
+/// init_test_suite!(CliSuite, prints_version, prints_help);
+/// extel::libtest_bridge!(CliSuite);
+/// ```
+#[macro_export]
+macro_rules! libtest_bridge {
+    ($test_suite:ident) => {
+        #[test]
+        #[allow(non_snake_case)]
+        fn $test_suite() {
+            $crate::libtest::run_suite::<$test_suite>();
+        }
+    };
+
+    ($suite:ident, $($test_name:ident),+ $(,)?) => {
+        mod $suite {
+            $(
+                #[test]
+                fn $test_name() {
+                    $crate::libtest::run_test(
+                        stringify!($suite),
+                        stringify!($test_name),
+                        super::$test_name,
+                    );
+                }
+            )+
+        }
+    };
+}
+
+#[cfg(test)]
 mod tests {
     use std::{error::Error, path::Path};
 
-    use crate::{ExtelResult, OutputDest, RunnableTestSet, TestConfig};
+    use crate::{ExtelResult, OutputDest, RunnableTestSet, SharedBuffer, TestConfig};
 
     /// # TEST
     ///   - Return a constant success!
@@ -370,23 +1484,151 @@ mod tests {
         init_test_suite!(BasicTestSet, always_succeed, always_fail);
 
         // Create output buffer
-        let output_buffer: &mut Vec<u8> = &mut Vec::new();
+        let output_buffer = SharedBuffer::new();
         BasicTestSet::run(
             TestConfig::default()
-                .output(OutputDest::Buffer(output_buffer))
+                .output(OutputDest::Buffer(output_buffer.clone()))
                 .colored(false),
         );
 
-        let output = String::from_utf8_lossy(output_buffer);
+        let output = output_buffer.to_string_lossy();
+        let exe = std::env::current_exe().unwrap();
 
         assert_eq!(
             output,
-            *"[extel::macros::tests::init_test_suite_basic::BasicTestSet]\n\t\
-            Test #1 (always_succeed) ... ok\n\t\
+            format!(
+                "[extel::macros::tests::init_test_suite_basic::BasicTestSet]\n\t\
+                Test #1 (always_succeed) ... ok\n\t\
+                Test #2 (always_fail) ... FAILED\n\t  [x] this test failed?\n\t  \
+                [rerun] EXTEL_EXACT='extel::macros::tests::init_test_suite_basic::BasicTestSet::\
+                always_fail' '{}'\n",
+                exe.display()
+            )
+        );
+    }
+
+    #[test]
+    fn init_test_suite_panic() {
+        fn panics() -> ExtelResult {
+            let v: Vec<usize> = Vec::new();
+            extel_assert!(v[0] == 0)
+        }
+
+        init_test_suite!(PanicTestSet, panics, always_succeed);
+        let results = PanicTestSet::run(TestConfig::default().output(OutputDest::None));
+
+        assert!(matches!(
+            results[0].test_result.cases[0].result,
+            Err(crate::errors::Error::Panicked(_))
+        ));
+        assert!(results[1].test_result.is_ok());
+    }
+
+    #[test]
+    fn init_test_suite_display_name() {
+        init_test_suite!(NamedTestSet as "Always succeeding tests", always_succeed);
+
+        let output_buffer = SharedBuffer::new();
+        NamedTestSet::run(
+            TestConfig::default()
+                .output(OutputDest::Buffer(output_buffer.clone()))
+                .colored(false),
+        );
+
+        assert_eq!(
+            output_buffer.to_string_lossy(),
+            "[Always succeeding tests]\n\tTest #1 (always_succeed) ... ok\n"
+        );
+    }
+
+    /// A module without imports whose names shadow those the generated code uses.
+    mod unhygienic {
+        #[allow(dead_code)]
+        struct Vec;
+        #[allow(dead_code)]
+        struct Box;
+
+        fn always_succeed() -> crate::ExtelResult {
+            Ok(())
+        }
+
+        crate::init_test_suite!(FirstTestSet, always_succeed);
+        crate::init_test_suite!(SecondTestSet as "second", always_succeed);
+    }
+
+    #[test]
+    fn init_test_suite_is_hygienic() {
+        let cfg = || TestConfig::default().output(OutputDest::None);
+        assert_eq!(unhygienic::FirstTestSet::run(cfg()).len(), 1);
+        assert_eq!(unhygienic::SecondTestSet::run(cfg()).len(), 1);
+    }
+
+    #[test]
+    fn init_test_suite_tags() {
+        init_test_suite!(
+            TaggedTestSet as "Tagged",
+            always_succeed => ["smoke"],
+            always_fail => ["slow", "network"]
+        );
+
+        let results = TaggedTestSet::run(TestConfig::default().output(OutputDest::None));
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[1].tags, ["slow", "network"]);
+
+        let results = TaggedTestSet::run(
+            TestConfig::default()
+                .output(OutputDest::None)
+                .tags(["network"]),
+        );
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].test_name, "always_fail");
+    }
+
+    #[test]
+    fn init_test_suite_filter() {
+        init_test_suite!(FilteredTestSet, always_succeed, always_fail);
+
+        let output_buffer = SharedBuffer::new();
+        let results = FilteredTestSet::run(
+            TestConfig::default()
+                .output(OutputDest::Buffer(output_buffer.clone()))
+                .colored(false)
+                .rerun_hint(false)
+                .filter("fail"),
+        );
+
+        assert_eq!(results.len(), 1);
+        assert_eq!(
+            output_buffer.to_string_lossy(),
+            *"[extel::macros::tests::init_test_suite_filter::FilteredTestSet]\n\t\
             Test #2 (always_fail) ... FAILED\n\t  [x] this test failed?\n"
         );
     }
 
+    #[test]
+    fn init_test_suite_sorted_and_shuffled() {
+        init_test_suite!(OrderedTestSet as "ordered", always_succeed, always_fail);
+
+        let results = OrderedTestSet::run(
+            TestConfig::default()
+                .output(OutputDest::None)
+                .sort_by_name(),
+        );
+        assert_eq!(results[0].test_name, "always_fail");
+
+        let output_buffer = SharedBuffer::new();
+        let results = OrderedTestSet::run(
+            TestConfig::default()
+                .output(OutputDest::Buffer(output_buffer.clone()))
+                .colored(false)
+                .shuffle(Some(7)),
+        );
+        assert_eq!(results.len(), 2);
+        assert!(output_buffer
+            .to_string_lossy()
+            .starts_with("[ordered]\n\t[shuffled with seed 7]\n"));
+    }
+
     #[test]
     fn test_cmd() {
         fn __test_cmd() -> ExtelResult {
@@ -410,15 +1652,15 @@ mod tests {
         }
 
         init_test_suite!(__test_cmd_suite, __test_cmd);
-        let mut output_buffer: Vec<u8> = Vec::new();
+        let output_buffer = SharedBuffer::new();
 
         __test_cmd_suite::run(
             TestConfig::default()
-                .output(OutputDest::Buffer(&mut output_buffer))
+                .output(OutputDest::Buffer(output_buffer.clone()))
                 .colored(false),
         );
 
-        let output_result = String::from_utf8_lossy(&output_buffer);
+        let output_result = output_buffer.to_string_lossy();
         assert_eq!(
             output_result,
             "[extel::macros::tests::test_cmd::__test_cmd_suite]\n\tTest #1 (__test_cmd) ... ok\n"
@@ -450,15 +1692,15 @@ mod tests {
         }
 
         init_test_suite!(__test_cmd_suite, __test_cmd);
-        let mut output_buffer: Vec<u8> = Vec::new();
+        let output_buffer = SharedBuffer::new();
 
         __test_cmd_suite::run(
             TestConfig::default()
-                .output(OutputDest::Buffer(&mut output_buffer))
+                .output(OutputDest::Buffer(output_buffer.clone()))
                 .colored(false),
         );
 
-        let output_result = String::from_utf8_lossy(&output_buffer);
+        let output_result = output_buffer.to_string_lossy();
         assert_eq!(
             output_result,
             "[extel::macros::tests::test_cmd_fmt_arg::__test_cmd_suite]\n\tTest #1 (__test_cmd) ... ok\n"
@@ -484,21 +1726,66 @@ mod tests {
         }
 
         init_test_suite!(__test_cmd_suite, __test_cmd);
-        let mut output_buffer: Vec<u8> = Vec::new();
+        let output_buffer = SharedBuffer::new();
 
         __test_cmd_suite::run(
             TestConfig::default()
-                .output(OutputDest::Buffer(&mut output_buffer))
+                .output(OutputDest::Buffer(output_buffer.clone()))
                 .colored(false),
         );
 
-        let output_result = String::from_utf8_lossy(&output_buffer);
+        let output_result = output_buffer.to_string_lossy();
         assert_eq!(
             output_result,
             "[extel::macros::tests::test_extel_assert::__test_cmd_suite]\n\tTest #1 (__test_cmd) ... ok\n"
         );
     }
 
+    #[test]
+    fn test_extel_assert_eq_shows_values() {
+        fn __test_cmd() -> ExtelResult {
+            let output = cmd!("echo -n hello").output()?;
+            extel_assert_eq!(String::from_utf8(output.stdout)?, "goodbye")
+        }
+
+        init_test_suite!(__test_cmd_suite, __test_cmd);
+        let output_buffer = SharedBuffer::new();
+
+        __test_cmd_suite::run(
+            TestConfig::default()
+                .output(OutputDest::Buffer(output_buffer.clone()))
+                .colored(false)
+                .rerun_hint(false),
+        );
+
+        let output_result = output_buffer.to_string_lossy();
+        assert!(output_result.contains("left: \"hello\"\n right: \"goodbye\""));
+    }
+
+    #[test]
+    fn test_approx_assertions_reject_nan() {
+        assert!(assert_approx_eq!(f64::NAN, f64::NAN, 1.0).is_err());
+        assert!(assert_relative_eq!(0.0, f64::NAN, 1.0).is_err());
+        assert!(assert_relative_eq!(0, 0, 0.0).is_ok());
+        assert!(assert_relative_eq!(0.0, 1e-12, 0.5).is_err());
+    }
+
+    #[test]
+    fn test_string_assertions_abbreviate_long_output() {
+        let long_output = "x".repeat(500);
+
+        assert!(assert_contains!(long_output, "xxx").is_ok());
+        assert!(assert_starts_with!(&long_output, String::from("x")).is_ok());
+        assert!(assert_ends_with!(long_output.as_str(), "x").is_ok());
+
+        let message = assert_contains!(long_output, "y").unwrap_err().to_string();
+        assert!(message.contains("... (300 more chars)"));
+        assert!(message.ends_with("needle: \"y\""));
+
+        let message = assert_ends_with!(long_output, "y").unwrap_err().to_string();
+        assert!(message.contains("haystack: (300 more chars) ..."));
+    }
+
     #[test]
     fn test_cmd_path() {
         const EXPECTED: &str = "viva las vegas";
@@ -519,15 +1806,15 @@ mod tests {
         }
 
         init_test_suite!(__test_cmd_suite, __test_cmd);
-        let mut output_buffer: Vec<u8> = Vec::new();
+        let output_buffer = SharedBuffer::new();
 
         __test_cmd_suite::run(
             TestConfig::default()
-                .output(OutputDest::Buffer(&mut output_buffer))
+                .output(OutputDest::Buffer(output_buffer.clone()))
                 .colored(false),
         );
 
-        let output_result = String::from_utf8_lossy(&output_buffer);
+        let output_result = output_buffer.to_string_lossy();
         assert_eq!(
             output_result,
             "[extel::macros::tests::test_cmd_path::__test_cmd_suite]\n\tTest #1 (__test_cmd) ... ok\n"
@@ -551,15 +1838,15 @@ mod tests {
         }
 
         init_test_suite!(__test_cmd_suite, __test_cmd);
-        let mut output_buffer: Vec<u8> = Vec::new();
+        let output_buffer = SharedBuffer::new();
 
         __test_cmd_suite::run(
             TestConfig::default()
-                .output(OutputDest::Buffer(&mut output_buffer))
+                .output(OutputDest::Buffer(output_buffer.clone()))
                 .colored(false),
         );
 
-        let output_result = String::from_utf8_lossy(&output_buffer);
+        let output_result = output_buffer.to_string_lossy();
         assert_eq!(
             output_result,
             "[extel::macros::tests::test_cmd_question_mark_operator::__test_cmd_suite]\n\tTest #1 (__test_cmd) ... ok\n"
@@ -0,0 +1,189 @@
+//! A machine-readable record of a test run, for joining with coverage, flakiness, or dashboard
+//! tooling. See [`TestConfig::manifest`](crate::TestConfig::manifest).
+//!
+//! The manifest is a [JSON Lines](https://jsonlines.org) file with one object per suite run:
+//!
+//! ```json
+//! {"suite":"Cli","tests":[{"name":"help","passed":true,"duration_ms":12.5,"commands":[{"command":"app --help","exit_code":0,"duration_ms":11.9}]}],"run":{"id":"9f86d081884c7d65","started":"2024-05-01T12:30:00Z","finished":"2024-05-01T12:30:01Z","host":"ci-runner-3","os":"linux x86_64","env":{"CI":"true"}}}
+//! ```
+//!
+//! Only commands run through [`CommandExt`](crate::command::CommandExt) are listed. The
+//! `exit_code` of a command killed by a signal or timed out is `null`. The `run` object is the
+//! [run information](crate::run_info) of the suite, with the environment variables selected with
+//! [`TestConfig::report_env`](crate::TestConfig::report_env). A failing test also has a `rerun`
+//! list with the [command](crate::report::rerun_command) rerunning it, followed by the one
+//! rerunning its first failing case, if it is parameterized.
+
+use std::{
+    collections::HashSet,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::Duration,
+};
+
+use crate::{
+    report,
+    run_info::{self, RunInfo},
+    TestResult,
+};
+
+/// The manifests written to by this process, which are appended to rather than truncated.
+static STARTED: Mutex<Option<HashSet<PathBuf>>> = Mutex::new(None);
+
+/// Add the results of a run of `suite_name`, and where and when it ran, to the manifest at `path`.
+///
+/// # Example
+/// ```rust
+/// use extel::{fixture::TempDir, prelude::*, OutputDest};
+///
+/// fn lists_files() -> ExtelResult {
+///     let output = cmd!("ls").output_checked()?;
+///     extel_assert!(output.status.success())
+/// }
+///
+/// let tmp = TempDir::new().unwrap();
+/// let manifest = tmp.path().join("manifest.jsonl");
+///
+/// init_test_suite!(ManifestSuite as "manifest", lists_files);
+/// ManifestSuite::run(TestConfig::default().output(OutputDest::None).manifest(&manifest));
+///
+/// let contents = std::fs::read_to_string(&manifest).unwrap();
+/// assert!(contents.starts_with(r#"{"suite":"manifest","tests":[{"name":"lists_files","passed":true"#));
+/// assert!(contents.contains(r#"{"command":"ls","exit_code":0,"#));
+/// assert!(contents.contains(&format!(r#""run":{{"id":"{}","started":"#, extel::run_info::run_id())));
+/// ```
+pub fn record_suite(
+    path: impl AsRef<Path>,
+    suite_name: &str,
+    results: &[TestResult],
+    run: &RunInfo,
+) -> io::Result<()> {
+    let path = path.as_ref();
+    let mut started = STARTED
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let first_write = started
+        .get_or_insert_with(HashSet::new)
+        .insert(path.to_path_buf());
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(!first_write)
+        .truncate(first_write)
+        .open(path)?;
+
+    writeln!(file, "{}", suite_json(suite_name, results, run))
+}
+
+fn suite_json(suite_name: &str, results: &[TestResult], run: &RunInfo) -> String {
+    let tests: Vec<String> = results
+        .iter()
+        .map(|result| {
+            let commands: Vec<String> = result
+                .commands
+                .iter()
+                .map(|cmd| {
+                    format!(
+                        r#"{{"command":{},"exit_code":{},"duration_ms":{}}}"#,
+                        json_string(&cmd.command),
+                        cmd.exit_code
+                            .map_or_else(|| String::from("null"), |code| code.to_string()),
+                        millis(cmd.duration)
+                    )
+                })
+                .collect();
+
+            let rerun = match result.test_result.is_ok() {
+                true => String::new(),
+                false => {
+                    let rerun: Vec<String> = report::rerun_commands(suite_name, result)
+                        .iter()
+                        .map(|command| json_string(command))
+                        .collect();
+                    format!(r#","rerun":[{}]"#, rerun.join(","))
+                }
+            };
+
+            format!(
+                r#"{{"name":{},"passed":{},"duration_ms":{},"commands":[{}]{}}}"#,
+                json_string(&result.test_name),
+                result.test_result.is_ok(),
+                millis(result.duration),
+                commands.join(","),
+                rerun
+            )
+        })
+        .collect();
+
+    format!(
+        r#"{{"suite":{},"tests":[{}],"run":{}}}"#,
+        json_string(suite_name),
+        tests.join(","),
+        run_json(run)
+    )
+}
+
+fn run_json(run: &RunInfo) -> String {
+    let env: Vec<String> = run
+        .env
+        .iter()
+        .map(|(name, value)| format!("{}:{}", json_string(name), json_string(value)))
+        .collect();
+
+    format!(
+        r#"{{"id":{},"started":{},"finished":{},"host":{},"os":{},"env":{{{}}}}}"#,
+        json_string(&run.run_id),
+        json_string(&run_info::timestamp(run.started)),
+        run.finished.map_or_else(
+            || String::from("null"),
+            |finished| json_string(&run_info::timestamp(finished))
+        ),
+        json_string(&run.hostname),
+        json_string(&run.os),
+        env.join(",")
+    )
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Quote `s` as a JSON string.
+pub(crate) fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_string_escapes() {
+        assert_eq!(json_string("plain"), r#""plain""#);
+        assert_eq!(
+            json_string("say \"hi\"\n\\\u{1}"),
+            r#""say \"hi\"\n\\\u0001""#
+        );
+    }
+}
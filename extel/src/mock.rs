@@ -0,0 +1,398 @@
+//! A lightweight mock HTTP server for testing binaries that talk to HTTP APIs.
+//!
+//! The server is bound to an ephemeral port on the loopback interface and lives only as long as
+//! the [`MockServer`] handle does, so each test gets its own hermetic server. Expectations are
+//! registered with [`MockServer::expect`] and checked with [`MockServer::verify`], which reports
+//! any mismatch as an [`Error::TestFailed`](crate::errors::Error::TestFailed).
+//!
+//! > *This is only available with the `http-mock` feature enabled.*
+
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, MutexGuard,
+    },
+    thread::{self, JoinHandle},
+    time::Duration,
+};
+
+use crate::{err, ExtelResult};
+
+/// The largest request body the server reads. Larger requests are answered with a `413` without
+/// being recorded.
+pub const MAX_BODY_LEN: usize = 16 * 1024 * 1024;
+
+/// A request received by a [`MockServer`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MockRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+#[derive(Debug)]
+struct Endpoint {
+    method: String,
+    path: String,
+    status: u16,
+    body: String,
+    times: Option<usize>,
+    hits: usize,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    endpoints: Vec<Endpoint>,
+    requests: Vec<MockRequest>,
+    unmatched: Vec<MockRequest>,
+}
+
+/// Lock the shared server state, ignoring poisoning since the state is only ever appended to.
+fn lock(state: &Mutex<State>) -> MutexGuard<'_, State> {
+    state
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// Start a new mock HTTP server on an ephemeral loopback port.
+///
+/// # Example
+/// ```rust
+/// use extel::{mock::mock_http, prelude::*};
+/// use std::{io::{Read, Write}, net::TcpStream};
+///
+/// fn api_test() -> ExtelResult {
+///     let server = mock_http();
+///     server.expect("GET", "/health").respond(200, "ok");
+///
+///     let mut stream = TcpStream::connect(server.addr())?;
+///     stream.write_all(b"GET /health HTTP/1.1\r\nHost: localhost\r\n\r\n")?;
+///     let mut response = String::new();
+///     stream.read_to_string(&mut response)?;
+///
+///     server.verify()?;
+///     extel_assert!(response.ends_with("ok"))
+/// }
+///
+/// assert!(api_test().is_ok());
+/// ```
+pub fn mock_http() -> MockServer {
+    MockServer::start().expect("could not start mock HTTP server")
+}
+
+/// A mock HTTP server whose lifetime is tied to this handle. Dropping the handle shuts the server
+/// down.
+#[derive(Debug)]
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<State>>,
+    shutdown: Arc<AtomicBool>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl MockServer {
+    /// Bind a new mock server to an ephemeral loopback port and begin serving requests.
+    pub fn start() -> std::io::Result<Self> {
+        let listener = TcpListener::bind("127.0.0.1:0")?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(Mutex::new(State::default()));
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let handle = {
+            let state = Arc::clone(&state);
+            let shutdown = Arc::clone(&shutdown);
+            thread::spawn(move || {
+                for stream in listener.incoming() {
+                    if shutdown.load(Ordering::SeqCst) {
+                        break;
+                    }
+
+                    if let Ok(stream) = stream {
+                        // A malformed request should never take down the server.
+                        let _ = handle_connection(stream, &state);
+                    }
+                }
+            })
+        };
+
+        Ok(Self {
+            addr,
+            state,
+            shutdown,
+            handle: Some(handle),
+        })
+    }
+
+    /// The socket address the server is listening on.
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// The base URL of the server, e.g. `http://127.0.0.1:43123`.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Register an expected request. By default the endpoint responds with `200` and an empty body
+    /// and is expected to be hit at least once.
+    pub fn expect(&self, method: &str, path: &str) -> MockEndpoint {
+        let mut state = lock(&self.state);
+        state.endpoints.push(Endpoint {
+            method: method.to_uppercase(),
+            path: path.to_string(),
+            status: 200,
+            body: String::new(),
+            times: None,
+            hits: 0,
+        });
+
+        MockEndpoint {
+            id: state.endpoints.len() - 1,
+            state: Arc::clone(&self.state),
+        }
+    }
+
+    /// All requests received so far, in the order they arrived.
+    pub fn requests(&self) -> Vec<MockRequest> {
+        lock(&self.state).requests.clone()
+    }
+
+    /// Verify that every expectation was met and that no unexpected requests were received.
+    pub fn verify(&self) -> ExtelResult {
+        let state = lock(&self.state);
+        let mut problems: Vec<String> = Vec::new();
+
+        for endpoint in &state.endpoints {
+            match endpoint.times {
+                Some(n) if endpoint.hits != n => problems.push(format!(
+                    "expected {} {} to be called {} time(s), got {}",
+                    endpoint.method, endpoint.path, n, endpoint.hits
+                )),
+                None if endpoint.hits == 0 => problems.push(format!(
+                    "expected {} {} to be called, but it never was",
+                    endpoint.method, endpoint.path
+                )),
+                _ => {}
+            }
+        }
+
+        for request in &state.unmatched {
+            problems.push(format!(
+                "unexpected request: {} {}",
+                request.method, request.path
+            ));
+        }
+
+        match problems.is_empty() {
+            true => Ok(()),
+            false => Err(err!("{}", problems.join("; "))),
+        }
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+
+        // Wake the accept loop so it can observe the shutdown flag.
+        let _ = TcpStream::connect(self.addr);
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+/// A handle to an expectation registered on a [`MockServer`].
+#[derive(Debug)]
+pub struct MockEndpoint {
+    id: usize,
+    state: Arc<Mutex<State>>,
+}
+
+impl MockEndpoint {
+    /// Change the response status and body returned by this endpoint.
+    pub fn respond(self, status: u16, body: impl Into<String>) -> Self {
+        {
+            let mut state = lock(&self.state);
+            let endpoint = &mut state.endpoints[self.id];
+            endpoint.status = status;
+            endpoint.body = body.into();
+        }
+        self
+    }
+
+    /// Require this endpoint to be hit exactly `n` times.
+    pub fn times(self, n: usize) -> Self {
+        lock(&self.state).endpoints[self.id].times = Some(n);
+        self
+    }
+
+    /// The number of times this endpoint has been hit so far.
+    pub fn hits(&self) -> usize {
+        lock(&self.state).endpoints[self.id].hits
+    }
+}
+
+/// Read a single request off of the stream, record it, and write the matching response.
+fn handle_connection(stream: TcpStream, state: &Mutex<State>) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(5)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line)? == 0 {
+        return Ok(());
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or_default().to_uppercase();
+    let path = parts.next().unwrap_or_default().to_string();
+
+    let mut headers: Vec<(String, String)> = Vec::new();
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+
+        if let Some((key, value)) = line.split_once(':') {
+            headers.push((key.trim().to_string(), value.trim().to_string()));
+        }
+    }
+
+    let content_length = headers
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    if content_length > MAX_BODY_LEN {
+        return respond(reader.into_inner(), 413, "");
+    }
+    let mut body = vec![0; content_length];
+    reader.read_exact(&mut body)?;
+
+    let request = MockRequest {
+        method,
+        path,
+        headers,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    };
+
+    let (status, response_body) = {
+        let mut state = lock(state);
+        let matched = state
+            .endpoints
+            .iter_mut()
+            .find(|e| e.method == request.method && e.path == request.path)
+            .map(|endpoint| {
+                endpoint.hits += 1;
+                (endpoint.status, endpoint.body.clone())
+            });
+
+        state.requests.push(request.clone());
+        match matched {
+            Some(response) => response,
+            None => {
+                state.unmatched.push(request);
+                (404, String::new())
+            }
+        }
+    };
+
+    respond(reader.into_inner(), status, &response_body)
+}
+
+/// Write a response with `status` and `body` to `stream`.
+fn respond(mut stream: TcpStream, status: u16, body: &str) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status,
+        reason_phrase(status),
+        body.len(),
+        body
+    )?;
+    stream.flush()
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        413 => "Content Too Large",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn send(server: &MockServer, request: &str) -> String {
+        let mut stream = TcpStream::connect(server.addr()).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn mock_http_matches_expectations() {
+        let server = mock_http();
+        let endpoint = server
+            .expect("POST", "/items")
+            .respond(201, "created")
+            .times(1);
+
+        let response = send(
+            &server,
+            "POST /items HTTP/1.1\r\nContent-Length: 5\r\n\r\nhello",
+        );
+
+        assert!(response.starts_with("HTTP/1.1 201 Created"));
+        assert!(response.ends_with("created"));
+        assert_eq!(endpoint.hits(), 1);
+        assert_eq!(server.requests()[0].body, "hello");
+        assert!(server.verify().is_ok());
+    }
+
+    #[test]
+    fn mock_http_reports_unmet_and_unexpected() {
+        let server = mock_http();
+        server.expect("GET", "/never");
+
+        let response = send(&server, "GET /other HTTP/1.1\r\n\r\n");
+        assert!(response.starts_with("HTTP/1.1 404"));
+
+        let err = server.verify().unwrap_err().to_string();
+        assert_eq!(
+            err,
+            "expected GET /never to be called, but it never was; unexpected request: GET /other"
+        );
+    }
+
+    #[test]
+    fn mock_http_rejects_oversized_bodies() {
+        let server = mock_http();
+        server.expect("POST", "/upload");
+
+        let response = send(
+            &server,
+            &format!(
+                "POST /upload HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+                usize::MAX
+            ),
+        );
+        assert!(response.starts_with("HTTP/1.1 413 Content Too Large"));
+        assert!(server.requests().is_empty());
+    }
+}
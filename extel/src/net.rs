@@ -0,0 +1,125 @@
+//! Picking free local ports for servers started by tests, so that tests running in parallel do not
+//! collide on hard-coded ports.
+//!
+//! Ports are chosen by the operating system, which hands out recently used ephemeral ports last,
+//! and every port handed out is remembered for the rest of the process so that it is never handed
+//! out twice, even after the server using it has stopped.
+//!
+//! ```rust
+//! use extel::{net::free_port, prelude::*};
+//! use std::net::TcpListener;
+//!
+//! fn serves_on_free_port() -> ExtelResult {
+//!     let port = free_port()?;
+//!     let _server = TcpListener::bind(("127.0.0.1", port))?;
+//!     pass!()
+//! }
+//!
+//! assert!(serves_on_free_port().is_ok());
+//! ```
+
+use std::{collections::BTreeSet, net::TcpListener, ops::Range, sync::Mutex};
+
+use crate::{err, errors::Error};
+
+/// How many times the operating system is asked for a port before giving up.
+const MAX_ATTEMPTS: usize = 100;
+
+/// Every port handed out in this process.
+static RESERVED: Mutex<BTreeSet<u16>> = Mutex::new(BTreeSet::new());
+
+/// A local TCP port that nothing is listening on and that has not been handed out before in this
+/// process.
+///
+/// # Example
+/// ```rust
+/// use extel::net::free_port;
+///
+/// let (a, b) = (free_port().unwrap(), free_port().unwrap());
+/// assert_ne!(a, b);
+/// ```
+pub fn free_port() -> Result<u16, Error> {
+    free_port_range(1).map(|ports| ports.start)
+}
+
+/// `n` consecutive local TCP ports that nothing is listening on and that have not been handed out
+/// before in this process, for servers that need several ports next to each other.
+///
+/// # Example
+/// ```rust
+/// use extel::net::free_port_range;
+///
+/// let ports = free_port_range(3).unwrap();
+/// assert_eq!(ports.len(), 3);
+/// ```
+pub fn free_port_range(n: u16) -> Result<Range<u16>, Error> {
+    if n == 0 {
+        return Err(err!("cannot reserve an empty range of ports"));
+    }
+
+    let mut reserved = RESERVED
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    for _ in 0..MAX_ATTEMPTS {
+        let start = TcpListener::bind("127.0.0.1:0")?.local_addr()?.port();
+        let Some(end) = start.checked_add(n) else {
+            continue;
+        };
+
+        // Hold every port of the range until all of them are known to be free.
+        let ports = start..end;
+        let listeners: Option<Vec<TcpListener>> = ports
+            .clone()
+            .map(|port| match reserved.contains(&port) {
+                true => None,
+                false => TcpListener::bind(("127.0.0.1", port)).ok(),
+            })
+            .collect();
+
+        if listeners.is_some() {
+            reserved.extend(ports.clone());
+            return Ok(ports);
+        }
+    }
+
+    Err(err!(
+        "could not find {} free consecutive port(s) after {} attempts",
+        n,
+        MAX_ATTEMPTS
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::thread;
+
+    #[test]
+    fn ports_are_never_handed_out_twice() {
+        let handles: Vec<_> = (0..8)
+            .map(|_| thread::spawn(|| (0..10).map(|_| free_port().unwrap()).collect::<Vec<_>>()))
+            .collect();
+
+        let mut ports: Vec<u16> = handles
+            .into_iter()
+            .flat_map(|handle| handle.join().unwrap())
+            .collect();
+        let count = ports.len();
+        ports.sort();
+        ports.dedup();
+        assert_eq!(ports.len(), count);
+    }
+
+    #[test]
+    fn ranges_are_free_and_consecutive() {
+        let ports = free_port_range(4).unwrap();
+        let listeners: Vec<TcpListener> = ports
+            .clone()
+            .map(|port| TcpListener::bind(("127.0.0.1", port)).unwrap())
+            .collect();
+        assert_eq!(listeners.len(), 4);
+
+        assert!(free_port_range(0).is_err());
+    }
+}
@@ -0,0 +1,370 @@
+//! Normalizing captured output before it is compared, so that golden files and expectations do
+//! not depend on the terminal, the platform, or when and where a test ran.
+//!
+//! A [`Normalizer`] is a pipeline of steps applied in order, such as stripping ANSI escape codes,
+//! turning `\r\n` line endings into `\n`, and masking timestamps, UUIDs, and paths with fixed
+//! placeholders. A normalizer set with [`TestConfig::normalize`](crate::TestConfig::normalize) is
+//! applied to the actual output by [`assert_matches_file`](crate::assert_matches_file) and
+//! [`assert_cmd_matches`](crate::assert_cmd_matches) before comparing it, and to the golden files
+//! they write in [update mode](crate::golden::update_mode). Output checked outside of a test run
+//! is left as it is, but can be normalized with [`Normalizer::apply`].
+//!
+//! ```rust
+//! use extel::{fixture::TempDir, normalize::Normalizer, prelude::*, OutputDest};
+//!
+//! fn prints_report() -> ExtelResult {
+//!     let tmp = TempDir::new()?;
+//!     let golden = tmp.path().join("report.txt");
+//!     std::fs::write(&golden, "saved [UUID] at [TIMESTAMP]\n")?;
+//!
+//!     let output = cmd!("printf" => ["\\033[1msaved\\033[0m 0c9a3d1e-8d4f-4b5a-9c2e-5f6a7b8c9d0e at 2024-05-01T12:30:00Z\\r\\n"])
+//!         .output()?;
+//!     assert_matches_file!(String::from_utf8(output.stdout)?, &golden)
+//! }
+//!
+//! init_test_suite!(ReportSuite, prints_report);
+//! let results = ReportSuite::run(
+//!     TestConfig::default()
+//!         .output(OutputDest::None)
+//!         .normalize(
+//!             Normalizer::new()
+//!                 .strip_ansi()
+//!                 .line_endings()
+//!                 .mask_timestamps()
+//!                 .mask_uuids(),
+//!         ),
+//! );
+//! assert!(results[0].test_result.is_ok());
+//! ```
+
+use std::{borrow::Cow, fmt, ops::Range, path::Path, sync::Arc};
+
+#[cfg(feature = "regex")]
+use crate::errors::Error;
+use crate::{clock, context};
+
+/// The placeholder replacing timestamps masked by [`Normalizer::mask_timestamps`].
+pub const TIMESTAMP_MASK: &str = "[TIMESTAMP]";
+
+/// The placeholder replacing UUIDs masked by [`Normalizer::mask_uuids`].
+pub const UUID_MASK: &str = "[UUID]";
+
+/// A step of a [`Normalizer`].
+pub type NormalizeFn = Arc<dyn Fn(&str) -> String + Send + Sync>;
+
+/// A pipeline of steps rewriting output before it is compared. Steps run in the order they were
+/// added, each on the output of the previous one.
+///
+/// # Example
+/// ```rust
+/// use extel::normalize::Normalizer;
+///
+/// let normalizer = Normalizer::new()
+///     .strip_ansi()
+///     .line_endings()
+///     .mask_path("/home/ci/work", "[WORK]")
+///     .replace("took 12ms", "took [ELAPSED]");
+///
+/// assert_eq!(
+///     normalizer.apply("\x1b[32mok\x1b[0m /home/ci/work/a.txt took 12ms\r\n"),
+///     "ok [WORK]/a.txt took [ELAPSED]\n"
+/// );
+/// ```
+#[derive(Clone, Default)]
+pub struct Normalizer {
+    steps: Vec<(String, NormalizeFn)>,
+}
+
+impl Normalizer {
+    /// A normalizer that leaves output as it is.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a custom step called `name`, which is shown when the normalizer is debug-printed.
+    pub fn with(
+        mut self,
+        name: impl Into<String>,
+        step: impl Fn(&str) -> String + Send + Sync + 'static,
+    ) -> Self {
+        self.steps.push((name.into(), Arc::new(step)));
+        self
+    }
+
+    /// Remove ANSI escape sequences, such as colors and cursor movements.
+    pub fn strip_ansi(self) -> Self {
+        self.with("strip_ansi", strip_ansi)
+    }
+
+    /// Turn `\r\n` line endings into `\n`. A lone `\r`, as used to redraw progress bars, is kept.
+    pub fn line_endings(self) -> Self {
+        self.with("line_endings", |text| text.replace("\r\n", "\n"))
+    }
+
+    /// Replace every RFC 3339 timestamp, such as `2024-05-01T12:30:00Z` or
+    /// `2024-05-01 12:30:00.25+02:00`, with [`TIMESTAMP_MASK`]. See
+    /// [`clock::parse_timestamp`].
+    pub fn mask_timestamps(self) -> Self {
+        self.with("mask_timestamps", |text| {
+            mask_ranges(
+                text,
+                clock::find_timestamps(text)
+                    .into_iter()
+                    .map(|(range, _)| range),
+                TIMESTAMP_MASK,
+            )
+        })
+    }
+
+    /// Replace every UUID, such as `0c9a3d1e-8d4f-4b5a-9c2e-5f6a7b8c9d0e`, with [`UUID_MASK`].
+    /// Hexadecimal digits of either case are accepted.
+    pub fn mask_uuids(self) -> Self {
+        self.with("mask_uuids", |text| {
+            mask_ranges(text, find_uuids(text), UUID_MASK)
+        })
+    }
+
+    /// Replace every occurrence of `path` with `placeholder`, such as a temporary directory with
+    /// `[TMP]`. The path is also replaced when written with `/` instead of `\` as its separator, so
+    /// the same golden file works on Windows.
+    pub fn mask_path(self, path: impl AsRef<Path>, placeholder: impl Into<String>) -> Self {
+        let path = path.as_ref().display().to_string();
+        let placeholder = placeholder.into();
+        let name = format!("mask_path({})", path);
+        self.with(name, move |text| {
+            let text = text.replace(&path, &placeholder);
+            match path.contains('\\') {
+                true => text.replace(&path.replace('\\', "/"), &placeholder),
+                false => text,
+            }
+        })
+    }
+
+    /// Replace every occurrence of `from` with `to`.
+    pub fn replace(self, from: impl Into<String>, to: impl Into<String>) -> Self {
+        let (from, to) = (from.into(), to.into());
+        let name = format!("replace({:?})", from);
+        self.with(name, move |text| text.replace(&from, &to))
+    }
+
+    /// Replace every match of the regular expression `pattern` with `replacement`, which may
+    /// refer to capture groups as `$1` or `$name`.
+    ///
+    /// > *This is only available with the `regex` feature enabled.*
+    ///
+    /// # Errors
+    /// Fails with an [`Error::Config`] if `pattern` is not a valid regular expression.
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::normalize::Normalizer;
+    ///
+    /// let normalizer = Normalizer::new().replace_regex(r"took \d+ms", "took [ELAPSED]").unwrap();
+    /// assert_eq!(normalizer.apply("built in 2 steps, took 315ms"), "built in 2 steps, took [ELAPSED]");
+    ///
+    /// assert!(Normalizer::new().replace_regex("(unclosed", "").is_err());
+    /// ```
+    #[cfg(feature = "regex")]
+    pub fn replace_regex(
+        self,
+        pattern: &str,
+        replacement: impl Into<String>,
+    ) -> Result<Self, Error> {
+        let regex = regex::Regex::new(pattern)
+            .map_err(|e| Error::Config(format!("invalid pattern '{}': {}", pattern, e)))?;
+        let replacement = replacement.into();
+        let name = format!("replace_regex({:?})", pattern);
+        Ok(self.with(name, move |text| {
+            regex.replace_all(text, replacement.as_str()).into_owned()
+        }))
+    }
+
+    /// Whether or not the normalizer has no steps.
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+
+    /// Run every step on `text`.
+    pub fn apply(&self, text: &str) -> String {
+        self.steps
+            .iter()
+            .fold(text.to_string(), |text, (_, step)| step(&text))
+    }
+}
+
+impl fmt::Debug for Normalizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list()
+            .entries(self.steps.iter().map(|(name, _)| name))
+            .finish()
+    }
+}
+
+/// Normalize `text` with the normalizer of the current test, if it has one.
+pub(crate) fn in_scope(text: &str) -> Cow<'_, str> {
+    let normalizer = context::with_scope(|scope| scope.and_then(|scope| scope.normalizer.clone()));
+    match normalizer {
+        Some(normalizer) => Cow::Owned(normalizer.apply(text)),
+        None => Cow::Borrowed(text),
+    }
+}
+
+/// Normalize `bytes` with the normalizer of the current test, if it has one. Bytes that are not
+/// valid UTF-8 are left as they are.
+pub(crate) fn bytes_in_scope(bytes: &[u8]) -> Cow<'_, [u8]> {
+    match std::str::from_utf8(bytes).map(in_scope) {
+        Ok(Cow::Owned(text)) => Cow::Owned(text.into_bytes()),
+        _ => Cow::Borrowed(bytes),
+    }
+}
+
+/// `text` without ANSI escape sequences: CSI sequences like `ESC [ 1 m`, OSC sequences like
+/// terminal titles and hyperlinks, character set designations, and two-character escapes.
+fn strip_ansi(text: &str) -> String {
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            stripped.push(c);
+            continue;
+        }
+
+        match chars.next() {
+            // Parameters and intermediates, up to a final byte in `@`..=`~`.
+            Some('[') => {
+                for c in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        break;
+                    }
+                }
+            }
+            // Anything up to BEL or ST (`ESC \`).
+            Some(']') => {
+                while let Some(c) = chars.next() {
+                    if c == '\x07' || (c == '\x1b' && chars.next_if_eq(&'\\').is_some()) {
+                        break;
+                    }
+                }
+            }
+            // Character set designations, like `ESC ( B`.
+            Some('(' | ')') => {
+                chars.next();
+            }
+            _ => {}
+        }
+    }
+    stripped
+}
+
+/// Where every UUID in `text` is, in order.
+fn find_uuids(text: &str) -> Vec<Range<usize>> {
+    const GROUPS: [usize; 5] = [8, 4, 4, 4, 12];
+    const LEN: usize = 36;
+
+    let bytes = text.as_bytes();
+    let is_uuid = |candidate: &[u8]| {
+        let mut at = 0;
+        GROUPS.iter().enumerate().all(|(i, &len)| {
+            let group = candidate[at..at + len].iter().all(u8::is_ascii_hexdigit);
+            at += len;
+            let dash = i == GROUPS.len() - 1 || candidate[at] == b'-';
+            at += 1;
+            group && dash
+        })
+    };
+    let boundary = |at: Option<&u8>| at.is_none_or(|b| !b.is_ascii_alphanumeric());
+
+    let mut found = Vec::new();
+    let mut i = 0;
+    while i + LEN <= bytes.len() {
+        match is_uuid(&bytes[i..i + LEN])
+            && boundary(i.checked_sub(1).and_then(|before| bytes.get(before)))
+            && boundary(bytes.get(i + LEN))
+        {
+            true => {
+                found.push(i..i + LEN);
+                i += LEN;
+            }
+            false => i += 1,
+        }
+    }
+    found
+}
+
+/// `text` with each of the sorted, non-overlapping `ranges` replaced by `mask`.
+fn mask_ranges(text: &str, ranges: impl IntoIterator<Item = Range<usize>>, mask: &str) -> String {
+    let mut masked = String::with_capacity(text.len());
+    let mut end = 0;
+    for range in ranges {
+        masked.push_str(&text[end..range.start]);
+        masked.push_str(mask);
+        end = range.end;
+    }
+    masked.push_str(&text[end..]);
+    masked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_sequences_are_stripped() {
+        assert_eq!(
+            strip_ansi("\x1b[1;31mred\x1b[0m \x1b]8;;https://example.com\x1b\\link\x1b]8;;\x07 \x1b(Bdone\x1b[2K"),
+            "red link done"
+        );
+    }
+
+    #[test]
+    fn only_whole_uuids_are_masked() {
+        let normalizer = Normalizer::new().mask_uuids().mask_timestamps();
+        assert_eq!(
+            normalizer.apply(
+                "id=0C9A3D1E-8D4F-4B5A-9C2E-5F6A7B8C9D0E, \
+                 x0c9a3d1e-8d4f-4b5a-9c2e-5f6a7b8c9d0e, 0c9a3d1e-8d4f-4b5a-9c2e-5f6a7b8c9d0, \
+                 at 2024-05-01 12:30:00"
+            ),
+            "id=[UUID], x0c9a3d1e-8d4f-4b5a-9c2e-5f6a7b8c9d0e, 0c9a3d1e-8d4f-4b5a-9c2e-5f6a7b8c9d0, \
+             at [TIMESTAMP]"
+        );
+        assert_eq!(
+            format!("{:?}", normalizer),
+            "[\"mask_uuids\", \"mask_timestamps\"]"
+        );
+    }
+
+    fn prints_status() -> crate::ExtelResult {
+        crate::assert_cmd_matches!(
+            crate::cmd!("printf" => ["\\033[32mok\\033[0m\\r\\n"]),
+            crate::expect::Expectation::new().stdout("ok\n")
+        )
+    }
+
+    #[test]
+    fn expectations_compare_normalized_output() {
+        let run = |normalizer: Normalizer| {
+            crate::SuiteBuilder::new("status")
+                .add_test("prints_status", prints_status)
+                .run(
+                    crate::TestConfig::default()
+                        .output(crate::OutputDest::None)
+                        .normalize(normalizer),
+                )
+        };
+
+        assert!(!run(Normalizer::new())[0].test_result.is_ok());
+        assert!(run(Normalizer::new().strip_ansi().line_endings())[0]
+            .test_result
+            .is_ok());
+    }
+
+    #[test]
+    fn windows_paths_are_masked_with_either_separator() {
+        let normalizer = Normalizer::new().mask_path(r"C:\Users\ci\tmp", "[TMP]");
+        assert_eq!(
+            normalizer.apply(r"wrote C:\Users\ci\tmp\out.txt and C:/Users/ci/tmp/log.txt"),
+            r"wrote [TMP]\out.txt and [TMP]/log.txt"
+        );
+    }
+}
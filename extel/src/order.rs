@@ -0,0 +1,141 @@
+//! The order in which the tests of a suite are run.
+
+use std::{
+    cmp::Reverse,
+    collections::BTreeMap,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::history::PreviousRun;
+
+/// The order in which the selected tests of a suite are run. Test numbers in the output always
+/// refer to a test's position in the suite declaration, regardless of the order it ran in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TestOrder {
+    /// Run tests in the order they were declared.
+    #[default]
+    Declared,
+    /// Run tests sorted by name.
+    Alphabetical,
+    /// Run tests in a pseudo-random order derived from the seed. The same seed always produces the
+    /// same order for the same suite.
+    Shuffled(u64),
+    /// Run the tests that failed the last time they ran first, then tests that have never run,
+    /// then the rest, each in the order they were declared. Uses the
+    /// [history file](crate::TestConfig::history), and runs tests in the order they were declared
+    /// without one.
+    FailedFirst,
+    /// Run tests from the slowest to the fastest according to the last time they ran, followed by
+    /// tests that have never run in the order they were declared. Uses the
+    /// [history file](crate::TestConfig::history), and runs tests in the order they were declared
+    /// without one.
+    SlowestFirst,
+}
+
+impl TestOrder {
+    /// Reorder `items` in place, using `name` to get the test name of an item. Orders that depend
+    /// on previous runs keep the declared order; use [`apply_with_history`](Self::apply_with_history)
+    /// for those.
+    pub fn apply<T>(&self, items: &mut [T], name: impl Fn(&T) -> &str) {
+        self.apply_with_history(items, name, &BTreeMap::new());
+    }
+
+    /// Reorder `items` in place, using `name` to get the test name of an item and `previous` to
+    /// look up the last run of a test by name, as read by
+    /// [`history::previous_runs`](crate::history::previous_runs).
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::{history::PreviousRun, TestOrder};
+    /// use std::{collections::BTreeMap, time::Duration};
+    ///
+    /// let previous = BTreeMap::from([
+    ///     (String::from("quick"), PreviousRun { passed: true, duration: Duration::from_millis(5) }),
+    ///     (String::from("broken"), PreviousRun { passed: false, duration: Duration::from_millis(20) }),
+    ///     (String::from("slow"), PreviousRun { passed: true, duration: Duration::from_secs(3) }),
+    /// ]);
+    ///
+    /// let mut names = vec!["quick", "new", "broken", "slow"];
+    /// TestOrder::FailedFirst.apply_with_history(&mut names, |s| s, &previous);
+    /// assert_eq!(names, ["broken", "new", "quick", "slow"]);
+    ///
+    /// TestOrder::SlowestFirst.apply_with_history(&mut names, |s| s, &previous);
+    /// assert_eq!(names, ["slow", "broken", "quick", "new"]);
+    /// ```
+    pub fn apply_with_history<T>(
+        &self,
+        items: &mut [T],
+        name: impl Fn(&T) -> &str,
+        previous: &BTreeMap<String, PreviousRun>,
+    ) {
+        match *self {
+            TestOrder::Declared => {}
+            TestOrder::Alphabetical => items.sort_by(|a, b| name(a).cmp(name(b))),
+            TestOrder::Shuffled(seed) => {
+                // Fisher-Yates shuffle.
+                let mut rng = SplitMix64(seed);
+                for i in (1..items.len()).rev() {
+                    let j = (rng.next() % (i as u64 + 1)) as usize;
+                    items.swap(i, j);
+                }
+            }
+            // Both sorts are stable, so ties keep their declared order.
+            TestOrder::FailedFirst => items.sort_by_key(|item| match previous.get(name(item)) {
+                Some(run) if !run.passed => 0,
+                None => 1,
+                Some(_) => 2,
+            }),
+            TestOrder::SlowestFirst => {
+                items.sort_by_key(|item| Reverse(previous.get(name(item)).map(|run| run.duration)))
+            }
+        }
+    }
+}
+
+/// Pick a seed for [`TestOrder::Shuffled`] when the user did not provide one.
+pub fn random_seed() -> u64 {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default();
+    SplitMix64(nanos ^ u64::from(std::process::id())).next()
+}
+
+/// A small, fast PRNG that is plenty for shuffling tests.
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shuffle_is_deterministic_for_a_seed() {
+        let names: Vec<String> = (0..20).map(|i| format!("test_{:02}", i)).collect();
+
+        let (mut a, mut b) = (names.clone(), names.clone());
+        TestOrder::Shuffled(42).apply(&mut a, |s| s.as_str());
+        TestOrder::Shuffled(42).apply(&mut b, |s| s.as_str());
+        assert_eq!(a, b);
+        assert_ne!(a, names);
+
+        a.sort();
+        assert_eq!(a, names);
+    }
+
+    #[test]
+    fn alphabetical_sorts_by_name() {
+        let mut names = vec!["c", "a", "b"];
+        TestOrder::Alphabetical.apply(&mut names, |s| s);
+        assert_eq!(names, vec!["a", "b", "c"]);
+    }
+}
@@ -0,0 +1,186 @@
+//! The outcome of running a test, as one or more case results.
+
+use std::time::Duration;
+
+use crate::{errors::Error, ExtelResult};
+
+/// The result of a single case of a test. A standard test has exactly one case, and a
+/// parameterized test has one case per parameter.
+#[derive(Debug)]
+pub struct CaseResult {
+    /// A name for the case, if the test gave it one.
+    pub name: Option<String>,
+    /// Whether the case passed.
+    pub result: ExtelResult,
+    /// How long the case took to run, if it was timed on its own.
+    pub duration: Option<Duration>,
+    /// The output the case wrote, if it was captured on its own.
+    ///
+    /// > *This is only available with the `capture` feature enabled.*
+    #[cfg(all(feature = "capture", unix))]
+    pub captured: Option<crate::capture::CapturedOutput>,
+}
+
+impl CaseResult {
+    /// An unnamed case with the given result.
+    pub fn new(result: ExtelResult) -> Self {
+        Self {
+            name: None,
+            result,
+            duration: None,
+            #[cfg(all(feature = "capture", unix))]
+            captured: None,
+        }
+    }
+
+    /// A case called `name` with the given result.
+    pub fn named(name: impl Into<String>, result: ExtelResult) -> Self {
+        Self {
+            name: Some(name.into()),
+            ..Self::new(result)
+        }
+    }
+
+    /// Change how long the case took to run.
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = Some(duration);
+        self
+    }
+
+    /// Whether or not the case passed or was [skipped](Error::Skipped), that is, did not fail.
+    pub fn is_ok(&self) -> bool {
+        matches!(self.result, Ok(()) | Err(Error::Skipped(_)))
+    }
+
+    /// Whether or not the case was [skipped](Error::Skipped).
+    pub fn is_skipped(&self) -> bool {
+        matches!(self.result, Err(Error::Skipped(_)))
+    }
+}
+
+impl From<ExtelResult> for CaseResult {
+    fn from(result: ExtelResult) -> Self {
+        Self::new(result)
+    }
+}
+
+/// The cases of a parameterized test that ran but were not kept on its [`TestOutcome`], because
+/// the run only kept a sample of the failures with
+/// [`TestConfig::summarize_cases`](crate::TestConfig::summarize_cases).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct OmittedCases {
+    /// The number of omitted cases that passed.
+    pub passed: usize,
+    /// The number of omitted cases that failed.
+    pub failed: usize,
+    /// The number of omitted cases that were skipped.
+    pub skipped: usize,
+}
+
+impl OmittedCases {
+    /// The number of omitted cases.
+    pub fn total(&self) -> usize {
+        self.passed + self.failed + self.skipped
+    }
+}
+
+/// The outcome of running a test: its case results, and whether it was parameterized. Any value
+/// convertible into a `TestOutcome` can be returned by a test function, which includes
+/// [`ExtelResult`] for standard tests and `Vec<ExtelResult>` for parameterized tests.
+///
+/// # Example
+/// ```rust
+/// use extel::{outcome::{CaseResult, TestOutcome}, prelude::*, OutputDest};
+///
+/// fn named_cases() -> TestOutcome {
+///     TestOutcome::parameterized(vec![
+///         CaseResult::named("empty", pass!()),
+///         CaseResult::named("unicode", fail!("mangled")),
+///     ])
+/// }
+///
+/// init_test_suite!(OutcomeSuite, named_cases);
+/// let results = OutcomeSuite::run(TestConfig::default().output(OutputDest::None));
+///
+/// let outcome = &results[0].test_result;
+/// assert_eq!((outcome.passed(), outcome.failed()), (1, 1));
+/// assert_eq!(outcome.cases[1].name.as_deref(), Some("unicode"));
+/// ```
+#[derive(Debug)]
+pub struct TestOutcome {
+    /// The result of every case, in the order they ran.
+    pub cases: Vec<CaseResult>,
+    /// Whether the cases came from a parameterized test, which reporters number individually.
+    pub parameterized: bool,
+    /// The cases that ran but are not in [`cases`](Self::cases), which are still counted by the
+    /// methods of the outcome.
+    pub omitted: OmittedCases,
+}
+
+impl TestOutcome {
+    /// The outcome of a standard test.
+    pub fn single(result: ExtelResult) -> Self {
+        Self {
+            cases: vec![CaseResult::new(result)],
+            parameterized: false,
+            omitted: OmittedCases::default(),
+        }
+    }
+
+    /// The outcome of a parameterized test.
+    pub fn parameterized(cases: impl IntoIterator<Item = impl Into<CaseResult>>) -> Self {
+        Self {
+            cases: cases.into_iter().map(Into::into).collect(),
+            parameterized: true,
+            omitted: OmittedCases::default(),
+        }
+    }
+
+    /// Whether or not every case passed or was skipped.
+    pub fn is_ok(&self) -> bool {
+        self.omitted.failed == 0 && self.cases.iter().all(CaseResult::is_ok)
+    }
+
+    /// Whether or not the test was skipped, which is when every one of its cases was skipped.
+    pub fn is_skipped(&self) -> bool {
+        self.case_count() > 0 && self.skipped() == self.case_count()
+    }
+
+    /// The number of cases that ran, including [omitted](Self::omitted) ones.
+    pub fn case_count(&self) -> usize {
+        self.cases.len() + self.omitted.total()
+    }
+
+    /// The number of cases that passed.
+    pub fn passed(&self) -> usize {
+        self.case_count() - self.failed() - self.skipped()
+    }
+
+    /// The number of cases that failed.
+    pub fn failed(&self) -> usize {
+        self.omitted.failed + self.cases.iter().filter(|case| !case.is_ok()).count()
+    }
+
+    /// The number of cases that were skipped.
+    pub fn skipped(&self) -> usize {
+        self.omitted.skipped + self.cases.iter().filter(|case| case.is_skipped()).count()
+    }
+}
+
+impl From<ExtelResult> for TestOutcome {
+    fn from(result: ExtelResult) -> Self {
+        Self::single(result)
+    }
+}
+
+impl From<Vec<ExtelResult>> for TestOutcome {
+    fn from(results: Vec<ExtelResult>) -> Self {
+        Self::parameterized(results)
+    }
+}
+
+impl From<Vec<CaseResult>> for TestOutcome {
+    fn from(cases: Vec<CaseResult>) -> Self {
+        Self::parameterized(cases)
+    }
+}
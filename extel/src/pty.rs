@@ -0,0 +1,187 @@
+//! Running commands attached to a pseudo-terminal, so that programs that behave differently when
+//! writing to a terminal, such as by coloring their output, prompting, or paging, can be tested
+//! the way a user would see them.
+//!
+//! > *This is only available with the `pty` feature enabled, on Unix platforms.*
+
+use std::{
+    fs::File,
+    io::{self, Write},
+    os::{
+        fd::{FromRawFd, OwnedFd},
+        unix::process::CommandExt as _,
+    },
+    process::{Command, Output, Stdio},
+    ptr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Instant,
+};
+
+use crate::{command, errors::Error};
+
+/// A [`Command`] that will run attached to a new pseudo-terminal. Created with
+/// [`CommandExt::with_pty`](crate::command::CommandExt::with_pty).
+///
+/// The command's stdin, stdout, and stderr are all connected to the terminal, so everything it
+/// writes to either stream is collected as its stdout, the way it would appear on screen. Running
+/// the command replaces the stdio configured on it.
+///
+/// # Example
+/// ```rust
+/// use extel::{command::CommandExt, prelude::*};
+///
+/// fn detects_terminal() -> ExtelResult {
+///     let output = cmd!("sh" => ["-c", "[ -t 1 ] && echo tty || echo pipe"])
+///         .with_pty()
+///         .output()?;
+///
+///     extel_assert_eq!(String::from_utf8(output.stdout)?, "tty\r\n")
+/// }
+///
+/// assert!(detects_terminal().is_ok());
+/// ```
+///
+/// > *This is only available with the `pty` feature enabled, on Unix platforms.*
+#[derive(Debug)]
+pub struct PtyCommand<'a> {
+    command: &'a mut Command,
+    rows: u16,
+    cols: u16,
+    input: Vec<u8>,
+}
+
+impl<'a> PtyCommand<'a> {
+    pub(crate) fn new(command: &'a mut Command) -> Self {
+        Self {
+            command,
+            rows: 24,
+            cols: 80,
+            input: Vec::new(),
+        }
+    }
+
+    /// Change the size of the terminal, which is 24 rows by 80 columns by default.
+    pub fn size(mut self, rows: u16, cols: u16) -> Self {
+        self.rows = rows;
+        self.cols = cols;
+        self
+    }
+
+    /// Type `input` into the terminal once the command has started.
+    pub fn input(mut self, input: impl AsRef<[u8]>) -> Self {
+        self.input = input.as_ref().to_vec();
+        self
+    }
+
+    /// Run the command to completion, returning everything written to the terminal as stdout.
+    /// Stderr is always empty, since it is written to the terminal as well. The terminal
+    /// translates newlines, so lines end with `\r\n`.
+    pub fn output(self) -> Result<Output, Error> {
+        let started = Instant::now();
+        let (master, slave) = open(self.rows, self.cols)?;
+
+        self.command
+            .stdin(Stdio::from(slave.try_clone()?))
+            .stdout(Stdio::from(slave.try_clone()?))
+            .stderr(Stdio::from(slave));
+
+        // The hook stays on the command, so it is switched off again once this run has spawned
+        // in case the command is reused without a terminal.
+        let attach = Arc::new(AtomicBool::new(true));
+        let hook = Arc::clone(&attach);
+
+        // SAFETY: only async-signal-safe functions are called between fork and exec. Making the
+        // child a session leader lets the terminal become its controlling terminal.
+        unsafe {
+            self.command.pre_exec(move || {
+                if hook.load(Ordering::Relaxed)
+                    && (libc::setsid() < 0 || libc::ioctl(0, libc::TIOCSCTTY as _, 0) < 0)
+                {
+                    return Err(io::Error::last_os_error());
+                }
+                Ok(())
+            });
+        }
+
+        let spawned = self.command.spawn();
+        attach.store(false, Ordering::Relaxed);
+
+        // The command holds on to its copies of the terminal until its stdio is replaced, and the
+        // terminal only reports the end of the output once every copy is closed.
+        self.command
+            .stdin(Stdio::inherit())
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit());
+        let mut child = spawned?;
+
+        let mut master = File::from(master);
+        let output = command::capture(Some(master.try_clone()?), command::Stream::Stdout);
+        if !self.input.is_empty() {
+            master.write_all(&self.input)?;
+        }
+
+        let status = child.wait()?;
+        command::finish(
+            self.command,
+            started,
+            Output {
+                status,
+                stdout: output.finish(),
+                stderr: Vec::new(),
+            },
+        )
+    }
+}
+
+/// Open a new pseudo-terminal of the given size, returning its master and slave ends.
+fn open(rows: u16, cols: u16) -> io::Result<(OwnedFd, OwnedFd)> {
+    let size = libc::winsize {
+        ws_row: rows,
+        ws_col: cols,
+        ws_xpixel: 0,
+        ws_ypixel: 0,
+    };
+    let (mut master, mut slave) = (-1, -1);
+
+    // SAFETY: `openpty` writes two new descriptors, which are immediately given owners.
+    unsafe {
+        if libc::openpty(
+            &mut master,
+            &mut slave,
+            ptr::null_mut(),
+            ptr::null_mut(),
+            &size as *const _ as *mut _,
+        ) < 0
+        {
+            return Err(io::Error::last_os_error());
+        }
+        Ok((OwnedFd::from_raw_fd(master), OwnedFd::from_raw_fd(slave)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{cmd, command::CommandExt};
+
+    #[test]
+    fn terminal_has_requested_size() {
+        let output = cmd!("stty size").with_pty().size(40, 120).output().unwrap();
+
+        assert_eq!(String::from_utf8_lossy(&output.stdout), "40 120\r\n");
+    }
+
+    #[test]
+    fn input_is_typed_into_terminal() {
+        let output = cmd!("sh" => ["-c", "read line; echo \"got $line\""])
+            .with_pty()
+            .input("hello\n")
+            .output()
+            .unwrap();
+
+        assert!(output.status.success());
+        assert!(String::from_utf8_lossy(&output.stdout).ends_with("got hello\r\n"));
+    }
+}
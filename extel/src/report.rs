@@ -0,0 +1,1436 @@
+//! Reporters that receive test events as a suite runs and decide how results are presented.
+
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    ffi::OsStr,
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, MutexGuard},
+};
+
+use crate::{
+    artifacts, context,
+    errors::Error,
+    history::Comparison,
+    manifest::json_string,
+    output_styled_test_result,
+    run_info::{self, RunInfo},
+    style::Style,
+    CaseResult, SuiteReport, TestResult, EXACT_ENV,
+};
+
+/// A sink for test events emitted while a test suite runs. Every method has a default no-op
+/// implementation, so a reporter only needs to implement the events it is interested in.
+///
+/// A reporter can be attached to a run with [`TestConfig::reporter`](crate::TestConfig::reporter),
+/// replacing the default [`TextReporter`].
+///
+/// # Example
+/// ```rust
+/// use extel::{prelude::*, Reporter, TestResult};
+///
+/// #[derive(Default)]
+/// struct CountingReporter {
+///     finished: usize,
+/// }
+///
+/// impl Reporter for CountingReporter {
+///     fn on_test_finish(&mut self, _result: &TestResult, _test_num: usize) {
+///         self.finished += 1;
+///     }
+///
+///     fn on_suite_end(&mut self, _results: &[TestResult]) {
+///         println!("{} test(s) finished", self.finished);
+///     }
+/// }
+///
+/// fn always_succeed() -> ExtelResult {
+///     pass!()
+/// }
+///
+/// init_test_suite!(CountingSuite, always_succeed);
+/// CountingSuite::run(TestConfig::default().reporter(CountingReporter::default()));
+/// ```
+pub trait Reporter {
+    /// Called once before any test in the suite is run.
+    fn on_suite_start(&mut self, _suite_name: &str) {}
+
+    /// Called once after [`on_suite_start`](Reporter::on_suite_start) with the suite-level
+    /// [metadata](crate::TestConfig::metadata), if any was set.
+    fn on_metadata(&mut self, _metadata: &HashMap<String, String>) {}
+
+    /// Called once after [`on_suite_start`](Reporter::on_suite_start) with where and when the
+    /// suite is running, when [run information](crate::TestConfig::run_info) is enabled.
+    fn on_run_info(&mut self, _info: &RunInfo) {}
+
+    /// Called once after [`on_suite_start`](Reporter::on_suite_start) when the tests are run in
+    /// a [shuffled](crate::TestConfig::shuffle) order, with the seed that produced the order.
+    fn on_shuffle(&mut self, _seed: u64) {}
+
+    /// Called once after [`on_suite_start`](Reporter::on_suite_start) with the number of tests
+    /// that were selected to run, after filtering.
+    fn on_plan(&mut self, _test_count: usize) {}
+
+    /// Called instead of running a test when the suite is only being
+    /// [listed](crate::TestConfig::list), once per selected test in the order it would run.
+    fn on_list(&mut self, _test_name: &str) {}
+
+    /// Called once after [`on_plan`](Reporter::on_plan) with the names of the selected tests, in
+    /// the order they will run.
+    fn on_selected(&mut self, _test_names: &[&str]) {}
+
+    /// Called as each case of a parameterized test finishes, before the test itself has
+    /// finished, when only a summary of the cases is kept with
+    /// [`TestConfig::summarize_cases`](crate::TestConfig::summarize_cases). `case_num` is the
+    /// 1-based position of the case. With [`case_jobs`](crate::TestConfig::case_jobs), cases
+    /// may finish out of order.
+    fn on_case_finish(&mut self, _test_name: &str, _case_num: usize, _case: &CaseResult) {}
+
+    /// Called after each test finishes. `test_num` is the 1-based position of the test in the
+    /// suite.
+    fn on_test_finish(&mut self, _result: &TestResult, _test_num: usize) {}
+
+    /// Called once after the last test finishes with how the run differs from the previous one,
+    /// when a [history file](crate::TestConfig::history) is kept and there is a previous run to
+    /// compare against.
+    fn on_comparison(&mut self, _comparison: &Comparison) {}
+
+    /// Called once after every test in the suite has finished.
+    fn on_suite_end(&mut self, _results: &[TestResult]) {}
+
+    /// Called once after every suite of a [composed suite](crate::compose_suites) called `name`
+    /// has run, with the report of each suite.
+    fn on_summary(&mut self, _name: &str, _reports: &[SuiteReport]) {}
+}
+
+/// A reporter shared with the code that attached it, which can still inspect it once the suite
+/// has run.
+///
+/// # Example
+/// ```rust
+/// use extel::{prelude::*, Reporter, TestResult};
+/// use std::sync::{Arc, Mutex};
+///
+/// #[derive(Default)]
+/// struct NameLog(Vec<String>);
+///
+/// impl Reporter for NameLog {
+///     fn on_test_finish(&mut self, result: &TestResult, _test_num: usize) {
+///         self.0.push(result.test_name.to_string());
+///     }
+/// }
+///
+/// fn always_succeed() -> ExtelResult {
+///     pass!()
+/// }
+///
+/// let log = Arc::new(Mutex::new(NameLog::default()));
+/// init_test_suite!(LoggedSuite, always_succeed);
+/// LoggedSuite::run(TestConfig::default().reporter(log.clone()));
+///
+/// assert_eq!(log.lock().unwrap().0, ["always_succeed"]);
+/// ```
+impl<R: Reporter + ?Sized> Reporter for Arc<Mutex<R>> {
+    fn on_suite_start(&mut self, suite_name: &str) {
+        lock(self).on_suite_start(suite_name);
+    }
+
+    fn on_metadata(&mut self, metadata: &HashMap<String, String>) {
+        lock(self).on_metadata(metadata);
+    }
+
+    fn on_run_info(&mut self, info: &RunInfo) {
+        lock(self).on_run_info(info);
+    }
+
+    fn on_shuffle(&mut self, seed: u64) {
+        lock(self).on_shuffle(seed);
+    }
+
+    fn on_plan(&mut self, test_count: usize) {
+        lock(self).on_plan(test_count);
+    }
+
+    fn on_list(&mut self, test_name: &str) {
+        lock(self).on_list(test_name);
+    }
+
+    fn on_selected(&mut self, test_names: &[&str]) {
+        lock(self).on_selected(test_names);
+    }
+
+    fn on_case_finish(&mut self, test_name: &str, case_num: usize, case: &CaseResult) {
+        lock(self).on_case_finish(test_name, case_num, case);
+    }
+
+    fn on_test_finish(&mut self, result: &TestResult, test_num: usize) {
+        lock(self).on_test_finish(result, test_num);
+    }
+
+    fn on_comparison(&mut self, comparison: &Comparison) {
+        lock(self).on_comparison(comparison);
+    }
+
+    fn on_suite_end(&mut self, results: &[TestResult]) {
+        lock(self).on_suite_end(results);
+    }
+
+    fn on_summary(&mut self, name: &str, reports: &[SuiteReport]) {
+        lock(self).on_summary(name, reports);
+    }
+}
+
+pub(crate) fn lock<R: ?Sized>(reporter: &Mutex<R>) -> MutexGuard<'_, R> {
+    reporter
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// The default reporter. Writes a `[suite name]` header followed by one line per test (or per
+/// parameterized case) to the provided writer.
+pub struct TextReporter<'a> {
+    writer: ReportWriter<'a>,
+    colored: bool,
+    rerun_hint: bool,
+    style: Style,
+    align: bool,
+    layout: Layout,
+    verbose: bool,
+    suite_name: String,
+}
+
+impl<'a> TextReporter<'a> {
+    /// Create a text reporter that writes to `writer`, optionally using ANSI color codes.
+    ///
+    /// The writer is flushed after every test, so a report cut short by a killed runner still
+    /// holds every test that finished. If writing fails, a warning is printed to stderr and the
+    /// rest of the report is dropped rather than failing the suite.
+    pub fn new(writer: impl Write + Send + 'a, colored: bool) -> Self {
+        Self {
+            writer: ReportWriter::new(writer),
+            colored,
+            rerun_hint: false,
+            style: Style::default(),
+            align: false,
+            layout: Layout::default(),
+            verbose: false,
+            suite_name: String::new(),
+        }
+    }
+
+    /// Change whether or not the [captured logs](crate::TestResult::logs) of passing tests are
+    /// printed too, rather than only those of failing tests.
+    pub fn verbose(mut self, yes: bool) -> Self {
+        self.verbose = yes;
+        self
+    }
+
+    /// Change whether or not test names and statuses are lined up in columns, based on the
+    /// longest test name in the run, and long failure messages are wrapped to the terminal width.
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::{prelude::*, SharedBuffer, TextReporter};
+    ///
+    /// fn short() -> ExtelResult {
+    ///     pass!()
+    /// }
+    ///
+    /// fn much_longer_name() -> ExtelResult {
+    ///     pass!()
+    /// }
+    ///
+    /// init_test_suite!(AlignedSuite as "aligned", short, much_longer_name);
+    ///
+    /// let output_buffer = SharedBuffer::new();
+    /// AlignedSuite::run(TestConfig::default().reporter(
+    ///     TextReporter::new(output_buffer.clone(), false).align(true),
+    /// ));
+    ///
+    /// assert_eq!(
+    ///     output_buffer.to_string_lossy(),
+    ///     "[aligned]\n\
+    ///      \tTest #1 (short)            ... ok\n\
+    ///      \tTest #2 (much_longer_name) ... ok\n"
+    /// );
+    /// ```
+    pub fn align(mut self, yes: bool) -> Self {
+        self.align = yes;
+        self
+    }
+
+    /// Limit printed failure messages to `max_len` bytes, pointing to the test's artifact holding
+    /// the full message. See [`TestConfig::max_failure_len`](crate::TestConfig::max_failure_len).
+    pub fn max_failure_len(mut self, max_len: Option<usize>) -> Self {
+        self.layout.max_failure_len = max_len;
+        self
+    }
+
+    /// Change whether or not a [rerun command](rerun_command) is printed under each failing test.
+    pub fn rerun_hint(mut self, yes: bool) -> Self {
+        self.rerun_hint = yes;
+        self
+    }
+
+    /// Change the words and colors used to show test statuses.
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Change whether or not the cases of a parameterized test that fail with the same message
+    /// pattern are shown as a single line, with the number of cases and a few example case
+    /// numbers, in place of the first of them. Messages have the same pattern when they only
+    /// differ in their numbers, which are shown as `N` unless every message is the same.
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::{prelude::*, SharedBuffer, SuiteBuilder, TextReporter};
+    ///
+    /// let output_buffer = SharedBuffer::new();
+    /// SuiteBuilder::new("clustered")
+    ///     .add_param_test("small", [1, 20, 3, 40, 50], |n| {
+    ///         extel_assert!(n < 10, "expected less than 10, got {}", n)
+    ///     })
+    ///     .run(
+    ///         TestConfig::default()
+    ///             .reporter(TextReporter::new(output_buffer.clone(), false).cluster_failures(true)),
+    ///     );
+    ///
+    /// assert_eq!(
+    ///     output_buffer.to_string_lossy(),
+    ///     "[clustered]\n\
+    ///      \tTest #1.0 (small) ... ok\n\
+    ///      \tTest #1.2 (small) ... FAILED\n\
+    ///      \t  [x] 3 cases failed with: expected less than N, got N (e.g. cases 2, 4, 5)\n\
+    ///      \tTest #1.2 (small) ... ok\n"
+    /// );
+    /// ```
+    pub fn cluster_failures(mut self, yes: bool) -> Self {
+        self.layout.cluster_failures = yes;
+        self
+    }
+}
+
+impl<'a> Reporter for TextReporter<'a> {
+    fn on_suite_start(&mut self, suite_name: &str) {
+        self.suite_name = suite_name.to_string();
+        let _ = writeln!(self.writer, "[{}]", suite_name);
+    }
+
+    fn on_run_info(&mut self, info: &RunInfo) {
+        let _ = write_run_info(&mut self.writer, info);
+    }
+
+    fn on_metadata(&mut self, metadata: &HashMap<String, String>) {
+        for (key, value) in context::sorted(metadata) {
+            let _ = writeln!(self.writer, "\t[{}: {}]", key, value);
+        }
+    }
+
+    fn on_shuffle(&mut self, seed: u64) {
+        let _ = writeln!(self.writer, "\t[shuffled with seed {}]", seed);
+    }
+
+    fn on_list(&mut self, test_name: &str) {
+        let _ = writeln!(self.writer, "\t{}", test_name);
+    }
+
+    fn on_selected(&mut self, test_names: &[&str]) {
+        if self.align {
+            self.layout = Layout {
+                max_failure_len: self.layout.max_failure_len,
+                cluster_failures: self.layout.cluster_failures,
+                ..Layout::aligned(test_names)
+            };
+        }
+    }
+
+    fn on_test_finish(&mut self, result: &TestResult, test_num: usize) {
+        let lines = format_test_result(result, test_num, self.colored, &self.style, &self.layout);
+        let _ = self.writer.write_all(lines.as_bytes());
+
+        for usage in &result.resource_usage {
+            let _ = writeln!(self.writer, "\t  [usage] {}", usage);
+        }
+
+        for (key, value) in context::sorted(&result.metadata) {
+            let _ = writeln!(self.writer, "\t  [{}: {}]", key, value);
+        }
+
+        let _ = match result.test_result.is_ok() {
+            true if self.verbose => write_logs(&mut self.writer, result),
+            true => Ok(()),
+            false => {
+                write_failure_details(&mut self.writer, &self.suite_name, result, self.rerun_hint)
+            }
+        };
+
+        let _ = self.writer.flush();
+    }
+
+    fn on_comparison(&mut self, comparison: &Comparison) {
+        if comparison.is_empty() {
+            return;
+        }
+
+        let _ = writeln!(self.writer, "\t[since last run]");
+        for (change, tests) in [
+            ("newly failing", &comparison.newly_failing),
+            ("newly passing", &comparison.newly_passing),
+        ] {
+            if !tests.is_empty() {
+                let _ = writeln!(self.writer, "\t  {}: {}", change, tests.join(", "));
+            }
+        }
+
+        let millis = |duration: std::time::Duration| {
+            std::time::Duration::from_millis(duration.as_millis() as u64)
+        };
+        for slow in &comparison.newly_slow {
+            let _ = writeln!(
+                self.writer,
+                "\t  newly slow: {} ({:?} -> {:?})",
+                slow.test_name,
+                millis(slow.previous),
+                millis(slow.current)
+            );
+        }
+    }
+
+    fn on_suite_end(&mut self, _results: &[TestResult]) {
+        let _ = self.writer.flush();
+    }
+
+    fn on_summary(&mut self, name: &str, reports: &[SuiteReport]) {
+        let _ = write_summary(&mut self.writer, name, reports);
+    }
+}
+
+/// A reporter that renders a single, continuously refreshed progress line such as
+/// `12/58 passed, 1 failed` instead of one line per test. Failing tests are still printed in full
+/// above the progress line as they finish.
+///
+/// The progress line relies on carriage returns and ANSI escape codes, so it should only be
+/// written to a terminal. [`TestConfig::progress`](crate::TestConfig::progress) takes care of
+/// falling back to a [`TextReporter`] when stdout is not a TTY.
+pub struct ProgressReporter<'a> {
+    writer: ReportWriter<'a>,
+    colored: bool,
+    rerun_hint: bool,
+    style: Style,
+    total: usize,
+    passed: usize,
+    failed: usize,
+    suite_name: String,
+}
+
+impl<'a> ProgressReporter<'a> {
+    /// Create a progress reporter that writes to `writer`, optionally using ANSI color codes.
+    /// Write errors are handled as by a [`TextReporter`].
+    pub fn new(writer: impl Write + Send + 'a, colored: bool) -> Self {
+        Self {
+            writer: ReportWriter::new(writer),
+            colored,
+            rerun_hint: false,
+            style: Style::default(),
+            total: 0,
+            passed: 0,
+            failed: 0,
+            suite_name: String::new(),
+        }
+    }
+
+    /// Change whether or not a [rerun command](rerun_command) is printed under each failing test.
+    pub fn rerun_hint(mut self, yes: bool) -> Self {
+        self.rerun_hint = yes;
+        self
+    }
+
+    /// Change the words and colors used to show test statuses.
+    pub fn style(mut self, style: Style) -> Self {
+        self.style = style;
+        self
+    }
+
+    /// Clear the current line and draw the progress line in its place.
+    fn redraw(&mut self) {
+        let _ = write!(
+            self.writer,
+            "\r\x1b[2K{}/{} passed, {} failed",
+            self.passed, self.total, self.failed
+        );
+        let _ = self.writer.flush();
+    }
+}
+
+impl<'a> Reporter for ProgressReporter<'a> {
+    fn on_suite_start(&mut self, suite_name: &str) {
+        self.suite_name = suite_name.to_string();
+        let _ = writeln!(self.writer, "[{}]", suite_name);
+    }
+
+    fn on_run_info(&mut self, info: &RunInfo) {
+        let _ = write_run_info(&mut self.writer, info);
+    }
+
+    fn on_metadata(&mut self, metadata: &HashMap<String, String>) {
+        for (key, value) in context::sorted(metadata) {
+            let _ = writeln!(self.writer, "\t[{}: {}]", key, value);
+        }
+    }
+
+    fn on_shuffle(&mut self, seed: u64) {
+        let _ = writeln!(self.writer, "\t[shuffled with seed {}]", seed);
+    }
+
+    fn on_list(&mut self, test_name: &str) {
+        let _ = writeln!(self.writer, "\t{}", test_name);
+    }
+
+    fn on_plan(&mut self, test_count: usize) {
+        self.total = test_count;
+        self.redraw();
+    }
+
+    fn on_test_finish(&mut self, result: &TestResult, test_num: usize) {
+        match result.test_result.is_ok() {
+            true => self.passed += 1,
+            false => {
+                self.failed += 1;
+                let _ = write!(self.writer, "\r\x1b[2K");
+                let _ = output_styled_test_result(
+                    &mut self.writer,
+                    result,
+                    test_num,
+                    self.colored,
+                    &self.style,
+                );
+                let _ = write_failure_details(
+                    &mut self.writer,
+                    &self.suite_name,
+                    result,
+                    self.rerun_hint,
+                );
+            }
+        }
+
+        self.redraw();
+    }
+
+    fn on_suite_end(&mut self, _results: &[TestResult]) {
+        let _ = writeln!(self.writer);
+        let _ = self.writer.flush();
+    }
+
+    fn on_summary(&mut self, name: &str, reports: &[SuiteReport]) {
+        let _ = write_summary(&mut self.writer, name, reports);
+    }
+}
+
+/// A reporter writing one [JSON Lines](https://jsonlines.org) event per line, for tools that
+/// consume results as they arrive. Selected with `--output json` on the
+/// [command line](crate::cli) or [`OutputDest::Json`](crate::OutputDest::Json).
+///
+/// Each suite writes a `started` event, one event per test with its status, and a final event
+/// with the status of the suite and its counts:
+///
+/// ```json
+/// {"type":"suite","event":"started","name":"Cli","test_count":2}
+/// {"type":"test","event":"ok","name":"help","duration_ms":12.5}
+/// {"type":"test","event":"failed","name":"version","duration_ms":3.1,"failures":[{"case":1,"message":"expected 1.2.0"}]}
+/// {"type":"suite","event":"failed","name":"Cli","passed":1,"failed":1,"skipped":0}
+/// ```
+///
+/// The status of a test is `ok`, `failed`, or `skipped`, and `case` is the 1-based position of the
+/// failing case.
+///
+/// # Example
+/// ```rust
+/// use extel::{prelude::*, JsonReporter, SharedBuffer};
+///
+/// fn always_succeed() -> ExtelResult {
+///     pass!()
+/// }
+///
+/// let output_buffer = SharedBuffer::new();
+/// init_test_suite!(JsonSuite as "json", always_succeed);
+/// JsonSuite::run(TestConfig::default().reporter(JsonReporter::new(output_buffer.clone())));
+///
+/// let output = output_buffer.to_string_lossy();
+/// let lines: Vec<&str> = output.lines().collect();
+/// assert_eq!(lines[0], r#"{"type":"suite","event":"started","name":"json","test_count":1}"#);
+/// assert!(lines[1].starts_with(r#"{"type":"test","event":"ok","name":"always_succeed","#));
+/// assert_eq!(lines[2], r#"{"type":"suite","event":"ok","name":"json","passed":1,"failed":0,"skipped":0}"#);
+/// ```
+pub struct JsonReporter<'a> {
+    writer: ReportWriter<'a>,
+    suite_name: String,
+}
+
+impl<'a> JsonReporter<'a> {
+    /// Create a JSON reporter that writes to `writer`, flushing it after every event.
+    pub fn new(writer: impl Write + Send + 'a) -> Self {
+        Self {
+            writer: ReportWriter::new(writer),
+            suite_name: String::new(),
+        }
+    }
+
+    fn event(&mut self, line: String) {
+        let _ = writeln!(self.writer, "{}", line);
+        let _ = self.writer.flush();
+    }
+}
+
+impl<'a> Reporter for JsonReporter<'a> {
+    fn on_suite_start(&mut self, suite_name: &str) {
+        self.suite_name = suite_name.to_string();
+    }
+
+    fn on_plan(&mut self, test_count: usize) {
+        self.event(format!(
+            r#"{{"type":"suite","event":"started","name":{},"test_count":{}}}"#,
+            json_string(&self.suite_name),
+            test_count
+        ));
+    }
+
+    fn on_list(&mut self, test_name: &str) {
+        self.event(format!(
+            r#"{{"type":"test","event":"listed","name":{}}}"#,
+            json_string(test_name)
+        ));
+    }
+
+    fn on_test_finish(&mut self, result: &TestResult, _test_num: usize) {
+        let mut line = format!(
+            r#"{{"type":"test","event":"{}","name":{},"duration_ms":{}"#,
+            test_status(result),
+            json_string(&result.test_name),
+            result.duration.as_secs_f64() * 1000.0
+        );
+
+        let failures: Vec<String> = result
+            .test_result
+            .cases
+            .iter()
+            .enumerate()
+            .filter_map(|(i, case)| match &case.result {
+                Err(e) if !case.is_ok() => Some(format!(
+                    r#"{{"case":{},"message":{}}}"#,
+                    i + 1,
+                    json_string(&e.to_string())
+                )),
+                _ => None,
+            })
+            .collect();
+        if !failures.is_empty() {
+            line.push_str(&format!(r#","failures":[{}]"#, failures.join(",")));
+        }
+        line.push('}');
+        self.event(line);
+    }
+
+    fn on_suite_end(&mut self, results: &[TestResult]) {
+        let count = |status: &str| {
+            results
+                .iter()
+                .filter(|result| test_status(result) == status)
+                .count()
+        };
+        let failed = count("failed");
+        self.event(format!(
+            r#"{{"type":"suite","event":"{}","name":{},"passed":{},"failed":{},"skipped":{}}}"#,
+            match failed {
+                0 => "ok",
+                _ => "failed",
+            },
+            json_string(&self.suite_name),
+            count("ok"),
+            failed,
+            count("skipped")
+        ));
+    }
+}
+
+/// The status of a test in the events of a [`JsonReporter`].
+fn test_status(result: &TestResult) -> &'static str {
+    match (result.test_result.is_ok(), result.test_result.is_skipped()) {
+        (false, _) => "failed",
+        (true, true) => "skipped",
+        (true, false) => "ok",
+    }
+}
+
+/// The writer behind the built-in reporters. The first failed write or flush prints a warning to
+/// stderr and every later write is discarded, so a broken report, such as one on a full disk or a
+/// closed pipe, does not stop the suite. As errors are reported here, the reporters ignore the
+/// results of their writes.
+struct ReportWriter<'a> {
+    inner: Box<dyn Write + Send + 'a>,
+    failed: bool,
+}
+
+impl<'a> ReportWriter<'a> {
+    fn new(inner: impl Write + Send + 'a) -> Self {
+        Self {
+            inner: Box::new(inner),
+            failed: false,
+        }
+    }
+
+    /// Give up on the report after `err`, warning about it the first time.
+    fn fail(&mut self, err: io::Error) {
+        if !self.failed {
+            self.failed = true;
+            eprintln!(
+                "warning: could not write the test report, the rest of it is dropped: {}",
+                err
+            );
+        }
+    }
+}
+
+impl<'a> Write for ReportWriter<'a> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.failed {
+            return Ok(buf.len());
+        }
+
+        match self.inner.write_all(buf) {
+            Ok(()) => Ok(buf.len()),
+            Err(err) => {
+                self.fail(err);
+                Ok(buf.len())
+            }
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.failed {
+            return Ok(());
+        }
+
+        if let Err(err) = self.inner.flush() {
+            self.fail(err);
+        }
+        Ok(())
+    }
+}
+
+/// Column widths used to line up test results, as computed by a [`TextReporter`] with
+/// [alignment](TextReporter::align) enabled. The default layout adds no padding or wrapping.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct Layout {
+    /// The width test numbers are padded to.
+    number_width: usize,
+    /// The width test names are padded to.
+    name_width: usize,
+    /// The width failure messages are wrapped at, if they are wrapped.
+    wrap_width: Option<usize>,
+    /// The length failure messages are truncated to, if they are truncated.
+    max_failure_len: Option<usize>,
+    /// Whether cases of a parameterized test failing with the same message pattern are shown
+    /// together.
+    cluster_failures: bool,
+}
+
+impl Layout {
+    /// A layout fitting the names of every test in a run of `test_names`, wrapping failure
+    /// messages at the terminal width given by `COLUMNS`, or 100 columns.
+    fn aligned(test_names: &[&str]) -> Self {
+        let columns = std::env::var("COLUMNS")
+            .ok()
+            .and_then(|columns| columns.parse().ok())
+            .unwrap_or(100);
+
+        Self {
+            number_width: test_names.len().to_string().len(),
+            name_width: test_names
+                .iter()
+                .map(|name| name.chars().count())
+                .max()
+                .unwrap_or_default(),
+            wrap_width: Some(columns),
+            max_failure_len: None,
+            cluster_failures: false,
+        }
+    }
+
+    /// Truncate `msg` to the maximum failure length, pointing to the artifact holding the full
+    /// message if there is one.
+    fn truncate<'m>(&self, msg: &'m str, artifact: Option<&Path>) -> Cow<'m, str> {
+        let Some(max_len) = self.max_failure_len.filter(|&max_len| msg.len() > max_len) else {
+            return Cow::Borrowed(msg);
+        };
+
+        let mut end = max_len;
+        while !msg.is_char_boundary(end) {
+            end -= 1;
+        }
+        Cow::Owned(match artifact {
+            Some(path) => format!(
+                "{}... [truncated, full output in {}]",
+                &msg[..end],
+                path.display()
+            ),
+            None => format!("{}... [truncated]", &msg[..end]),
+        })
+    }
+
+    /// Wrap `msg` into lines for the `[x]` line under a failing test.
+    fn failure_lines(&self, msg: &str) -> String {
+        // The first line is prefixed by a tab and `  [x] `, and the tab is assumed to be 8
+        // columns wide.
+        const INDENT: usize = 14;
+
+        let Some(width) = self.wrap_width else {
+            return format!("\t  [x] {}\n", msg);
+        };
+
+        let mut lines: Vec<String> = Vec::new();
+        for text in msg.lines() {
+            let mut line = String::new();
+            for word in text.split(' ') {
+                let fits =
+                    line.chars().count() + 1 + word.chars().count() <= width.saturating_sub(INDENT);
+                if line.is_empty() {
+                    line.push_str(word);
+                } else if fits {
+                    line.push(' ');
+                    line.push_str(word);
+                } else {
+                    lines.push(std::mem::replace(&mut line, word.to_string()));
+                }
+            }
+            lines.push(line);
+        }
+
+        lines
+            .iter()
+            .enumerate()
+            .map(|(idx, line)| match idx {
+                0 => format!("\t  [x] {}\n", line),
+                _ => format!("\t      {}\n", line),
+            })
+            .collect()
+    }
+}
+
+/// Format the result lines of a test, showing statuses in `style` and padding to `layout`.
+pub(crate) fn format_test_result(
+    result: &TestResult,
+    test_num: usize,
+    colored: bool,
+    style: &Style,
+    layout: &Layout,
+) -> String {
+    let line = |number: String, idx: usize, case: &CaseResult| {
+        let number_pad = layout.number_width.saturating_sub(number.len());
+        let name_pad = layout
+            .name_width
+            .saturating_sub(result.test_name.chars().count());
+
+        let case_name = case
+            .name
+            .as_ref()
+            .map(|name| format!(" [{}]", name))
+            .unwrap_or_default();
+        let status = match &case.result {
+            Err(Error::Skipped(reason)) => {
+                format!("{} ({})", style.skipped_status(colored), reason)
+            }
+            _ => style.status(case.is_ok(), colored),
+        };
+        let mut line = format!(
+            "\tTest #{}{} ({}){}{} ... {}\n",
+            number,
+            " ".repeat(number_pad),
+            result.test_name,
+            case_name,
+            " ".repeat(name_pad.saturating_sub(case_name.chars().count())),
+            status
+        );
+        match &case.result {
+            Err(Error::Skipped(_)) | Ok(()) => {}
+            Err(err_msg) => {
+                let name = artifacts::failure_file_name(idx, result.test_result.parameterized);
+                let artifact = result
+                    .artifacts
+                    .iter()
+                    .find(|path| path.file_name() == Some(OsStr::new(&name)));
+                let msg = err_msg.to_string();
+                let msg = layout.truncate(&msg, artifact.map(PathBuf::as_path));
+                line.push_str(&layout.failure_lines(&msg));
+            }
+        }
+        line
+    };
+
+    let outcome = &result.test_result;
+    let clusters = match (outcome.parameterized, layout.cluster_failures) {
+        (true, true) => failure_clusters(&outcome.cases),
+        _ => Vec::new(),
+    };
+    let mut lines: String = match outcome.parameterized {
+        false => outcome
+            .cases
+            .iter()
+            .enumerate()
+            .map(|(idx, case)| line(test_num.to_string(), idx, case))
+            .collect(),
+        true => outcome
+            .cases
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, case)| {
+                // Failing cases have always been numbered from 1 and passing cases from 0.
+                let case_num = match case.is_ok() {
+                    true => idx,
+                    false => idx + 1,
+                };
+                let number = format!("{}.{}", test_num, case_num);
+
+                let Some(cluster) = clusters.iter().find(|c| c.cases.contains(&idx)) else {
+                    return Some(line(number, idx, case));
+                };
+                match cluster.cases[0] == idx {
+                    true => Some(line(number, idx, &cluster.summary())),
+                    false => None,
+                }
+            })
+            .collect(),
+    };
+
+    let omitted = &outcome.omitted;
+    if omitted.total() > 0 {
+        lines.push_str(&format!(
+            "\t  ... and {} more case(s) of ({}): {} passed; {} failed",
+            omitted.total(),
+            result.test_name,
+            omitted.passed,
+            omitted.failed
+        ));
+        match omitted.skipped {
+            0 => lines.push('\n'),
+            skipped => lines.push_str(&format!("; {} skipped\n", skipped)),
+        }
+    }
+    lines
+}
+
+/// Failing cases of a parameterized test whose messages have the same pattern.
+struct FailureCluster {
+    /// The message shared by every case, or the pattern if the messages differ.
+    message: String,
+    /// The indices of the cases, in the order they ran.
+    cases: Vec<usize>,
+}
+
+impl FailureCluster {
+    /// How many examples of case numbers are shown.
+    const EXAMPLES: usize = 3;
+
+    /// A failing case standing in for every case of the cluster.
+    fn summary(&self) -> CaseResult {
+        let examples: Vec<String> = self
+            .cases
+            .iter()
+            .take(Self::EXAMPLES)
+            .map(|idx| (idx + 1).to_string())
+            .collect();
+        CaseResult::new(Err(Error::TestFailed(format!(
+            "{} cases failed with: {} (e.g. cases {})",
+            self.cases.len(),
+            self.message,
+            examples.join(", ")
+        ))))
+    }
+}
+
+/// Group the failing `cases` by the pattern of their messages, keeping only groups of more than
+/// one case. Skipped cases are not failures, and are never grouped.
+fn failure_clusters(cases: &[CaseResult]) -> Vec<FailureCluster> {
+    let mut clusters: Vec<(String, FailureCluster)> = Vec::new();
+    for (idx, case) in cases.iter().enumerate() {
+        let Err(e) = &case.result else {
+            continue;
+        };
+        if case.is_ok() {
+            continue;
+        }
+
+        let message = e.to_string();
+        let pattern = failure_pattern(&message);
+        match clusters.iter_mut().find(|(p, _)| *p == pattern) {
+            Some((pattern, cluster)) => {
+                if cluster.message != message {
+                    cluster.message = pattern.clone();
+                }
+                cluster.cases.push(idx);
+            }
+            None => clusters.push((
+                pattern,
+                FailureCluster {
+                    message,
+                    cases: vec![idx],
+                },
+            )),
+        }
+    }
+
+    clusters
+        .into_iter()
+        .map(|(_, cluster)| cluster)
+        .filter(|cluster| cluster.cases.len() > 1)
+        .collect()
+}
+
+/// The pattern of a failure message, where every number is replaced with `N`.
+fn failure_pattern(message: &str) -> String {
+    let mut pattern = String::with_capacity(message.len());
+    let mut in_number = false;
+    for c in message.chars() {
+        match (c.is_ascii_digit(), in_number) {
+            (true, true) => {}
+            (true, false) => pattern.push('N'),
+            (false, _) => pattern.push(c),
+        }
+        in_number = c.is_ascii_digit();
+    }
+    pattern
+}
+
+/// Write the captured output, artifact paths, and rerun hint that follow a failing test's result line.
+fn write_failure_details(
+    writer: &mut impl Write,
+    suite_name: &str,
+    result: &TestResult,
+    rerun_hint: bool,
+) -> io::Result<()> {
+    #[cfg(all(feature = "capture", unix))]
+    if let Some(captured) = &result.captured {
+        for (stream, text) in [("stdout", &captured.stdout), ("stderr", &captured.stderr)] {
+            if text.is_empty() {
+                continue;
+            }
+
+            writeln!(writer, "\t  [{}]", stream)?;
+            for line in text.lines() {
+                writeln!(writer, "\t    {}", line)?;
+            }
+        }
+    }
+
+    write_logs(writer, result)?;
+
+    for (name, context) in &result.failure_context {
+        writeln!(writer, "\t  [{}]", name)?;
+        for line in context.lines() {
+            writeln!(writer, "\t    {}", line)?;
+        }
+    }
+
+    for step in &result.steps {
+        writeln!(
+            writer,
+            "\t  [step] {} ... {} ({:?})",
+            step.name,
+            match step.passed {
+                true => "ok",
+                false => "FAILED",
+            },
+            std::time::Duration::from_millis(step.duration.as_millis() as u64)
+        )?;
+    }
+
+    for artifact in &result.artifacts {
+        writeln!(writer, "\t  [artifact] {}", artifact.display())?;
+    }
+
+    if rerun_hint {
+        writeln!(
+            writer,
+            "\t  [rerun] {}",
+            rerun_command(suite_name, &result.test_name, None)
+        )?;
+        if let Some(case) = first_failing_case(result) {
+            writeln!(
+                writer,
+                "\t  [rerun case {}] {}",
+                case,
+                rerun_command(suite_name, &result.test_name, Some(case))
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Write the run ID, start time, host, and selected environment variables of a run.
+fn write_run_info(writer: &mut impl Write, info: &RunInfo) -> io::Result<()> {
+    writeln!(
+        writer,
+        "\t[run {} on {} ({}), started {}]",
+        info.run_id,
+        info.hostname,
+        info.os,
+        run_info::timestamp(info.started)
+    )?;
+    for (name, value) in &info.env {
+        writeln!(writer, "\t[env {}={}]", name, value)?;
+    }
+    Ok(())
+}
+
+/// Write the totals of every suite of a composed suite called `name`, followed by the totals of
+/// the whole run.
+fn write_summary(writer: &mut impl Write, name: &str, reports: &[SuiteReport]) -> io::Result<()> {
+    let totals = |passed: usize, failed: usize, skipped: usize| match skipped {
+        0 => format!("{} passed; {} failed", passed, failed),
+        skipped => format!("{} passed; {} failed; {} skipped", passed, failed, skipped),
+    };
+
+    writeln!(writer, "[{}]", name)?;
+    for report in reports {
+        let counts = &report.counts;
+        writeln!(
+            writer,
+            "\t{}: {}",
+            report.name,
+            totals(counts.passed, counts.failed, counts.skipped)
+        )?;
+    }
+
+    let sum = |count: fn(&SuiteReport) -> usize| reports.iter().map(count).sum::<usize>();
+    writeln!(
+        writer,
+        "\ttotal: {}",
+        totals(
+            sum(|report| report.counts.passed),
+            sum(|report| report.counts.failed),
+            sum(|report| report.counts.skipped)
+        )
+    )?;
+    writer.flush()
+}
+
+/// Write the captured log lines of a test.
+fn write_logs(writer: &mut impl Write, result: &TestResult) -> io::Result<()> {
+    for line in &result.logs {
+        writeln!(writer, "\t  [log] {}", line)?;
+    }
+    Ok(())
+}
+
+/// Build a shell command that reruns only the test `test_name` of `suite_name`, or only its case
+/// `case` (from 1), using the currently running executable and an [`EXACT_ENV`] selector.
+///
+/// # Example
+/// ```rust
+/// use extel::report::rerun_command;
+///
+/// assert!(rerun_command("cli", "my_test", None).starts_with("EXTEL_EXACT='cli::my_test' "));
+/// assert!(rerun_command("cli", "my_test", Some(3)).starts_with("EXTEL_EXACT='cli::my_test#3' "));
+/// ```
+pub fn rerun_command(suite_name: &str, test_name: &str, case: Option<usize>) -> String {
+    let exe = std::env::current_exe()
+        .map(|path| path.display().to_string())
+        .unwrap_or_else(|_| String::from("<test binary>"));
+
+    format!(
+        "{}={} {}",
+        EXACT_ENV,
+        shell_quote(&exact_selector(suite_name, test_name, case)),
+        shell_quote(&exe)
+    )
+}
+
+/// The [`TestConfig::exact`](crate::TestConfig::exact) selector for the test `test_name` of
+/// `suite_name`, or only its case `case` (from 1): `suite::test` or `suite::test#case`.
+pub fn exact_selector(suite_name: &str, test_name: &str, case: Option<usize>) -> String {
+    match case {
+        Some(case) => format!("{}::{}#{}", suite_name, test_name, case),
+        None => format!("{}::{}", suite_name, test_name),
+    }
+}
+
+/// Whether `selector` selects the test `test_name` of `suite_name`, and if so, the case it
+/// selects, if only one.
+pub(crate) fn exact_match(
+    selector: &str,
+    suite_name: &str,
+    test_name: &str,
+) -> Option<Option<usize>> {
+    let (test, case) = match selector.rsplit_once('#') {
+        Some((test, case)) => match case.parse() {
+            Ok(case) => (test, Some(case)),
+            Err(_) => (selector, None),
+        },
+        None => (selector, None),
+    };
+
+    let selected = test
+        .strip_prefix(suite_name)
+        .and_then(|rest| rest.strip_prefix("::"))
+        == Some(test_name);
+    selected.then_some(case)
+}
+
+/// The number (from 1) of the first failing case of a parameterized test, for rerunning it on its
+/// own. Cases named `case N`, as kept by
+/// [`TestConfig::summarize_cases`](crate::TestConfig::summarize_cases) or selected by
+/// [`TestConfig::exact`](crate::TestConfig::exact), keep their number.
+pub(crate) fn first_failing_case(result: &TestResult) -> Option<usize> {
+    if !result.test_result.parameterized {
+        return None;
+    }
+
+    let (idx, case) = result
+        .test_result
+        .cases
+        .iter()
+        .enumerate()
+        .find(|(_, case)| !case.is_ok())?;
+    match case
+        .name
+        .as_deref()
+        .and_then(|name| name.strip_prefix("case "))
+    {
+        Some(number) => number.parse().ok(),
+        None if result.test_result.omitted.total() == 0 => Some(idx + 1),
+        None => None,
+    }
+}
+
+/// The commands rerunning a failing test and its first failing case, as they are written to the
+/// structured reports.
+pub(crate) fn rerun_commands(suite_name: &str, result: &TestResult) -> Vec<String> {
+    let mut commands = vec![rerun_command(suite_name, &result.test_name, None)];
+    if let Some(case) = first_failing_case(result) {
+        commands.push(rerun_command(suite_name, &result.test_name, Some(case)));
+    }
+    commands
+}
+
+/// Wrap `arg` in single quotes so that it is passed to a POSIX shell verbatim.
+pub(crate) fn shell_quote(arg: &str) -> String {
+    format!("'{}'", arg.replace('\'', "'\\''"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prelude::*, SharedBuffer};
+
+    #[derive(Default)]
+    struct EventLog {
+        events: Vec<String>,
+    }
+
+    impl Reporter for EventLog {
+        fn on_suite_start(&mut self, suite_name: &str) {
+            self.events.push(format!("start {}", suite_name));
+        }
+
+        fn on_test_finish(&mut self, result: &TestResult, test_num: usize) {
+            let passed = result.test_result.is_ok();
+            self.events
+                .push(format!("{} {} {}", test_num, result.test_name, passed));
+        }
+
+        fn on_suite_end(&mut self, results: &[TestResult]) {
+            self.events.push(format!("end {}", results.len()));
+        }
+    }
+
+    fn always_succeed() -> ExtelResult {
+        pass!()
+    }
+
+    fn always_fail() -> ExtelResult {
+        fail!("this test failed?")
+    }
+
+    #[test]
+    fn shell_quote_escapes_single_quotes() {
+        assert_eq!(shell_quote("plain"), "'plain'");
+        assert_eq!(shell_quote("it's"), "'it'\\''s'");
+    }
+
+    #[test]
+    fn long_failure_messages_are_truncated() {
+        let layout = Layout {
+            max_failure_len: Some(4),
+            ..Layout::default()
+        };
+
+        assert_eq!(layout.truncate("fits", None), "fits");
+        assert_eq!(layout.truncate("abcñ", None), "abc... [truncated]");
+        assert_eq!(
+            layout.truncate("overflow", Some(Path::new("out/failure.txt"))),
+            "over... [truncated, full output in out/failure.txt]"
+        );
+    }
+
+    #[test]
+    fn failures_are_clustered_by_message_pattern() {
+        let cases: Vec<CaseResult> = vec![
+            CaseResult::new(Err(Error::TestFailed(String::from("timed out")))),
+            CaseResult::new(Err(Error::TestFailed(String::from("got 12 rows")))),
+            CaseResult::new(Ok(())),
+            CaseResult::new(Err(Error::Skipped(String::from("timed out")))),
+            CaseResult::new(Err(Error::TestFailed(String::from("timed out")))),
+            CaseResult::new(Err(Error::TestFailed(String::from("got 7 rows")))),
+            CaseResult::new(Err(Error::TestFailed(String::from("disk full")))),
+        ];
+
+        let clusters = failure_clusters(&cases);
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(
+            (clusters[0].message.as_str(), &clusters[0].cases[..]),
+            ("timed out", &[0, 4][..])
+        );
+        assert_eq!(
+            (clusters[1].message.as_str(), &clusters[1].cases[..]),
+            ("got N rows", &[1, 5][..])
+        );
+        assert_eq!(
+            clusters[1].summary().result.unwrap_err().to_string(),
+            "2 cases failed with: got N rows (e.g. cases 2, 6)"
+        );
+    }
+
+    #[test]
+    fn aligned_layout_wraps_failure_messages() {
+        let layout = Layout {
+            wrap_width: Some(30),
+            ..Layout::default()
+        };
+
+        assert_eq!(
+            layout.failure_lines("expected four words here\ngot two"),
+            "\t  [x] expected four\n\t      words here\n\t      got two\n"
+        );
+    }
+
+    /// A writer that counts its writes and flushes, failing every write once `broken`. Clones
+    /// share their counts.
+    #[derive(Default, Clone)]
+    struct CountingWriter {
+        counts: Arc<Mutex<(usize, usize)>>,
+        broken: bool,
+    }
+
+    impl CountingWriter {
+        fn counts(&self) -> (usize, usize) {
+            *self.counts.lock().unwrap()
+        }
+    }
+
+    impl Write for CountingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.counts.lock().unwrap().0 += 1;
+            match self.broken {
+                true => Err(io::ErrorKind::BrokenPipe.into()),
+                false => Ok(buf.len()),
+            }
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            self.counts.lock().unwrap().1 += 1;
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn text_reporter_flushes_after_each_test() {
+        init_test_suite!(FlushSuite, always_succeed, always_fail);
+
+        let writer = CountingWriter::default();
+        FlushSuite::run(TestConfig::default().reporter(TextReporter::new(writer.clone(), false)));
+        assert_eq!(writer.counts().1, 3);
+    }
+
+    #[test]
+    fn report_write_errors_do_not_stop_the_suite() {
+        init_test_suite!(BrokenSuite, always_succeed, always_fail);
+
+        let writer = CountingWriter {
+            broken: true,
+            ..CountingWriter::default()
+        };
+        let results = BrokenSuite::run(
+            TestConfig::default().reporter(TextReporter::new(writer.clone(), false)),
+        );
+        assert_eq!(results.len(), 2);
+        assert_eq!(writer.counts(), (1, 0));
+    }
+
+    #[test]
+    fn progress_reporter_redraws_counter() {
+        init_test_suite!(ProgressSuite as "progress", always_succeed, always_fail);
+
+        let output_buffer = SharedBuffer::new();
+        ProgressSuite::run(
+            TestConfig::default().reporter(ProgressReporter::new(output_buffer.clone(), false)),
+        );
+
+        assert_eq!(
+            output_buffer.to_string_lossy(),
+            "[progress]\n\
+             \r\x1b[2K0/2 passed, 0 failed\
+             \r\x1b[2K1/2 passed, 0 failed\
+             \r\x1b[2K\tTest #2 (always_fail) ... FAILED\n\t  [x] this test failed?\n\
+             \r\x1b[2K1/2 passed, 1 failed\n"
+        );
+    }
+
+    #[test]
+    fn custom_reporter_receives_events() {
+        init_test_suite!(ReporterSuite, always_succeed, always_fail);
+
+        let log = Arc::new(Mutex::new(EventLog::default()));
+        let results = ReporterSuite::run(TestConfig::default().reporter(log.clone()));
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            log.lock().unwrap().events,
+            vec![
+                "start extel::report::tests::custom_reporter_receives_events::ReporterSuite",
+                "1 always_succeed true",
+                "2 always_fail false",
+                "end 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn exact_selectors_name_one_test() {
+        assert_eq!(exact_match("cli::help", "cli", "help"), Some(None));
+        assert_eq!(exact_match("cli::help#3", "cli", "help"), Some(Some(3)));
+        assert_eq!(exact_match("cli::help", "cli", "help_all"), None);
+        assert_eq!(exact_match("cli::help", "other::cli", "help"), None);
+        assert_eq!(exact_match("a::cli::help", "a::cli", "help"), Some(None));
+        assert_eq!(exact_match("cli::help#x", "cli", "help#x"), Some(None));
+        assert_eq!(exact_selector("cli", "help", Some(2)), "cli::help#2");
+    }
+
+    #[test]
+    fn json_events_describe_failures() {
+        init_test_suite!(JsonSuite as "json", always_fail);
+
+        let output_buffer = SharedBuffer::new();
+        JsonSuite::run(TestConfig::default().reporter(JsonReporter::new(output_buffer.clone())));
+
+        let output = output_buffer.to_string_lossy();
+        let lines: Vec<&str> = output.lines().collect();
+        assert!(lines[1].starts_with(r#"{"type":"test","event":"failed","name":"always_fail","#));
+        assert!(lines[1].ends_with(r#","failures":[{"case":1,"message":"this test failed?"}]}"#));
+        assert_eq!(
+            lines[2],
+            r#"{"type":"suite","event":"failed","name":"json","passed":0,"failed":1,"skipped":0}"#
+        );
+    }
+}
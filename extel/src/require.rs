@@ -0,0 +1,168 @@
+//! Guards that skip a test when the machine running it cannot run it, such as when a tool it
+//! drives is not installed, instead of failing it. This lets a suite shared between machines with
+//! different toolchains degrade gracefully.
+//!
+//! Every guard returns an [`Error::Skipped`] with the reason when its precondition is not met,
+//! so it is meant to be propagated with `?`, which is what the [`require_binary`],
+//! [`require_env`], and [`require_port_free`] macros do. A skipped test is reported as skipped
+//! rather than as passed or failed, and does not fail the run.
+//!
+//! ```rust
+//! use extel::{prelude::*, OutputDest};
+//!
+//! fn deploys_container() -> ExtelResult {
+//!     require_binary!("extel-missing-binary");
+//!     let _token = require_env!("EXTEL_MISSING_TOKEN");
+//!     fail!("unreachable on this machine")
+//! }
+//!
+//! init_test_suite!(DeploySuite, deploys_container);
+//! let report = DeploySuite::run_report(TestConfig::default().output(OutputDest::None));
+//!
+//! assert!(report.all_passed());
+//! assert_eq!(report.counts.skipped, 1);
+//! ```
+//!
+//! [`require_binary`]: crate::require_binary
+//! [`require_env`]: crate::require_env
+//! [`require_port_free`]: crate::require_port_free
+
+use std::{env, net::TcpListener, path::PathBuf};
+
+use crate::{errors::Error, ExtelResult};
+
+/// Find the executable `name` on the `PATH`, skipping the test if it is not there. A `name`
+/// containing a path separator is checked as a path instead. Returns the path of the executable.
+///
+/// # Example
+/// ```rust
+/// use extel::{prelude::*, require};
+///
+/// assert!(require::binary("sh").is_ok());
+/// assert!(matches!(
+///     require::binary("extel-missing-binary"),
+///     Err(Error::Skipped(_))
+/// ));
+/// ```
+pub fn binary(name: &str) -> Result<PathBuf, Error> {
+    let candidates: Vec<PathBuf> = match name.contains(std::path::is_separator) {
+        true => vec![PathBuf::from(name)],
+        false => env::var_os("PATH")
+            .map(|path| env::split_paths(&path).map(|dir| dir.join(name)).collect())
+            .unwrap_or_default(),
+    };
+
+    candidates
+        .into_iter()
+        .flat_map(|candidate| {
+            let mut exe = candidate.clone().into_os_string();
+            exe.push(env::consts::EXE_SUFFIX);
+            [candidate, PathBuf::from(exe)]
+        })
+        .find(|candidate| is_executable(candidate))
+        .ok_or_else(|| Error::Skipped(format!("`{}` was not found on the PATH", name)))
+}
+
+#[cfg(unix)]
+fn is_executable(path: &std::path::Path) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    path.metadata()
+        .is_ok_and(|metadata| metadata.is_file() && metadata.permissions().mode() & 0o111 != 0)
+}
+
+#[cfg(not(unix))]
+fn is_executable(path: &std::path::Path) -> bool {
+    path.is_file()
+}
+
+/// Read the environment variable `key`, skipping the test if it is unset or empty.
+///
+/// # Example
+/// ```rust
+/// use extel::{prelude::*, require};
+///
+/// assert!(matches!(require::env("EXTEL_MISSING_TOKEN"), Err(Error::Skipped(_))));
+/// ```
+pub fn env(key: &str) -> Result<String, Error> {
+    match env::var(key) {
+        Ok(value) if !value.is_empty() => Ok(value),
+        _ => Err(Error::Skipped(format!("${} is not set", key))),
+    }
+}
+
+/// Skip the test if something is already listening on the local TCP `port`.
+///
+/// # Example
+/// ```rust
+/// use extel::{prelude::*, require};
+/// use std::net::TcpListener;
+///
+/// let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+/// let port = listener.local_addr().unwrap().port();
+/// assert!(matches!(require::port_free(port), Err(Error::Skipped(_))));
+///
+/// drop(listener);
+/// assert!(require::port_free(port).is_ok());
+/// ```
+pub fn port_free(port: u16) -> ExtelResult {
+    match TcpListener::bind(("127.0.0.1", port)) {
+        Ok(_) => Ok(()),
+        Err(e) => Err(Error::Skipped(format!("port {} is not free: {}", port, e))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prelude::*, OutputDest, SharedBuffer};
+
+    fn needs_missing_binary() -> ExtelResult {
+        require_binary!("extel-missing-binary");
+        fail!("ran without its binary")
+    }
+
+    fn needs_shell() -> ExtelResult {
+        let sh = require_binary!("sh");
+        extel_assert!(sh.is_absolute())
+    }
+
+    fn skips_itself() -> ExtelResult {
+        skip!("not supported on {}", "this machine")
+    }
+
+    #[test]
+    fn unmet_requirements_skip_tests() {
+        init_test_suite!(RequireSuite as "require", needs_missing_binary, needs_shell, skips_itself);
+
+        let output_buffer = SharedBuffer::new();
+        let report = RequireSuite::run_report(
+            TestConfig::default()
+                .output(OutputDest::Buffer(output_buffer.clone()))
+                .colored(false),
+        );
+
+        assert!(report.all_passed());
+        assert_eq!(
+            (
+                report.counts.passed,
+                report.counts.skipped,
+                report.counts.failed
+            ),
+            (1, 2, 0)
+        );
+        assert_eq!(
+            output_buffer.to_string_lossy(),
+            "[require]\n\
+             \tTest #1 (needs_missing_binary) ... skipped (`extel-missing-binary` was not found on the PATH)\n\
+             \tTest #2 (needs_shell) ... ok\n\
+             \tTest #3 (skips_itself) ... skipped (not supported on this machine)\n"
+        );
+    }
+
+    #[test]
+    fn paths_are_checked_directly() {
+        assert!(binary("/bin/sh").is_ok());
+        assert!(matches!(binary("./missing/sh"), Err(Error::Skipped(_))));
+    }
+}
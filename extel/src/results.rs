@@ -0,0 +1,223 @@
+//! Helpers for consuming the results of a test suite programmatically.
+
+use std::{ops::Deref, time::Duration};
+
+use crate::{Reporter, TestResult, TextReporter};
+
+/// The results of a test suite run, with helpers for inspecting them without having to inspect
+/// each [`TestOutcome`](crate::TestOutcome). A test counts as passed only if none of its cases
+/// failed and not all of them were skipped, and as skipped if all of them were.
+///
+/// # Example
+/// ```rust
+/// use extel::{prelude::*, OutputDest, SuiteResults};
+///
+/// fn always_succeed() -> ExtelResult {
+///     pass!()
+/// }
+///
+/// fn always_fail() -> ExtelResult {
+///     fail!("no luck")
+/// }
+///
+/// init_test_suite!(MixedSuite, always_succeed, always_fail);
+/// let results = SuiteResults::from(MixedSuite::run(TestConfig::default().output(OutputDest::None)));
+///
+/// assert_eq!((results.passed(), results.failed()), (1, 1));
+/// assert!(!results.all_passed());
+/// assert_eq!(results.failures().next().unwrap().test_name, "always_fail");
+/// ```
+#[derive(Debug, Default)]
+pub struct SuiteResults {
+    results: Vec<TestResult>,
+}
+
+impl SuiteResults {
+    /// The number of tests that passed.
+    pub fn passed(&self) -> usize {
+        self.results.len() - self.failed() - self.skipped()
+    }
+
+    /// The number of tests that were skipped.
+    pub fn skipped(&self) -> usize {
+        self.results
+            .iter()
+            .filter(|result| result.test_result.is_skipped())
+            .count()
+    }
+
+    /// The number of tests that failed.
+    pub fn failed(&self) -> usize {
+        self.failures().count()
+    }
+
+    /// The tests that failed, in the order they were run.
+    pub fn failures(&self) -> impl Iterator<Item = &TestResult> {
+        self.results
+            .iter()
+            .filter(|result| !result.test_result.is_ok())
+    }
+
+    /// Whether or not every test passed or was skipped.
+    pub fn all_passed(&self) -> bool {
+        self.failures().next().is_none()
+    }
+
+    /// Render the results as plain text in the format of the default [`TextReporter`], followed by
+    /// a summary line with the number of passed and failed tests, and skipped tests if any.
+    pub fn into_report(self) -> String {
+        let mut buffer = Vec::new();
+        {
+            let mut reporter = TextReporter::new(&mut buffer, false);
+            for (i, result) in self.results.iter().enumerate() {
+                reporter.on_test_finish(result, i + 1);
+            }
+        }
+
+        let mut report = String::from_utf8_lossy(&buffer).into_owned();
+        report.push_str(&format!(
+            "{} passed; {} failed",
+            self.passed(),
+            self.failed()
+        ));
+        match self.skipped() {
+            0 => report.push('\n'),
+            skipped => report.push_str(&format!("; {} skipped\n", skipped)),
+        }
+        report
+    }
+
+    /// Take the underlying results.
+    pub fn into_inner(self) -> Vec<TestResult> {
+        self.results
+    }
+}
+
+/// The number of tests in a suite run that passed, failed, and were skipped.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SuiteCounts {
+    /// The number of tests that ran.
+    pub total: usize,
+    /// The number of tests that passed.
+    pub passed: usize,
+    /// The number of tests that failed.
+    pub failed: usize,
+    /// The number of tests that were [skipped](crate::errors::Error::Skipped).
+    pub skipped: usize,
+}
+
+/// The report of a whole suite run: the name of the suite, its results, how long it took, and
+/// its totals. Returned by [`RunnableTestSet::run_report`](crate::RunnableTestSet::run_report)
+/// and [`SuiteBuilder::run_report`](crate::SuiteBuilder::run_report).
+#[derive(Debug)]
+pub struct SuiteReport {
+    /// The name of the suite.
+    pub name: String,
+    /// The results of every test that ran, in the order they were run.
+    pub results: SuiteResults,
+    /// How long the whole suite took to run.
+    pub duration: Duration,
+    /// The number of tests that passed and failed.
+    pub counts: SuiteCounts,
+}
+
+impl SuiteReport {
+    /// Build the report of a suite called `name` from its results.
+    pub fn new(name: impl Into<String>, results: Vec<TestResult>, duration: Duration) -> Self {
+        let results = SuiteResults::from(results);
+        let counts = SuiteCounts {
+            total: results.len(),
+            passed: results.passed(),
+            failed: results.failed(),
+            skipped: results.skipped(),
+        };
+
+        Self {
+            name: name.into(),
+            results,
+            duration,
+            counts,
+        }
+    }
+
+    /// Whether or not every test passed or was skipped.
+    pub fn all_passed(&self) -> bool {
+        self.counts.failed == 0
+    }
+}
+
+impl From<Vec<TestResult>> for SuiteResults {
+    fn from(results: Vec<TestResult>) -> Self {
+        Self { results }
+    }
+}
+
+impl Deref for SuiteResults {
+    type Target = [TestResult];
+
+    fn deref(&self) -> &[TestResult] {
+        &self.results
+    }
+}
+
+impl IntoIterator for SuiteResults {
+    type Item = TestResult;
+    type IntoIter = std::vec::IntoIter<TestResult>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.results.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prelude::*, OutputDest};
+
+    fn always_succeed() -> ExtelResult {
+        pass!()
+    }
+
+    fn always_fail() -> ExtelResult {
+        fail!("this test failed?")
+    }
+
+    #[test]
+    fn into_report_renders_results_and_summary() {
+        init_test_suite!(ReportSuite, always_succeed, always_fail);
+
+        let results = SuiteResults::from(ReportSuite::run(
+            TestConfig::default().output(OutputDest::None),
+        ));
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            results.into_report(),
+            "\tTest #1 (always_succeed) ... ok\n\
+             \tTest #2 (always_fail) ... FAILED\n\t  [x] this test failed?\n\
+             1 passed; 1 failed\n"
+        );
+    }
+
+    #[test]
+    fn suite_builder_reports_totals() {
+        let report = crate::SuiteBuilder::new("Built")
+            .add_test("always_succeed", always_succeed)
+            .add_test("always_fail", always_fail)
+            .add_test("also_succeeds", always_succeed)
+            .run_report(TestConfig::default().output(OutputDest::None));
+
+        assert_eq!(report.name, "Built");
+        assert_eq!(
+            report.counts,
+            SuiteCounts {
+                total: 3,
+                passed: 2,
+                failed: 1,
+                skipped: 0
+            }
+        );
+        assert!(!report.all_passed());
+        assert_eq!(report.results.len(), 3);
+    }
+}
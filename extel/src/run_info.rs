@@ -0,0 +1,152 @@
+//! Where and when a test run happened, so that results from different machines can be told apart
+//! and compared.
+//!
+//! Every run has a [`RunInfo`] with a run ID shared by every suite run by the same process, the
+//! time the suite started and finished, the host name, the operating system, and the values of
+//! the environment variables selected with
+//! [`TestConfig::report_env`](crate::TestConfig::report_env). It is written to the
+//! [manifest](crate::manifest) and the [Allure results](crate::allure), and shown at the top of
+//! the console output with [`TestConfig::run_info`](crate::TestConfig::run_info).
+
+use std::{
+    collections::{hash_map::RandomState, BTreeMap},
+    fs,
+    hash::{BuildHasher, Hasher},
+    sync::OnceLock,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// The environment variable that sets the run ID instead of generating one, such as to use the ID
+/// of a CI job.
+pub const RUN_ID_ENV: &str = "EXTEL_RUN_ID";
+
+/// Where and when a suite ran.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RunInfo {
+    /// The ID of the run, shared by every suite run by the same process.
+    pub run_id: String,
+    /// When the suite started running.
+    pub started: SystemTime,
+    /// When the suite finished running, once it has.
+    pub finished: Option<SystemTime>,
+    /// The name of the machine running the suite, or `unknown`.
+    pub hostname: String,
+    /// The operating system and architecture, such as `linux x86_64`.
+    pub os: String,
+    /// The selected environment variables that are set, by name.
+    pub env: BTreeMap<String, String>,
+}
+
+impl RunInfo {
+    /// Describe a suite starting now on this machine, with the values of the environment
+    /// variables named in `env_names`.
+    pub fn collect(env_names: &[String]) -> Self {
+        Self {
+            run_id: run_id().to_string(),
+            started: SystemTime::now(),
+            finished: None,
+            hostname: hostname(),
+            os: format!("{} {}", std::env::consts::OS, std::env::consts::ARCH),
+            env: env_names
+                .iter()
+                .filter_map(|name| Some((name.clone(), std::env::var(name).ok()?)))
+                .collect(),
+        }
+    }
+}
+
+/// The ID of the current run: the value of [`RUN_ID_ENV`] if it is set, or else 16 random hex
+/// digits generated once per process.
+pub fn run_id() -> &'static str {
+    static RUN_ID: OnceLock<String> = OnceLock::new();
+    RUN_ID.get_or_init(|| match std::env::var(RUN_ID_ENV) {
+        Ok(id) if !id.is_empty() => id,
+        _ => {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u32(std::process::id());
+            hasher.write_u128(
+                SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .unwrap_or_default()
+                    .as_nanos(),
+            );
+            format!("{:016x}", hasher.finish())
+        }
+    })
+}
+
+/// `time` in UTC, formatted like `2024-05-01T12:30:00Z`.
+///
+/// # Example
+/// ```rust
+/// use extel::run_info::timestamp;
+/// use std::time::{Duration, UNIX_EPOCH};
+///
+/// assert_eq!(timestamp(UNIX_EPOCH + Duration::from_secs(1_714_566_600)), "2024-05-01T12:30:00Z");
+/// ```
+pub fn timestamp(time: SystemTime) -> String {
+    let secs = time
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs();
+    let (days, secs) = (secs / 86_400, secs % 86_400);
+
+    // Civil date from days since the epoch, after Howard Hinnant's `civil_from_days`.
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + i64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        secs / 3_600,
+        secs % 3_600 / 60,
+        secs % 60
+    )
+}
+
+/// The name of this machine, from the environment or the system, or `unknown`.
+fn hostname() -> String {
+    ["HOSTNAME", "COMPUTERNAME"]
+        .iter()
+        .find_map(|name| std::env::var(name).ok().filter(|host| !host.is_empty()))
+        .or_else(|| {
+            ["/proc/sys/kernel/hostname", "/etc/hostname"]
+                .iter()
+                .find_map(|path| fs::read_to_string(path).ok())
+                .map(|host| host.trim().to_string())
+                .filter(|host| !host.is_empty())
+        })
+        .unwrap_or_else(|| String::from("unknown"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn timestamps_cover_leap_years() {
+        let at = |secs| timestamp(UNIX_EPOCH + Duration::from_secs(secs));
+        assert_eq!(at(0), "1970-01-01T00:00:00Z");
+        assert_eq!(at(951_782_400), "2000-02-29T00:00:00Z");
+        assert_eq!(at(4_102_444_799), "2099-12-31T23:59:59Z");
+    }
+
+    #[test]
+    fn run_ids_are_shared_by_the_process() {
+        let info = RunInfo::collect(&[String::from("PATH"), String::from("EXTEL_UNSET_VAR")]);
+        assert_eq!(info.run_id, run_id());
+        assert!(!info.run_id.is_empty());
+        assert!(info.env.contains_key("PATH"));
+        assert!(!info.env.contains_key("EXTEL_UNSET_VAR"));
+    }
+}
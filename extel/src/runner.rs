@@ -0,0 +1,114 @@
+//! Building blocks for implementing [`RunnableTestSet`] by hand, for suites whose tests are not
+//! known when the code is written, such as tests generated from data files or registered through
+//! FFI.
+//!
+//! Most implementations only need to build a [`Test`] for each of their tests and hand them to
+//! [`run_tests`], which does everything a suite created with
+//! [`init_test_suite!`](crate::init_test_suite) does: selecting and ordering tests, timing them,
+//! catching panics, reporting the results, and keeping the state and history files.
+//!
+//! ```rust
+//! use extel::{prelude::*, runner::{self, Test}, OutputDest, TestResult};
+//!
+//! /// One test per line of a table, as if read from a file.
+//! struct TableSuite;
+//!
+//! impl RunnableTestSet for TableSuite {
+//!     fn run(cfg: TestConfig) -> Vec<TestResult> {
+//!         let table = "1 + 1 = 2\n2 + 2 = 5";
+//!         let tests = table
+//!             .lines()
+//!             .map(|line| {
+//!                 let (sum, expected) = line.split_once(" = ").unwrap();
+//!                 let (sum, expected) = (sum.to_string(), expected.parse::<i32>().unwrap());
+//!                 Test::new(line.to_string(), move || {
+//!                     let actual: i32 = sum.split(" + ").map(|n| n.parse::<i32>().unwrap()).sum();
+//!                     extel_assert_eq!(actual, expected)
+//!                 })
+//!             })
+//!             .collect();
+//!         runner::run_tests(Self::suite_name(), tests, cfg)
+//!     }
+//! }
+//!
+//! let results = TableSuite::run(TestConfig::default().output(OutputDest::None));
+//! assert!(results[0].test_result.is_ok());
+//! assert!(!results[1].test_result.is_ok());
+//! ```
+//!
+//! Tests run by something else, such as a harness on the other side of an FFI boundary, can
+//! still be reported like any other suite: wrap each one in a [`Test`], run it with
+//! [`run_test`] to time it and catch its panics, and send the results to the configured
+//! reporter with [`report_results`].
+
+use std::time::Instant;
+
+pub use crate::{catch_panic, run_tests, Reporter, RunnableTestSet, Test, TestConfig, TestResult};
+
+/// Run a single test on its own, timing it and turning a panic into an
+/// [`Error::Panicked`](crate::errors::Error::Panicked) failure. Unlike [`run_tests`], nothing is
+/// reported and the configuration of the run, such as its filters and isolation, is not applied.
+///
+/// # Example
+/// ```rust
+/// use extel::{errors::Error, runner::{self, Test}};
+///
+/// let result = runner::run_test(Test::new("panics", || -> extel::ExtelResult { panic!("oops") }));
+/// assert!(matches!(&result.test_result.cases[0].result, Err(Error::Panicked(msg)) if msg.starts_with("oops at ")));
+/// ```
+pub fn run_test(test: Test) -> TestResult {
+    let started = Instant::now();
+    let mut result = test.run_test();
+    result.duration = started.elapsed();
+    result
+}
+
+/// Send the `results` of a suite called `suite_name` that has already run to the reporter
+/// described by `cfg`, as if the suite had been run by [`run_tests`]. Tests are numbered in the
+/// order of `results`.
+///
+/// # Example
+/// ```rust
+/// use extel::{prelude::*, runner::{self, Test}, OutputDest, SharedBuffer};
+///
+/// let results = vec![
+///     runner::run_test(Test::new("ffi_add", || pass!())),
+///     runner::run_test(Test::new("ffi_sub", || fail!("3 - 1 != 1"))),
+/// ];
+///
+/// let output = SharedBuffer::new();
+/// runner::report_results(
+///     "ffi",
+///     &results,
+///     TestConfig::default()
+///         .output(OutputDest::Buffer(output.clone()))
+///         .colored(false)
+///         .rerun_hint(false),
+/// );
+///
+/// assert_eq!(
+///     output.to_string_lossy(),
+///     "[ffi]\n\
+///      \tTest #1 (ffi_add) ... ok\n\
+///      \tTest #2 (ffi_sub) ... FAILED\n\
+///      \t  [x] 3 - 1 != 1\n"
+/// );
+/// ```
+pub fn report_results(suite_name: &str, results: &[TestResult], mut cfg: TestConfig) {
+    let Some(mut reporter) = cfg.take_reporter() else {
+        return;
+    };
+
+    reporter.on_suite_start(suite_name);
+    reporter.on_plan(results.len());
+    let test_names: Vec<&str> = results
+        .iter()
+        .map(|result| result.test_name.as_ref())
+        .collect();
+    reporter.on_selected(&test_names);
+
+    for (i, result) in results.iter().enumerate() {
+        reporter.on_test_finish(result, i + 1);
+    }
+    reporter.on_suite_end(results);
+}
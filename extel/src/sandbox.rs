@@ -0,0 +1,443 @@
+//! Opt-in hardened execution for spawned commands on Linux.
+//!
+//! A [`SandboxProfile`] describes what a child process is allowed to touch. Filesystem access is
+//! restricted with [Landlock](https://docs.kernel.org/userspace-api/landlock.html) and network
+//! access is blocked with a seccomp filter. Profiles are applied to every command a test builds
+//! with [`cmd!`](crate::cmd) through [`TestConfig::sandbox`](crate::TestConfig::sandbox), or to a
+//! single command through [`SandboxExt`].
+//!
+//! Violations are reported as an [`Error::SandboxViolation`](crate::errors::Error::SandboxViolation)
+//! by [`SandboxExt::sandboxed_output`] and by the checked runs of
+//! [`CommandExt`](crate::command::CommandExt). Network violations kill the child with `SIGSYS`.
+//! Filesystem violations are surfaced to the child as `EACCES`, so they are recognized by the
+//! command failing after writing "Permission denied" to stderr, which is how most binaries report
+//! the error.
+//!
+//! ```rust
+//! use extel::{prelude::*, sandbox::SandboxProfile, OutputDest};
+//!
+//! fn reads_manifest() -> ExtelResult {
+//!     cmd!("cat" => [concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml")]).output_checked()?;
+//!     Ok(())
+//! }
+//!
+//! init_test_suite!(Contained, reads_manifest);
+//! let results = Contained::run(
+//!     TestConfig::default()
+//!         .output(OutputDest::None)
+//!         .sandbox(SandboxProfile::new().allow_system_paths()),
+//! );
+//! assert!(matches!(
+//!     results[0].test_result.cases[0].result,
+//!     Err(Error::SandboxViolation(_))
+//! ));
+//! ```
+//!
+//! > *This is only available with the `sandbox` feature enabled, on x86_64 and aarch64 Linux.*
+
+use std::{
+    ffi::OsStr,
+    fs::OpenOptions,
+    io,
+    os::{
+        fd::{AsRawFd, OwnedFd},
+        unix::{
+            fs::OpenOptionsExt,
+            process::{CommandExt, ExitStatusExt},
+        },
+    },
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+use crate::errors::Error;
+
+const ACCESS_FS_EXECUTE: u64 = 1 << 0;
+const ACCESS_FS_WRITE_FILE: u64 = 1 << 1;
+const ACCESS_FS_READ_FILE: u64 = 1 << 2;
+const ACCESS_FS_READ_DIR: u64 = 1 << 3;
+
+/// Every access right defined by the first Landlock ABI.
+const ACCESS_FS_ALL: u64 = (1 << 13) - 1;
+
+/// The access rights that may be granted on a regular file rather than a directory.
+const ACCESS_FS_FILE: u64 = ACCESS_FS_EXECUTE | ACCESS_FS_WRITE_FILE | ACCESS_FS_READ_FILE;
+const ACCESS_FS_READ: u64 = ACCESS_FS_EXECUTE | ACCESS_FS_READ_FILE | ACCESS_FS_READ_DIR;
+
+const LANDLOCK_RULE_PATH_BENEATH: libc::c_int = 1;
+
+#[cfg(target_arch = "x86_64")]
+const AUDIT_ARCH: u32 = 0xC000_003E;
+#[cfg(target_arch = "aarch64")]
+const AUDIT_ARCH: u32 = 0xC000_00B7;
+
+/// Syscall numbers at or above this value belong to the x32 ABI on x86_64.
+const X32_SYSCALL_BIT: u32 = 0x4000_0000;
+
+#[repr(C)]
+struct RulesetAttr {
+    handled_access_fs: u64,
+}
+
+#[repr(C, packed)]
+struct PathBeneathAttr {
+    allowed_access: u64,
+    parent_fd: i32,
+}
+
+/// How [`strerror`](libc::strerror) describes `EACCES`, which a denied path is reported as.
+const PERMISSION_DENIED: &str = "Permission denied";
+
+/// Paths that most dynamically linked binaries need to be able to read in order to start.
+const SYSTEM_PATHS: &[&str] = &["/bin", "/sbin", "/usr", "/lib", "/lib64", "/etc", "/dev"];
+
+/// A description of what a sandboxed command may access.
+///
+/// An empty profile places no restrictions on the command. Adding any filesystem rule switches
+/// the command to deny-by-default filesystem access, where only the listed paths (and everything
+/// beneath them) are reachable.
+///
+/// # Example
+/// ```rust
+/// use extel::{prelude::*, sandbox::{SandboxExt, SandboxProfile}};
+///
+/// fn contained_echo() -> ExtelResult {
+///     let profile = SandboxProfile::new().allow_system_paths().deny_network();
+///     let output = cmd!("echo -n hello").sandboxed_output(&profile)?;
+///     extel_assert!(output.stdout == b"hello")
+/// }
+///
+/// assert!(contained_echo().is_ok());
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SandboxProfile {
+    rules: Vec<PathRule>,
+    deny_network: bool,
+}
+
+#[derive(Debug, Clone)]
+struct PathRule {
+    path: PathBuf,
+    access: u64,
+    required: bool,
+}
+
+impl SandboxProfile {
+    /// Create an empty profile that does not restrict the command.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Allow reading and executing anything beneath `path`.
+    pub fn allow_read(mut self, path: impl AsRef<Path>) -> Self {
+        self.rules.push(PathRule {
+            path: path.as_ref().to_path_buf(),
+            access: ACCESS_FS_READ,
+            required: true,
+        });
+        self
+    }
+
+    /// Allow full access, including creating and removing files, beneath `path`.
+    pub fn allow_write(mut self, path: impl AsRef<Path>) -> Self {
+        self.rules.push(PathRule {
+            path: path.as_ref().to_path_buf(),
+            access: ACCESS_FS_ALL,
+            required: true,
+        });
+        self
+    }
+
+    /// Allow read access to the standard system directories (`/usr`, `/lib`, `/etc`, ...) so
+    /// that ordinary binaries can be loaded. Directories that do not exist are skipped.
+    pub fn allow_system_paths(mut self) -> Self {
+        self.rules.extend(SYSTEM_PATHS.iter().map(|path| PathRule {
+            path: PathBuf::from(path),
+            access: ACCESS_FS_READ,
+            required: false,
+        }));
+        self
+    }
+
+    /// Kill the command if it attempts to open an IPv4 or IPv6 socket.
+    pub fn deny_network(mut self) -> Self {
+        self.deny_network = true;
+        self
+    }
+
+    /// Open a handle to every path rule so it can be registered with Landlock in the child.
+    fn open_rules(&self) -> io::Result<Vec<(OwnedFd, u64)>> {
+        let mut handles = Vec::new();
+        for rule in &self.rules {
+            let file = match OpenOptions::new()
+                .read(true)
+                .custom_flags(libc::O_PATH)
+                .open(&rule.path)
+            {
+                Ok(file) => file,
+                Err(e) if e.kind() == io::ErrorKind::NotFound && !rule.required => continue,
+                Err(e) => return Err(e),
+            };
+
+            let access = match file.metadata()?.is_dir() {
+                true => rule.access,
+                false => rule.access & ACCESS_FS_FILE,
+            };
+            handles.push((OwnedFd::from(file), access));
+        }
+
+        Ok(handles)
+    }
+}
+
+/// An extension trait for running a [`Command`] inside a [`SandboxProfile`].
+pub trait SandboxExt {
+    /// Apply `profile` to the command. The restrictions take effect in the child process only,
+    /// right before the program is executed. Spawning fails if the kernel does not support the
+    /// requested restrictions.
+    fn sandbox(&mut self, profile: &SandboxProfile) -> io::Result<&mut Self>;
+
+    /// Apply `profile`, run the command to completion, and convert a sandbox kill into an
+    /// [`Error::SandboxViolation`].
+    fn sandboxed_output(&mut self, profile: &SandboxProfile) -> Result<Output, Error>;
+}
+
+impl SandboxExt for Command {
+    fn sandbox(&mut self, profile: &SandboxProfile) -> io::Result<&mut Self> {
+        let restrict_fs = !profile.rules.is_empty();
+        let deny_network = profile.deny_network;
+        let rules = profile.open_rules()?;
+
+        // SAFETY: the hook only issues raw syscalls on data prepared in the parent and does not
+        // allocate, so it is safe to run between fork and exec.
+        unsafe {
+            self.pre_exec(move || {
+                set_no_new_privs()?;
+                if restrict_fs {
+                    restrict_filesystem(&rules)?;
+                }
+                if deny_network {
+                    install_network_filter()?;
+                }
+                Ok(())
+            });
+        }
+
+        Ok(self)
+    }
+
+    fn sandboxed_output(&mut self, profile: &SandboxProfile) -> Result<Output, Error> {
+        let output = self.sandbox(profile)?.output()?;
+        match violation(self.get_program(), profile, &output) {
+            Some(err) => Err(err),
+            None => Ok(output),
+        }
+    }
+}
+
+/// Apply `profile` to `command`, which is built for a test. If the profile cannot be applied,
+/// spawning the command fails with the error instead.
+pub(crate) fn apply(command: &mut Command, profile: &SandboxProfile) {
+    if let Err(e) = command.sandbox(profile) {
+        let code = e.raw_os_error().unwrap_or(libc::EINVAL);
+        // SAFETY: the hook only builds an error from an integer, which does not allocate.
+        unsafe {
+            command.pre_exec(move || Err(io::Error::from_raw_os_error(code)));
+        }
+    }
+}
+
+/// The violation `output` shows `program`, run under `profile`, was stopped for, if any.
+pub(crate) fn violation(
+    program: impl AsRef<OsStr>,
+    profile: &SandboxProfile,
+    output: &Output,
+) -> Option<Error> {
+    if output.status.signal() == Some(libc::SIGSYS) {
+        return Some(Error::SandboxViolation(format!(
+            "{:?} was killed for making a forbidden system call (network access denied)",
+            program.as_ref()
+        )));
+    }
+    if profile.rules.is_empty() || output.status.success() {
+        return None;
+    }
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    stderr
+        .lines()
+        .find(|line| line.contains(PERMISSION_DENIED))
+        .map(|line| {
+            Error::SandboxViolation(format!(
+                "{:?} was denied filesystem access: {}",
+                program.as_ref(),
+                line.trim()
+            ))
+        })
+}
+
+fn check(ret: libc::c_long) -> io::Result<libc::c_long> {
+    match ret < 0 {
+        true => Err(io::Error::last_os_error()),
+        false => Ok(ret),
+    }
+}
+
+fn set_no_new_privs() -> io::Result<()> {
+    // SAFETY: prctl with integer arguments has no memory safety requirements.
+    let ret = unsafe { libc::prctl(libc::PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0) };
+    check(ret as libc::c_long).map(|_| ())
+}
+
+fn restrict_filesystem(rules: &[(OwnedFd, u64)]) -> io::Result<()> {
+    let attr = RulesetAttr {
+        handled_access_fs: ACCESS_FS_ALL,
+    };
+
+    // SAFETY: `attr` outlives the call and its size is passed alongside it.
+    let ruleset = check(unsafe {
+        libc::syscall(
+            libc::SYS_landlock_create_ruleset,
+            &attr as *const RulesetAttr,
+            std::mem::size_of::<RulesetAttr>(),
+            0,
+        )
+    })? as libc::c_int;
+
+    let result = (|| {
+        for (fd, access) in rules {
+            let rule = PathBeneathAttr {
+                allowed_access: *access,
+                parent_fd: fd.as_raw_fd(),
+            };
+
+            // SAFETY: `rule` outlives the call and matches the kernel's packed layout.
+            check(unsafe {
+                libc::syscall(
+                    libc::SYS_landlock_add_rule,
+                    ruleset,
+                    LANDLOCK_RULE_PATH_BENEATH,
+                    &rule as *const PathBeneathAttr,
+                    0,
+                )
+            })?;
+        }
+
+        // SAFETY: `ruleset` is a valid Landlock ruleset descriptor.
+        check(unsafe { libc::syscall(libc::SYS_landlock_restrict_self, ruleset, 0) }).map(|_| ())
+    })();
+
+    // SAFETY: `ruleset` is owned by this function and closed exactly once.
+    unsafe { libc::close(ruleset) };
+    result
+}
+
+fn install_network_filter() -> io::Result<()> {
+    const LD_W_ABS: u16 = (libc::BPF_LD | libc::BPF_W | libc::BPF_ABS) as u16;
+    const JEQ_K: u16 = (libc::BPF_JMP | libc::BPF_JEQ | libc::BPF_K) as u16;
+    const JGE_K: u16 = (libc::BPF_JMP | libc::BPF_JGE | libc::BPF_K) as u16;
+    const RET_K: u16 = (libc::BPF_RET | libc::BPF_K) as u16;
+
+    // Offsets into `struct seccomp_data`. The first argument is read as its low 32 bits, which
+    // sit first on the little-endian targets this module supports.
+    const NR: u32 = 0;
+    const ARCH: u32 = 4;
+    const ARG0: u32 = 16;
+
+    let op = |code: u16, jt: u8, jf: u8, k: u32| libc::sock_filter { code, jt, jf, k };
+    let filter = [
+        op(LD_W_ABS, 0, 0, ARCH),
+        op(JEQ_K, 1, 0, AUDIT_ARCH),
+        op(RET_K, 0, 0, libc::SECCOMP_RET_KILL_PROCESS),
+        op(LD_W_ABS, 0, 0, NR),
+        op(JGE_K, 5, 0, X32_SYSCALL_BIT),
+        op(JEQ_K, 0, 3, libc::SYS_socket as u32),
+        op(LD_W_ABS, 0, 0, ARG0),
+        op(JEQ_K, 2, 0, libc::AF_INET as u32),
+        op(JEQ_K, 1, 0, libc::AF_INET6 as u32),
+        op(RET_K, 0, 0, libc::SECCOMP_RET_ALLOW),
+        op(RET_K, 0, 0, libc::SECCOMP_RET_KILL_PROCESS),
+    ];
+
+    let program = libc::sock_fprog {
+        len: filter.len() as u16,
+        filter: filter.as_ptr() as *mut libc::sock_filter,
+    };
+
+    // SAFETY: `program` and the filter it points to outlive the call.
+    let ret = unsafe {
+        libc::prctl(
+            libc::PR_SET_SECCOMP,
+            libc::SECCOMP_MODE_FILTER,
+            &program as *const libc::sock_fprog,
+        )
+    };
+    check(ret as libc::c_long).map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd;
+
+    #[test]
+    fn sandbox_allows_permitted_command() {
+        let profile = SandboxProfile::new().allow_system_paths().deny_network();
+        let output = cmd!("echo -n hello").sandboxed_output(&profile).unwrap();
+        assert_eq!(output.stdout, b"hello");
+    }
+
+    #[test]
+    fn sandbox_reports_network_violation() {
+        let profile = SandboxProfile::new().deny_network();
+        let result =
+            cmd!("bash" => ["-c", "echo > /dev/tcp/127.0.0.1/9"]).sandboxed_output(&profile);
+        assert!(matches!(result, Err(Error::SandboxViolation(_))));
+    }
+
+    #[test]
+    fn sandbox_blocks_unlisted_paths() {
+        let manifest = concat!(env!("CARGO_MANIFEST_DIR"), "/Cargo.toml");
+        let profile = SandboxProfile::new().allow_system_paths();
+
+        match cmd!("cat" => [manifest]).sandboxed_output(&profile) {
+            Err(Error::SandboxViolation(message)) => {
+                assert!(message.contains("denied filesystem access"), "{}", message)
+            }
+            other => panic!("expected a sandbox violation, got {:?}", other),
+        }
+
+        let output = cmd!("cat" => [manifest])
+            .sandboxed_output(&profile.allow_read(manifest))
+            .unwrap();
+        assert!(output.status.success());
+    }
+
+    #[test]
+    fn test_profiles_apply_to_built_commands() {
+        use crate::{command::CommandExt, prelude::*, OutputDest};
+
+        fn connects() -> ExtelResult {
+            cmd!("bash" => ["-c", "echo > /dev/tcp/127.0.0.1/9"]).output_checked()?;
+            Ok(())
+        }
+
+        fn echoes() -> ExtelResult {
+            let output = cmd!("echo -n hello").output_checked()?;
+            extel_assert_eq!(output.stdout, b"hello")
+        }
+
+        init_test_suite!(ProfiledSuite, connects, echoes);
+        let results = ProfiledSuite::run(
+            TestConfig::default()
+                .output(OutputDest::None)
+                .sandbox(SandboxProfile::new().allow_system_paths().deny_network()),
+        );
+
+        assert!(matches!(
+            results[0].test_result.cases[0].result,
+            Err(Error::SandboxViolation(_))
+        ));
+        assert!(results[1].test_result.is_ok(), "{:?}", results[1]);
+    }
+}
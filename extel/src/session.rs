@@ -0,0 +1,251 @@
+//! Driving interactive programs, such as REPLs and installers that prompt for input, in the style
+//! of `expect`.
+
+use std::{
+    io::Write,
+    process::{Child, ChildStdin, Command, Output, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    command::{self, capture, Capture, Stream},
+    diff::{abbreviate_tail, MESSAGE_LIMIT},
+    err,
+    errors::Error,
+    wait::POLL_INTERVAL,
+};
+
+/// An interactive command whose stdin is written to as the test goes, and whose stdout is waited
+/// on for prompts. The process is killed when the session is dropped without being
+/// [waited](Session::wait) on.
+///
+/// # Example
+/// ```rust
+/// use extel::{prelude::*, session::Session};
+/// use std::time::Duration;
+///
+/// fn greeter() -> ExtelResult {
+///     let script = "printf 'name> '; read name; echo \"hello, $name\"; printf 'again? '; read _";
+///     let mut session = Session::spawn(cmd!("sh" => ["-c", script]))?;
+///
+///     session.expect("name> ", Duration::from_secs(5))?;
+///     session.send_line("extel")?;
+///     let greeting = session.expect("again? ", Duration::from_secs(5))?;
+///     extel_assert_eq!(greeting, "hello, extel\nagain? ")?;
+///
+///     session.send_line("no")?;
+///     let output = session.wait(Duration::from_secs(5))?;
+///     extel_assert!(output.status.success())?;
+///     assert_contains!(session.transcript(), "hello, extel")
+/// }
+///
+/// assert!(greeter().is_ok());
+/// ```
+pub struct Session {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    stdout: Capture,
+    stderr: Capture,
+    command: String,
+    wrapped: bool,
+    started: Instant,
+    cursor: usize,
+    finished: bool,
+}
+
+impl Session {
+    /// Spawn `command` with its stdin, stdout, and stderr connected to the session.
+    pub fn spawn(command: &mut Command) -> Result<Self, Error> {
+        let started = Instant::now();
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()?;
+
+        Ok(Self {
+            stdin: child.stdin.take(),
+            stdout: capture(child.stdout.take(), Stream::Stdout),
+            stderr: capture(child.stderr.take(), Stream::Stderr),
+            child,
+            command: command::describe(command),
+            wrapped: command::is_wrapped(command),
+            started,
+            cursor: 0,
+            finished: false,
+        })
+    }
+
+    /// Write `text` to the command's stdin.
+    pub fn send(&mut self, text: impl AsRef<[u8]>) -> Result<(), Error> {
+        let stdin = self.stdin.as_mut().ok_or_else(|| {
+            Error::Io(std::io::Error::new(
+                std::io::ErrorKind::BrokenPipe,
+                "stdin of the session was closed",
+            ))
+        })?;
+        stdin.write_all(text.as_ref())?;
+        Ok(stdin.flush()?)
+    }
+
+    /// Write `line` followed by a newline to the command's stdin.
+    pub fn send_line(&mut self, line: impl AsRef<str>) -> Result<(), Error> {
+        self.send(format!("{}\n", line.as_ref()))
+    }
+
+    /// Close the command's stdin, signalling the end of its input.
+    pub fn close_stdin(&mut self) {
+        self.stdin = None;
+    }
+
+    /// Wait until `pattern` appears in stdout after the text matched by the previous call,
+    /// returning everything up to and including it. Fails with an [`Error::Timeout`] holding the
+    /// output so far if `pattern` does not appear within `timeout`, or with an
+    /// [`Error::TestFailed`] if the command exits first.
+    pub fn expect(&mut self, pattern: &str, timeout: Duration) -> Result<String, Error> {
+        let deadline = Instant::now() + timeout;
+        let mut exited = None;
+        loop {
+            let stdout = self.stdout.snapshot();
+            let unread = &stdout[self.cursor..];
+            let found = match pattern.is_empty() {
+                true => Some(0),
+                false => unread
+                    .windows(pattern.len())
+                    .position(|window| window == pattern.as_bytes()),
+            };
+            if let Some(idx) = found {
+                let end = idx + pattern.len();
+                self.cursor += end;
+                return Ok(String::from_utf8_lossy(&unread[..end]).into_owned());
+            }
+
+            // Look at the output one last time once the command has exited and it has all been
+            // read, in case the pattern arrived along with the exit.
+            if let Some(status) = exited {
+                return Err(err!(
+                    "session exited with {} while waiting for {:?}\n  unread: {}",
+                    status,
+                    pattern,
+                    abbreviate_tail(&String::from_utf8_lossy(unread), MESSAGE_LIMIT)
+                ));
+            }
+
+            if let Some(status) = self.child.try_wait()? {
+                self.stdout.join();
+                exited = Some(status);
+                continue;
+            }
+
+            if Instant::now() >= deadline {
+                return Err(Error::Timeout {
+                    timeout,
+                    stdout: self.stdout.snapshot(),
+                    stderr: self.stderr.snapshot(),
+                });
+            }
+
+            thread::sleep(POLL_INTERVAL);
+        }
+    }
+
+    /// Everything the command has written to stdout so far, lossily converted to UTF-8.
+    pub fn transcript(&self) -> String {
+        String::from_utf8_lossy(&self.stdout.snapshot()).into_owned()
+    }
+
+    /// Everything the command has written to stderr so far, lossily converted to UTF-8.
+    pub fn stderr(&self) -> String {
+        String::from_utf8_lossy(&self.stderr.snapshot()).into_owned()
+    }
+
+    /// Close stdin and wait for the command to exit, returning its status and everything it
+    /// wrote. The command is killed and an [`Error::Timeout`] returned if it does not exit within
+    /// `timeout`. The run is recorded on the current test like any other
+    /// [command](crate::command::CommandExt).
+    pub fn wait(&mut self, timeout: Duration) -> Result<Output, Error> {
+        self.close_stdin();
+        let status = command::wait_with_deadline(&mut self.child, Instant::now() + timeout)?;
+        self.finished = true;
+
+        let Some(status) = status else {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+            command::record(self.command.clone(), self.started, None);
+            return Err(Error::Timeout {
+                timeout,
+                stdout: self.stdout.snapshot(),
+                stderr: self.stderr.snapshot(),
+            });
+        };
+
+        self.stdout.join();
+        self.stderr.join();
+        command::record(self.command.clone(), self.started, status.code());
+        command::check_status(
+            &self.command,
+            self.wrapped,
+            Output {
+                status,
+                stdout: self.stdout.snapshot(),
+                stderr: self.stderr.snapshot(),
+            },
+        )
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        if !self.finished {
+            let _ = self.child.kill();
+            let _ = self.child.wait();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd;
+
+    #[test]
+    fn expect_consumes_output_in_order() {
+        let mut session = Session::spawn(&mut cmd!("cat")).unwrap();
+
+        session.send_line("first").unwrap();
+        session.send_line("second").unwrap();
+        assert_eq!(
+            session.expect("first", Duration::from_secs(5)).unwrap(),
+            "first"
+        );
+        assert_eq!(
+            session.expect("second", Duration::from_secs(5)).unwrap(),
+            "\nsecond"
+        );
+
+        let output = session.wait(Duration::from_secs(5)).unwrap();
+        assert_eq!(output.stdout, b"first\nsecond\n");
+    }
+
+    #[test]
+    fn expect_times_out_with_partial_output() {
+        let mut session = Session::spawn(&mut cmd!("cat")).unwrap();
+
+        session.send_line("prompt?").unwrap();
+        match session.expect("never", Duration::from_millis(200)) {
+            Err(Error::Timeout { stdout, .. }) => assert_eq!(stdout, b"prompt?\n"),
+            other => panic!("expected a timeout, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn expect_fails_when_session_exits() {
+        let mut session = Session::spawn(cmd!("sh" => ["-c", "echo bye"])).unwrap();
+
+        match session.expect("prompt>", Duration::from_secs(5)) {
+            Err(Error::TestFailed(msg)) => assert!(msg.ends_with("\"bye\\n\"")),
+            other => panic!("expected the session to exit, got {:?}", other),
+        }
+    }
+}
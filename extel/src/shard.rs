@@ -0,0 +1,122 @@
+//! Splitting the tests of a suite between several runs, such as parallel CI jobs. See
+//! [`TestConfig::shard`](crate::TestConfig::shard).
+
+use std::{fmt, str::FromStr};
+
+use crate::{errors::Error, fs::fnv1a};
+
+/// One of `total` disjoint slices of a suite's tests. Every test belongs to exactly one shard,
+/// decided by a stable hash of its suite and test name, so the same test always lands in the same
+/// shard regardless of the machine, the order of the tests, or which other tests exist. A
+/// parameterized test belongs to a shard as a whole, and all of its cases run there.
+///
+/// # Example
+/// ```rust
+/// use extel::shard::Shard;
+///
+/// let shards: Vec<Shard> = (0..3).map(|index| Shard::new(index, 3).unwrap()).collect();
+/// let owners = shards.iter().filter(|shard| shard.contains("Suite", "parses_flags")).count();
+/// assert_eq!(owners, 1);
+///
+/// assert_eq!("1/3".parse::<Shard>().unwrap(), shards[1]);
+/// assert!(Shard::new(3, 3).is_err());
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Shard {
+    index: usize,
+    total: usize,
+}
+
+impl Shard {
+    /// The shard at the 0-based `index` out of `total` shards. Fails with an
+    /// [`Error::Config`] unless `index < total`.
+    pub fn new(index: usize, total: usize) -> Result<Self, Error> {
+        match index < total {
+            true => Ok(Self { index, total }),
+            false => Err(Error::Config(format!(
+                "shard index {} is out of range for {} shard(s)",
+                index, total
+            ))),
+        }
+    }
+
+    /// The 0-based index of the shard.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
+    /// The number of shards the tests are split between.
+    pub fn total(&self) -> usize {
+        self.total
+    }
+
+    /// Whether or not `test_name` in `suite_name` belongs to this shard.
+    pub fn contains(&self, suite_name: &str, test_name: &str) -> bool {
+        let identity = format!("{}::{}", suite_name, test_name);
+        fnv1a(identity.as_bytes()) % self.total as u64 == self.index as u64
+    }
+}
+
+impl FromStr for Shard {
+    type Err = Error;
+
+    /// Parse a shard written as `INDEX/TOTAL`, such as `0/4`.
+    fn from_str(s: &str) -> Result<Self, Error> {
+        let invalid = || Error::Config(format!("shard must be INDEX/TOTAL, got '{}'", s));
+        let (index, total) = s.split_once('/').ok_or_else(invalid)?;
+        Shard::new(
+            index.trim().parse().map_err(|_| invalid())?,
+            total.trim().parse().map_err(|_| invalid())?,
+        )
+    }
+}
+
+impl fmt::Display for Shard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.index, self.total)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{prelude::*, OutputDest, SuiteBuilder};
+
+    #[test]
+    fn shards_split_tests_without_overlap() {
+        let names: Vec<String> = (0..100).map(|n| format!("test_{}", n)).collect();
+        let run_shard = |index| {
+            let mut suite = SuiteBuilder::new("Sharded");
+            for name in &names {
+                suite = suite.add_test(name.clone(), || -> ExtelResult { pass!() });
+            }
+            suite
+                .run(
+                    TestConfig::default()
+                        .output(OutputDest::None)
+                        .shard(index, 4),
+                )
+                .into_iter()
+                .map(|result| result.test_name.into_owned())
+                .collect::<Vec<_>>()
+        };
+
+        let mut seen: Vec<String> = Vec::new();
+        for index in 0..4 {
+            let shard = run_shard(index);
+            assert!(shard.len() > 10, "shard {} only ran {:?}", index, shard);
+            seen.extend(shard);
+        }
+        seen.sort();
+        let mut expected = names.clone();
+        expected.sort();
+        assert_eq!(seen, expected);
+    }
+
+    #[test]
+    fn invalid_shards_are_rejected() {
+        for invalid in ["4", "a/4", "1/0", "4/4"] {
+            assert!(matches!(invalid.parse::<Shard>(), Err(Error::Config(_))));
+        }
+    }
+}
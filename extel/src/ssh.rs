@@ -0,0 +1,205 @@
+//! Running commands on a remote host over SSH, for programs that only exist on another machine,
+//! such as a lab board.
+//!
+//! Commands are run through the system `ssh` client in batch mode, so the host must be reachable
+//! without a password prompt, through a key or an agent. The returned [`Command`] runs like any
+//! local command: its output is captured, it can be run through
+//! [`CommandExt`](crate::command::CommandExt), and its exit status is the exit status of the
+//! remote command. The `ssh` client itself exits with [`SSH_FAILURE`] when it cannot connect.
+//!
+//! ```rust,no_run
+//! use extel::{prelude::*, remote_cmd, ssh::Remote};
+//!
+//! fn firmware_reports_version() -> ExtelResult {
+//!     let board = Remote::new("lab-board-3").user("ci").port(2222);
+//!     let output = remote_cmd!(board, "fwctl --version").output_checked()?;
+//!     assert_contains!(String::from_utf8_lossy(&output.stdout), "fwctl 4.")
+//! }
+//! ```
+//!
+//! > *This is only available with the `ssh` feature enabled.*
+
+use std::{
+    ffi::{OsStr, OsString},
+    path::PathBuf,
+    process::Command,
+};
+
+use crate::{executor::CommandExecutor, report::shell_quote};
+
+/// The exit code of the `ssh` client when it fails to connect or authenticate, rather than running
+/// the remote command.
+pub const SSH_FAILURE: i32 = 255;
+
+/// A remote host to run commands on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Remote {
+    program: OsString,
+    host: String,
+    user: Option<String>,
+    port: Option<u16>,
+    identity: Option<PathBuf>,
+    options: Vec<String>,
+}
+
+impl Remote {
+    /// A remote `host`, as a host name, an address, a `user@host` pair, or an alias from the SSH
+    /// client configuration.
+    pub fn new(host: impl Into<String>) -> Self {
+        Self {
+            program: OsString::from("ssh"),
+            host: host.into(),
+            user: None,
+            port: None,
+            identity: None,
+            options: Vec::new(),
+        }
+    }
+
+    /// Log in as `user`.
+    pub fn user(mut self, user: impl Into<String>) -> Self {
+        self.user = Some(user.into());
+        self
+    }
+
+    /// Connect to `port` instead of the default SSH port.
+    pub fn port(mut self, port: u16) -> Self {
+        self.port = Some(port);
+        self
+    }
+
+    /// Authenticate with the private key at `path`.
+    pub fn identity(mut self, path: impl Into<PathBuf>) -> Self {
+        self.identity = Some(path.into());
+        self
+    }
+
+    /// Pass a client configuration option, such as `StrictHostKeyChecking=no`, to `ssh`.
+    pub fn option(mut self, option: impl Into<String>) -> Self {
+        self.options.push(option.into());
+        self
+    }
+
+    /// Use another SSH client program instead of `ssh`.
+    pub fn program(mut self, program: impl Into<OsString>) -> Self {
+        self.program = program.into();
+        self
+    }
+
+    /// A command that runs `command_line` on the remote host. The command line is interpreted by
+    /// the remote user's shell, so it may use pipes, redirections, and quotes. This is what
+    /// [`remote_cmd!`](crate::remote_cmd) uses to create its commands.
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::ssh::Remote;
+    ///
+    /// let command = Remote::new("lab-board-3").port(2222).command("uname -a");
+    /// let args: Vec<_> = command.get_args().collect();
+    /// assert_eq!(args, ["-o", "BatchMode=yes", "-p", "2222", "lab-board-3", "--", "uname -a"]);
+    /// ```
+    pub fn command(&self, command_line: &str) -> Command {
+        let mut command = Command::new(&self.program);
+        command.args(["-o", "BatchMode=yes"]);
+        for option in &self.options {
+            command.args(["-o", option]);
+        }
+        if let Some(user) = &self.user {
+            command.args(["-l", user]);
+        }
+        if let Some(port) = self.port {
+            command.args(["-p", &port.to_string()]);
+        }
+        if let Some(identity) = &self.identity {
+            command.arg("-i").arg(identity);
+        }
+        command.args([self.host.as_str(), "--", command_line]);
+        command
+    }
+
+    /// A command that runs `program` with `args` on the remote host. Unlike
+    /// [`command`](Self::command), every argument is quoted, so it reaches the remote program
+    /// verbatim.
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::ssh::Remote;
+    ///
+    /// let command = Remote::new("lab-board-3").command_args("echo", ["it's here"]);
+    /// assert_eq!(command.get_args().last().unwrap(), r"'echo' 'it'\''s here'");
+    /// ```
+    pub fn command_args<I, S>(&self, program: &str, args: I) -> Command
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let command_line = std::iter::once(shell_quote(program))
+            .chain(args.into_iter().map(|arg| shell_quote(arg.as_ref())))
+            .collect::<Vec<_>>()
+            .join(" ");
+        self.command(&command_line)
+    }
+}
+
+impl CommandExecutor for Remote {
+    fn command(&self, program: &OsStr, args: &[OsString]) -> Command {
+        self.command_args(
+            &program.to_string_lossy(),
+            args.iter().map(|arg| arg.to_string_lossy()),
+        )
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::fixture::TempDir;
+    use std::{fs, os::unix::fs::PermissionsExt};
+
+    /// A stand-in for `ssh` that prints its arguments one per line and runs the remote command
+    /// locally.
+    fn fake_ssh(tmp: &TempDir) -> PathBuf {
+        let path = tmp.path().join("ssh");
+        fs::write(
+            &path,
+            "#!/bin/sh\n\
+             for arg; do echo \"$arg\" >&2; done\n\
+             while [ \"$1\" != -- ]; do shift; done\n\
+             exec sh -c \"$2\"\n",
+        )
+        .unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    #[test]
+    fn remote_commands_capture_output_and_status() {
+        let tmp = TempDir::new().unwrap();
+        let remote = Remote::new("board")
+            .program(fake_ssh(&tmp))
+            .user("ci")
+            .identity("/keys/ci")
+            .option("ConnectTimeout=5");
+
+        let output = remote.command("echo hello; exit 3").output().unwrap();
+        assert_eq!(output.stdout, b"hello\n");
+        assert_eq!(output.status.code(), Some(3));
+        assert_eq!(
+            String::from_utf8_lossy(&output.stderr),
+            "-o\nBatchMode=yes\n-o\nConnectTimeout=5\n-l\nci\n-i\n/keys/ci\nboard\n--\n\
+             echo hello; exit 3\n"
+        );
+    }
+
+    #[test]
+    fn remote_arguments_are_quoted() {
+        let tmp = TempDir::new().unwrap();
+        let remote = Remote::new("board").program(fake_ssh(&tmp));
+
+        let output = remote
+            .command_args("printf", ["%s|", "a b", "$HOME", "it's"])
+            .output()
+            .unwrap();
+        assert_eq!(output.stdout, b"a b|$HOME|it's|");
+    }
+}
@@ -0,0 +1,143 @@
+//! State persisted between test runs, such as which tests failed the last time they ran.
+//!
+//! The state file is a plain text file with one `suite name<TAB>test name` line per failing test.
+//! It is updated after every suite run: tests that ran and failed are added, and tests that ran
+//! and passed are removed. Tests that did not run keep their previous state.
+
+use std::{collections::BTreeSet, fs, io, path::Path, sync::Mutex};
+
+use crate::TestResult;
+
+/// The default location of the state file, relative to the current working directory.
+pub const DEFAULT_STATE_FILE: &str = ".extel/last-run";
+
+/// Serializes updates so that suites run on different threads do not clobber each other's state.
+static STATE_LOCK: Mutex<()> = Mutex::new(());
+
+/// Read every `(suite name, test name)` pair from the state file. A missing file has no entries.
+fn read_entries(path: &Path) -> io::Result<BTreeSet<(String, String)>> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(BTreeSet::new()),
+        Err(e) => return Err(e),
+    };
+
+    Ok(contents
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(suite, test)| (suite.to_string(), test.to_string()))
+        .collect())
+}
+
+/// The names of the tests in `suite_name` that failed the last time they ran.
+///
+/// # Example
+/// ```rust
+/// use extel::{fixture::TempDir, prelude::*, state, OutputDest};
+///
+/// fn always_fail() -> ExtelResult {
+///     fail!("no luck")
+/// }
+///
+/// let tmp = TempDir::new().unwrap();
+/// let state_file = tmp.path().join("last-run");
+///
+/// init_test_suite!(FailingSuite as "failing", always_fail);
+/// FailingSuite::run(
+///     TestConfig::default()
+///         .output(OutputDest::None)
+///         .state_file(Some(state_file.clone())),
+/// );
+///
+/// let failures = state::last_failures(&state_file, "failing").unwrap();
+/// assert!(failures.contains("always_fail"));
+/// ```
+pub fn last_failures(path: impl AsRef<Path>, suite_name: &str) -> io::Result<BTreeSet<String>> {
+    let _guard = STATE_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    Ok(read_entries(path.as_ref())?
+        .into_iter()
+        .filter(|(suite, _)| suite == suite_name)
+        .map(|(_, test)| test)
+        .collect())
+}
+
+/// Update the state file with the results of a run of `suite_name`.
+pub fn record_results(
+    path: impl AsRef<Path>,
+    suite_name: &str,
+    results: &[TestResult],
+) -> io::Result<()> {
+    let path = path.as_ref();
+    let _guard = STATE_LOCK
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    let mut entries = read_entries(path)?;
+    for result in results {
+        let entry = (suite_name.to_string(), result.test_name.to_string());
+        match result.test_result.is_ok() {
+            true => entries.remove(&entry),
+            false => entries.insert(entry),
+        };
+    }
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let contents: String = entries
+        .iter()
+        .map(|(suite, test)| format!("{}\t{}\n", suite, test))
+        .collect();
+    fs::write(path, contents)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{fixture::TempDir, prelude::*, OutputDest};
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    static SHOULD_FAIL: AtomicBool = AtomicBool::new(true);
+
+    fn flaky() -> ExtelResult {
+        match SHOULD_FAIL.load(Ordering::SeqCst) {
+            true => fail!("flaky failure"),
+            false => pass!(),
+        }
+    }
+
+    fn always_succeed() -> ExtelResult {
+        pass!()
+    }
+
+    #[test]
+    fn rerun_failed_runs_only_last_failures() {
+        init_test_suite!(RerunTestSet as "rerun", always_succeed, flaky);
+
+        let tmp = TempDir::new().unwrap();
+        let state_file = tmp.path().join("nested").join("last-run");
+        let cfg = || {
+            TestConfig::default()
+                .output(OutputDest::None)
+                .state_file(Some(state_file.clone()))
+                .rerun_failed(true)
+        };
+
+        // No state yet, so every test runs.
+        assert_eq!(RerunTestSet::run(cfg()).len(), 2);
+        assert_eq!(fs::read_to_string(&state_file).unwrap(), "rerun\tflaky\n");
+
+        SHOULD_FAIL.store(false, Ordering::SeqCst);
+        let results = RerunTestSet::run(cfg());
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].test_name, "flaky");
+        assert_eq!(fs::read_to_string(&state_file).unwrap(), "");
+
+        // With nothing left to rerun, every test runs again.
+        assert_eq!(RerunTestSet::run(cfg()).len(), 2);
+    }
+}
@@ -0,0 +1,91 @@
+//! Named steps within a test, so that the report of a failing multi-stage test shows which steps
+//! completed and which one failed, rather than a single failure message.
+//!
+//! Steps are run with [`step!`](crate::step). Each step is timed and recorded on the
+//! [result](crate::TestResult::steps) of the test running it, and the steps of a failing test are
+//! listed below its failure.
+//!
+//! ```rust
+//! use extel::{prelude::*, step, OutputDest};
+//!
+//! fn deploys() -> ExtelResult {
+//!     step!("build", || cmd!("true").output_checked())?;
+//!     let output = step!("start", || cmd!("echo started").output_checked())?;
+//!     step!("check health", || {
+//!         assert_contains!(String::from_utf8_lossy(&output.stdout), "healthy")
+//!     })
+//! }
+//!
+//! init_test_suite!(DeploySuite, deploys);
+//! let results = DeploySuite::run(TestConfig::default().output(OutputDest::None));
+//!
+//! let steps: Vec<_> = results[0].steps.iter().map(|s| (s.name.as_str(), s.passed)).collect();
+//! assert_eq!(steps, [("build", true), ("start", true), ("check health", false)]);
+//! ```
+
+use std::time::{Duration, Instant};
+
+use crate::{catch_panic, context, errors::Error};
+
+/// A step run by a test, as recorded on its [result](crate::TestResult::steps).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StepRecord {
+    /// The description of the step.
+    pub name: String,
+    /// Whether the step succeeded.
+    pub passed: bool,
+    /// How long the step ran for.
+    pub duration: Duration,
+}
+
+/// Run `f` as a step called `name` of the current test, recording whether it succeeded and how
+/// long it took. A panic in `f` fails the step with an [`Error::Panicked`]. This is what
+/// [`step!`](crate::step) calls.
+pub fn run<T>(name: impl Into<String>, f: impl FnOnce() -> Result<T, Error>) -> Result<T, Error> {
+    let started = Instant::now();
+    let result = catch_panic(f).and_then(|result| result);
+    let step = StepRecord {
+        name: name.into(),
+        passed: result.is_ok(),
+        duration: started.elapsed(),
+    };
+
+    context::with_scope(|scope| {
+        if let Some(scope) = scope {
+            scope.recorded.steps.push(step);
+        }
+    });
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{prelude::*, OutputDest, SharedBuffer};
+
+    fn stops_at_second_step() -> ExtelResult {
+        step!("first", || pass!())?;
+        step!("second", || -> ExtelResult { panic!("boom") })?;
+        step!("third", || pass!())
+    }
+
+    #[test]
+    fn failing_tests_list_their_steps() {
+        init_test_suite!(StepSuite, stops_at_second_step);
+        let output = SharedBuffer::new();
+        let results = StepSuite::run(
+            TestConfig::default()
+                .output(OutputDest::Buffer(output.clone()))
+                .colored(false),
+        );
+
+        assert_eq!(results[0].steps.len(), 2);
+        let output = output.to_string_lossy();
+        assert!(output.contains("\t  [step] first ... ok ("), "{}", output);
+        assert!(
+            output.contains("\t  [step] second ... FAILED ("),
+            "{}",
+            output
+        );
+        assert!(!output.contains("third"), "{}", output);
+    }
+}
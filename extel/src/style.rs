@@ -0,0 +1,143 @@
+//! The words and colors used to show test statuses. See
+//! [`TestConfig::style`](crate::TestConfig::style).
+
+/// How the status of each test is shown by the built-in reporters. Colors are given as ANSI SGR
+/// parameters, such as `"32"` for green or `"1;91"` for bold bright red, and are only used when
+/// output is [colored](crate::TestConfig::colored).
+///
+/// # Example
+/// ```rust
+/// use extel::{prelude::*, style::Style, OutputDest, SharedBuffer};
+///
+/// fn always_succeed() -> ExtelResult {
+///     pass!()
+/// }
+///
+/// init_test_suite!(StyledSuite as "styled", always_succeed);
+///
+/// let output_buffer = SharedBuffer::new();
+/// StyledSuite::run(
+///     TestConfig::default()
+///         .output(OutputDest::Buffer(output_buffer.clone()))
+///         .colored(true)
+///         .style(Style::unicode().ok_color("36")),
+/// );
+///
+/// assert_eq!(
+///     output_buffer.to_string_lossy(),
+///     "[styled]\n\tTest #1 (always_succeed) ... \x1b[36m✓\x1b[0m\n"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Style {
+    /// The word shown for a passing test.
+    pub ok: String,
+    /// The word shown for a failing test.
+    pub failed: String,
+    /// The word shown for a [skipped](crate::errors::Error::Skipped) test.
+    pub skipped: String,
+    /// The color of [`ok`](Style::ok).
+    pub ok_color: String,
+    /// The color of [`failed`](Style::failed).
+    pub failed_color: String,
+    /// The color of [`skipped`](Style::skipped).
+    pub skipped_color: String,
+}
+
+impl Default for Style {
+    /// `ok` in green, `FAILED` in red, and `skipped` in yellow.
+    fn default() -> Self {
+        Self {
+            ok: String::from("ok"),
+            failed: String::from("FAILED"),
+            skipped: String::from("skipped"),
+            ok_color: String::from("32"),
+            failed_color: String::from("31"),
+            skipped_color: String::from("33"),
+        }
+    }
+}
+
+impl Style {
+    /// `✓` in green, `✗` in red, and `-` in yellow.
+    pub fn unicode() -> Self {
+        Self::default().ok("✓").failed("✗").skipped("-")
+    }
+
+    /// `PASS`, `FAIL`, and `SKIP` in bold, for log viewers with limited or no color support.
+    pub fn plain() -> Self {
+        Self::default()
+            .ok("PASS")
+            .failed("FAIL")
+            .skipped("SKIP")
+            .ok_color("1")
+            .failed_color("1")
+            .skipped_color("1")
+    }
+
+    /// Look up a built-in style by name: `default`, `unicode`, or `plain`.
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(Self::default()),
+            "unicode" => Some(Self::unicode()),
+            "plain" => Some(Self::plain()),
+            _ => None,
+        }
+    }
+
+    /// Change the word shown for a passing test.
+    pub fn ok(mut self, word: impl Into<String>) -> Self {
+        self.ok = word.into();
+        self
+    }
+
+    /// Change the word shown for a failing test.
+    pub fn failed(mut self, word: impl Into<String>) -> Self {
+        self.failed = word.into();
+        self
+    }
+
+    /// Change the word shown for a skipped test.
+    pub fn skipped(mut self, word: impl Into<String>) -> Self {
+        self.skipped = word.into();
+        self
+    }
+
+    /// Change the color of the word shown for a passing test.
+    pub fn ok_color(mut self, sgr: impl Into<String>) -> Self {
+        self.ok_color = sgr.into();
+        self
+    }
+
+    /// Change the color of the word shown for a failing test.
+    pub fn failed_color(mut self, sgr: impl Into<String>) -> Self {
+        self.failed_color = sgr.into();
+        self
+    }
+
+    /// Change the color of the word shown for a skipped test.
+    pub fn skipped_color(mut self, sgr: impl Into<String>) -> Self {
+        self.skipped_color = sgr.into();
+        self
+    }
+
+    /// The status word for a passing or failing test, colored if `colored` is set.
+    pub(crate) fn status(&self, passed: bool, colored: bool) -> String {
+        match passed {
+            true => paint(&self.ok, &self.ok_color, colored),
+            false => paint(&self.failed, &self.failed_color, colored),
+        }
+    }
+
+    /// The status word for a skipped test, colored if `colored` is set.
+    pub(crate) fn skipped_status(&self, colored: bool) -> String {
+        paint(&self.skipped, &self.skipped_color, colored)
+    }
+}
+
+fn paint(word: &str, color: &str, colored: bool) -> String {
+    match colored {
+        true => format!("\x1b[{}m{}\x1b[0m", color, word),
+        false => word.to_string(),
+    }
+}
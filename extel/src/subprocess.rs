@@ -0,0 +1,476 @@
+//! Running each test in its own re-execution of the test binary. See
+//! [`TestConfig::isolate_process`](crate::TestConfig::isolate_process).
+//!
+//! The runner starts the current executable again with the same arguments, naming the test to
+//! run in environment variables. When the child reaches the suite containing that test, it runs
+//! only that test, writes the result to a file named by the runner, and exits. The result file
+//! holds one tab-separated record per line, with tabs, newlines, and backslashes escaped.
+
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+    process::{Command, ExitStatus},
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use crate::{
+    command::CommandRecord, err, errors::Error, outcome::OmittedCases, step::StepRecord,
+    usage::ResourceUsage, CaseResult, Test, TestOutcome, TestResult,
+};
+
+/// The suite containing the test a child process should run.
+const SUITE_ENV: &str = "__EXTEL_CHILD_SUITE";
+/// The test a child process should run.
+const TEST_ENV: &str = "__EXTEL_CHILD_TEST";
+/// The file a child process should write its result to.
+const RESULT_ENV: &str = "__EXTEL_RESULT_FILE";
+
+/// A request, received from the runner, to run a single test in this process.
+pub(crate) struct ChildRequest {
+    pub(crate) suite_name: String,
+    pub(crate) test_name: String,
+    result_file: PathBuf,
+}
+
+impl ChildRequest {
+    /// The request this process was started with, if it was started by a runner.
+    pub(crate) fn from_env() -> Option<Self> {
+        Some(Self {
+            suite_name: std::env::var(SUITE_ENV).ok()?,
+            test_name: std::env::var(TEST_ENV).ok()?,
+            result_file: std::env::var_os(RESULT_ENV)?.into(),
+        })
+    }
+
+    /// Send `result` back to the runner and exit.
+    pub(crate) fn finish(self, result: &TestResult) -> ! {
+        let code = match fs::write(&self.result_file, encode(result)) {
+            Ok(()) => 0,
+            Err(_) => 1,
+        };
+        std::process::exit(code)
+    }
+}
+
+/// Run `test` in a new process started from the current executable, with the arguments this
+/// process was started with.
+pub(crate) fn run_in_child(suite_name: &str, test: Test) -> TestResult {
+    let exe = std::env::current_exe()
+        .unwrap_or_else(|_| PathBuf::from(std::env::args_os().next().unwrap_or_default()));
+    run_child(&exe, std::env::args_os().skip(1), suite_name, test)
+}
+
+fn run_child(
+    exe: &Path,
+    args: impl IntoIterator<Item = OsString>,
+    suite_name: &str,
+    test: Test,
+) -> TestResult {
+    static RESULT_ID: AtomicUsize = AtomicUsize::new(0);
+    let result_file = std::env::temp_dir().join(format!(
+        "extel-result-{}-{}",
+        std::process::id(),
+        RESULT_ID.fetch_add(1, Ordering::Relaxed)
+    ));
+
+    let status = Command::new(exe)
+        .args(args)
+        .env(SUITE_ENV, suite_name)
+        .env(TEST_ENV, test.test_name.as_ref())
+        .env(RESULT_ENV, &result_file)
+        .status();
+    let contents = fs::read_to_string(&result_file);
+    let _ = fs::remove_file(&result_file);
+
+    let mut result = blank_result(test);
+    result.test_result = match (status, contents) {
+        (Err(e), _) => TestOutcome::single(Err(Error::Io(e))),
+        (Ok(_), Ok(contents)) => decode(&contents, &mut result),
+        (Ok(status), Err(_)) => TestOutcome::single(Err(crashed(status))),
+    };
+
+    result
+}
+
+/// A passing result for `test`, to be filled in with what the child process reported.
+pub(crate) fn blank_result(test: Test) -> TestResult {
+    TestResult {
+        test_name: test.test_name,
+        test_result: TestOutcome::single(Ok(())),
+        tags: test.tags,
+        artifacts: Vec::new(),
+        resource_usage: Vec::new(),
+        metadata: HashMap::new(),
+        duration: Default::default(),
+        commands: Vec::new(),
+        logs: Vec::new(),
+        steps: Vec::new(),
+        failure_context: Vec::new(),
+        #[cfg(all(feature = "capture", unix))]
+        captured: None,
+    }
+}
+
+/// The error of a test whose process exited without reporting a result.
+fn crashed(status: ExitStatus) -> Error {
+    err!(
+        "test process exited with {} before reporting a result",
+        status
+    )
+}
+
+/// Serialize the parts of `result` that the runner does not already know.
+fn encode(result: &TestResult) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    lines.push(String::from(match result.test_result.parameterized {
+        true => "parameterized",
+        false => "single",
+    }));
+
+    for case in &result.test_result.cases {
+        lines.push(match &case.result {
+            Ok(()) => String::from("ok"),
+            Err(e) => {
+                let (kind, msg) = match e {
+                    Error::Skipped(msg) => ("skipped", msg.clone()),
+                    Error::NotRun(msg) => ("not_run", msg.clone()),
+                    Error::Panicked(msg) => ("panicked", msg.clone()),
+                    e => ("failed", e.to_string()),
+                };
+                format!("err\t{}\t{}", kind, escape(&msg))
+            }
+        });
+        if let Some(name) = &case.name {
+            lines.push(format!("case_name\t{}", escape(name)));
+        }
+        if let Some(duration) = case.duration {
+            lines.push(format!("case_duration\t{}", duration.as_nanos()));
+        }
+        #[cfg(all(feature = "capture", unix))]
+        if let Some(captured) = &case.captured {
+            lines.push(format!(
+                "case_captured\t{}\t{}",
+                escape(&captured.stdout),
+                escape(&captured.stderr)
+            ));
+        }
+    }
+
+    let omitted = result.test_result.omitted;
+    if omitted.total() > 0 {
+        lines.push(format!(
+            "omitted\t{}\t{}\t{}",
+            omitted.passed, omitted.failed, omitted.skipped
+        ));
+    }
+
+    for artifact in &result.artifacts {
+        lines.push(format!("artifact\t{}", escape(&artifact.to_string_lossy())));
+    }
+
+    for usage in &result.resource_usage {
+        lines.push(format!(
+            "usage\t{}\t{}\t{}",
+            usage.max_rss,
+            usage.user_time.as_nanos(),
+            usage.system_time.as_nanos()
+        ));
+    }
+
+    for command in &result.commands {
+        lines.push(format!(
+            "command\t{}\t{}\t{}",
+            escape(&command.command),
+            command
+                .exit_code
+                .map_or_else(|| String::from("-"), |code| code.to_string()),
+            command.duration.as_nanos()
+        ));
+    }
+
+    for line in &result.logs {
+        lines.push(format!("log\t{}", escape(line)));
+    }
+
+    for step in &result.steps {
+        lines.push(format!(
+            "step\t{}\t{}\t{}",
+            escape(&step.name),
+            step.passed,
+            step.duration.as_nanos()
+        ));
+    }
+
+    for (name, context) in &result.failure_context {
+        lines.push(format!("context\t{}\t{}", escape(name), escape(context)));
+    }
+
+    for (key, value) in &result.metadata {
+        lines.push(format!("metadata\t{}\t{}", escape(key), escape(value)));
+    }
+
+    #[cfg(all(feature = "capture", unix))]
+    if let Some(captured) = &result.captured {
+        lines.push(format!(
+            "captured\t{}\t{}",
+            escape(&captured.stdout),
+            escape(&captured.stderr)
+        ));
+    }
+
+    lines.join("\n") + "\n"
+}
+
+/// Deserialize the result written by [`encode`], filling in the details carried alongside the
+/// test status on `result`. Errors are reported with their original kind and message.
+fn decode(contents: &str, result: &mut TestResult) -> TestOutcome {
+    let mut kind = "single";
+    let mut cases: Vec<CaseResult> = Vec::new();
+    let mut omitted = OmittedCases::default();
+
+    for line in contents.lines() {
+        let fields: Vec<String> = line.split('\t').map(unescape).collect();
+        match fields.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+            ["single"] => kind = "single",
+            ["parameterized"] => kind = "parameterized",
+            ["ok"] => cases.push(CaseResult::new(Ok(()))),
+            ["err", kind, msg] => {
+                let msg = msg.to_string();
+                cases.push(CaseResult::new(Err(match kind {
+                    "skipped" => Error::Skipped(msg),
+                    "not_run" => Error::NotRun(msg),
+                    "panicked" => Error::Panicked(msg),
+                    _ => Error::TestFailed(msg),
+                })));
+            }
+            ["err", msg] => cases.push(CaseResult::new(Err(Error::TestFailed(msg.to_string())))),
+            ["case_name", name] => {
+                if let Some(case) = cases.last_mut() {
+                    case.name = Some(name.to_string());
+                }
+            }
+            ["case_duration", duration] => {
+                if let Some(case) = cases.last_mut() {
+                    case.duration = duration.parse().ok().map(Duration::from_nanos);
+                }
+            }
+            #[cfg(all(feature = "capture", unix))]
+            ["case_captured", stdout, stderr] => {
+                if let Some(case) = cases.last_mut() {
+                    case.captured = Some(crate::capture::CapturedOutput {
+                        stdout: stdout.to_string(),
+                        stderr: stderr.to_string(),
+                    });
+                }
+            }
+            ["omitted", passed, failed, skipped] => {
+                omitted = OmittedCases {
+                    passed: passed.parse().unwrap_or_default(),
+                    failed: failed.parse().unwrap_or_default(),
+                    skipped: skipped.parse().unwrap_or_default(),
+                };
+            }
+            ["artifact", path] => result.artifacts.push(path.into()),
+            ["usage", max_rss, user, system] => {
+                let nanos = |s: &str| Duration::from_nanos(s.parse().unwrap_or_default());
+                result.resource_usage.push(ResourceUsage {
+                    max_rss: max_rss.parse().unwrap_or_default(),
+                    user_time: nanos(user),
+                    system_time: nanos(system),
+                });
+            }
+            ["command", command, exit_code, duration] => {
+                result.commands.push(CommandRecord {
+                    command: command.to_string(),
+                    exit_code: exit_code.parse().ok(),
+                    duration: Duration::from_nanos(duration.parse().unwrap_or_default()),
+                });
+            }
+            ["log", line] => result.logs.push(line.to_string()),
+            ["context", name, context] => result
+                .failure_context
+                .push((name.to_string(), context.to_string())),
+            ["step", name, passed, duration] => result.steps.push(StepRecord {
+                name: name.to_string(),
+                passed: passed == "true",
+                duration: Duration::from_nanos(duration.parse().unwrap_or_default()),
+            }),
+            ["metadata", key, value] => {
+                result.metadata.insert(key.to_string(), value.to_string());
+            }
+            #[cfg(all(feature = "capture", unix))]
+            ["captured", stdout, stderr] => {
+                result.captured = Some(crate::capture::CapturedOutput {
+                    stdout: stdout.to_string(),
+                    stderr: stderr.to_string(),
+                });
+            }
+            _ => {}
+        }
+    }
+
+    match kind {
+        "parameterized" => TestOutcome {
+            cases,
+            parameterized: true,
+            omitted,
+        },
+        _ => TestOutcome {
+            cases: vec![cases
+                .pop()
+                .unwrap_or_else(|| CaseResult::new(Err(err!("test process reported no result"))))],
+            parameterized: false,
+            omitted: OmittedCases::default(),
+        },
+    }
+}
+
+fn escape(s: &str) -> String {
+    s.replace('\\', "\\\\")
+        .replace('\n', "\\n")
+        .replace('\t', "\\t")
+}
+
+fn unescape(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    let mut chars = s.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some(other) => out.push(other),
+                None => out.push('\\'),
+            },
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ExtelResult;
+
+    fn never_called() -> ExtelResult {
+        unreachable!("tests run in a child process are not called by the runner")
+    }
+
+    fn test(name: &'static str) -> Test {
+        Test {
+            test_name: name.into(),
+            test_fn: Box::new(|| never_called().into()),
+            tags: &["slow"],
+            module_path: "",
+        }
+    }
+
+    #[test]
+    fn results_survive_encoding() {
+        let mut original = blank_result(test("case"));
+        original.test_result = TestOutcome::parameterized([
+            CaseResult::new(Ok(())),
+            CaseResult::named("quoted", Err(err!("expected\t'a'\ngot \\'b'")))
+                .duration(Duration::from_millis(3)),
+        ]);
+        original.artifacts.push(PathBuf::from("/tmp/out.log"));
+        original.commands.push(CommandRecord {
+            command: String::from("app --flag"),
+            exit_code: None,
+            duration: Duration::from_millis(5),
+        });
+        original.steps.push(StepRecord {
+            name: String::from("start\tserver"),
+            passed: false,
+            duration: Duration::from_millis(7),
+        });
+        original.failure_context.push((
+            String::from("docker ps"),
+            String::from("ID\tIMAGE\nabc\tpostgres"),
+        ));
+        original
+            .metadata
+            .insert(String::from("seed"), String::from("42"));
+
+        let mut decoded = blank_result(test("case"));
+        let outcome = decode(&encode(&original), &mut decoded);
+
+        assert!(outcome.parameterized);
+        assert!(outcome.cases[0].is_ok());
+        assert_eq!(
+            outcome.cases[1].result.as_ref().unwrap_err().to_string(),
+            "expected\t'a'\ngot \\'b'"
+        );
+        assert_eq!(outcome.cases[1].name.as_deref(), Some("quoted"));
+        assert_eq!(outcome.cases[1].duration, Some(Duration::from_millis(3)));
+        assert_eq!(decoded.artifacts, original.artifacts);
+        assert_eq!(decoded.commands, original.commands);
+        assert_eq!(decoded.steps, original.steps);
+        assert_eq!(decoded.failure_context, original.failure_context);
+        assert_eq!(decoded.metadata, original.metadata);
+    }
+
+    #[test]
+    fn error_kinds_survive_encoding() {
+        let mut original = blank_result(test("case"));
+        original.test_result = TestOutcome::parameterized([
+            CaseResult::new(Err(Error::Skipped(String::from("needs docker")))),
+            CaseResult::new(Err(Error::NotRun(String::from("suite timed out")))),
+            CaseResult::new(Err(Error::Panicked(String::from("index out of bounds")))),
+            CaseResult::new(Err(err!("wrong answer"))),
+            CaseResult::new(Err(Error::Config(String::from("bad flag")))),
+        ]);
+
+        let mut decoded = blank_result(test("case"));
+        let outcome = decode(&encode(&original), &mut decoded);
+
+        assert!(matches!(
+            &outcome.cases[..],
+            [
+                CaseResult { result: Err(Error::Skipped(skipped)), .. },
+                CaseResult { result: Err(Error::NotRun(not_run)), .. },
+                CaseResult { result: Err(Error::Panicked(panicked)), .. },
+                CaseResult { result: Err(Error::TestFailed(failed)), .. },
+                CaseResult { result: Err(Error::TestFailed(config)), .. },
+            ] if skipped == "needs docker"
+                && not_run == "suite timed out"
+                && panicked == "index out of bounds"
+                && failed == "wrong answer"
+                && config == "invalid configuration: bad flag"
+        ));
+        assert_eq!(outcome.skipped(), 1);
+    }
+
+    #[test]
+    fn child_result_file_is_read() {
+        let script = "printf 'single\\nerr\\tbad\\\\nthing\\n' > \"$__EXTEL_RESULT_FILE\"";
+        let result = run_child(
+            Path::new("sh"),
+            [OsString::from("-c"), OsString::from(script)],
+            "suite",
+            test("case"),
+        );
+
+        assert_eq!(result.test_name, "case");
+        assert_eq!(result.tags, &["slow"]);
+        assert!(matches!(
+            &result.test_result.cases[..],
+            [CaseResult { result: Err(Error::TestFailed(msg)), .. }] if msg == "bad\nthing"
+        ));
+    }
+
+    #[test]
+    fn crashed_child_fails_the_test() {
+        let result = run_child(Path::new("false"), [], "suite", test("case"));
+
+        assert!(matches!(
+            &result.test_result.cases[..],
+            [CaseResult { result: Err(Error::TestFailed(msg)), .. }]
+                if msg == "test process exited with exit status: 1 before reporting a result"
+        ));
+    }
+}
@@ -0,0 +1,181 @@
+//! Building test suites at runtime, for tests that cannot be listed in
+//! [`init_test_suite`](crate::init_test_suite) ahead of time, such as one test per file in a
+//! directory of fixtures.
+
+use std::{borrow::Cow, time::Instant};
+
+use crate::{
+    cases, catch_panic, run_tests, CaseResult, ExtelResult, SuiteReport, Test, TestConfig, TestFn,
+    TestOutcome, TestResult,
+};
+
+/// A test suite assembled at runtime. Tests can be any function accepted by
+/// [`init_test_suite`](crate::init_test_suite), as well as closures capturing their inputs.
+///
+/// # Example
+/// ```rust
+/// use extel::{prelude::*, SuiteBuilder};
+///
+/// fn always_succeed() -> ExtelResult {
+///     pass!()
+/// }
+///
+/// let mut suite = SuiteBuilder::new("Generated").add_test("always_succeed", always_succeed);
+/// for word in ["hello", "world"] {
+///     suite = suite.add_test(format!("echo_{}", word), move || {
+///         let output = cmd!("echo -n {}", word).output()?;
+///         extel_assert!(output.stdout == word.as_bytes())
+///     });
+/// }
+///
+/// let results = suite.add_param_test("small", [1, 2, 5], |x| extel_assert!(x < 3)).run(
+///     TestConfig::default().output(extel::OutputDest::None),
+/// );
+/// assert_eq!(results.len(), 4);
+/// assert!(!results[3].test_result.is_ok());
+/// ```
+pub struct SuiteBuilder {
+    suite_name: String,
+    tests: Vec<Test>,
+}
+
+impl SuiteBuilder {
+    /// Create an empty suite called `suite_name`.
+    pub fn new(suite_name: impl Into<String>) -> Self {
+        Self {
+            suite_name: suite_name.into(),
+            tests: Vec::new(),
+        }
+    }
+
+    /// Add a test called `test_name`.
+    pub fn add_test<Args>(
+        self,
+        test_name: impl Into<Cow<'static, str>>,
+        test: impl TestFn<Args> + 'static,
+    ) -> Self {
+        self.add_tagged_test(test_name, &[], test)
+    }
+
+    /// Add a test called `test_name` with [tags](crate::TestConfig::tags).
+    pub fn add_tagged_test<Args>(
+        mut self,
+        test_name: impl Into<Cow<'static, str>>,
+        tags: &'static [&'static str],
+        test: impl TestFn<Args> + 'static,
+    ) -> Self {
+        self.tests.push(Test {
+            tags,
+            ..Test::new(test_name, test)
+        });
+        self
+    }
+
+    /// Add a parameterized test called `test_name` that calls `test` once with each of `cases`,
+    /// like a function annotated with `#[parameters]`.
+    pub fn add_param_test<P: Send + 'static>(
+        self,
+        test_name: impl Into<Cow<'static, str>>,
+        cases: impl IntoIterator<Item = P>,
+        test: impl Fn(P) -> ExtelResult + Sync + 'static,
+    ) -> Self {
+        let cases: Vec<P> = cases.into_iter().collect();
+        self.add_test(test_name, move || {
+            TestOutcome::parameterized(cases::run_streamed(cases, |case| {
+                let start = Instant::now();
+                let result = catch_panic(|| test(case)).and_then(|r| r);
+                CaseResult::new(result).duration(start.elapsed())
+            }))
+        })
+    }
+
+    /// Add a group of tests built by `build`. The tests of the group are named
+    /// `group_name::test_name`, so a whole group can be selected with a
+    /// [filter](crate::TestConfig::filter) on its path. Groups can be nested.
+    ///
+    /// # Example
+    /// ```rust
+    /// use extel::{prelude::*, SuiteBuilder};
+    ///
+    /// fn always_succeed() -> ExtelResult {
+    ///     pass!()
+    /// }
+    ///
+    /// let results = SuiteBuilder::new("Service")
+    ///     .group("auth", |auth| {
+    ///         auth.add_test("login", always_succeed)
+    ///             .group("tokens", |tokens| tokens.add_test("refresh", always_succeed))
+    ///     })
+    ///     .add_test("health", always_succeed)
+    ///     .run(TestConfig::default().filter("auth::"));
+    ///
+    /// let names: Vec<_> = results.iter().map(|r| r.test_name.as_ref()).collect();
+    /// assert_eq!(names, ["auth::login", "auth::tokens::refresh"]);
+    /// ```
+    pub fn group(self, group_name: &str, build: impl FnOnce(SuiteBuilder) -> SuiteBuilder) -> Self {
+        self.add_suite(build(SuiteBuilder::new(group_name)))
+    }
+
+    /// Add every test of `suite` as a [group](SuiteBuilder::group) named after it.
+    pub fn add_suite(mut self, suite: SuiteBuilder) -> Self {
+        self.tests.extend(suite.tests.into_iter().map(|test| Test {
+            test_name: format!("{}::{}", suite.suite_name, test.test_name).into(),
+            ..test
+        }));
+        self
+    }
+
+    /// The name of the suite.
+    pub fn suite_name(&self) -> &str {
+        &self.suite_name
+    }
+
+    /// Run the suite with the provided configuration, like
+    /// [`RunnableTestSet::run`](crate::RunnableTestSet::run).
+    pub fn run(self, cfg: TestConfig) -> Vec<TestResult> {
+        run_tests(&self.suite_name, self.tests, cfg)
+    }
+
+    /// Run the suite like [`run`](SuiteBuilder::run), collecting the results into a
+    /// [`SuiteReport`].
+    pub fn run_report(self, cfg: TestConfig) -> SuiteReport {
+        let start = Instant::now();
+        let name = self.suite_name.clone();
+        let results = self.run(cfg);
+        SuiteReport::new(name, results, start.elapsed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{context::TestContext, prelude::*, OutputDest, SharedBuffer};
+
+    #[test]
+    fn suite_builder_runs_every_kind_of_test() {
+        let output_buffer = SharedBuffer::new();
+        let results = SuiteBuilder::new("built")
+            .add_test("plain", || -> ExtelResult { pass!() })
+            .add_test("with_context", |ctx: &TestContext| {
+                extel_assert_eq!(ctx.name(), "with_context")
+            })
+            .add_tagged_test("tagged", &["slow"], || -> ExtelResult { fail!("too slow") })
+            .add_param_test("params", ["a", "bb"], |s| extel_assert!(s.len() == 1))
+            .run(
+                TestConfig::default()
+                    .output(OutputDest::Buffer(output_buffer.clone()))
+                    .colored(false)
+                    .rerun_hint(false),
+            );
+
+        assert_eq!(results.len(), 4);
+        assert!(results[0].test_result.is_ok());
+        assert!(results[1].test_result.is_ok());
+        assert_eq!(results[2].tags, &["slow"]);
+        let cases = &results[3].test_result.cases;
+        assert!(results[3].test_result.parameterized);
+        assert!(cases.len() == 2 && cases[0].is_ok() && !cases[1].is_ok());
+        assert!(cases.iter().all(|case| case.duration.is_some()));
+        assert!(output_buffer.to_string_lossy().starts_with("[built]\n"));
+    }
+}
@@ -0,0 +1,135 @@
+//! Resource usage of child processes, such as peak memory and CPU time.
+//!
+//! Usage is measured by `output_with_usage` on [`CommandExt`](crate::command::CommandExt). Every
+//! measurement taken while a test runs is also recorded on its [`TestResult`](crate::TestResult),
+//! and the default reporter prints it under the test.
+//!
+//! > *Measuring usage is only available with the `resource-usage` feature enabled, on Unix
+//! > platforms.*
+
+use std::{fmt, time::Duration};
+
+/// The resources used by a child process over its lifetime.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ResourceUsage {
+    /// The peak resident set size, in bytes.
+    pub max_rss: u64,
+    /// Time spent executing in user mode.
+    pub user_time: Duration,
+    /// Time spent executing in kernel mode.
+    pub system_time: Duration,
+}
+
+impl ResourceUsage {
+    /// The total CPU time, in both user and kernel mode.
+    pub fn cpu_time(&self) -> Duration {
+        self.user_time + self.system_time
+    }
+}
+
+impl fmt::Display for ResourceUsage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "peak RSS {} KiB, CPU {:.3}s",
+            self.max_rss / 1024,
+            self.cpu_time().as_secs_f64()
+        )
+    }
+}
+
+/// Record `usage` against the test currently running on this thread, if any.
+#[cfg(all(feature = "resource-usage", unix))]
+pub(crate) fn record(usage: ResourceUsage) {
+    crate::context::with_scope(|scope| {
+        if let Some(scope) = scope {
+            scope.recorded.resource_usage.push(usage);
+        }
+    });
+}
+
+/// Run `command` to completion, capturing its output, and reap it with `wait4` to get its
+/// resource usage.
+#[cfg(all(feature = "resource-usage", unix))]
+pub(crate) fn output_with_usage(
+    command: &mut std::process::Command,
+) -> Result<(std::process::Output, ResourceUsage), crate::errors::Error> {
+    use std::{
+        os::unix::process::ExitStatusExt,
+        process::{ExitStatus, Output, Stdio},
+    };
+
+    let mut child = command
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let stdout = crate::command::capture(child.stdout.take(), crate::command::Stream::Stdout);
+    let stderr = crate::command::capture(child.stderr.take(), crate::command::Stream::Stderr);
+
+    let mut status: libc::c_int = 0;
+    // SAFETY: `rusage` is plain old data that `wait4` fills in.
+    let mut rusage: libc::rusage = unsafe { std::mem::zeroed() };
+    let pid = child.id() as libc::pid_t;
+
+    // SAFETY: `pid` is our own child and has not been reaped yet.
+    while unsafe { libc::wait4(pid, &mut status, 0, &mut rusage) } < 0 {
+        let err = std::io::Error::last_os_error();
+        if err.kind() != std::io::ErrorKind::Interrupted {
+            return Err(err.into());
+        }
+    }
+
+    let timeval = |tv: libc::timeval| {
+        Duration::from_secs(tv.tv_sec as u64) + Duration::from_micros(tv.tv_usec as u64)
+    };
+
+    // Linux reports the peak RSS in KiB, while macOS reports it in bytes.
+    let rss_unit: u64 = match cfg!(target_os = "macos") {
+        true => 1,
+        false => 1024,
+    };
+
+    let usage = ResourceUsage {
+        max_rss: rusage.ru_maxrss as u64 * rss_unit,
+        user_time: timeval(rusage.ru_utime),
+        system_time: timeval(rusage.ru_stime),
+    };
+    record(usage);
+
+    Ok((
+        Output {
+            status: ExitStatus::from_raw(status),
+            stdout: stdout.finish(),
+            stderr: stderr.finish(),
+        },
+        usage,
+    ))
+}
+
+#[cfg(all(test, feature = "resource-usage", unix))]
+mod tests {
+    use crate::{command::CommandExt, prelude::*, OutputDest, SharedBuffer};
+
+    fn measured_command() -> ExtelResult {
+        let (output, usage) = cmd!("echo -n measured").output_with_usage()?;
+        extel_assert!(output.stdout == b"measured" && usage.max_rss > 0)
+    }
+
+    #[test]
+    fn usage_is_recorded_on_result() {
+        init_test_suite!(UsageTestSet as "usage", measured_command);
+
+        let output_buffer = SharedBuffer::new();
+        let results = UsageTestSet::run(
+            TestConfig::default()
+                .output(OutputDest::Buffer(output_buffer.clone()))
+                .colored(false),
+        );
+
+        assert!(results[0].test_result.is_ok());
+        assert_eq!(results[0].resource_usage.len(), 1);
+        assert!(output_buffer
+            .to_string_lossy()
+            .contains("\t  [usage] peak RSS "));
+    }
+}
@@ -0,0 +1,146 @@
+//! Utilities for waiting on conditions, such as a server started during a test accepting
+//! connections, without resorting to fixed sleeps.
+//!
+//! Every helper polls until its condition holds or the timeout elapses, and reports a timeout as
+//! an [`Error::TestFailed`](crate::errors::Error::TestFailed) so it can be propagated with `?`.
+//!
+//! # Example
+//! ```rust
+//! use extel::{prelude::*, wait::wait_for_port};
+//! use std::{net::TcpListener, time::Duration};
+//!
+//! fn server_is_up() -> ExtelResult {
+//!     let listener = TcpListener::bind("127.0.0.1:0")?;
+//!     wait_for_port(listener.local_addr()?, Duration::from_secs(1))
+//! }
+//!
+//! assert!(server_is_up().is_ok());
+//! ```
+
+use std::{
+    net::{SocketAddr, TcpStream, ToSocketAddrs},
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{err, http_client, ExtelResult};
+
+/// How often conditions are re-checked while waiting.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(20);
+
+/// Poll `condition` until it returns `true`, failing if it does not within `timeout`.
+///
+/// # Example
+/// ```rust
+/// use extel::wait::wait_for;
+/// use std::time::{Duration, Instant};
+///
+/// let start = Instant::now();
+/// assert!(wait_for(|| start.elapsed() > Duration::from_millis(50), Duration::from_secs(1)).is_ok());
+/// assert!(wait_for(|| false, Duration::from_millis(50)).is_err());
+/// ```
+pub fn wait_for(mut condition: impl FnMut() -> bool, timeout: Duration) -> ExtelResult {
+    let deadline = Instant::now() + timeout;
+    loop {
+        if condition() {
+            return Ok(());
+        }
+
+        let now = Instant::now();
+        if now >= deadline {
+            return Err(err!("condition was not met within {:?}", timeout));
+        }
+        thread::sleep(POLL_INTERVAL.min(deadline - now));
+    }
+}
+
+/// Wait until a TCP connection to `addr` succeeds.
+pub fn wait_for_port(addr: impl ToSocketAddrs, timeout: Duration) -> ExtelResult {
+    let addrs: Vec<SocketAddr> = addr.to_socket_addrs()?.collect();
+    wait_for(|| addrs.iter().any(port_open), timeout).map_err(|_| {
+        err!(
+            "port {} did not accept connections within {:?}",
+            describe(&addrs),
+            timeout
+        )
+    })
+}
+
+/// Wait until an HTTP `GET` of `url` responds with a `2xx` status. Only plain `http://` URLs are
+/// supported. Requests are sent with the client of the `http` module.
+pub fn wait_for_http(url: &str, timeout: Duration) -> ExtelResult {
+    http_client::split_url(url)?;
+    wait_for(
+        || {
+            http_client::request("GET", url)
+                .timeout(Duration::from_secs(1))
+                .send()
+                .is_ok_and(|response| (200..300).contains(&response.status))
+        },
+        timeout,
+    )
+    .map_err(|_| err!("{} did not respond with 2xx within {:?}", url, timeout))
+}
+
+/// Whether or not a TCP connection to `addr` can currently be established.
+pub(crate) fn port_open(addr: &SocketAddr) -> bool {
+    TcpStream::connect_timeout(addr, POLL_INTERVAL).is_ok()
+}
+
+fn describe(addrs: &[SocketAddr]) -> String {
+    addrs
+        .iter()
+        .map(ToString::to_string)
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{
+        io::{Read, Write},
+        net::TcpListener,
+    };
+
+    #[test]
+    fn wait_for_port_closed() {
+        // Bind and immediately release a port so that nothing is listening on it.
+        let addr = TcpListener::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+
+        let err = wait_for_port(addr, Duration::from_millis(50)).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with(&format!("port {} did not", addr)));
+    }
+
+    #[test]
+    fn wait_for_http_status() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/health", listener.local_addr().unwrap());
+
+        let server = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = Vec::new();
+            let mut buf = [0u8; 64];
+            while !request.ends_with(b"\r\n\r\n") {
+                match stream.read(&mut buf) {
+                    Ok(0) | Err(_) => break,
+                    Ok(n) => request.extend_from_slice(&buf[..n]),
+                }
+            }
+            stream.write_all(b"HTTP/1.0 200 OK\r\n\r\n").unwrap();
+        });
+
+        assert!(wait_for_http(&url, Duration::from_secs(5)).is_ok());
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn wait_for_http_rejects_https() {
+        assert!(wait_for_http("https://localhost", Duration::ZERO).is_err());
+    }
+}
@@ -0,0 +1,242 @@
+//! Re-running suites whenever the files they test change, for a fast edit-and-test loop while
+//! developing against a binary.
+//!
+//! Files are watched by polling their modification times, which works the same on every platform
+//! and needs no extra dependencies. Changes are debounced, so saving several files at once, or a
+//! build rewriting a binary in several steps, triggers a single run.
+//!
+//! ```rust,no_run
+//! use extel::{prelude::*, watch::Watcher};
+//!
+//! fn prints_version() -> ExtelResult {
+//!     let output = cmd!("target/debug/app --version").output_checked()?;
+//!     assert_starts_with!(String::from_utf8(output.stdout)?, "app ")
+//! }
+//!
+//! init_test_suite!(AppSuite, prints_version);
+//!
+//! fn main() {
+//!     Watcher::new()
+//!         .path("target/debug/app")
+//!         .path("tests/fixtures")
+//!         .run(|| {
+//!             AppSuite::run(extel::cli::parse_args());
+//!         })
+//!         .expect("could not watch files");
+//! }
+//! ```
+//!
+//! > *This is only available with the `watch` feature enabled.*
+
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    io::{IsTerminal, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, SystemTime},
+};
+
+/// The ANSI escape sequence that clears the terminal and moves the cursor to its top left.
+const CLEAR_SCREEN: &str = "\x1b[2J\x1b[H";
+
+/// What is known about a watched file: its modification time and size.
+type Snapshot = BTreeMap<PathBuf, (Option<SystemTime>, u64)>;
+
+/// Watches a set of files and directories for changes. Directories are watched recursively.
+#[derive(Debug, Clone)]
+pub struct Watcher {
+    paths: Vec<PathBuf>,
+    debounce: Duration,
+    poll_interval: Duration,
+    clear_screen: bool,
+}
+
+impl Default for Watcher {
+    fn default() -> Self {
+        Self {
+            paths: Vec::new(),
+            debounce: Duration::from_millis(200),
+            poll_interval: Duration::from_millis(250),
+            clear_screen: true,
+        }
+    }
+}
+
+impl Watcher {
+    /// A watcher with no paths, a debounce of 200ms, and a poll interval of 250ms that clears the
+    /// screen between runs.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Watch the file or directory at `path`, which does not need to exist yet.
+    pub fn path(mut self, path: impl Into<PathBuf>) -> Self {
+        self.paths.push(path.into());
+        self
+    }
+
+    /// Wait until no further changes have been seen for `debounce` before reporting a change.
+    pub fn debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// Check the watched paths for changes every `interval`.
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+
+    /// Change whether or not the screen is cleared before each run. The screen is only ever
+    /// cleared when stdout is a terminal.
+    pub fn clear_screen(mut self, yes: bool) -> Self {
+        self.clear_screen = yes;
+        self
+    }
+
+    /// Call `run` once, and then again every time a watched path changes. This only returns if
+    /// the watched paths can no longer be read.
+    pub fn run(self, mut run: impl FnMut()) -> io::Result<()> {
+        let mut snapshot = self.snapshot()?;
+        loop {
+            if self.clear_screen && io::stdout().is_terminal() {
+                print!("{}", CLEAR_SCREEN);
+                let _ = io::stdout().flush();
+            }
+            run();
+
+            let changed = self.wait_for_change(&mut snapshot)?;
+            println!(
+                "\n[watch] {} changed, running again",
+                describe_changes(&changed)
+            );
+        }
+    }
+
+    /// Block until a watched path differs from `snapshot`, and no further changes have happened
+    /// for the debounce period. Returns the paths that changed, and updates `snapshot` to match.
+    fn wait_for_change(&self, snapshot: &mut Snapshot) -> io::Result<Vec<PathBuf>> {
+        let mut current = loop {
+            thread::sleep(self.poll_interval);
+            let current = self.snapshot()?;
+            if current != *snapshot {
+                break current;
+            }
+        };
+
+        // Keep waiting while the files are still being written.
+        loop {
+            thread::sleep(self.debounce);
+            let settled = self.snapshot()?;
+            if settled == current {
+                break;
+            }
+            current = settled;
+        }
+
+        let changed = current
+            .iter()
+            .filter(|(path, state)| snapshot.get(*path) != Some(state))
+            .map(|(path, _)| path)
+            .chain(snapshot.keys().filter(|path| !current.contains_key(*path)))
+            .cloned()
+            .collect();
+        *snapshot = current;
+        Ok(changed)
+    }
+
+    /// Record the current state of every watched file.
+    fn snapshot(&self) -> io::Result<Snapshot> {
+        let mut snapshot = Snapshot::new();
+        for path in &self.paths {
+            collect(path, &mut snapshot)?;
+        }
+        Ok(snapshot)
+    }
+}
+
+/// Add `path`, and everything under it if it is a directory, to `snapshot`. Files that disappear
+/// while being read are skipped, and symlinks to directories are not followed.
+fn collect(path: &Path, snapshot: &mut Snapshot) -> io::Result<()> {
+    let metadata = match fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(e),
+    };
+
+    if metadata.is_dir() {
+        for entry in fs::read_dir(path)? {
+            collect(&entry?.path(), snapshot)?;
+        }
+    } else {
+        let metadata = fs::metadata(path).unwrap_or(metadata);
+        snapshot.insert(
+            path.to_path_buf(),
+            (metadata.modified().ok(), metadata.len()),
+        );
+    }
+    Ok(())
+}
+
+fn describe_changes(changed: &[PathBuf]) -> String {
+    match changed {
+        [path] => path.display().to_string(),
+        [path, rest @ ..] => format!("{} and {} other file(s)", path.display(), rest.len()),
+        [] => String::from("nothing"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixture::TempDir;
+
+    #[test]
+    fn changes_are_detected_once_settled() {
+        let tmp = TempDir::new().unwrap();
+        let nested = tmp.path().join("nested");
+        fs::create_dir(&nested).unwrap();
+        fs::write(nested.join("a.txt"), "a").unwrap();
+
+        let watcher = Watcher::new()
+            .path(tmp.path())
+            .poll_interval(Duration::from_millis(10))
+            .debounce(Duration::from_millis(50));
+        let mut snapshot = watcher.snapshot().unwrap();
+
+        let writer = {
+            let nested = nested.clone();
+            thread::spawn(move || {
+                thread::sleep(Duration::from_millis(50));
+                fs::write(nested.join("a.txt"), "changed").unwrap();
+                fs::write(nested.join("b.txt"), "new").unwrap();
+            })
+        };
+
+        let changed = watcher.wait_for_change(&mut snapshot).unwrap();
+        writer.join().unwrap();
+        assert_eq!(changed, [nested.join("a.txt"), nested.join("b.txt")]);
+        assert_eq!(snapshot.len(), 2);
+    }
+
+    #[test]
+    fn missing_paths_are_watched_for_creation() {
+        let tmp = TempDir::new().unwrap();
+        let binary = tmp.path().join("app");
+
+        let watcher = Watcher::new()
+            .path(&binary)
+            .poll_interval(Duration::from_millis(10))
+            .debounce(Duration::from_millis(10));
+        let mut snapshot = watcher.snapshot().unwrap();
+        assert!(snapshot.is_empty());
+
+        fs::write(&binary, "#!/bin/sh").unwrap();
+        assert_eq!(watcher.wait_for_change(&mut snapshot).unwrap(), [binary]);
+        assert_eq!(
+            describe_changes(&[PathBuf::from("a"), PathBuf::from("b")]),
+            "a and 1 other file(s)"
+        );
+    }
+}
@@ -1,7 +1,8 @@
 //! ## Extel Parameterized - Writing Parameterized Tests in Rust
 //! Extel Parameterized, or just *parameterized*, is a proc macro crate that serves to offer a
-//! [`parameters`](macro@crate::parameters) macro for converting single argument functions into a valid function that
-//! `Extel` can interpret.
+//! [`parameters`](macro@crate::parameters) macro for generating a `<name>_cases` function from a
+//! function with arguments that `Extel` can interpret. Functions with multiple arguments take one
+//! tuple per case, which is destructured into the argument list.
 //!
 //! ```rust
 //! use extel::prelude::*;
@@ -24,21 +25,155 @@
 //!     extel_assert!(x > 0, "{} <= 0", x)
 //! }
 //!
+//! #[parameters((1, "1"), (2, "two"))]
+//! fn multi_arg_test(x: i32, s: &str) -> ExtelResult {
+//!     extel_assert!(s.parse::<i32>().is_ok_and(|parsed| parsed == x), "{} != {}", s, x)
+//! }
+//!
+//! #[parameters(case("1", pass), case("one", fail("invalid digit")))]
+//! fn expected_test(s: &str) -> ExtelResult {
+//!     extel_assert!(s.parse::<i32>()? > 0)
+//! }
+//!
 //! fn main() {
-//!     init_test_suite!(ExtelDemo, single_test, param_test);
+//!     init_test_suite!(
+//!         ExtelDemo,
+//!         single_test,
+//!         param_test_cases,
+//!         multi_arg_test_cases,
+//!         expected_test_cases
+//!     );
 //!     ExtelDemo::run(TestConfig::default());
 //! }
 extern crate proc_macro;
 
-use proc_macro::{Ident, TokenStream, TokenTree};
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
 
+/// Generate a zero argument `<name>_cases` function that runs the annotated function once per case
+/// and returns a `Vec<ExtelResult>`. The annotated function itself is left untouched, so it can
+/// still be called directly with an argument. Functions with multiple arguments take one tuple per
+/// case, which is destructured into the argument list.
+///
+/// Register the generated `<name>_cases` function with a test suite:
+/// ```rust
+/// use extel::prelude::*;
+/// use extel_parameterized::parameters;
+///
+/// #[parameters(1, 2, 3)]
+/// fn positive(x: i32) -> ExtelResult {
+///     extel_assert!(x > 0)
+/// }
+///
+/// assert!(positive(5).is_ok());
+/// assert_eq!(positive_cases().len(), 3);
+///
+/// init_test_suite!(PositiveSuite, positive_cases);
+/// ```
+///
+/// A case written as `case(..., pass)`, `case(..., fail)`, or `case(..., fail("message"))` states
+/// the outcome it is expected to have, where the values before the expectation are the arguments
+/// of the case. A case expected to fail passes when the function returns an error containing the
+/// message, if one is given. See `extel::cases::Expected`.
+/// ```rust
+/// use extel::prelude::*;
+/// use extel_parameterized::parameters;
+///
+/// #[parameters(case(1, 1, pass), case(2, 0, fail("divide by zero")), (4, 2))]
+/// fn divides(a: i32, b: i32) -> ExtelResult {
+///     extel_assert!(b != 0, "cannot divide by zero")?;
+///     extel_assert_eq!(a % b, 0)
+/// }
+///
+/// assert!(divides_cases().iter().all(|result| result.is_ok()));
+/// ```
+///
+/// # Errors
+/// Misuse of the attribute is reported as a compile error pointing at the offending tokens. The
+/// attribute must be applied to a function:
+/// ```compile_fail
+/// use extel_parameterized::parameters;
+///
+/// #[parameters(1, 2)]
+/// struct NotAFunction;
+/// ```
+///
+/// The function must take at least one argument:
+/// ```compile_fail
+/// use extel::prelude::*;
+/// use extel_parameterized::parameters;
+///
+/// #[parameters(1, 2)]
+/// fn no_args() -> ExtelResult {
+///     pass!()
+/// }
+/// ```
+///
+/// The function must return an `ExtelResult`:
+/// ```compile_fail
+/// use extel_parameterized::parameters;
+///
+/// #[parameters(1, 2)]
+/// fn returns_bool(x: i32) -> bool {
+///     x > 0
+/// }
+/// ```
+///
+/// Every case of a multi-argument function must be a tuple with one value per argument:
+/// ```compile_fail
+/// use extel::prelude::*;
+/// use extel_parameterized::parameters;
+///
+/// #[parameters((1, 2), (3, 4, 5))]
+/// fn sums(a: i32, b: i32) -> ExtelResult {
+///     extel_assert!(a + b > 0)
+/// }
+/// ```
+///
+/// `from = TABLE` in place of the cases, where `TABLE` is a const or static slice or array, or a
+/// function returning a `Vec`, names a table of cases that several tests can share. Any type
+/// implementing `extel::cases::IntoParams` can be used. A lone value, such as
+/// `#[parameters(LIMIT)]` or `#[parameters(true)]`, is always a single case.
+/// ```rust
+/// use extel::prelude::*;
+/// use extel_parameterized::parameters;
+///
+/// const PORTS: &[(u16, bool)] = &[(80, true), (0, false), (8080, true)];
+///
+/// fn generated_ports() -> Vec<u16> {
+///     (1..=3).map(|n| n * 1000).collect()
+/// }
+///
+/// #[parameters(from = PORTS)]
+/// fn valid_port(port: u16, valid: bool) -> ExtelResult {
+///     extel_assert_eq!(port != 0, valid)
+/// }
+///
+/// #[parameters(from = generated_ports)]
+/// fn nonzero_port(port: u16) -> ExtelResult {
+///     extel_assert!(port != 0)
+/// }
+///
+/// assert_eq!(valid_port_cases().len(), 3);
+/// assert_eq!(nonzero_port_cases().len(), 3);
+/// ```
+///
+/// The last value of a `case(...)` must be its expectation:
+/// ```compile_fail
+/// use extel::prelude::*;
+/// use extel_parameterized::parameters;
+///
+/// #[parameters(case(1, ok))]
+/// fn positive(x: i32) -> ExtelResult {
+///     extel_assert!(x > 0)
+/// }
+/// ```
 #[proc_macro_attribute]
 pub fn parameters(attr: TokenStream, function: TokenStream) -> TokenStream {
-    let mut tokens: Vec<TokenTree> = function.clone().into_iter().collect();
+    let tokens: Vec<TokenTree> = function.clone().into_iter().collect();
 
-    let func_name_idx = match validate_parameters_spec(&tokens) {
+    let func_name_idx = match validate_function_spec(&tokens, "#[parameters(...)]") {
         Ok(name) => name,
-        Err(e) => panic!("{}", e),
+        Err(e) => return e,
     };
 
     // Get function name and parameter(s)
@@ -47,38 +182,545 @@ pub fn parameters(attr: TokenStream, function: TokenStream) -> TokenStream {
         tokens[func_name_idx].span(),
     );
 
-    let attr_list = attr.to_string();
-    let inner_func_name = format!("__{}", func_name);
+    let Some(args) = argument_list(&tokens[func_name_idx..]) else {
+        return compile_error(
+            &format!(
+                "#[parameters(...)] could not find the argument list of {}",
+                func_name
+            ),
+            span,
+        );
+    };
 
-    tokens[func_name_idx] = TokenTree::Ident(Ident::new(&inner_func_name, span));
+    let arg_count = argument_types(args.stream()).len();
+    if arg_count == 0 {
+        return compile_error(
+            "#[parameters(...)] requires a function with at least one argument",
+            args.span(),
+        );
+    }
+
+    if let Err(e) = validate_return_type(&tokens[func_name_idx..], span, "#[parameters(...)]") {
+        return e;
+    }
+
+    // `from = TABLE` names a table of cases, such as a const, static, or function, that can be
+    // shared between tests.
+    let table = match table(&attr) {
+        Some(Ok(table)) => Some(table),
+        Some(Err(e)) => return e,
+        None => None,
+    };
+
+    let cases = match table {
+        Some(_) => Vec::new(),
+        None => match parse_cases(attr) {
+            Ok(cases) => cases,
+            Err(e) => return e,
+        },
+    };
+
+    if arg_count > 1 && table.is_none() {
+        if let Err(e) = validate_tuple_cases(&cases, arg_count) {
+            return e;
+        }
+    }
+
+    // Cases are only paired with their expectations if any of them has one.
+    let expects = cases.iter().any(|case| case.expected.is_some());
+    let case_list = cases
+        .iter()
+        .map(|case| {
+            let value = case.value.iter().cloned().collect::<TokenStream>();
+            match expects {
+                true => format!(
+                    "({}, {})",
+                    value,
+                    case.expected.as_deref().unwrap_or(EXPECT_PASS)
+                ),
+                false => value.to_string(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    // A case for a function with multiple arguments is a tuple that is destructured into the
+    // argument list.
+    let (case_pattern, case_args) = match arg_count {
+        1 => (String::from("case"), String::from("case")),
+        n => {
+            let args = (0..n).map(|i| format!("arg{}", i)).collect::<Vec<_>>();
+            (format!("({})", args.join(", ")), args.join(", "))
+        }
+    };
 
     // Build test runner
+    let (runner, case_list) = match (table, expects) {
+        (Some(table), _) => (
+            "run",
+            format!("extel::cases::IntoParams::into_params({})", table),
+        ),
+        (None, true) => ("run_expected", format!("[{}]", case_list)),
+        (None, false) => ("run", format!("[{}]", case_list)),
+    };
     let test_runner_tokens = format!(
-        "[{attr_list}]
-            .into_iter()
-            .map({inner_func_name})
-            .collect::<Vec<extel::ExtelResult>>()"
+        "extel::cases::{}({}, |{}| {}({}))",
+        runner, case_list, case_pattern, func_name, case_args
     );
 
-    // Create wrapper around the input stream
+    // Keep the original function as-is and add a wrapper that runs every case
     let final_func = format!(
-        "{} {}() -> Vec<ExtelResult> {{ {} {} }}",
+        "{} {}_cases() -> Vec<extel::ExtelResult> {{ {} }} {}",
         tokens[0..func_name_idx]
             .iter()
             .map(|token| token.to_string())
             .collect::<Vec<_>>()
             .join(" "),
         func_name,
-        tokens.into_iter().collect::<TokenStream>(),
         test_runner_tokens,
+        function,
     );
 
     final_func.parse().unwrap()
 }
 
+/// Turn a function whose arguments all implement `extel::fixture::Fixture` into a zero argument
+/// test of the same name. Each fixture is set up, in order, before the body runs, and torn down
+/// when the body returns. A fixture that fails to set up fails the test before its body runs.
+///
+/// ```rust
+/// use extel::{fixture::TempDir, prelude::*};
+/// use extel_parameterized::fixtures;
+///
+/// #[fixtures]
+/// fn writes_file(tmp: TempDir) -> ExtelResult {
+///     std::fs::write(tmp.path().join("out.txt"), "hello")?;
+///     extel_assert!(tmp.path().join("out.txt").is_file())
+/// }
+///
+/// assert!(writes_file().is_ok());
+/// init_test_suite!(FixtureSuite, writes_file);
+/// ```
+///
+/// # Errors
+/// The function must return an `ExtelResult`:
+/// ```compile_fail
+/// use extel::fixture::TempDir;
+/// use extel_parameterized::fixtures;
+///
+/// #[fixtures]
+/// fn returns_bool(tmp: TempDir) -> bool {
+///     tmp.path().is_dir()
+/// }
+/// ```
+#[proc_macro_attribute]
+pub fn fixtures(_attr: TokenStream, function: TokenStream) -> TokenStream {
+    let mut tokens: Vec<TokenTree> = function.into_iter().collect();
+
+    let func_name_idx = match validate_function_spec(&tokens, "#[fixtures]") {
+        Ok(name) => name,
+        Err(e) => return e,
+    };
+
+    let (func_name, span) = (
+        tokens[func_name_idx].to_string(),
+        tokens[func_name_idx].span(),
+    );
+
+    let fixture_types = match argument_list(&tokens[func_name_idx..]) {
+        Some(args) => argument_types(args.stream()),
+        None => {
+            return compile_error(
+                &format!(
+                    "#[fixtures] could not find the argument list of {}",
+                    func_name
+                ),
+                span,
+            )
+        }
+    };
+
+    if let Err(e) = validate_return_type(&tokens[func_name_idx..], span, "#[fixtures]") {
+        return e;
+    }
+
+    let inner_func_name = format!("__{}", func_name);
+    tokens[func_name_idx] = TokenTree::Ident(Ident::new(&inner_func_name, span));
+
+    // Provision each fixture in order. Fixtures are dropped, and therefore torn down, as soon as
+    // the inner function returns.
+    let fixture_setup = fixture_types
+        .iter()
+        .map(|ty| format!("<{} as extel::fixture::Fixture>::setup()?", ty))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let final_func = format!(
+        "{} {}() -> extel::ExtelResult {{ {} {}({}) }}",
+        tokens[0..func_name_idx]
+            .iter()
+            .map(|token| token.to_string())
+            .collect::<Vec<_>>()
+            .join(" "),
+        func_name,
+        tokens.into_iter().collect::<TokenStream>(),
+        inner_func_name,
+        fixture_setup,
+    );
+
+    final_func.parse().unwrap()
+}
+
+/// Only run a test on platforms matching a `cfg` predicate, such as `#[extel_cfg(unix)]` or
+/// `#[extel_cfg(target_os = "linux")]`, and skip it elsewhere with the predicate as the reason.
+/// Unlike `#[cfg(...)]`, the test stays registered with its suite, so a platform-specific test
+/// shows up as skipped rather than silently disappearing from the report. The body of the test is
+/// still only compiled on matching platforms.
+///
+/// ```rust
+/// use extel::{errors::Error, prelude::*};
+/// use extel_parameterized::extel_cfg;
+///
+/// #[extel_cfg(unix)]
+/// fn uses_sh() -> ExtelResult {
+///     let output = cmd!("sh -c 'echo hi'").output()?;
+///     extel_assert!(output.status.success())
+/// }
+///
+/// #[extel_cfg(target_os = "plan9")]
+/// fn uses_rc() -> ExtelResult {
+///     fail!("never runs here")
+/// }
+///
+/// assert!(matches!(uses_rc(), Err(Error::Skipped(reason)) if reason == "requires cfg(target_os = \"plan9\")"));
+/// init_test_suite!(PlatformSuite, uses_sh, uses_rc);
+/// ```
+///
+/// Placed above [`parameters`](macro@crate::parameters), every case of the test is skipped.
+#[proc_macro_attribute]
+pub fn extel_cfg(attr: TokenStream, function: TokenStream) -> TokenStream {
+    let tokens: Vec<TokenTree> = function.clone().into_iter().collect();
+
+    let func_name_idx = match validate_function_spec(&tokens, "#[extel_cfg(...)]") {
+        Ok(name) => name,
+        Err(e) => return e,
+    };
+
+    let span = tokens[func_name_idx].span();
+    if attr.is_empty() {
+        return compile_error(
+            "#[extel_cfg(...)] requires a cfg predicate, such as #[extel_cfg(unix)]",
+            span,
+        );
+    }
+
+    if let Err(e) = validate_return_type(&tokens[func_name_idx..], span, "#[extel_cfg(...)]") {
+        return e;
+    }
+
+    // Everything up to the body is reused for the skipping function, arguments included, so that
+    // the test can be called and registered the same way on every platform.
+    let Some(TokenTree::Group(body)) = tokens.last() else {
+        return compile_error("#[extel_cfg(...)] requires a function with a body", span);
+    };
+    if body.delimiter() != Delimiter::Brace {
+        return compile_error("#[extel_cfg(...)] requires a function with a body", span);
+    }
+
+    let predicate = attr.to_string();
+    let skipping_func = format!(
+        "#[cfg(not({predicate}))] #[allow(unused_variables)] {} {{ \
+         ::core::result::Result::Err(extel::errors::Error::Skipped( \
+         ::std::string::String::from({:?}))) }}",
+        tokens[..tokens.len() - 1]
+            .iter()
+            .cloned()
+            .collect::<TokenStream>(),
+        format!("requires cfg({})", predicate),
+    );
+
+    format!("#[cfg({})] {} {}", predicate, function, skipping_func)
+        .parse()
+        .unwrap()
+}
+
+/// Find the argument list of a function, which is the first parenthesized group after its name.
+fn argument_list(tokens: &[TokenTree]) -> Option<&Group> {
+    tokens.iter().find_map(|token| match token {
+        TokenTree::Group(group) if group.delimiter() == Delimiter::Parenthesis => Some(group),
+        _ => None,
+    })
+}
+
+/// Validate that the function, starting at its name, declares a `Result` return type such as
+/// `ExtelResult`.
+fn validate_return_type(
+    tokens: &[TokenTree],
+    name_span: Span,
+    macro_name: &str,
+) -> Result<(), TokenStream> {
+    let arrow = tokens.windows(2).position(|pair| match pair {
+        [TokenTree::Punct(a), TokenTree::Punct(b)] => a.as_char() == '-' && b.as_char() == '>',
+        _ => false,
+    });
+
+    let Some(arrow) = arrow else {
+        return Err(compile_error(
+            &format!("{} requires the function to return ExtelResult", macro_name),
+            name_span,
+        ));
+    };
+
+    // The return type runs until the function body or a where clause. Only identifiers outside of
+    // generic arguments make up the path of the type itself.
+    let mut depth: usize = 0;
+    let mut last_ident: Option<&Ident> = None;
+    for token in &tokens[arrow + 2..] {
+        match token {
+            TokenTree::Group(group) if group.delimiter() == Delimiter::Brace => break,
+            TokenTree::Ident(ident) if depth == 0 && ident.to_string() == "where" => break,
+            TokenTree::Ident(ident) if depth == 0 => last_ident = Some(ident),
+            TokenTree::Punct(p) if p.as_char() == '<' => depth += 1,
+            TokenTree::Punct(p) if p.as_char() == '>' => depth = depth.saturating_sub(1),
+            _ => {}
+        }
+    }
+
+    match last_ident {
+        Some(ident) if ident.to_string().ends_with("Result") => Ok(()),
+        _ => Err(compile_error(
+            &format!("{} requires the function to return ExtelResult", macro_name),
+            tokens[arrow + 2..]
+                .first()
+                .map(TokenTree::span)
+                .unwrap_or(name_span),
+        )),
+    }
+}
+
+/// Split a comma separated token stream into its top level items.
+fn split_top_level(tokens: TokenStream) -> Vec<Vec<TokenTree>> {
+    let mut items: Vec<Vec<TokenTree>> = vec![Vec::new()];
+    for token in tokens {
+        match &token {
+            TokenTree::Punct(p) if p.as_char() == ',' => items.push(Vec::new()),
+            _ => items.last_mut().expect("items is never empty").push(token),
+        }
+    }
+
+    // Allow a trailing comma.
+    if items.last().is_some_and(Vec::is_empty) {
+        items.pop();
+    }
+    items
+}
+
+/// The table named by an attribute of the form `from = TABLE`, if it has that form.
+fn table(attr: &TokenStream) -> Option<Result<String, TokenStream>> {
+    let tokens: Vec<TokenTree> = attr.clone().into_iter().collect();
+    match &tokens[..] {
+        [TokenTree::Ident(from), TokenTree::Punct(eq), table @ ..]
+            if from.to_string() == "from"
+                && eq.as_char() == '='
+                && eq.spacing() == Spacing::Alone =>
+        {
+            Some(match table.is_empty() {
+                true => Err(compile_error(
+                    "#[parameters(from = ...)] requires a table of cases",
+                    eq.span(),
+                )),
+                false => Ok(table.iter().cloned().collect::<TokenStream>().to_string()),
+            })
+        }
+        _ => None,
+    }
+}
+
+const EXPECT_PASS: &str = "extel::cases::Expected::Pass";
+
+/// A case of `#[parameters(...)]`, along with the expectation it was given if it was written as
+/// `case(..., pass)` or `case(..., fail)`.
+struct Case {
+    value: Vec<TokenTree>,
+    expected: Option<String>,
+}
+
+/// Split the attribute into its cases.
+fn parse_cases(attr: TokenStream) -> Result<Vec<Case>, TokenStream> {
+    split_top_level(attr)
+        .into_iter()
+        .map(|tokens| match &tokens[..] {
+            [TokenTree::Ident(ident), TokenTree::Group(group)]
+                if ident.to_string() == "case" && group.delimiter() == Delimiter::Parenthesis =>
+            {
+                parse_expected_case(group)
+            }
+            _ => Ok(Case {
+                value: tokens,
+                expected: None,
+            }),
+        })
+        .collect()
+}
+
+/// Parse the contents of `case(...)`, whose last value is the expectation and whose other values
+/// make up the arguments of the case.
+fn parse_expected_case(group: &Group) -> Result<Case, TokenStream> {
+    let mut values = split_top_level(group.stream());
+    let expectation = values.pop().unwrap_or_default();
+
+    let expected =
+        match &expectation[..] {
+            [TokenTree::Ident(ident)] if ident.to_string() == "pass" => EXPECT_PASS.to_string(),
+            [TokenTree::Ident(ident)] if ident.to_string() == "fail" => {
+                String::from("extel::cases::Expected::Fail(None)")
+            }
+            [TokenTree::Ident(ident), TokenTree::Group(message)]
+                if ident.to_string() == "fail" && message.delimiter() == Delimiter::Parenthesis =>
+            {
+                format!("extel::cases::Expected::fail_with({})", message.stream())
+            }
+            _ => return Err(compile_error(
+                "#[parameters(...)] expected `pass`, `fail`, or `fail(\"message\")` as the last \
+                 value of case(...)",
+                expectation
+                    .first()
+                    .map(TokenTree::span)
+                    .unwrap_or_else(|| group.span()),
+            )),
+        };
+
+    let value =
+        match values.len() {
+            0 => return Err(compile_error(
+                "#[parameters(...)] case(...) requires at least one value before its expectation",
+                group.span(),
+            )),
+            1 => values.pop().expect("values has one item"),
+            _ => {
+                let mut tuple = TokenStream::new();
+                for value in values {
+                    tuple.extend(value);
+                    tuple.extend([TokenTree::Punct(Punct::new(',', Spacing::Alone))]);
+                }
+                vec![TokenTree::Group(Group::new(Delimiter::Parenthesis, tuple))]
+            }
+        };
+
+    Ok(Case {
+        value,
+        expected: Some(expected),
+    })
+}
+
+/// Validate that every case of a function with `arg_count` arguments is a tuple with one value per
+/// argument.
+fn validate_tuple_cases(cases: &[Case], arg_count: usize) -> Result<(), TokenStream> {
+    for case in cases {
+        let case = &case.value;
+        let values = match &case[..] {
+            [TokenTree::Group(group)] if group.delimiter() == Delimiter::Parenthesis => {
+                split_top_level(group.stream()).len()
+            }
+            _ => 1,
+        };
+
+        if values != arg_count {
+            return Err(compile_error(
+                &format!(
+                    "#[parameters(...)] expected a tuple of {} values for each case, found {}",
+                    arg_count, values
+                ),
+                case.first()
+                    .map(TokenTree::span)
+                    .unwrap_or_else(Span::call_site),
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Build a `compile_error!` invocation that reports `message` at `span`. This gives a much more
+/// helpful diagnostic than panicking inside of the macro.
+fn compile_error(message: &str, span: Span) -> TokenStream {
+    let mut message = Literal::string(message);
+    message.set_span(span);
+
+    let mut args = Group::new(
+        Delimiter::Parenthesis,
+        TokenStream::from(TokenTree::Literal(message)),
+    );
+    args.set_span(span);
+
+    [
+        TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+        TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        TokenTree::Ident(Ident::new("core", span)),
+        TokenTree::Punct(Punct::new(':', Spacing::Joint)),
+        TokenTree::Punct(Punct::new(':', Spacing::Alone)),
+        TokenTree::Ident(Ident::new("compile_error", span)),
+        TokenTree::Punct(Punct::new('!', Spacing::Alone)),
+        TokenTree::Group(args),
+        TokenTree::Punct(Punct::new(';', Spacing::Alone)),
+    ]
+    .into_iter()
+    .map(|mut token| {
+        token.set_span(span);
+        token
+    })
+    .collect()
+}
+
+/// Extract the type of every argument in a function argument list.
+fn argument_types(args: TokenStream) -> Vec<String> {
+    let mut types: Vec<String> = Vec::new();
+    let mut current: Vec<TokenTree> = Vec::new();
+    let mut depth: usize = 0;
+
+    let mut push_arg = |arg: &mut Vec<TokenTree>| {
+        // The type begins after the first lone ':' (as opposed to a path separator).
+        let colon = arg.iter().enumerate().position(|(i, token)| match token {
+            TokenTree::Punct(p) => {
+                let after_joint_colon = i > 0
+                    && matches!(&arg[i - 1], TokenTree::Punct(prev) if prev.as_char() == ':' && prev.spacing() == Spacing::Joint);
+                p.as_char() == ':' && p.spacing() == Spacing::Alone && !after_joint_colon
+            }
+            _ => false,
+        });
+
+        if let Some(colon) = colon {
+            types.push(
+                arg[colon + 1..]
+                    .iter()
+                    .cloned()
+                    .collect::<TokenStream>()
+                    .to_string(),
+            );
+        }
+        arg.clear();
+    };
+
+    for token in args {
+        match &token {
+            TokenTree::Punct(p) if p.as_char() == '<' => depth += 1,
+            TokenTree::Punct(p) if p.as_char() == '>' => depth = depth.saturating_sub(1),
+            TokenTree::Punct(p) if p.as_char() == ',' && depth == 0 => {
+                push_arg(&mut current);
+                continue;
+            }
+            _ => {}
+        }
+        current.push(token);
+    }
+    push_arg(&mut current);
+
+    types
+}
+
 /// Validate that the macro is being applied only to function. Return the resulting index of the
-/// function name.
-fn validate_parameters_spec(tokens: &[TokenTree]) -> Result<usize, &'static str> {
+/// function name, or a compile error if the macro was misapplied.
+fn validate_function_spec(tokens: &[TokenTree], macro_name: &str) -> Result<usize, TokenStream> {
     let mut i: usize = 0;
     while i < tokens.len() {
         // The only allowed starting idents are
@@ -91,12 +733,20 @@ fn validate_parameters_spec(tokens: &[TokenTree]) -> Result<usize, &'static str>
             match ident.to_string().as_str() {
                 "fn" => return Ok(i + 1),
                 "pub" => {}
-                _ => return Err("#[parameters(...)] can only be applied to functions"),
+                _ => {
+                    return Err(compile_error(
+                        &format!("{} can only be applied to functions", macro_name),
+                        ident.span(),
+                    ))
+                }
             };
         };
 
         i += 1;
     }
 
-    Err("reached end of token stream")
+    Err(compile_error(
+        &format!("{} can only be applied to functions", macro_name),
+        Span::call_site(),
+    ))
 }
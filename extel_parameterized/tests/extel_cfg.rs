@@ -0,0 +1,49 @@
+use extel::{errors::Error as XE, prelude::*};
+use extel_parameterized::{extel_cfg, parameters};
+
+#[extel_cfg(all())]
+fn always_included() -> ExtelResult {
+    pass!()
+}
+
+#[extel_cfg(any())]
+fn never_included() -> ExtelResult {
+    fail!("should have been skipped")
+}
+
+#[extel_cfg(not(any()))]
+/// This is a doc comment.
+pub(crate) fn included_pub_crate_fn() -> ExtelResult {
+    pass!()
+}
+
+#[extel_cfg(any())]
+#[parameters(1, 2)]
+fn never_included_cases(x: i32) -> ExtelResult {
+    fail!("case {} should have been skipped", x)
+}
+
+#[test]
+fn matching_tests_run() {
+    assert!(always_included().is_ok());
+    assert!(included_pub_crate_fn().is_ok());
+}
+
+#[test]
+fn other_tests_are_skipped_with_the_predicate() {
+    assert!(matches!(
+        never_included(),
+        Err(XE::Skipped(reason)) if reason == "requires cfg(any())"
+    ));
+    assert!(never_included_cases_cases()
+        .iter()
+        .all(|result| matches!(result, Err(XE::Skipped(_)))));
+}
+
+#[test]
+fn skipped_tests_stay_in_the_suite() {
+    init_test_suite!(CfgSuite, always_included, never_included);
+    let results = CfgSuite::run(TestConfig::default());
+    assert_eq!(results.len(), 2);
+    assert!(results[1].test_result.is_skipped());
+}
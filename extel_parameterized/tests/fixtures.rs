@@ -0,0 +1,60 @@
+use std::{fs, path::PathBuf};
+
+use extel::{
+    errors::Error as XE,
+    fixture::{FreePort, ScratchFile, TempDir},
+    prelude::*,
+};
+use extel_parameterized::fixtures;
+
+#[fixtures]
+fn temp_dir_exists(tmp: TempDir) -> ExtelResult {
+    extel_assert!(tmp.path().is_dir(), "{:?} is not a directory", tmp.path())
+}
+
+#[fixtures]
+fn multiple_fixtures(tmp: TempDir, file: ScratchFile, port: FreePort) -> ExtelResult {
+    fs::write(file.path(), "contents")?;
+    extel_assert!(
+        tmp.path() != file.dir() && port.port() > 0,
+        "fixtures were not provisioned independently"
+    )
+}
+
+#[fixtures]
+/// This is a doc comment.
+pub(crate) fn fixture_fails(tmp: TempDir) -> ExtelResult {
+    extel_assert!(!tmp.path().exists(), "temp dir exists")
+}
+
+thread_local! {
+    static LEAKED: std::cell::RefCell<Option<PathBuf>> = const { std::cell::RefCell::new(None) };
+}
+
+#[fixtures]
+fn leak_path(tmp: extel::fixture::TempDir) -> ExtelResult {
+    LEAKED.with(|leaked| *leaked.borrow_mut() = Some(tmp.path().to_path_buf()));
+    pass!()
+}
+
+#[test]
+fn fixtures_single() {
+    assert!(temp_dir_exists().is_ok());
+}
+
+#[test]
+fn fixtures_multiple() {
+    assert!(multiple_fixtures().is_ok());
+}
+
+#[test]
+fn fixtures_failure() {
+    assert!(matches!(fixture_fails(), Err(XE::TestFailed(_))));
+}
+
+#[test]
+fn fixtures_torn_down() {
+    assert!(leak_path().is_ok());
+    let path = LEAKED.with(|leaked| leaked.borrow().clone()).unwrap();
+    assert!(!path.exists());
+}
@@ -13,6 +13,11 @@ fn check_sum_into_two(sum: (i32, i32)) -> ExtelResult {
     )
 }
 
+#[parameters((1, "1"), (2, "two"), (3, "3"))]
+fn check_multi_arg(x: i32, s: &str) -> ExtelResult {
+    extel_assert!(s.parse::<i32>().is_ok_and(|parsed| parsed == x))
+}
+
 #[parameters(vec![], vec![1])]
 fn check_vec(x: Vec<usize>) -> ExtelResult {
     extel_assert!(!x.is_empty(), "input is empty")
@@ -39,6 +44,68 @@ fn doc_comment_fn(x: i32) -> ExtelResult {
     extel_assert!(x >= 0, "x less than 0")
 }
 
+#[parameters(1, 0, 2)]
+fn check_panicking_case(x: i32) -> ExtelResult {
+    extel_assert!(10 / x > 0)
+}
+
+#[parameters(case(2, pass), case(-1, fail("negative")), case(0, fail), case(3, fail), 4)]
+fn check_expected_cases(x: i32) -> ExtelResult {
+    extel_assert!(x > 0, "{} is negative", x)
+}
+
+#[parameters(case(1, "1", pass), case(2, "two", fail("invalid digit")))]
+fn check_expected_multi_arg(x: i32, s: &str) -> ExtelResult {
+    extel_assert_eq!(s.parse::<i32>()?, x)
+}
+
+static SHARED_CASES: &[(i32, &str)] = &[(1, "1"), (2, "two"), (3, "3")];
+
+#[parameters(from = SHARED_CASES)]
+fn check_shared_table(x: i32, s: &str) -> ExtelResult {
+    extel_assert_eq!(s.parse::<i32>()?, x)
+}
+
+#[parameters(from = SHARED_CASES)]
+fn check_shared_table_again(x: i32, s: &str) -> ExtelResult {
+    extel_assert!(x > 0 && !s.is_empty())
+}
+
+mod tables {
+    pub const LIMIT: i32 = 3;
+
+    pub fn evens() -> Vec<i32> {
+        (0..LIMIT).map(|n| n * 2).collect()
+    }
+}
+
+#[parameters(from = tables::evens)]
+fn check_function_table(x: i32) -> ExtelResult {
+    extel_assert_eq!(x % 2, 0)
+}
+
+#[parameters(tables::LIMIT)]
+fn check_single_const_case(x: i32) -> ExtelResult {
+    extel_assert_eq!(x, 3)
+}
+
+#[parameters(true)]
+fn check_single_bool_case(yes: bool) -> ExtelResult {
+    extel_assert!(yes)
+}
+
+#[parameters(None)]
+fn check_single_none_case(value: Option<u8>) -> ExtelResult {
+    extel_assert!(value.is_none())
+}
+
+#[parameters(MAX)]
+fn check_single_ident_case(x: u8) -> ExtelResult {
+    extel_assert_eq!(x, u8::MAX)
+}
+
+const MAX: u8 = u8::MAX;
+
 mod super_test {
     use super::*;
 
@@ -51,28 +118,63 @@ mod super_test {
 #[test]
 fn parameters_tuples() {
     assert!(matches!(
-        &check_sum_into_two()[..],
+        &check_sum_into_two_cases()[..],
         [Ok(_), Err(XE::TestFailed(_))]
     ));
 }
 
+#[test]
+fn parameters_multi_arg() {
+    assert!(matches!(
+        &check_multi_arg_cases()[..],
+        [Ok(_), Err(XE::TestFailed(_)), Ok(_)]
+    ));
+}
+
 #[test]
 fn parameters_vec() {
-    assert!(matches!(&check_vec()[..], [Err(XE::TestFailed(_)), Ok(_)]));
+    assert!(matches!(
+        &check_vec_cases()[..],
+        [Err(XE::TestFailed(_)), Ok(_)]
+    ));
 }
 
 #[test]
 fn parameters_pub() {
     assert!(matches!(
-        &check_pub_fn()[..],
+        &check_pub_fn_cases()[..],
         [Ok(_), Ok(_), Err(XE::TestFailed(_))]
     ));
 }
 
+#[test]
+fn parameters_expected() {
+    assert!(matches!(
+        &check_expected_cases_cases()[..],
+        [Ok(_), Ok(_), Ok(_), Err(XE::TestFailed(_)), Ok(_)]
+    ));
+    assert!(check_expected_multi_arg_cases().iter().all(Result::is_ok));
+}
+
+#[test]
+fn parameters_tables() {
+    assert!(matches!(
+        &check_shared_table_cases()[..],
+        [Ok(_), Err(XE::ParseInt(_)), Ok(_)]
+    ));
+    assert!(check_shared_table_again_cases().iter().all(Result::is_ok));
+    assert_eq!(check_function_table_cases().len(), 3);
+    assert!(check_function_table_cases().iter().all(Result::is_ok));
+    assert!(matches!(&check_single_const_case_cases()[..], [Ok(_)]));
+    assert!(matches!(&check_single_bool_case_cases()[..], [Ok(_)]));
+    assert!(matches!(&check_single_none_case_cases()[..], [Ok(_)]));
+    assert!(matches!(&check_single_ident_case_cases()[..], [Ok(_)]));
+}
+
 #[test]
 fn parameters_pub_crate() {
     assert!(matches!(
-        &check_pub_crate_fn()[..],
+        &check_pub_crate_fn_cases()[..],
         [Ok(_), Ok(_), Err(XE::TestFailed(_))]
     ));
 }
@@ -81,7 +183,7 @@ fn parameters_pub_crate() {
 fn parameters_pub_super() {
     use super_test::*;
     assert!(matches!(
-        &check_pub_super_fn()[..],
+        &check_pub_super_fn_cases()[..],
         [Ok(_), Ok(_), Err(XE::TestFailed(_))]
     ));
 }
@@ -89,7 +191,22 @@ fn parameters_pub_super() {
 #[test]
 fn doc_comment() {
     assert!(matches!(
-        &doc_comment_fn()[..],
+        &doc_comment_fn_cases()[..],
         [Ok(_), Ok(_), Err(XE::TestFailed(_))]
     ));
 }
+
+#[test]
+fn parameters_panic() {
+    assert!(matches!(
+        &check_panicking_case_cases()[..],
+        [Ok(_), Err(XE::Panicked(_)), Ok(_)]
+    ));
+}
+
+#[test]
+fn parameters_original_fn_is_callable() {
+    assert!(check_pub_fn(5).is_ok());
+    assert!(check_multi_arg(4, "4").is_ok());
+    assert!(matches!(check_vec(vec![]), Err(XE::TestFailed(_))));
+}